@@ -1,8 +1,11 @@
+use std::cmp::min;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, coins, to_binary, Addr, BankMsg, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env,
-    MessageInfo, Order, Response, StdResult, Uint128,
+    attr, coin, coins, to_binary, Addr, BankMsg, Binary, BlockInfo, Coin, CosmosMsg, Decimal, Deps,
+    DepsMut, Empty, Env, MessageInfo, Order, Reply, Response, StdError, StdResult, Storage, SubMsg,
+    Timestamp, Uint128, WasmMsg,
 };
 use cw_storage_plus::Bound;
 use mars_owner::{OwnerInit::SetInitialOwner, OwnerUpdate};
@@ -10,19 +13,33 @@ use mars_red_bank_types::{
     address_provider::{self, MarsAddressType},
     error::MarsError,
     incentives::{
-        AssetIncentive, AssetIncentiveResponse, Config, ConfigResponse, ExecuteMsg, InstantiateMsg,
-        QueryMsg,
+        AssetIncentive, AssetIncentiveResponse, Config, ConfigResponse, Emission, ExecuteMsg,
+        IncentiveSchedule, InstantiateMsg, PositionKind, QueryMsg, RewardDebugInfoResponse,
+        UserActiveIncentiveResponse, UserRewardStateResponse, VaultCallbackMsg, VestingTranche,
     },
-    red_bank,
+    oracle, red_bank, rewards_collector,
+};
+use mars_utils::{
+    helpers::{option_string_to_addr, validate_native_denom},
+    math,
 };
-use mars_utils::helpers::{option_string_to_addr, validate_native_denom};
 
 use crate::{
     error::ContractError,
     helpers::{
-        compute_user_accrued_rewards, compute_user_unclaimed_rewards, update_asset_incentive_index,
+        active_emission_per_second, active_incentive_schedules, compute_user_accrued_rewards,
+        compute_user_unclaimed_rewards, compute_user_unclaimed_rewards_for_denoms,
+        compute_user_vested_rewards, compute_user_vesting_rewards, compute_vested_amount,
+        incentive_end_time, incentive_schedules, load_user_unclaimed_rewards,
+        query_user_and_total_amount_scaled, update_asset_incentive_index,
+    },
+    migrations,
+    state::{
+        asset_incentives, user_asset_indices, user_asset_unclaimed_rewards, ASSET_INCENTIVES,
+        COMPOUNDING_VAULTS, CONFIG, INCENTIVE_DENOM_WHITELIST, OWNER, PENDING_REPAY,
+        TOTAL_UNCLAIMED_REWARDS, USER_ASSET_INDEX_SET_AT, USER_ASSET_INDICES,
+        USER_ASSET_UNCLAIMED_REWARDS, USER_CLAIMED_TOTALS, USER_UNCLAIMED_REWARDS, USER_VESTING,
     },
-    state::{ASSET_INCENTIVES, CONFIG, OWNER, USER_ASSET_INDICES, USER_UNCLAIMED_REWARDS},
 };
 
 pub const CONTRACT_NAME: &str = "crates.io:mars-incentives";
@@ -31,6 +48,11 @@ pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const DEFAULT_LIMIT: u32 = 5;
 const MAX_LIMIT: u32 = 10;
 
+const SECONDS_PER_YEAR: u64 = 31536000u64;
+
+/// Reply ID of the swap of a user's rewards into the denom of the debt they repay with them
+pub const REPAY_REPLY_ID: u64 = 1;
+
 // INIT
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -53,8 +75,11 @@ pub fn instantiate(
     let config = Config {
         address_provider: deps.api.addr_validate(&msg.address_provider)?,
         mars_denom: msg.mars_denom,
+        vesting_duration: 0,
+        funder: None,
     };
 
+    INCENTIVE_DENOM_WHITELIST.save(deps.storage, &vec![config.mars_denom.clone()])?;
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::default())
@@ -72,7 +97,8 @@ pub fn execute(
     match msg {
         ExecuteMsg::SetAssetIncentive {
             denom,
-            emission_per_second,
+            kind,
+            emission,
             start_time,
             duration,
         } => execute_set_asset_incentive(
@@ -80,7 +106,8 @@ pub fn execute(
             env,
             info,
             denom,
-            emission_per_second,
+            kind.unwrap_or_default(),
+            emission,
             start_time,
             duration,
         ),
@@ -89,20 +116,70 @@ pub fn execute(
             denom,
             user_amount_scaled_before,
             total_amount_scaled_before,
+            kind,
         } => execute_balance_change(
             deps,
             env,
             info,
             user_addr,
             denom,
+            kind.unwrap_or_default(),
             user_amount_scaled_before,
             total_amount_scaled_before,
         ),
-        ExecuteMsg::ClaimRewards {} => execute_claim_rewards(deps, env, info),
+        ExecuteMsg::ClaimRewards {
+            recipient,
+        } => execute_claim_rewards(deps, env, info, recipient, None),
+        ExecuteMsg::ClaimRewardsForDenoms {
+            collateral_denoms,
+        } => execute_claim_rewards(deps, env, info, None, Some(collateral_denoms)),
+        ExecuteMsg::ClaimAndRepay {
+            debt_denom,
+        } => execute_claim_and_repay(deps, env, info, debt_denom),
+        ExecuteMsg::AddIncentiveSchedule {
+            collateral_denom,
+            incentive_denom,
+            emission,
+            start_time,
+            duration,
+        } => execute_add_incentive_schedule(
+            deps,
+            env,
+            info,
+            collateral_denom,
+            incentive_denom,
+            emission,
+            start_time,
+            duration,
+        ),
+        ExecuteMsg::UpdateIncentiveDenomWhitelist {
+            add,
+            remove,
+        } => execute_update_incentive_denom_whitelist(deps, info, add, remove),
+        ExecuteMsg::UpdateCompoundingVault {
+            vault,
+            registered,
+        } => execute_update_compounding_vault(deps, info, vault, registered),
+        ExecuteMsg::ReclaimUnemittedIncentive {
+            collateral_denom,
+            incentive_denom,
+        } => {
+            execute_reclaim_unemitted_incentive(deps, env, info, collateral_denom, incentive_denom)
+        }
         ExecuteMsg::UpdateConfig {
             address_provider,
             mars_denom,
-        } => Ok(execute_update_config(deps, env, info, address_provider, mars_denom)?),
+            vesting_duration,
+            funder,
+        } => Ok(execute_update_config(
+            deps,
+            env,
+            info,
+            address_provider,
+            mars_denom,
+            vesting_duration,
+            funder,
+        )?),
         ExecuteMsg::UpdateOwner(update) => update_owner(deps, info, update),
     }
 }
@@ -112,25 +189,26 @@ pub fn execute_set_asset_incentive(
     env: Env,
     info: MessageInfo,
     denom: String,
-    emission_per_second: Option<Uint128>,
+    kind: PositionKind,
+    emission: Option<Emission>,
     start_time: Option<u64>,
     duration: Option<u64>,
 ) -> Result<Response, ContractError> {
     OWNER.assert_owner(deps.storage, &info.sender)?;
 
     validate_native_denom(&denom)?;
+    assert_incentive_denom_whitelisted(deps.storage, &CONFIG.load(deps.storage)?.mars_denom)?;
 
     let current_block_time = env.block.time.seconds();
-    let new_asset_incentive = match ASSET_INCENTIVES.may_load(deps.storage, &denom)? {
+    let new_asset_incentive = match asset_incentives(kind).may_load(deps.storage, &denom)? {
         Some(mut asset_incentive) => {
-            let (start_time, duration, emission_per_second) =
-                validate_params_for_existing_incentive(
-                    &asset_incentive,
-                    emission_per_second,
-                    start_time,
-                    duration,
-                    current_block_time,
-                )?;
+            let (start_time, duration, emission) = validate_params_for_existing_incentive(
+                &asset_incentive,
+                emission,
+                start_time,
+                duration,
+                current_block_time,
+            )?;
 
             let config = CONFIG.load(deps.storage)?;
 
@@ -147,44 +225,52 @@ pub fn execute_set_asset_incentive(
                 },
             )?;
 
+            let total_amount_scaled = match kind {
+                PositionKind::Collateral => market.collateral_total_scaled,
+                PositionKind::Debt => market.debt_total_scaled,
+            };
+
             // Update index up to now
-            update_asset_incentive_index(
-                &mut asset_incentive,
-                market.collateral_total_scaled,
-                current_block_time,
-            )?;
+            update_asset_incentive_index(&mut asset_incentive, total_amount_scaled, &env.block)?;
 
             // Set new emission
-            asset_incentive.emission_per_second = emission_per_second;
+            asset_incentive.emission = emission;
             asset_incentive.start_time = start_time;
             asset_incentive.duration = duration;
 
             asset_incentive
         }
         None => {
-            let (start_time, duration, emission_per_second) = validate_params_for_new_incentive(
+            let (start_time, duration, emission) = validate_params_for_new_incentive(
                 start_time,
                 duration,
-                emission_per_second,
+                emission,
                 current_block_time,
             )?;
 
             AssetIncentive {
-                emission_per_second,
+                emission,
                 start_time,
                 duration,
                 index: Decimal::zero(),
                 last_updated: current_block_time,
+                last_updated_height: env.block.height,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
             }
         }
     };
 
-    ASSET_INCENTIVES.save(deps.storage, &denom, &new_asset_incentive)?;
+    asset_incentives(kind).save(deps.storage, &denom, &new_asset_incentive)?;
 
     let response = Response::new().add_attributes(vec![
         attr("action", "set_asset_incentive"),
         attr("denom", denom),
-        attr("emission_per_second", new_asset_incentive.emission_per_second),
+        attr("kind", kind.to_string()),
+        match new_asset_incentive.emission {
+            Emission::PerSecond(amount) => attr("emission_per_second", amount),
+            Emission::PerBlock(amount) => attr("emission_per_block", amount),
+        },
         attr("start_time", new_asset_incentive.start_time.to_string()),
         attr("duration", new_asset_incentive.duration.to_string()),
     ]);
@@ -193,11 +279,11 @@ pub fn execute_set_asset_incentive(
 
 fn validate_params_for_existing_incentive(
     asset_incentive: &AssetIncentive,
-    emission_per_second: Option<Uint128>,
+    emission: Option<Emission>,
     start_time: Option<u64>,
     duration: Option<u64>,
     current_block_time: u64,
-) -> Result<(u64, u64, Uint128), ContractError> {
+) -> Result<(u64, u64, Emission), ContractError> {
     let end_time = asset_incentive.start_time + asset_incentive.duration;
     let start_time = match start_time {
         // current asset incentive hasn't finished yet
@@ -246,19 +332,20 @@ fn validate_params_for_existing_incentive(
         None => asset_incentive.duration,
     };
 
-    let emission_per_second = emission_per_second.unwrap_or(asset_incentive.emission_per_second);
+    let emission = emission.unwrap_or_else(|| asset_incentive.emission.clone());
 
-    Ok((start_time, duration, emission_per_second))
+    Ok((start_time, duration, emission))
 }
 
 fn validate_params_for_new_incentive(
     start_time: Option<u64>,
     duration: Option<u64>,
-    emission_per_second: Option<Uint128>,
+    emission: Option<Emission>,
     current_block_time: u64,
-) -> Result<(u64, u64, Uint128), ContractError> {
+) -> Result<(u64, u64, Emission), ContractError> {
     // all params are required during incentive initialization (if start_time = None then set to current block time)
-    let (Some(start_time), Some(duration), Some(emission_per_second)) = (start_time, duration, emission_per_second) else {
+    let (Some(start_time), Some(duration), Some(emission)) = (start_time, duration, emission)
+    else {
         return Err(ContractError::InvalidIncentive {
             reason: "all params are required during incentive initialization".to_string(),
         });
@@ -278,7 +365,76 @@ fn validate_params_for_new_incentive(
         });
     }
 
-    Ok((start_time, duration, emission_per_second))
+    Ok((start_time, duration, emission))
+}
+
+pub fn execute_add_incentive_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collateral_denom: String,
+    incentive_denom: String,
+    emission: Emission,
+    start_time: u64,
+    duration: u64,
+) -> Result<Response, ContractError> {
+    OWNER.assert_owner(deps.storage, &info.sender)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    if incentive_denom != config.mars_denom {
+        return Err(ContractError::InvalidIncentive {
+            reason: format!("no incentives are distributed in {incentive_denom}"),
+        });
+    }
+    assert_incentive_denom_whitelisted(deps.storage, &incentive_denom)?;
+
+    let mut asset_incentive = match ASSET_INCENTIVES.may_load(deps.storage, &collateral_denom)? {
+        Some(asset_incentive) => asset_incentive,
+        None => {
+            return Err(ContractError::InvalidIncentive {
+                reason: format!("no incentive is set for {collateral_denom}"),
+            })
+        }
+    };
+
+    let (start_time, duration, emission) = validate_params_for_new_incentive(
+        Some(start_time),
+        Some(duration),
+        Some(emission),
+        env.block.time.seconds(),
+    )?;
+
+    // Update index up to now, so the new schedule only emits from now on
+    let red_bank_addr = query_red_bank_address(deps.as_ref())?;
+    let market: red_bank::Market = deps.querier.query_wasm_smart(
+        red_bank_addr,
+        &red_bank::QueryMsg::Market {
+            denom: collateral_denom.clone(),
+        },
+    )?;
+    update_asset_incentive_index(&mut asset_incentive, market.collateral_total_scaled, &env.block)?;
+
+    // schedules are never removed, so ids aren't reused
+    let schedule = IncentiveSchedule {
+        id: asset_incentive.schedules.len() as u64 + 1,
+        emission,
+        start_time,
+        duration,
+    };
+    asset_incentive.schedules.push(schedule.clone());
+    ASSET_INCENTIVES.save(deps.storage, &collateral_denom, &asset_incentive)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "add_incentive_schedule"),
+        attr("collateral_denom", collateral_denom),
+        attr("schedule_id", schedule.id.to_string()),
+        match schedule.emission {
+            Emission::PerSecond(amount) => attr("emission_per_second", amount),
+            Emission::PerBlock(amount) => attr("emission_per_block", amount),
+        },
+        attr("start_time", schedule.start_time.to_string()),
+        attr("duration", schedule.duration.to_string()),
+    ]))
 }
 
 pub fn execute_balance_change(
@@ -287,6 +443,7 @@ pub fn execute_balance_change(
     info: MessageInfo,
     user_addr: Addr,
     denom: String,
+    kind: PositionKind,
     user_amount_scaled_before: Uint128,
     total_amount_scaled_before: Uint128,
 ) -> Result<Response, ContractError> {
@@ -296,7 +453,7 @@ pub fn execute_balance_change(
         return Err(MarsError::Unauthorized {}.into());
     }
 
-    let mut asset_incentive = match ASSET_INCENTIVES.may_load(deps.storage, &denom)? {
+    let mut asset_incentive = match asset_incentives(kind).may_load(deps.storage, &denom)? {
         // If there are no incentives,
         // an empty successful response is returned as the
         // success of the call is needed for the call that triggered the change to
@@ -306,15 +463,11 @@ pub fn execute_balance_change(
         Some(ai) => ai,
     };
 
-    update_asset_incentive_index(
-        &mut asset_incentive,
-        total_amount_scaled_before,
-        env.block.time.seconds(),
-    )?;
-    ASSET_INCENTIVES.save(deps.storage, &denom, &asset_incentive)?;
+    update_asset_incentive_index(&mut asset_incentive, total_amount_scaled_before, &env.block)?;
+    asset_incentives(kind).save(deps.storage, &denom, &asset_incentive)?;
 
     // Check if user has accumulated uncomputed rewards (which means index is not up to date)
-    let user_asset_index_key = USER_ASSET_INDICES.key((&user_addr, &denom));
+    let user_asset_index_key = user_asset_indices(kind).key((&user_addr, &denom));
 
     let user_asset_index =
         user_asset_index_key.may_load(deps.storage)?.unwrap_or_else(Decimal::zero);
@@ -329,21 +482,36 @@ pub fn execute_balance_change(
             asset_incentive.index,
         )?;
 
-        // Store user accrued rewards as unclaimed
-        if !accrued_rewards.is_zero() {
-            USER_UNCLAIMED_REWARDS.update(
+        // Store user accrued rewards as unclaimed, or as vesting if vesting is enabled
+        let vesting_duration = CONFIG.load(deps.storage)?.vesting_duration;
+        if !accrued_rewards.is_zero() && vesting_duration > 0 {
+            add_vesting_tranche(
                 deps.storage,
                 &user_addr,
-                |ur: Option<Uint128>| -> StdResult<Uint128> {
-                    match ur {
-                        Some(unclaimed_rewards) => Ok(unclaimed_rewards + accrued_rewards),
-                        None => Ok(accrued_rewards),
-                    }
-                },
+                accrued_rewards,
+                env.block.time.seconds(),
+            )?;
+        } else if !accrued_rewards.is_zero() {
+            let unclaimed_rewards = user_asset_unclaimed_rewards(kind)
+                .may_load(deps.storage, (&user_addr, &denom))?
+                .unwrap_or_default();
+            save_user_asset_unclaimed_rewards(
+                deps.storage,
+                &user_addr,
+                &denom,
+                kind,
+                unclaimed_rewards.checked_add(accrued_rewards)?,
             )?;
         }
 
-        user_asset_index_key.save(deps.storage, &asset_incentive.index)?;
+        save_user_asset_index(
+            deps.storage,
+            &user_addr,
+            &denom,
+            kind,
+            asset_incentive.index,
+            env.block.time.seconds(),
+        )?;
     }
 
     let response = Response::new().add_attributes(vec![
@@ -357,55 +525,443 @@ pub fn execute_balance_change(
     Ok(response)
 }
 
+/// Claim the sender's rewards. If `collateral_denoms` is given, only the rewards accrued on the
+/// collateral incentives of those denoms are claimed.
 pub fn execute_claim_rewards(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    recipient: Option<String>,
+    collateral_denoms: Option<Vec<String>>,
 ) -> Result<Response, ContractError> {
-    let red_bank_addr = query_red_bank_address(deps.as_ref())?;
     let user_addr = info.sender;
-    let (total_unclaimed_rewards, user_asset_incentive_statuses_to_update) =
-        compute_user_unclaimed_rewards(deps.as_ref(), &env.block, &red_bank_addr, &user_addr)?;
+    let recipient_addr = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => user_addr.clone(),
+    };
+    let (total_unclaimed_rewards, mars_denom, shortfall, skipped_denoms) =
+        claim_user_rewards(deps.branch(), &env, &user_addr, collateral_denoms.as_deref())?;
+
+    let mut response = Response::new();
+    if !total_unclaimed_rewards.is_zero() {
+        let rewards = coins(total_unclaimed_rewards.u128(), mars_denom);
+
+        // Build message to send mars to the recipient
+        response = response.add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient_addr.to_string(),
+            amount: rewards.clone(),
+        }));
+
+        // Compounding vaults are notified of the exact amounts claimed after receiving them, so
+        // they can update their share price atomically
+        if recipient_addr == user_addr && COMPOUNDING_VAULTS.has(deps.storage, &user_addr) {
+            response = response.add_message(WasmMsg::Execute {
+                contract_addr: user_addr.to_string(),
+                msg: to_binary(&VaultCallbackMsg::RewardsClaimed {
+                    rewards,
+                })?,
+                funds: vec![],
+            });
+        }
+    };
+
+    response = response.add_attributes(vec![
+        attr("action", "claim_rewards"),
+        attr("user", user_addr),
+        attr("recipient", recipient_addr),
+        attr("mars_rewards", total_unclaimed_rewards),
+    ]);
+    if let Some(collateral_denoms) = collateral_denoms {
+        response = response.add_attribute("collateral_denoms", collateral_denoms.join(","));
+    }
+    if !shortfall.is_zero() {
+        response = response.add_attribute("capped", shortfall);
+    }
+    if !skipped_denoms.is_empty() {
+        response = response.add_attribute("skipped_denoms", skipped_denoms.join(","));
+    }
+
+    Ok(response)
+}
+
+/// Claim the sender's rewards and use them to repay their debt at the Red Bank. Rewards in excess
+/// of the debt are sent to the user, as the Red Bank would refund them to this contract otherwise.
+///
+/// If the debt is in another denom than the rewards, the rewards are first swapped into it by the
+/// rewards collector, and the debt is repaid with the swap output on reply.
+pub fn execute_claim_and_repay(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    debt_denom: String,
+) -> Result<Response, ContractError> {
+    let user_addr = info.sender;
+    let (total_unclaimed_rewards, mars_denom, shortfall, skipped_denoms) =
+        claim_user_rewards(deps.branch(), &env, &user_addr, None)?;
+
+    let mut response = Response::new();
+    let mut repaid_amount = None;
+    if debt_denom == mars_denom {
+        let (messages, repay_amount) =
+            build_repay_msgs(deps.as_ref(), &user_addr, &debt_denom, total_unclaimed_rewards)?;
+        response = response.add_messages(messages);
+        repaid_amount = Some(repay_amount);
+    } else if !total_unclaimed_rewards.is_zero() {
+        let config = CONFIG.load(deps.storage)?;
+        let rewards_collector_addr = address_provider::helpers::query_contract_addr(
+            deps.as_ref(),
+            &config.address_provider,
+            MarsAddressType::RewardsCollector,
+        )?;
+
+        let balance_before = deps.querier.query_balance(&env.contract.address, &debt_denom)?.amount;
+        PENDING_REPAY
+            .save(deps.storage, &(user_addr.clone(), debt_denom.clone(), balance_before))?;
+
+        let swap_msg = WasmMsg::Execute {
+            contract_addr: rewards_collector_addr.to_string(),
+            msg: to_binary(&rewards_collector::ExecuteMsg::<Empty>::SwapFunds {
+                denom_out: debt_denom.clone(),
+            })?,
+            funds: coins(total_unclaimed_rewards.u128(), &mars_denom),
+        };
+        response = response.add_submessage(SubMsg::reply_on_success(swap_msg, REPAY_REPLY_ID));
+    }
+
+    if !shortfall.is_zero() {
+        response = response.add_attribute("capped", shortfall);
+    }
+    if !skipped_denoms.is_empty() {
+        response = response.add_attribute("skipped_denoms", skipped_denoms.join(","));
+    }
+
+    response = response.add_attributes(vec![
+        attr("action", "claim_and_repay"),
+        attr("user", user_addr),
+        attr("mars_rewards", total_unclaimed_rewards),
+        attr("debt_denom", debt_denom),
+    ]);
+    // when swapping first, the repaid amount is only known on reply
+    if let Some(repaid_amount) = repaid_amount {
+        response = response.add_attribute("repaid_amount", repaid_amount);
+    }
+
+    Ok(response)
+}
+
+/// Repay the user's debt with the output of the swap of their rewards, which the rewards collector
+/// sent back to this contract
+fn repay_with_swapped_rewards(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let (user_addr, debt_denom, balance_before) = PENDING_REPAY.load(deps.storage)?;
+    PENDING_REPAY.remove(deps.storage);
+
+    let balance = deps.querier.query_balance(&env.contract.address, &debt_denom)?.amount;
+    let swapped_amount = balance.checked_sub(balance_before)?;
+
+    let (messages, repay_amount) =
+        build_repay_msgs(deps.as_ref(), &user_addr, &debt_denom, swapped_amount)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "repay_with_swapped_rewards"),
+        attr("user", user_addr),
+        attr("debt_denom", debt_denom),
+        attr("swapped_amount", swapped_amount),
+        attr("repaid_amount", repay_amount),
+    ]))
+}
+
+/// Build the messages repaying the user's debt at the Red Bank with up to the given amount, and
+/// sending the rest to the user. Returns them along with the amount repaid
+fn build_repay_msgs(
+    deps: Deps,
+    user_addr: &Addr,
+    debt_denom: &str,
+    amount: Uint128,
+) -> StdResult<(Vec<CosmosMsg>, Uint128)> {
+    let red_bank_addr = query_red_bank_address(deps)?;
+    let debt: red_bank::UserDebtResponse = deps.querier.query_wasm_smart(
+        &red_bank_addr,
+        &red_bank::QueryMsg::UserDebt {
+            user: user_addr.to_string(),
+            denom: debt_denom.to_string(),
+        },
+    )?;
+    let repay_amount = min(amount, debt.amount);
+    let refund_amount = amount - repay_amount;
+
+    let mut messages = vec![];
+    if !repay_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: red_bank_addr.to_string(),
+            msg: to_binary(&red_bank::ExecuteMsg::Repay {
+                on_behalf_of: Some(user_addr.to_string()),
+            })?,
+            funds: coins(repay_amount.u128(), debt_denom),
+        }));
+    }
+    if !refund_amount.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: user_addr.to_string(),
+            amount: coins(refund_amount.u128(), debt_denom),
+        }));
+    }
+
+    Ok((messages, repay_amount))
+}
+
+/// Add rewards accrued by the user at `accrued_at` to their vesting tranches
+fn add_vesting_tranche(
+    storage: &mut dyn Storage,
+    user_addr: &Addr,
+    amount: Uint128,
+    accrued_at: u64,
+) -> StdResult<()> {
+    let mut tranches = USER_VESTING.may_load(storage, user_addr)?.unwrap_or_default();
+    match tranches.last_mut() {
+        Some(last) if last.accrued_at == accrued_at => {
+            last.amount = last.amount.checked_add(amount)?;
+        }
+        _ => tranches.push(VestingTranche {
+            amount,
+            accrued_at,
+            released: Uint128::zero(),
+        }),
+    }
+    USER_VESTING.save(storage, user_addr, &tranches)
+}
+
+/// Store the user's index for an asset, recording the time at which a collateral index is set for
+/// the first time
+fn save_user_asset_index(
+    storage: &mut dyn Storage,
+    user_addr: &Addr,
+    denom: &str,
+    kind: PositionKind,
+    index: Decimal,
+    current_time: u64,
+) -> StdResult<()> {
+    user_asset_indices(kind).save(storage, (user_addr, denom), &index)?;
+    if kind == PositionKind::Collateral && !USER_ASSET_INDEX_SET_AT.has(storage, (user_addr, denom))
+    {
+        USER_ASSET_INDEX_SET_AT.save(storage, (user_addr, denom), &current_time)?;
+    }
+    Ok(())
+}
+
+/// Store the user's unclaimed rewards that aren't attributed to an asset, adjusting the total
+/// across all users by the difference with the previously stored amount
+fn save_user_unclaimed_rewards(
+    storage: &mut dyn Storage,
+    user_addr: &Addr,
+    unclaimed_rewards: Uint128,
+) -> StdResult<()> {
+    let previous = USER_UNCLAIMED_REWARDS.may_load(storage, user_addr)?.unwrap_or_default();
+    update_total_unclaimed_rewards(storage, previous, unclaimed_rewards)?;
+    USER_UNCLAIMED_REWARDS.save(storage, user_addr, &unclaimed_rewards)
+}
+
+/// Store the user's unclaimed rewards on an asset's incentive, adjusting the total across all users
+/// by the difference with the previously stored amount. Cleared rewards are removed from storage
+fn save_user_asset_unclaimed_rewards(
+    storage: &mut dyn Storage,
+    user_addr: &Addr,
+    denom: &str,
+    kind: PositionKind,
+    unclaimed_rewards: Uint128,
+) -> StdResult<()> {
+    let key = (user_addr, denom);
+    let previous = user_asset_unclaimed_rewards(kind).may_load(storage, key)?.unwrap_or_default();
+    update_total_unclaimed_rewards(storage, previous, unclaimed_rewards)?;
+    if unclaimed_rewards.is_zero() {
+        user_asset_unclaimed_rewards(kind).remove(storage, key);
+        Ok(())
+    } else {
+        user_asset_unclaimed_rewards(kind).save(storage, key, &unclaimed_rewards)
+    }
+}
+
+/// Adjust the total of unclaimed rewards across all users after a user's unclaimed rewards changed
+/// from `previous` to `current`
+fn update_total_unclaimed_rewards(
+    storage: &mut dyn Storage,
+    previous: Uint128,
+    current: Uint128,
+) -> StdResult<()> {
+    let total = TOTAL_UNCLAIMED_REWARDS.may_load(storage)?.unwrap_or_default();
+    let total = if current >= previous {
+        total.checked_add(current - previous)?
+    } else {
+        total.checked_sub(previous - current)?
+    };
+    TOTAL_UNCLAIMED_REWARDS.save(storage, &total)
+}
+
+/// Clear the rewards stored as unclaimed for the user, returning their amount. If
+/// `collateral_denoms` is given, only the rewards of those denoms' collateral incentives are
+/// cleared, otherwise the rewards of every asset and the ones not attributed to an asset are.
+fn clear_user_unclaimed_rewards(
+    storage: &mut dyn Storage,
+    user_addr: &Addr,
+    collateral_denoms: Option<&[String]>,
+) -> StdResult<Uint128> {
+    let mut keys = vec![];
+    match collateral_denoms {
+        Some(collateral_denoms) => {
+            let mut denoms = collateral_denoms.to_vec();
+            denoms.sort();
+            denoms.dedup();
+            keys.extend(denoms.into_iter().map(|denom| (PositionKind::Collateral, denom)));
+        }
+        None => {
+            for kind in [PositionKind::Collateral, PositionKind::Debt] {
+                for denom in user_asset_unclaimed_rewards(kind).prefix(user_addr).keys(
+                    storage,
+                    None,
+                    None,
+                    Order::Ascending,
+                ) {
+                    keys.push((kind, denom?));
+                }
+            }
+        }
+    }
+
+    let mut cleared_rewards = Uint128::zero();
+    for (kind, denom) in keys {
+        let unclaimed_rewards = user_asset_unclaimed_rewards(kind)
+            .may_load(storage, (user_addr, &denom))?
+            .unwrap_or_default();
+        cleared_rewards = cleared_rewards.checked_add(unclaimed_rewards)?;
+        save_user_asset_unclaimed_rewards(storage, user_addr, &denom, kind, Uint128::zero())?;
+    }
+
+    if collateral_denoms.is_none() {
+        let unclaimed_rewards =
+            USER_UNCLAIMED_REWARDS.may_load(storage, user_addr)?.unwrap_or_default();
+        cleared_rewards = cleared_rewards.checked_add(unclaimed_rewards)?;
+        save_user_unclaimed_rewards(storage, user_addr, Uint128::zero())?;
+    }
+
+    Ok(cleared_rewards)
+}
+
+/// Commit the user's accrued rewards and clear them, returning the amount released to the user, its
+/// denom, the shortfall if the contract's balance couldn't cover the whole amount, and the denoms
+/// of the assets skipped because their balances couldn't be queried.
+///
+/// Rewards stored as unclaimed were accrued with vesting disabled and are released in full. If
+/// vesting is enabled, rewards accrued since the user's last balance change start vesting at the
+/// current block, and only the vested portion of each tranche is released.
+///
+/// The released amount is capped to the contract's balance so that an under-funded contract
+/// doesn't make the claim revert. The shortfall is kept as unclaimed rewards, not attributed to an
+/// asset, for a later claim.
+fn claim_user_rewards(
+    deps: DepsMut,
+    env: &Env,
+    user_addr: &Addr,
+    collateral_denoms: Option<&[String]>,
+) -> Result<(Uint128, String, Uint128, Vec<String>), ContractError> {
+    let red_bank_addr = query_red_bank_address(deps.as_ref())?;
+    let (total_unclaimed_rewards, user_asset_incentive_statuses_to_update, skipped_denoms) =
+        match collateral_denoms {
+            Some(collateral_denoms) => compute_user_unclaimed_rewards_for_denoms(
+                deps.as_ref(),
+                &env.block,
+                &red_bank_addr,
+                user_addr,
+                collateral_denoms,
+            )?,
+            None => compute_user_unclaimed_rewards(
+                deps.as_ref(),
+                &env.block,
+                &red_bank_addr,
+                user_addr,
+            )?,
+        };
 
     // Commit updated asset_incentives and user indexes
     for user_asset_incentive_status in user_asset_incentive_statuses_to_update {
         let asset_incentive_updated = user_asset_incentive_status.asset_incentive_updated;
 
-        ASSET_INCENTIVES.save(
+        asset_incentives(user_asset_incentive_status.kind).save(
             deps.storage,
             &user_asset_incentive_status.denom,
             &asset_incentive_updated,
         )?;
 
         if asset_incentive_updated.index != user_asset_incentive_status.user_index_current {
-            USER_ASSET_INDICES.save(
+            save_user_asset_index(
                 deps.storage,
-                (&user_addr, &user_asset_incentive_status.denom),
-                &asset_incentive_updated.index,
+                user_addr,
+                &user_asset_incentive_status.denom,
+                user_asset_incentive_status.kind,
+                asset_incentive_updated.index,
+                env.block.time.seconds(),
             )?
         }
     }
 
-    // clear unclaimed rewards
-    USER_UNCLAIMED_REWARDS.save(deps.storage, &user_addr, &Uint128::zero())?;
+    // clear the unclaimed rewards being claimed
+    let stored_unclaimed_rewards =
+        clear_user_unclaimed_rewards(deps.storage, user_addr, collateral_denoms)?;
 
-    let mut response = Response::new();
-    if !total_unclaimed_rewards.is_zero() {
-        let config = CONFIG.load(deps.storage)?;
-        // Build message to send mars to the user
-        response = response.add_message(CosmosMsg::Bank(BankMsg::Send {
-            to_address: user_addr.to_string(),
-            amount: coins(total_unclaimed_rewards.u128(), config.mars_denom),
-        }));
-    };
+    let config = CONFIG.load(deps.storage)?;
+    let current_time = env.block.time.seconds();
 
-    response = response.add_attributes(vec![
-        attr("action", "claim_rewards"),
-        attr("user", user_addr),
-        attr("mars_rewards", total_unclaimed_rewards),
-    ]);
+    let mut released_rewards = total_unclaimed_rewards;
+    if config.vesting_duration > 0 {
+        let accrued_rewards = total_unclaimed_rewards.checked_sub(stored_unclaimed_rewards)?;
+        if !accrued_rewards.is_zero() {
+            add_vesting_tranche(deps.storage, user_addr, accrued_rewards, current_time)?;
+        }
+        released_rewards = stored_unclaimed_rewards;
+    }
 
-    Ok(response)
+    // release the vested portion of each tranche. Tranches left over from a previous vesting
+    // config are released in full once vesting is disabled
+    if let Some(mut tranches) = USER_VESTING.may_load(deps.storage, user_addr)? {
+        for tranche in tranches.iter_mut() {
+            // lengthening the vesting duration doesn't claw back rewards already released
+            let vested = compute_vested_amount(tranche, current_time, config.vesting_duration)
+                .max(tranche.released);
+            released_rewards =
+                released_rewards.checked_add(vested.checked_sub(tranche.released)?)?;
+            tranche.released = vested;
+        }
+
+        tranches.retain(|tranche| tranche.released < tranche.amount);
+        if tranches.is_empty() {
+            USER_VESTING.remove(deps.storage, user_addr);
+        } else {
+            USER_VESTING.save(deps.storage, user_addr, &tranches)?;
+        }
+    }
+
+    let balance = deps.querier.query_balance(&env.contract.address, &config.mars_denom)?.amount;
+    let shortfall = released_rewards.saturating_sub(balance);
+    if !shortfall.is_zero() {
+        let unclaimed_rewards =
+            USER_UNCLAIMED_REWARDS.may_load(deps.storage, user_addr)?.unwrap_or_default();
+        save_user_unclaimed_rewards(
+            deps.storage,
+            user_addr,
+            unclaimed_rewards.checked_add(shortfall)?,
+        )?;
+        released_rewards = balance;
+    }
+
+    if !released_rewards.is_zero() {
+        USER_CLAIMED_TOTALS.update(
+            deps.storage,
+            (user_addr, &config.mars_denom),
+            |total| -> StdResult<_> {
+                Ok(total.unwrap_or_default().checked_add(released_rewards)?)
+            },
+        )?;
+    }
+
+    Ok((released_rewards, config.mars_denom, shortfall, skipped_denoms))
 }
 
 pub fn execute_update_config(
@@ -414,6 +970,8 @@ pub fn execute_update_config(
     info: MessageInfo,
     address_provider: Option<String>,
     mars_denom: Option<String>,
+    vesting_duration: Option<u64>,
+    funder: Option<String>,
 ) -> Result<Response, ContractError> {
     OWNER.assert_owner(deps.storage, &info.sender)?;
 
@@ -426,6 +984,10 @@ pub fn execute_update_config(
     config.address_provider =
         option_string_to_addr(deps.api, address_provider, config.address_provider)?;
     config.mars_denom = mars_denom.unwrap_or(config.mars_denom);
+    config.vesting_duration = vesting_duration.unwrap_or(config.vesting_duration);
+    if let Some(funder) = funder {
+        config.funder = Some(deps.api.addr_validate(&funder)?);
+    }
 
     CONFIG.save(deps.storage, &config)?;
 
@@ -434,6 +996,126 @@ pub fn execute_update_config(
     Ok(response)
 }
 
+pub fn execute_reclaim_unemitted_incentive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collateral_denom: String,
+    incentive_denom: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let funder = config.funder.ok_or(ContractError::FunderNotSet {})?;
+    if info.sender != funder && !OWNER.is_owner(deps.storage, &info.sender)? {
+        return Err(MarsError::Unauthorized {}.into());
+    }
+
+    if incentive_denom != config.mars_denom {
+        return Err(ContractError::InvalidIncentive {
+            reason: format!("no incentives are distributed in {incentive_denom}"),
+        });
+    }
+
+    let mut asset_incentive = ASSET_INCENTIVES.load(deps.storage, &collateral_denom)?;
+
+    // amounts unemitted by a schedule could still be distributed while another one emits
+    let end_time = incentive_end_time(&asset_incentive);
+    if env.block.time.seconds() < end_time {
+        return Err(ContractError::IncentiveNotEnded {
+            denom: collateral_denom,
+            end_time,
+        });
+    }
+
+    // account for the emissions since the last update, up to the end of the incentive
+    let red_bank_addr = query_red_bank_address(deps.as_ref())?;
+    let market: red_bank::Market = deps.querier.query_wasm_smart(
+        red_bank_addr,
+        &red_bank::QueryMsg::Market {
+            denom: collateral_denom.clone(),
+        },
+    )?;
+    update_asset_incentive_index(&mut asset_incentive, market.collateral_total_scaled, &env.block)?;
+
+    let amount = asset_incentive.unemitted;
+    if amount.is_zero() {
+        return Err(ContractError::NothingToReclaim {
+            denom: collateral_denom,
+        });
+    }
+
+    asset_incentive.unemitted = Uint128::zero();
+    ASSET_INCENTIVES.save(deps.storage, &collateral_denom, &asset_incentive)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: funder.to_string(),
+            amount: coins(amount.u128(), &incentive_denom),
+        }))
+        .add_attribute("action", "reclaim_unemitted_incentive")
+        .add_attribute("collateral_denom", collateral_denom)
+        .add_attribute("incentive_denom", incentive_denom)
+        .add_attribute("funder", funder)
+        .add_attribute("amount", amount))
+}
+
+/// Incentives can only be set up or extended in whitelisted denoms
+fn assert_incentive_denom_whitelisted(
+    storage: &dyn Storage,
+    denom: &str,
+) -> Result<(), ContractError> {
+    if !INCENTIVE_DENOM_WHITELIST.load(storage)?.iter().any(|whitelisted| whitelisted == denom) {
+        return Err(ContractError::IncentiveDenomNotWhitelisted {
+            denom: denom.to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub fn execute_update_incentive_denom_whitelist(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    OWNER.assert_owner(deps.storage, &info.sender)?;
+
+    let mut whitelist = INCENTIVE_DENOM_WHITELIST.load(deps.storage)?;
+    for denom in &add {
+        validate_native_denom(denom)?;
+        if !whitelist.contains(denom) {
+            whitelist.push(denom.clone());
+        }
+    }
+    whitelist.retain(|denom| !remove.contains(denom));
+    INCENTIVE_DENOM_WHITELIST.save(deps.storage, &whitelist)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_incentive_denom_whitelist")
+        .add_attribute("added", add.join(","))
+        .add_attribute("removed", remove.join(",")))
+}
+
+pub fn execute_update_compounding_vault(
+    deps: DepsMut,
+    info: MessageInfo,
+    vault: String,
+    registered: bool,
+) -> Result<Response, ContractError> {
+    OWNER.assert_owner(deps.storage, &info.sender)?;
+
+    let vault_addr = deps.api.addr_validate(&vault)?;
+    if registered {
+        COMPOUNDING_VAULTS.save(deps.storage, &vault_addr, &Empty {})?;
+    } else {
+        COMPOUNDING_VAULTS.remove(deps.storage, &vault_addr);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_compounding_vault")
+        .add_attribute("vault", vault_addr)
+        .add_attribute("registered", registered.to_string()))
+}
+
 fn update_owner(
     deps: DepsMut,
     info: MessageInfo,
@@ -442,6 +1124,18 @@ fn update_owner(
     Ok(OWNER.update(deps, info, update)?)
 }
 
+// REPLIES
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
+    match reply.id {
+        REPAY_REPLY_ID => repay_with_swapped_rewards(deps, env),
+        id => Err(ContractError::UnknownReplyId {
+            id,
+        }),
+    }
+}
+
 // QUERIES
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -450,14 +1144,60 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::AssetIncentive {
             denom,
-        } => to_binary(&query_asset_incentive(deps, denom)?),
+            kind,
+        } => to_binary(&query_asset_incentive(deps, denom, kind.unwrap_or_default())?),
         QueryMsg::AssetIncentives {
+            kind,
             start_after,
             limit,
-        } => to_binary(&query_asset_incentives(deps, start_after, limit)?),
+        } => {
+            to_binary(&query_asset_incentives(deps, kind.unwrap_or_default(), start_after, limit)?)
+        }
+        QueryMsg::IncentiveSchedules {
+            collateral_denom,
+            incentive_denom,
+        } => to_binary(&query_incentive_schedules(deps, collateral_denom, incentive_denom)?),
+        QueryMsg::IncentiveDenomWhitelist {} => to_binary(&query_incentive_denom_whitelist(deps)?),
         QueryMsg::UserUnclaimedRewards {
             user,
         } => to_binary(&query_user_unclaimed_rewards(deps, env, user)?),
+        QueryMsg::TotalUnclaimedRewards {} => to_binary(&query_total_unclaimed_rewards(deps)?),
+        QueryMsg::RewardDebugInfo {
+            user,
+            collateral_denom,
+            incentive_denom,
+        } => {
+            to_binary(&query_reward_debug_info(deps, env, user, collateral_denom, incentive_denom)?)
+        }
+        QueryMsg::UserRewardState {
+            user,
+            collateral_denom,
+            incentive_denom,
+        } => {
+            to_binary(&query_user_reward_state(deps, env, user, collateral_denom, incentive_denom)?)
+        }
+        QueryMsg::UserClaimedTotals {
+            user,
+        } => to_binary(&query_user_claimed_totals(deps, user)?),
+        QueryMsg::UserActiveIncentives {
+            user,
+        } => to_binary(&query_user_active_incentives(deps, env, user)?),
+        QueryMsg::UserAssetIndexSetAt {
+            user,
+            denom,
+        } => to_binary(&query_user_asset_index_set_at(deps, user, denom)?),
+        QueryMsg::ProjectedUserRewards {
+            user,
+            future_timestamp,
+        } => to_binary(&query_projected_user_rewards(deps, env, user, future_timestamp)?),
+        QueryMsg::RewardRatePerUnit {
+            collateral_denom,
+            incentive_denom,
+        } => to_binary(&query_reward_rate_per_unit(deps, env, collateral_denom, incentive_denom)?),
+        QueryMsg::AssetIncentiveApr {
+            collateral_denom,
+            incentive_denom,
+        } => to_binary(&query_asset_incentive_apr(deps, env, collateral_denom, incentive_denom)?),
     }
 }
 
@@ -469,39 +1209,389 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         proposed_new_owner: owner_state.proposed,
         address_provider: config.address_provider,
         mars_denom: config.mars_denom,
+        vesting_duration: config.vesting_duration,
+        funder: config.funder,
     })
 }
 
-pub fn query_asset_incentive(deps: Deps, denom: String) -> StdResult<AssetIncentiveResponse> {
-    let asset_incentive = ASSET_INCENTIVES.load(deps.storage, &denom)?;
-    Ok(AssetIncentiveResponse::from(denom, asset_incentive))
+pub fn query_asset_incentive(
+    deps: Deps,
+    denom: String,
+    kind: PositionKind,
+) -> StdResult<AssetIncentiveResponse> {
+    let asset_incentive = asset_incentives(kind).load(deps.storage, &denom)?;
+    Ok(AssetIncentiveResponse::from(denom, kind, asset_incentive))
 }
 
 pub fn query_asset_incentives(
     deps: Deps,
+    kind: PositionKind,
     start_after: Option<String>,
     limit: Option<u32>,
 ) -> StdResult<Vec<AssetIncentiveResponse>> {
     let start = start_after.map(|denom| Bound::ExclusiveRaw(denom.into_bytes()));
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
 
-    ASSET_INCENTIVES
+    asset_incentives(kind)
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
             let (denom, ai) = item?;
-            Ok(AssetIncentiveResponse::from(denom, ai))
+            Ok(AssetIncentiveResponse::from(denom, kind, ai))
         })
         .collect()
 }
 
+pub fn query_incentive_schedules(
+    deps: Deps,
+    collateral_denom: String,
+    incentive_denom: String,
+) -> StdResult<Vec<IncentiveSchedule>> {
+    let config = CONFIG.load(deps.storage)?;
+    if incentive_denom != config.mars_denom {
+        return Err(StdError::generic_err(format!(
+            "no incentives are distributed in {incentive_denom}"
+        )));
+    }
+
+    let asset_incentive = match ASSET_INCENTIVES.may_load(deps.storage, &collateral_denom)? {
+        Some(asset_incentive) => asset_incentive,
+        None => return Ok(vec![]),
+    };
+
+    Ok(incentive_schedules(&asset_incentive))
+}
+
+pub fn query_incentive_denom_whitelist(deps: Deps) -> StdResult<Vec<String>> {
+    INCENTIVE_DENOM_WHITELIST.load(deps.storage)
+}
+
 pub fn query_user_unclaimed_rewards(deps: Deps, env: Env, user: String) -> StdResult<Uint128> {
+    let user_addr = deps.api.addr_validate(&user)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    // with vesting enabled, the rewards accrued since the user's last balance change only start
+    // vesting when claimed, so only the ones already stored as unclaimed are released right away
+    let released_rewards = if config.vesting_duration > 0 {
+        load_user_unclaimed_rewards(deps.storage, &user_addr)?
+    } else {
+        let red_bank_addr = query_red_bank_address(deps)?;
+        let (unclaimed_rewards, _, _) =
+            compute_user_unclaimed_rewards(deps, &env.block, &red_bank_addr, &user_addr)?;
+        unclaimed_rewards
+    };
+    let vested_rewards = compute_user_vested_rewards(
+        deps,
+        &user_addr,
+        env.block.time.seconds(),
+        config.vesting_duration,
+    )?;
+
+    Ok(released_rewards.checked_add(vested_rewards)?)
+}
+
+pub fn query_total_unclaimed_rewards(deps: Deps) -> StdResult<Uint128> {
+    Ok(TOTAL_UNCLAIMED_REWARDS.may_load(deps.storage)?.unwrap_or_default())
+}
+
+pub fn query_projected_user_rewards(
+    deps: Deps,
+    env: Env,
+    user: String,
+    future_timestamp: u64,
+) -> StdResult<Uint128> {
+    let current_time = env.block.time.seconds();
+    if future_timestamp < current_time {
+        return Err(StdError::generic_err(format!(
+            "future timestamp {future_timestamp} is before the current block time {current_time}"
+        )));
+    }
+
+    // accruing up to a block at the future time uses the current supplies and balances, and the
+    // asset indexes are only updated up to the end of each incentive. The future block height
+    // isn't known, so per-block incentives aren't projected
+    let future_block = BlockInfo {
+        time: Timestamp::from_seconds(future_timestamp),
+        ..env.block
+    };
+
+    let red_bank_addr = query_red_bank_address(deps)?;
+    let user_addr = deps.api.addr_validate(&user)?;
+    let (unclaimed_rewards, _, _) =
+        compute_user_unclaimed_rewards(deps, &future_block, &red_bank_addr, &user_addr)?;
+    let vesting_rewards = compute_user_vesting_rewards(deps, &user_addr)?;
+
+    Ok(unclaimed_rewards.checked_add(vesting_rewards)?)
+}
+
+pub fn query_reward_rate_per_unit(
+    deps: Deps,
+    env: Env,
+    collateral_denom: String,
+    incentive_denom: String,
+) -> StdResult<Decimal> {
+    let config = CONFIG.load(deps.storage)?;
+    if incentive_denom != config.mars_denom {
+        return Err(StdError::generic_err(format!(
+            "no incentives are distributed in {incentive_denom}"
+        )));
+    }
+
+    let asset_incentive = match ASSET_INCENTIVES.may_load(deps.storage, &collateral_denom)? {
+        Some(asset_incentive) => asset_incentive,
+        None => return Ok(Decimal::zero()),
+    };
+
+    let emission_per_second =
+        active_emission_per_second(&asset_incentive, env.block.time.seconds())?;
+    if emission_per_second.is_zero() {
+        return Ok(Decimal::zero());
+    }
+
+    let red_bank_addr = query_red_bank_address(deps)?;
+    let market: red_bank::Market = deps.querier.query_wasm_smart(
+        red_bank_addr,
+        &red_bank::QueryMsg::Market {
+            denom: collateral_denom,
+        },
+    )?;
+    if market.collateral_total_scaled.is_zero() {
+        return Ok(Decimal::zero());
+    }
+
+    Ok(Decimal::from_ratio(emission_per_second, market.collateral_total_scaled))
+}
+
+pub fn query_asset_incentive_apr(
+    deps: Deps,
+    env: Env,
+    collateral_denom: String,
+    incentive_denom: String,
+) -> StdResult<Decimal> {
+    let config = CONFIG.load(deps.storage)?;
+    if incentive_denom != config.mars_denom {
+        return Err(StdError::generic_err(format!(
+            "no incentives are distributed in {incentive_denom}"
+        )));
+    }
+
+    let asset_incentive = match ASSET_INCENTIVES.may_load(deps.storage, &collateral_denom)? {
+        Some(asset_incentive) => asset_incentive,
+        None => return Ok(Decimal::zero()),
+    };
+
+    let emission_per_second =
+        active_emission_per_second(&asset_incentive, env.block.time.seconds())?;
+    if emission_per_second.is_zero() {
+        return Ok(Decimal::zero());
+    }
+
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps,
+        &config.address_provider,
+        vec![MarsAddressType::RedBank, MarsAddressType::Oracle],
+    )?;
+    let red_bank_addr = &addresses[&MarsAddressType::RedBank];
+    let oracle_addr = &addresses[&MarsAddressType::Oracle];
+
+    let market: red_bank::Market = deps.querier.query_wasm_smart(
+        red_bank_addr,
+        &red_bank::QueryMsg::Market {
+            denom: collateral_denom.clone(),
+        },
+    )?;
+    if market.collateral_total_scaled.is_zero() {
+        return Ok(Decimal::zero());
+    }
+    let total_collateral: Uint128 = deps.querier.query_wasm_smart(
+        red_bank_addr,
+        &red_bank::QueryMsg::UnderlyingLiquidityAmount {
+            denom: collateral_denom.clone(),
+            amount_scaled: market.collateral_total_scaled,
+        },
+    )?;
+
+    let collateral_price =
+        oracle::helpers::query_price(&deps.querier, oracle_addr, &collateral_denom)?;
+    if total_collateral.is_zero() || collateral_price.is_zero() {
+        return Ok(Decimal::zero());
+    }
+    let incentive_price =
+        oracle::helpers::query_price(&deps.querier, oracle_addr, incentive_denom)?;
+
+    // value of a year of emissions per unit of collateral, in units of the collateral's price
+    let emission_per_year = emission_per_second.checked_mul(Uint128::from(SECONDS_PER_YEAR))?;
+    let emission_value_per_unit =
+        Decimal::from_ratio(emission_per_year, total_collateral).checked_mul(incentive_price)?;
+    math::divide_decimal_by_decimal(emission_value_per_unit, collateral_price)
+}
+
+pub fn query_user_claimed_totals(deps: Deps, user: String) -> StdResult<Vec<Coin>> {
+    let user_addr = deps.api.addr_validate(&user)?;
+
+    USER_CLAIMED_TOTALS
+        .prefix(&user_addr)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, amount) = item?;
+            Ok(coin(amount.u128(), denom))
+        })
+        .collect()
+}
+
+pub fn query_user_asset_index_set_at(
+    deps: Deps,
+    user: String,
+    denom: String,
+) -> StdResult<Option<u64>> {
+    let user_addr = deps.api.addr_validate(&user)?;
+    USER_ASSET_INDEX_SET_AT.may_load(deps.storage, (&user_addr, &denom))
+}
+
+pub fn query_user_active_incentives(
+    deps: Deps,
+    env: Env,
+    user: String,
+) -> StdResult<Vec<UserActiveIncentiveResponse>> {
+    let config = CONFIG.load(deps.storage)?;
     let red_bank_addr = query_red_bank_address(deps)?;
     let user_addr = deps.api.addr_validate(&user)?;
-    let (unclaimed_rewards, _) =
-        compute_user_unclaimed_rewards(deps, &env.block, &red_bank_addr, &user_addr)?;
+    let current_time = env.block.time.seconds();
+
+    let mut active_incentives = vec![];
+    for kind in [PositionKind::Collateral, PositionKind::Debt] {
+        let incentives = asset_incentives(kind)
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for (denom, asset_incentive) in incentives {
+            let schedules = active_incentive_schedules(&asset_incentive, current_time)
+                .into_iter()
+                .filter(|schedule| !schedule.emission.is_zero())
+                .collect::<Vec<_>>();
+            if schedules.is_empty() {
+                continue;
+            }
 
-    Ok(unclaimed_rewards)
+            let earning = match kind {
+                PositionKind::Collateral => {
+                    let collateral: red_bank::UserCollateralResponse =
+                        deps.querier.query_wasm_smart(
+                            &red_bank_addr,
+                            &red_bank::QueryMsg::UserCollateral {
+                                user: user_addr.to_string(),
+                                denom: denom.clone(),
+                            },
+                        )?;
+                    collateral.enabled && !collateral.amount_scaled.is_zero()
+                }
+                PositionKind::Debt => {
+                    let (debt_amount_scaled, _) = query_user_and_total_amount_scaled(
+                        deps,
+                        &red_bank_addr,
+                        &user_addr,
+                        &denom,
+                        kind,
+                    )?;
+                    !debt_amount_scaled.is_zero()
+                }
+            };
+            if !earning {
+                continue;
+            }
+
+            active_incentives.extend(schedules.into_iter().map(|schedule| {
+                UserActiveIncentiveResponse {
+                    collateral_denom: denom.clone(),
+                    kind,
+                    incentive_denom: config.mars_denom.clone(),
+                    emission: schedule.emission,
+                }
+            }));
+        }
+    }
+
+    Ok(active_incentives)
+}
+
+pub fn query_reward_debug_info(
+    deps: Deps,
+    env: Env,
+    user: String,
+    collateral_denom: String,
+    incentive_denom: String,
+) -> StdResult<RewardDebugInfoResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    if incentive_denom != config.mars_denom {
+        return Err(StdError::generic_err(format!(
+            "no incentives are distributed in {incentive_denom}"
+        )));
+    }
+
+    let red_bank_addr = query_red_bank_address(deps)?;
+    let user_addr = deps.api.addr_validate(&user)?;
+
+    let collateral: red_bank::UserCollateralResponse = deps.querier.query_wasm_smart(
+        &red_bank_addr,
+        &red_bank::QueryMsg::UserCollateral {
+            user: user_addr.to_string(),
+            denom: collateral_denom.clone(),
+        },
+    )?;
+
+    // same update as the one applied on a balance change or claim, without committing it
+    let asset_index_now = match ASSET_INCENTIVES.may_load(deps.storage, &collateral_denom)? {
+        Some(mut asset_incentive) => {
+            let market: red_bank::Market = deps.querier.query_wasm_smart(
+                &red_bank_addr,
+                &red_bank::QueryMsg::Market {
+                    denom: collateral_denom.clone(),
+                },
+            )?;
+            update_asset_incentive_index(
+                &mut asset_incentive,
+                market.collateral_total_scaled,
+                &env.block,
+            )?;
+            asset_incentive.index
+        }
+        None => Decimal::zero(),
+    };
+
+    let user_index_stored = USER_ASSET_INDICES
+        .may_load(deps.storage, (&user_addr, &collateral_denom))?
+        .unwrap_or_else(Decimal::zero);
+
+    let accrued_since =
+        compute_user_accrued_rewards(collateral.amount_scaled, user_index_stored, asset_index_now)?;
+
+    Ok(RewardDebugInfoResponse {
+        asset_index_now,
+        user_index_stored,
+        user_collateral_scaled: collateral.amount_scaled,
+        accrued_since,
+    })
+}
+
+pub fn query_user_reward_state(
+    deps: Deps,
+    env: Env,
+    user: String,
+    collateral_denom: String,
+    incentive_denom: String,
+) -> StdResult<UserRewardStateResponse> {
+    let user_addr = deps.api.addr_validate(&user)?;
+    let unclaimed = USER_ASSET_UNCLAIMED_REWARDS
+        .may_load(deps.storage, (&user_addr, &collateral_denom))?
+        .unwrap_or_default();
+    let debug_info = query_reward_debug_info(deps, env, user, collateral_denom, incentive_denom)?;
+
+    Ok(UserRewardStateResponse {
+        user_index: debug_info.user_index_stored,
+        asset_index: debug_info.asset_index_now,
+        unclaimed,
+        pending: debug_info.accrued_since,
+    })
 }
 
 fn query_red_bank_address(deps: Deps) -> StdResult<Addr> {
@@ -512,3 +1602,10 @@ fn query_red_bank_address(deps: Deps) -> StdResult<Addr> {
         MarsAddressType::RedBank,
     )
 }
+
+// MIGRATION
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: Empty) -> Result<Response, ContractError> {
+    migrations::v1_0_1::migrate(deps, env)
+}
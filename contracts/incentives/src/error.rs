@@ -1,6 +1,6 @@
 use std::string::FromUtf8Error;
 
-use cosmwasm_std::StdError;
+use cosmwasm_std::{OverflowError, StdError};
 use mars_owner::OwnerError;
 use mars_red_bank_types::error::MarsError;
 use mars_utils::error::ValidationError;
@@ -17,9 +17,15 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
     #[error("{0}")]
     FromUtf8(#[from] FromUtf8Error),
 
+    #[error("{0}")]
+    Version(#[from] cw2::VersionError),
+
     #[error("{0}")]
     Owner(#[from] OwnerError),
 
@@ -27,4 +33,28 @@ pub enum ContractError {
     InvalidIncentive {
         reason: String,
     },
+
+    #[error("Incentives can't be set up in {denom}, as it isn't whitelisted")]
+    IncentiveDenomNotWhitelisted {
+        denom: String,
+    },
+
+    #[error("No funder is set to refund unemitted incentives to")]
+    FunderNotSet {},
+
+    #[error("Incentive for {denom} can't be reclaimed before it ends at {end_time}")]
+    IncentiveNotEnded {
+        denom: String,
+        end_time: u64,
+    },
+
+    #[error("No unemitted incentive to reclaim for {denom}")]
+    NothingToReclaim {
+        denom: String,
+    },
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId {
+        id: u64,
+    },
 }
@@ -2,44 +2,160 @@ use std::cmp::{max, min};
 
 use cosmwasm_std::{
     Addr, BlockInfo, Decimal, Deps, Order, OverflowError, OverflowOperation, StdError, StdResult,
-    Uint128,
+    Storage, Uint128,
+};
+use mars_red_bank_types::{
+    incentives::{AssetIncentive, Emission, IncentiveSchedule, PositionKind, VestingTranche},
+    red_bank,
 };
-use mars_red_bank_types::{incentives::AssetIncentive, red_bank};
 
-use crate::state::{ASSET_INCENTIVES, USER_ASSET_INDICES, USER_UNCLAIMED_REWARDS};
+use crate::state::{
+    asset_incentives, user_asset_indices, user_asset_unclaimed_rewards,
+    USER_ASSET_UNCLAIMED_REWARDS, USER_UNCLAIMED_REWARDS, USER_VESTING,
+};
 
-/// Updates asset incentive index and last updated timestamp by computing
-/// how many rewards were accrued since last time updated given incentive's
-/// emission per second.
-/// Total supply is the total (liquidity) token supply during the period being computed.
+/// Updates asset incentive index and last updated timestamp and height by computing
+/// how many rewards were accrued since last time updated given the emission per second or per
+/// block of each of the incentive's schedules.
+/// Total supply is the total (liquidity) token supply during the period being computed. If it's
+/// zero, the rewards emitted over the period can't be distributed and are added to the incentive's
+/// unemitted amount instead. They are distributed along with the rewards of the first later period
+/// with a non zero supply in which the incentive emits, so a gap in supply doesn't forfeit them.
+/// Amounts still unemitted once the incentive ends are left for the funder to reclaim.
 /// Note that this method does not commit updates to state as that should be executed by the
 /// caller
 pub fn update_asset_incentive_index(
     asset_incentive: &mut AssetIncentive,
     total_amount_scaled: Uint128,
-    current_block_time: u64,
+    block: &BlockInfo,
 ) -> StdResult<()> {
-    let end_time_sec = asset_incentive.start_time + asset_incentive.duration;
-    if (current_block_time != asset_incentive.last_updated)
-        && current_block_time > asset_incentive.start_time
-        && asset_incentive.last_updated < end_time_sec
-        && !total_amount_scaled.is_zero()
-        && !asset_incentive.emission_per_second.is_zero()
-    {
-        let time_start = max(asset_incentive.start_time, asset_incentive.last_updated);
-        let time_end = min(current_block_time, end_time_sec);
-        asset_incentive.index = compute_asset_incentive_index(
-            asset_incentive.index,
-            asset_incentive.emission_per_second,
-            total_amount_scaled,
-            time_start,
-            time_end,
-        )?;
+    let mut emitted = compute_emitted_since_last_update(
+        asset_incentive,
+        &asset_incentive.emission,
+        asset_incentive.start_time,
+        asset_incentive.duration,
+        block,
+    )?;
+    for schedule in &asset_incentive.schedules {
+        emitted = emitted.checked_add(compute_emitted_since_last_update(
+            asset_incentive,
+            &schedule.emission,
+            schedule.start_time,
+            schedule.duration,
+            block,
+        )?)?;
+    }
+    if total_amount_scaled.is_zero() {
+        asset_incentive.unemitted = asset_incentive.unemitted.checked_add(emitted)?;
+    } else if !emitted.is_zero() {
+        let distributed = emitted.checked_add(asset_incentive.unemitted)?;
+        asset_incentive.index =
+            asset_incentive.index + Decimal::from_ratio(distributed, total_amount_scaled);
+        asset_incentive.unemitted = Uint128::zero();
     }
-    asset_incentive.last_updated = current_block_time;
+    asset_incentive.last_updated = block.time.seconds();
+    asset_incentive.last_updated_height = block.height;
     Ok(())
 }
 
+/// Computes how much a schedule of an asset incentive emitted since the incentive was last updated
+fn compute_emitted_since_last_update(
+    asset_incentive: &AssetIncentive,
+    emission: &Emission,
+    start_time: u64,
+    duration: u64,
+    block: &BlockInfo,
+) -> StdResult<Uint128> {
+    let current_block_time = block.time.seconds();
+    let end_time_sec = start_time + duration;
+    let is_emitting =
+        current_block_time > start_time && asset_incentive.last_updated < end_time_sec;
+    let time_start = max(start_time, asset_incentive.last_updated);
+    let time_end = min(current_block_time, end_time_sec);
+    let seconds_in_incentive = || {
+        time_end.checked_sub(time_start).ok_or_else(|| {
+            StdError::overflow(OverflowError::new(OverflowOperation::Sub, time_start, time_end))
+        })
+    };
+    let emitted = match emission {
+        Emission::PerSecond(emission_per_second)
+            if is_emitting && current_block_time != asset_incentive.last_updated =>
+        {
+            emission_per_second.checked_mul(Uint128::from(seconds_in_incentive()?))?
+        }
+        Emission::PerBlock(emission_per_block)
+            if is_emitting && block.height != asset_incentive.last_updated_height =>
+        {
+            // the incentive starts and ends at a time rather than at a height, so when only part
+            // of the period since the last update falls within it, the blocks are prorated by time
+            let seconds_in_incentive = seconds_in_incentive()?;
+            let blocks_elapsed =
+                Uint128::from(block.height.saturating_sub(asset_incentive.last_updated_height));
+            let seconds_elapsed = current_block_time - asset_incentive.last_updated;
+            let blocks_in_incentive = if seconds_elapsed == 0 {
+                blocks_elapsed
+            } else {
+                blocks_elapsed.multiply_ratio(seconds_in_incentive, seconds_elapsed)
+            };
+            emission_per_block.checked_mul(blocks_in_incentive)?
+        }
+        _ => Uint128::zero(),
+    };
+    Ok(emitted)
+}
+
+/// Returns all the emission schedules of an asset incentive: its main schedule, with id 0, followed
+/// by the additional ones
+pub fn incentive_schedules(asset_incentive: &AssetIncentive) -> Vec<IncentiveSchedule> {
+    let main_schedule = IncentiveSchedule {
+        id: 0,
+        emission: asset_incentive.emission.clone(),
+        start_time: asset_incentive.start_time,
+        duration: asset_incentive.duration,
+    };
+    std::iter::once(main_schedule).chain(asset_incentive.schedules.iter().cloned()).collect()
+}
+
+/// Returns the schedules of an asset incentive emitting at the given time
+pub fn active_incentive_schedules(
+    asset_incentive: &AssetIncentive,
+    current_time: u64,
+) -> Vec<IncentiveSchedule> {
+    incentive_schedules(asset_incentive)
+        .into_iter()
+        .filter(|schedule| {
+            current_time >= schedule.start_time
+                && current_time < schedule.start_time + schedule.duration
+        })
+        .collect()
+}
+
+/// Returns the time at which the last of the schedules of an asset incentive ends
+pub fn incentive_end_time(asset_incentive: &AssetIncentive) -> u64 {
+    incentive_schedules(asset_incentive)
+        .iter()
+        .map(|schedule| schedule.start_time + schedule.duration)
+        .max()
+        .unwrap_or_default()
+}
+
+/// Sums the emissions per second of the schedules of an asset incentive emitting at the given
+/// time. Fails if one of them emits per block, as the time blocks take isn't known
+pub fn active_emission_per_second(
+    asset_incentive: &AssetIncentive,
+    current_time: u64,
+) -> StdResult<Uint128> {
+    active_incentive_schedules(asset_incentive, current_time).iter().try_fold(
+        Uint128::zero(),
+        |total, schedule| match schedule.emission {
+            Emission::PerSecond(amount) => Ok(total.checked_add(amount)?),
+            Emission::PerBlock(_) => Err(StdError::generic_err(
+                "the emission of an incentive emitting per block can't be given per second",
+            )),
+        },
+    )
+}
+
 pub fn compute_asset_incentive_index(
     previous_index: Decimal,
     emission_per_second: Uint128,
@@ -75,11 +191,73 @@ pub fn compute_user_accrued_rewards(
     Ok(result)
 }
 
+/// Computes the amount of a tranche vested by `current_time`, following the formula documented on
+/// `VestingTranche`. If vesting is disabled, the whole tranche is vested.
+pub fn compute_vested_amount(
+    tranche: &VestingTranche,
+    current_time: u64,
+    vesting_duration: u64,
+) -> Uint128 {
+    let elapsed = current_time.saturating_sub(tranche.accrued_at);
+    if vesting_duration == 0 || elapsed >= vesting_duration {
+        return tranche.amount;
+    }
+    tranche.amount.multiply_ratio(elapsed, vesting_duration)
+}
+
+/// Computes the rewards accrued by the user that are still vesting or vested but not yet released
+pub fn compute_user_vesting_rewards(deps: Deps, user_addr: &Addr) -> StdResult<Uint128> {
+    USER_VESTING.may_load(deps.storage, user_addr)?.unwrap_or_default().iter().try_fold(
+        Uint128::zero(),
+        |total, tranche| -> StdResult<_> {
+            Ok(total.checked_add(tranche.amount.checked_sub(tranche.released)?)?)
+        },
+    )
+}
+
+/// Computes the vested rewards of the user's tranches that are not yet released, i.e. the part of
+/// their vesting rewards a claim at `current_time` would release
+pub fn compute_user_vested_rewards(
+    deps: Deps,
+    user_addr: &Addr,
+    current_time: u64,
+    vesting_duration: u64,
+) -> StdResult<Uint128> {
+    USER_VESTING.may_load(deps.storage, user_addr)?.unwrap_or_default().iter().try_fold(
+        Uint128::zero(),
+        |total, tranche| -> StdResult<_> {
+            let vested = compute_vested_amount(tranche, current_time, vesting_duration)
+                .max(tranche.released);
+            Ok(total.checked_add(vested.checked_sub(tranche.released)?)?)
+        },
+    )
+}
+
+/// Load all the rewards stored as unclaimed for the user, i.e. the ones not attributed to an asset
+/// and those set aside on the incentives of each asset
+pub fn load_user_unclaimed_rewards(storage: &dyn Storage, user_addr: &Addr) -> StdResult<Uint128> {
+    let mut total = USER_UNCLAIMED_REWARDS.may_load(storage, user_addr)?.unwrap_or_default();
+    for kind in [PositionKind::Collateral, PositionKind::Debt] {
+        for item in user_asset_unclaimed_rewards(kind).prefix(user_addr).range(
+            storage,
+            None,
+            None,
+            Order::Ascending,
+        ) {
+            let (_, unclaimed_rewards) = item?;
+            total = total.checked_add(unclaimed_rewards)?;
+        }
+    }
+    Ok(total)
+}
+
 /// Result of querying and updating the status of the user and a give asset incentives in order to
 /// compute unclaimed rewards.
 pub struct UserAssetIncentiveStatus {
     /// Denom of the asset that's the incentives target
     pub denom: String,
+    /// Kind of position the incentive is distributed to
+    pub kind: PositionKind,
     /// Current user index's value on the contract store (not updated by current asset index)
     pub user_index_current: Decimal,
     /// Asset incentive with values updated to the current block (not neccesarily commited
@@ -87,69 +265,181 @@ pub struct UserAssetIncentiveStatus {
     pub asset_incentive_updated: AssetIncentive,
 }
 
+/// Compute the user's unclaimed rewards along with the asset incentive statuses to commit, across
+/// the incentives of both collateral and debt positions.
+///
+/// Assets whose balances can't be queried from the Red Bank are skipped so that a single broken
+/// market doesn't prevent the user from claiming the rewards of the others. Their denoms are
+/// returned; rewards accrued on them are left in place, to be computed once they can be queried.
 pub fn compute_user_unclaimed_rewards(
     deps: Deps,
     block: &BlockInfo,
     red_bank_addr: &Addr,
     user_addr: &Addr,
-) -> StdResult<(Uint128, Vec<UserAssetIncentiveStatus>)> {
-    let mut total_unclaimed_rewards =
-        USER_UNCLAIMED_REWARDS.may_load(deps.storage, user_addr)?.unwrap_or_else(Uint128::zero);
+) -> StdResult<(Uint128, Vec<UserAssetIncentiveStatus>, Vec<String>)> {
+    let mut incentives = vec![];
+    for kind in [PositionKind::Collateral, PositionKind::Debt] {
+        for item in asset_incentives(kind).range(deps.storage, None, None, Order::Ascending) {
+            let (denom, asset_incentive) = item?;
+            incentives.push((kind, denom, asset_incentive));
+        }
+    }
+
+    let (accrued_rewards, user_asset_incentive_statuses_to_update, skipped_denoms) =
+        compute_user_accrued_rewards_on(deps, block, red_bank_addr, user_addr, incentives)?;
+
+    let stored_unclaimed_rewards = load_user_unclaimed_rewards(deps.storage, user_addr)?;
+    let total_unclaimed_rewards = stored_unclaimed_rewards.checked_add(accrued_rewards)?;
+
+    Ok((total_unclaimed_rewards, user_asset_incentive_statuses_to_update, skipped_denoms))
+}
+
+/// Compute the user's unclaimed rewards on the collateral incentives of the given denoms only, along
+/// with the asset incentive statuses to commit. Each denom is only counted once.
+///
+/// Rewards stored as unclaimed that aren't attributed to an asset are not included.
+pub fn compute_user_unclaimed_rewards_for_denoms(
+    deps: Deps,
+    block: &BlockInfo,
+    red_bank_addr: &Addr,
+    user_addr: &Addr,
+    collateral_denoms: &[String],
+) -> StdResult<(Uint128, Vec<UserAssetIncentiveStatus>, Vec<String>)> {
+    let mut denoms = collateral_denoms.to_vec();
+    denoms.sort();
+    denoms.dedup();
 
-    let result_asset_incentives: StdResult<Vec<_>> =
-        ASSET_INCENTIVES.range(deps.storage, None, None, Order::Ascending).collect();
+    let mut stored_unclaimed_rewards = Uint128::zero();
+    let mut incentives = vec![];
+    for denom in denoms {
+        stored_unclaimed_rewards = stored_unclaimed_rewards.checked_add(
+            USER_ASSET_UNCLAIMED_REWARDS
+                .may_load(deps.storage, (user_addr, &denom))?
+                .unwrap_or_default(),
+        )?;
+        if let Some(asset_incentive) =
+            asset_incentives(PositionKind::Collateral).may_load(deps.storage, &denom)?
+        {
+            incentives.push((PositionKind::Collateral, denom, asset_incentive));
+        }
+    }
 
+    let (accrued_rewards, user_asset_incentive_statuses_to_update, skipped_denoms) =
+        compute_user_accrued_rewards_on(deps, block, red_bank_addr, user_addr, incentives)?;
+    let total_unclaimed_rewards = stored_unclaimed_rewards.checked_add(accrued_rewards)?;
+
+    Ok((total_unclaimed_rewards, user_asset_incentive_statuses_to_update, skipped_denoms))
+}
+
+/// Compute the rewards the user accrued on each of the given incentives since their user index was
+/// last updated. Assets whose balances can't be queried are skipped and their denoms returned.
+fn compute_user_accrued_rewards_on(
+    deps: Deps,
+    block: &BlockInfo,
+    red_bank_addr: &Addr,
+    user_addr: &Addr,
+    incentives: Vec<(PositionKind, String, AssetIncentive)>,
+) -> StdResult<(Uint128, Vec<UserAssetIncentiveStatus>, Vec<String>)> {
+    let mut total_accrued_rewards = Uint128::zero();
     let mut user_asset_incentive_statuses_to_update: Vec<UserAssetIncentiveStatus> = vec![];
+    let mut skipped_denoms = vec![];
 
-    for (denom, mut asset_incentive) in result_asset_incentives? {
+    for (kind, denom, mut asset_incentive) in incentives {
         // Get asset user balances and total supply
-        let collateral: red_bank::UserCollateralResponse = deps.querier.query_wasm_smart(
-            red_bank_addr,
-            &red_bank::QueryMsg::UserCollateral {
-                user: user_addr.to_string(),
-                denom: denom.clone(),
-            },
-        )?;
-        let market: red_bank::Market = deps.querier.query_wasm_smart(
+        let (user_amount_scaled, total_amount_scaled) = match query_user_and_total_amount_scaled(
+            deps,
             red_bank_addr,
-            &red_bank::QueryMsg::Market {
-                denom: denom.clone(),
-            },
-        )?;
+            user_addr,
+            &denom,
+            kind,
+        ) {
+            Ok(res) => res,
+            Err(_) => {
+                if !skipped_denoms.contains(&denom) {
+                    skipped_denoms.push(denom);
+                }
+                continue;
+            }
+        };
 
         // If user's balance is 0 there should be no rewards to accrue, so we don't care about
-        // updating indexes. If the user's balance changes, the indexes will be updated correctly at
-        // that point in time.
-        if collateral.amount_scaled.is_zero() {
+        // updating indexes. If the user's balance changes, the indexes will be updated
+        // correctly at that point in time.
+        if user_amount_scaled.is_zero() {
             continue;
         }
 
-        update_asset_incentive_index(
-            &mut asset_incentive,
-            market.collateral_total_scaled,
-            block.time.seconds(),
-        )?;
+        update_asset_incentive_index(&mut asset_incentive, total_amount_scaled, block)?;
 
-        let user_asset_index = USER_ASSET_INDICES
+        let user_asset_index = user_asset_indices(kind)
             .may_load(deps.storage, (user_addr, &denom))?
             .unwrap_or_else(Decimal::zero);
 
         if user_asset_index != asset_incentive.index {
             // Compute user accrued rewards and update user index
             let asset_accrued_rewards = compute_user_accrued_rewards(
-                collateral.amount_scaled,
+                user_amount_scaled,
                 user_asset_index,
                 asset_incentive.index,
             )?;
-            total_unclaimed_rewards += asset_accrued_rewards;
+            total_accrued_rewards += asset_accrued_rewards;
         }
 
         user_asset_incentive_statuses_to_update.push(UserAssetIncentiveStatus {
             denom,
+            kind,
             user_index_current: user_asset_index,
             asset_incentive_updated: asset_incentive,
         });
     }
 
-    Ok((total_unclaimed_rewards, user_asset_incentive_statuses_to_update))
+    Ok((total_accrued_rewards, user_asset_incentive_statuses_to_update, skipped_denoms))
+}
+
+/// Query the user's scaled amount of a position and the market's total scaled amount of that kind
+/// of position from the Red Bank.
+///
+/// Debt that doesn't accrue interest isn't part of the market's total debt, so it doesn't earn
+/// incentives and is reported as zero.
+pub fn query_user_and_total_amount_scaled(
+    deps: Deps,
+    red_bank_addr: &Addr,
+    user_addr: &Addr,
+    denom: &str,
+    kind: PositionKind,
+) -> StdResult<(Uint128, Uint128)> {
+    let market: red_bank::Market = deps.querier.query_wasm_smart(
+        red_bank_addr,
+        &red_bank::QueryMsg::Market {
+            denom: denom.to_string(),
+        },
+    )?;
+    match kind {
+        PositionKind::Collateral => {
+            let collateral: red_bank::UserCollateralResponse = deps.querier.query_wasm_smart(
+                red_bank_addr,
+                &red_bank::QueryMsg::UserCollateral {
+                    user: user_addr.to_string(),
+                    denom: denom.to_string(),
+                },
+            )?;
+            Ok((collateral.amount_scaled, market.collateral_total_scaled))
+        }
+        PositionKind::Debt => {
+            let debt: red_bank::UserDebtResponse = deps.querier.query_wasm_smart(
+                red_bank_addr,
+                &red_bank::QueryMsg::UserDebt {
+                    user: user_addr.to_string(),
+                    denom: denom.to_string(),
+                },
+            )?;
+            let amount_scaled =
+                if debt.uncollateralized && !market.uncollateralized_debt_accrues_interest {
+                    Uint128::zero()
+                } else {
+                    debt.amount_scaled
+                };
+            Ok((amount_scaled, market.debt_total_scaled))
+        }
+    }
 }
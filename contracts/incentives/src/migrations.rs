@@ -0,0 +1,211 @@
+/// Migration logic for Incentives contract with version: 1.0.1
+pub mod v1_0_1 {
+    use cosmwasm_std::{DepsMut, Env, Order, Response, StdResult, Uint128};
+    use mars_red_bank_types::incentives::{AssetIncentive, Config, Emission};
+
+    use crate::{
+        contract::{CONTRACT_NAME, CONTRACT_VERSION},
+        state::{
+            ASSET_INCENTIVES, CONFIG, INCENTIVE_DENOM_WHITELIST, TOTAL_UNCLAIMED_REWARDS,
+            USER_UNCLAIMED_REWARDS,
+        },
+        ContractError,
+    };
+
+    const FROM_VERSION: &str = "1.0.1";
+
+    pub fn migrate(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+        // make sure we're migrating the correct contract and from the correct version
+        cw2::assert_contract_version(deps.as_ref().storage, CONTRACT_NAME, FROM_VERSION)?;
+
+        // existing incentives keep emitting per second. The height they were last updated at
+        // isn't known, but it's only used by per-block incentives. Emissions that couldn't be
+        // distributed before the migration weren't tracked, so none can be reclaimed. No
+        // schedules run alongside the existing ones yet
+        let old_asset_incentives = old_state::ASSET_INCENTIVES
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (denom, old_asset_incentive) in old_asset_incentives {
+            let asset_incentive = AssetIncentive {
+                emission: Emission::PerSecond(old_asset_incentive.emission_per_second),
+                start_time: old_asset_incentive.start_time,
+                duration: old_asset_incentive.duration,
+                index: old_asset_incentive.index,
+                last_updated: old_asset_incentive.last_updated,
+                last_updated_height: env.block.height,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
+            };
+            // the new incentive is stored under the same key, replacing the old one
+            ASSET_INCENTIVES.save(deps.storage, &denom, &asset_incentive)?;
+        }
+
+        // the total of unclaimed rewards wasn't tracked before, so it's summed up from all users
+        let total_unclaimed_rewards = USER_UNCLAIMED_REWARDS
+            .range(deps.storage, None, None, Order::Ascending)
+            .try_fold(Uint128::zero(), |total, item| -> StdResult<_> {
+                let (_, unclaimed_rewards) = item?;
+                Ok(total.checked_add(unclaimed_rewards)?)
+            })?;
+        TOTAL_UNCLAIMED_REWARDS.save(deps.storage, &total_unclaimed_rewards)?;
+
+        // vesting is disabled and no funder is set until configured by the owner
+        let old_config = old_state::CONFIG.load(deps.storage)?;
+        let config = Config {
+            address_provider: old_config.address_provider,
+            mars_denom: old_config.mars_denom,
+            vesting_duration: 0,
+            funder: None,
+        };
+        CONFIG.save(deps.storage, &config)?;
+
+        // incentives have only been set up in MARS so far
+        INCENTIVE_DENOM_WHITELIST.save(deps.storage, &vec![config.mars_denom])?;
+
+        // update contract version
+        cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", FROM_VERSION)
+            .add_attribute("to_version", CONTRACT_VERSION))
+    }
+
+    pub mod old_state {
+        use cosmwasm_schema::cw_serde;
+        use cosmwasm_std::{Addr, Decimal, Uint128};
+        use cw_storage_plus::{Item, Map};
+
+        pub const CONFIG: Item<Config> = Item::new("config");
+        pub const ASSET_INCENTIVES: Map<&str, AssetIncentive> = Map::new("incentives");
+
+        #[cw_serde]
+        pub struct Config {
+            pub address_provider: Addr,
+            pub mars_denom: String,
+        }
+
+        /// Asset incentive emitting a fixed amount per second
+        #[cw_serde]
+        pub struct AssetIncentive {
+            pub emission_per_second: Uint128,
+            pub start_time: u64,
+            pub duration: u64,
+            pub index: Decimal,
+            pub last_updated: u64,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use cosmwasm_std::{
+            attr,
+            testing::{mock_dependencies, mock_env},
+            Addr, Decimal, Uint128,
+        };
+
+        use super::*;
+
+        fn save_old_config(deps: DepsMut) {
+            old_state::CONFIG
+                .save(
+                    deps.storage,
+                    &old_state::Config {
+                        address_provider: Addr::unchecked("address_provider"),
+                        mars_denom: "umars".to_string(),
+                    },
+                )
+                .unwrap();
+        }
+
+        #[test]
+        fn migrating_asset_incentives_as_per_second() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+
+            cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, FROM_VERSION).unwrap();
+            save_old_config(deps.as_mut());
+            old_state::ASSET_INCENTIVES
+                .save(
+                    deps.as_mut().storage,
+                    "uosmo",
+                    &old_state::AssetIncentive {
+                        emission_per_second: Uint128::new(100),
+                        start_time: 1_000_000,
+                        duration: 86400,
+                        index: Decimal::percent(150),
+                        last_updated: 1_050_000,
+                    },
+                )
+                .unwrap();
+
+            let res = migrate(deps.as_mut(), env.clone()).unwrap();
+            assert_eq!(res.messages, vec![]);
+            assert_eq!(
+                res.attributes,
+                vec![
+                    attr("action", "migrate"),
+                    attr("from_version", "1.0.1"),
+                    attr("to_version", "1.1.0")
+                ]
+            );
+
+            let asset_incentive = ASSET_INCENTIVES.load(&deps.storage, "uosmo").unwrap();
+            assert_eq!(
+                asset_incentive,
+                AssetIncentive {
+                    emission: Emission::PerSecond(Uint128::new(100)),
+                    start_time: 1_000_000,
+                    duration: 86400,
+                    index: Decimal::percent(150),
+                    last_updated: 1_050_000,
+                    last_updated_height: env.block.height,
+                    unemitted: Uint128::zero(),
+                    schedules: vec![],
+                }
+            );
+        }
+
+        #[test]
+        fn migrating_recomputes_total_unclaimed_rewards() {
+            let mut deps = mock_dependencies();
+
+            cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, FROM_VERSION).unwrap();
+            save_old_config(deps.as_mut());
+            USER_UNCLAIMED_REWARDS
+                .save(deps.as_mut().storage, &Addr::unchecked("alice"), &Uint128::new(1_200))
+                .unwrap();
+            USER_UNCLAIMED_REWARDS
+                .save(deps.as_mut().storage, &Addr::unchecked("bob"), &Uint128::new(345))
+                .unwrap();
+
+            migrate(deps.as_mut(), mock_env()).unwrap();
+
+            let total = TOTAL_UNCLAIMED_REWARDS.load(&deps.storage).unwrap();
+            assert_eq!(total, Uint128::new(1_545));
+        }
+
+        #[test]
+        fn migrating_config_and_whitelisting_mars() {
+            let mut deps = mock_dependencies();
+
+            cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, FROM_VERSION).unwrap();
+            save_old_config(deps.as_mut());
+
+            migrate(deps.as_mut(), mock_env()).unwrap();
+
+            let config = CONFIG.load(&deps.storage).unwrap();
+            assert_eq!(
+                config,
+                Config {
+                    address_provider: Addr::unchecked("address_provider"),
+                    mars_denom: "umars".to_string(),
+                    vesting_duration: 0,
+                    funder: None,
+                }
+            );
+            let whitelist = INCENTIVE_DENOM_WHITELIST.load(&deps.storage).unwrap();
+            assert_eq!(whitelist, vec!["umars".to_string()]);
+        }
+    }
+}
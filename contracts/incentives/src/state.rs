@@ -1,13 +1,64 @@
-use cosmwasm_std::{Addr, Decimal, Uint128};
+use cosmwasm_std::{Addr, Decimal, Empty, Uint128};
 use cw_storage_plus::{Item, Map};
 use mars_owner::Owner;
-use mars_red_bank_types::incentives::{AssetIncentive, Config};
+use mars_red_bank_types::incentives::{AssetIncentive, Config, PositionKind, VestingTranche};
 
 // keys (for singleton)
 pub const OWNER: Owner = Owner::new("owner");
 pub const CONFIG: Item<Config> = Item::new("config");
+/// Denoms incentives can be set up in
+pub const INCENTIVE_DENOM_WHITELIST: Item<Vec<String>> = Item::new("incentive_denom_whitelist");
 
 // namespaces (for buckets)
 pub const ASSET_INCENTIVES: Map<&str, AssetIncentive> = Map::new("incentives");
 pub const USER_ASSET_INDICES: Map<(&Addr, &str), Decimal> = Map::new("indices");
+pub const DEBT_ASSET_INCENTIVES: Map<&str, AssetIncentive> = Map::new("debt_incentives");
+pub const USER_DEBT_ASSET_INDICES: Map<(&Addr, &str), Decimal> = Map::new("debt_indices");
+/// Time (in seconds) at which a user's index for an asset's collateral incentive was first set,
+/// i.e. when they started accruing it
+pub const USER_ASSET_INDEX_SET_AT: Map<(&Addr, &str), u64> = Map::new("indices_set_at");
+/// Rewards set aside for a user that aren't attributed to an asset, i.e. stored before unclaimed
+/// rewards were tracked per asset, or held back because a claim exceeded the contract's balance.
+/// They are only released by a claim across all assets
 pub const USER_UNCLAIMED_REWARDS: Map<&Addr, Uint128> = Map::new("unclaimed_rewards");
+/// Rewards accrued by a user on an asset's collateral incentive, set aside on a balance change
+pub const USER_ASSET_UNCLAIMED_REWARDS: Map<(&Addr, &str), Uint128> =
+    Map::new("asset_unclaimed_rewards");
+/// Rewards accrued by a user on an asset's debt incentive, set aside on a balance change
+pub const USER_DEBT_ASSET_UNCLAIMED_REWARDS: Map<(&Addr, &str), Uint128> =
+    Map::new("debt_asset_unclaimed_rewards");
+/// Sum of all users' unclaimed rewards, kept in sync with `USER_UNCLAIMED_REWARDS` and the
+/// unclaimed rewards of each asset
+pub const TOTAL_UNCLAIMED_REWARDS: Item<Uint128> = Item::new("total_unclaimed_rewards");
+pub const USER_VESTING: Map<&Addr, Vec<VestingTranche>> = Map::new("vesting");
+pub const COMPOUNDING_VAULTS: Map<&Addr, Empty> = Map::new("compounding_vaults");
+pub const USER_CLAIMED_TOTALS: Map<(&Addr, &str), Uint128> = Map::new("claimed_totals");
+/// User, debt denom and the contract's balance of it before swapping the user's rewards into it to
+/// repay their debt
+pub const PENDING_REPAY: Item<(Addr, String, Uint128)> = Item::new("pending_repay");
+
+/// Asset incentives distributed to the given kind of position
+pub fn asset_incentives<'a>(kind: PositionKind) -> Map<'a, &'a str, AssetIncentive> {
+    match kind {
+        PositionKind::Collateral => ASSET_INCENTIVES,
+        PositionKind::Debt => DEBT_ASSET_INCENTIVES,
+    }
+}
+
+/// User indices of the asset incentives distributed to the given kind of position
+pub fn user_asset_indices<'a>(kind: PositionKind) -> Map<'a, (&'a Addr, &'a str), Decimal> {
+    match kind {
+        PositionKind::Collateral => USER_ASSET_INDICES,
+        PositionKind::Debt => USER_DEBT_ASSET_INDICES,
+    }
+}
+
+/// User unclaimed rewards of the asset incentives distributed to the given kind of position
+pub fn user_asset_unclaimed_rewards<'a>(
+    kind: PositionKind,
+) -> Map<'a, (&'a Addr, &'a str), Uint128> {
+    match kind {
+        PositionKind::Collateral => USER_ASSET_UNCLAIMED_REWARDS,
+        PositionKind::Debt => USER_DEBT_ASSET_UNCLAIMED_REWARDS,
+    }
+}
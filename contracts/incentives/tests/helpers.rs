@@ -2,7 +2,7 @@
 
 use cosmwasm_schema::serde;
 use cosmwasm_std::{
-    from_binary,
+    coins, from_binary,
     testing::{mock_env, mock_info, MockApi, MockStorage},
     Deps, Env, OwnedDeps,
 };
@@ -15,7 +15,8 @@ pub fn th_setup() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
 }
 
 pub fn th_setup_with_env(env: Env) -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
-    let mut deps = mock_dependencies(&[]);
+    // fund the contract with enough MARS to pay out any rewards
+    let mut deps = mock_dependencies(&coins(1_000_000_000_000, "umars"));
 
     let msg = InstantiateMsg {
         owner: String::from("owner"),
@@ -35,6 +35,8 @@ fn proper_initialization() {
     assert_eq!(config.proposed_new_owner, None);
     assert_eq!(config.address_provider, "address_provider".to_string());
     assert_eq!(config.mars_denom, "umars".to_string());
+    assert_eq!(config.vesting_duration, 0);
+    assert_eq!(config.funder, None);
 }
 
 #[test]
@@ -47,6 +49,8 @@ fn update_config() {
     let msg = ExecuteMsg::UpdateConfig {
         address_provider: None,
         mars_denom: None,
+        vesting_duration: None,
+        funder: None,
     };
     let info = mock_info("somebody", &[]);
     let error_res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
@@ -58,6 +62,8 @@ fn update_config() {
     let msg = ExecuteMsg::UpdateConfig {
         address_provider: None,
         mars_denom: Some("*!fdskfna".to_string()),
+        vesting_duration: None,
+        funder: None,
     };
     let info = mock_info("owner", &[]);
 
@@ -75,6 +81,8 @@ fn update_config() {
     let msg = ExecuteMsg::UpdateConfig {
         address_provider: Some("new_addr_provider".to_string()),
         mars_denom: None,
+        vesting_duration: Some(86400),
+        funder: Some("funder".to_string()),
     };
     let info = mock_info("owner", &[]);
 
@@ -87,4 +95,6 @@ fn update_config() {
     assert_eq!(new_config.proposed_new_owner, None);
     assert_eq!(new_config.address_provider, Addr::unchecked("new_addr_provider"));
     assert_eq!(new_config.mars_denom, "umars".to_string());
+    assert_eq!(new_config.vesting_duration, 86400);
+    assert_eq!(new_config.funder, Some(Addr::unchecked("funder")));
 }
@@ -1,17 +1,20 @@
 use cosmwasm_std::{
-    attr,
+    attr, from_binary,
     testing::{mock_env, mock_info},
-    Addr, Decimal, Response, Timestamp, Uint128,
+    Addr, Decimal, Deps, Response, Timestamp, Uint128,
 };
 use mars_incentives::{
-    contract::{execute, execute_balance_change, query_user_unclaimed_rewards},
+    contract::{execute, execute_balance_change, query, query_user_unclaimed_rewards},
     helpers::{compute_asset_incentive_index, compute_user_accrued_rewards},
-    state::{ASSET_INCENTIVES, USER_ASSET_INDICES, USER_UNCLAIMED_REWARDS},
+    state::{
+        ASSET_INCENTIVES, DEBT_ASSET_INCENTIVES, USER_ASSET_INDICES, USER_ASSET_UNCLAIMED_REWARDS,
+        USER_DEBT_ASSET_INDICES,
+    },
 };
 use mars_red_bank_types::{
     error::MarsError,
-    incentives::{AssetIncentive, ExecuteMsg},
-    red_bank::{Market, UserCollateralResponse},
+    incentives::{AssetIncentive, Emission, ExecuteMsg, PositionKind, QueryMsg},
+    red_bank::{Market, UserCollateralResponse, UserDebtResponse},
 };
 use mars_testing::MockEnvParams;
 
@@ -33,6 +36,7 @@ fn balance_change_unauthorized() {
             denom: "uosmo".to_string(),
             user_amount_scaled_before: Uint128::new(100000),
             total_amount_scaled_before: Uint128::new(100000),
+            kind: None,
         },
     )
     .unwrap_err();
@@ -50,6 +54,7 @@ fn execute_balance_change_noops() {
         denom: "uosmo".to_string(),
         user_amount_scaled_before: Uint128::new(100000),
         total_amount_scaled_before: Uint128::new(100000),
+        kind: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -69,11 +74,14 @@ fn balance_change_zero_emission() {
             deps.as_mut().storage,
             denom,
             &AssetIncentive {
-                emission_per_second: Uint128::zero(),
+                emission: Emission::PerSecond(Uint128::zero()),
                 start_time: env.block.time.seconds(),
                 duration: 86400,
                 index: asset_incentive_index,
                 last_updated: 500_000,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
             },
         )
         .unwrap();
@@ -88,6 +96,7 @@ fn balance_change_zero_emission() {
         denom: "uosmo".to_string(),
         user_amount_scaled_before: Uint128::new(100_000),
         total_amount_scaled_before: Uint128::new(100_000),
+        kind: None,
     };
 
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
@@ -119,7 +128,7 @@ fn balance_change_zero_emission() {
 
     // rewards get updated
     let user_unclaimed_rewards =
-        USER_UNCLAIMED_REWARDS.load(deps.as_ref().storage, &user_addr).unwrap();
+        USER_ASSET_UNCLAIMED_REWARDS.load(deps.as_ref().storage, (&user_addr, denom)).unwrap();
     assert_eq!(user_unclaimed_rewards, expected_accrued_rewards)
 }
 
@@ -142,11 +151,14 @@ fn balance_change_user_with_zero_balance() {
             deps.as_mut().storage,
             denom,
             &AssetIncentive {
-                emission_per_second,
+                emission: Emission::PerSecond(emission_per_second),
                 start_time: time_last_updated,
                 duration,
                 index: start_index,
                 last_updated: time_last_updated,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
             },
         )
         .unwrap();
@@ -161,6 +173,7 @@ fn balance_change_user_with_zero_balance() {
         denom: "uosmo".to_string(),
         user_amount_scaled_before: Uint128::zero(),
         total_amount_scaled_before: total_supply,
+        kind: None,
     };
 
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
@@ -197,7 +210,7 @@ fn balance_change_user_with_zero_balance() {
 
     // no new rewards
     let user_unclaimed_rewards =
-        USER_UNCLAIMED_REWARDS.may_load(deps.as_ref().storage, &user_addr).unwrap();
+        USER_ASSET_UNCLAIMED_REWARDS.may_load(deps.as_ref().storage, (&user_addr, denom)).unwrap();
     assert_eq!(user_unclaimed_rewards, None)
 }
 
@@ -218,11 +231,14 @@ fn with_zero_previous_balance_and_asset_with_zero_index_accumulates_rewards() {
             deps.as_mut().storage,
             denom,
             &AssetIncentive {
-                emission_per_second,
+                emission: Emission::PerSecond(emission_per_second),
                 start_time: time_last_updated,
                 duration: 8640000,
                 index: start_index,
                 last_updated: time_last_updated,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
             },
         )
         .unwrap();
@@ -238,6 +254,7 @@ fn with_zero_previous_balance_and_asset_with_zero_index_accumulates_rewards() {
             denom: "uosmo".to_string(),
             user_amount_scaled_before: Uint128::zero(),
             total_amount_scaled_before: Uint128::zero(),
+            kind: None,
         };
         // Execute balance changed, this is the first mint of the asset, so previous total
         // supply and user balance is 0
@@ -309,11 +326,14 @@ fn set_new_asset_incentive_user_non_zero_balance() {
                 deps.as_mut().storage,
                 denom,
                 &AssetIncentive {
-                    emission_per_second,
+                    emission: Emission::PerSecond(emission_per_second),
                     start_time: time_last_updated,
                     duration: 8640000,
                     index: asset_incentive_index,
                     last_updated: time_last_updated,
+                    last_updated_height: 0,
+                    unemitted: Uint128::zero(),
+                    schedules: vec![],
                 },
             )
             .unwrap();
@@ -409,11 +429,14 @@ fn balance_change_user_non_zero_balance() {
             deps.as_mut().storage,
             denom,
             &AssetIncentive {
-                emission_per_second,
+                emission: Emission::PerSecond(emission_per_second),
                 start_time: expected_time_last_updated,
                 duration: 8640000,
                 index: expected_asset_incentive_index,
                 last_updated: expected_time_last_updated,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
             },
         )
         .unwrap();
@@ -434,6 +457,7 @@ fn balance_change_user_non_zero_balance() {
             denom: "uosmo".to_string(),
             user_amount_scaled_before: user_balance,
             total_amount_scaled_before: total_supply,
+            kind: None,
         };
         let res = execute(deps.as_mut(), env, info.clone(), msg).unwrap();
 
@@ -477,7 +501,7 @@ fn balance_change_user_non_zero_balance() {
 
         // user gets new rewards
         let user_unclaimed_rewards =
-            USER_UNCLAIMED_REWARDS.load(deps.as_ref().storage, &user_addr).unwrap();
+            USER_ASSET_UNCLAIMED_REWARDS.load(deps.as_ref().storage, (&user_addr, denom)).unwrap();
         expected_accumulated_rewards += expected_accrued_rewards;
         assert_eq!(user_unclaimed_rewards, expected_accumulated_rewards)
     }
@@ -496,6 +520,7 @@ fn balance_change_user_non_zero_balance() {
             denom: "uosmo".to_string(),
             user_amount_scaled_before: user_balance,
             total_amount_scaled_before: total_supply,
+            kind: None,
         };
         let res = execute(deps.as_mut(), env, info.clone(), msg).unwrap();
 
@@ -540,7 +565,7 @@ fn balance_change_user_non_zero_balance() {
 
         // user gets new rewards
         let user_unclaimed_rewards =
-            USER_UNCLAIMED_REWARDS.load(deps.as_ref().storage, &user_addr).unwrap();
+            USER_ASSET_UNCLAIMED_REWARDS.load(deps.as_ref().storage, (&user_addr, denom)).unwrap();
         expected_accumulated_rewards += expected_accrued_rewards;
         assert_eq!(user_unclaimed_rewards, expected_accumulated_rewards)
     }
@@ -559,6 +584,7 @@ fn balance_change_user_non_zero_balance() {
             denom: "uosmo".to_string(),
             user_amount_scaled_before: user_balance,
             total_amount_scaled_before: total_supply,
+            kind: None,
         };
         let res = execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -585,7 +611,250 @@ fn balance_change_user_non_zero_balance() {
 
         // user gets no new rewards
         let user_unclaimed_rewards =
-            USER_UNCLAIMED_REWARDS.load(deps.as_ref().storage, &user_addr).unwrap();
+            USER_ASSET_UNCLAIMED_REWARDS.load(deps.as_ref().storage, (&user_addr, denom)).unwrap();
         assert_eq!(user_unclaimed_rewards, expected_accumulated_rewards)
     }
 }
+
+#[test]
+fn balance_change_records_when_user_index_is_first_set() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &AssetIncentive {
+                emission: Emission::PerSecond(Uint128::new(100)),
+                start_time: 0,
+                duration: 86400 * 10,
+                index: Decimal::zero(),
+                last_updated: 0,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
+            },
+        )
+        .unwrap();
+
+    let query_set_at = |deps: Deps| -> Option<u64> {
+        from_binary(
+            &query(
+                deps,
+                mock_env(),
+                QueryMsg::UserAssetIndexSetAt {
+                    user: "user".to_string(),
+                    denom: "uosmo".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    assert_eq!(query_set_at(deps.as_ref()), None);
+
+    for (block_time, user_amount_scaled_before) in [
+        (100_000, Uint128::zero()),
+        (200_000, Uint128::new(50_000)),
+        (300_000, Uint128::new(80_000)),
+    ] {
+        let env = mars_testing::mock_env(MockEnvParams {
+            block_time: Timestamp::from_seconds(block_time),
+            ..Default::default()
+        });
+        let msg = ExecuteMsg::BalanceChange {
+            user_addr: user_addr.clone(),
+            denom: "uosmo".to_string(),
+            user_amount_scaled_before,
+            total_amount_scaled_before: Uint128::new(100_000),
+            kind: None,
+        };
+        execute(deps.as_mut(), env, mock_info("red_bank", &[]), msg).unwrap();
+
+        // the time of the first accrual is kept even though the user index keeps being updated
+        let user_asset_index =
+            USER_ASSET_INDICES.load(deps.as_ref().storage, (&user_addr, "uosmo")).unwrap();
+        let asset_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
+        assert_eq!(user_asset_index, asset_incentive.index);
+        assert_eq!(query_set_at(deps.as_ref()), Some(100_000));
+    }
+}
+
+#[test]
+fn debt_balance_change_is_tracked_apart_from_collateral() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    // the same denom is incentivized for both lenders and borrowers
+    for (incentives, emission) in [(ASSET_INCENTIVES, 100), (DEBT_ASSET_INCENTIVES, 50)] {
+        incentives
+            .save(
+                deps.as_mut().storage,
+                "uosmo",
+                &AssetIncentive {
+                    emission: Emission::PerSecond(Uint128::new(emission)),
+                    start_time: 0,
+                    duration: 8640000,
+                    index: Decimal::zero(),
+                    last_updated: 100_000,
+                    last_updated_height: 0,
+                    unemitted: Uint128::zero(),
+                    schedules: vec![],
+                },
+            )
+            .unwrap();
+    }
+
+    // the user's first borrowing only moves the debt index
+    let env = mars_testing::mock_env(MockEnvParams {
+        block_time: Timestamp::from_seconds(101_000),
+        ..Default::default()
+    });
+    let msg = ExecuteMsg::BalanceChange {
+        user_addr: user_addr.clone(),
+        denom: "uosmo".to_string(),
+        user_amount_scaled_before: Uint128::zero(),
+        total_amount_scaled_before: Uint128::new(50_000),
+        kind: Some(PositionKind::Debt),
+    };
+    execute(deps.as_mut(), env, mock_info("red_bank", &[]), msg).unwrap();
+
+    let debt_incentive = DEBT_ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
+    assert_eq!(debt_incentive.index, Decimal::one());
+    let user_debt_index =
+        USER_DEBT_ASSET_INDICES.load(deps.as_ref().storage, (&user_addr, "uosmo")).unwrap();
+    assert_eq!(user_debt_index, Decimal::one());
+
+    let collateral_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
+    assert_eq!(collateral_incentive.index, Decimal::zero());
+    assert_eq!(collateral_incentive.last_updated, 100_000);
+    let user_collateral_index =
+        USER_ASSET_INDICES.may_load(deps.as_ref().storage, (&user_addr, "uosmo")).unwrap();
+    assert_eq!(user_collateral_index, None);
+
+    // rewards of both positions add up
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: Uint128::new(100_000),
+        debt_total_scaled: Uint128::new(50_000),
+        ..Default::default()
+    });
+    deps.querier.set_red_bank_user_collateral(
+        &user_addr,
+        UserCollateralResponse {
+            denom: "uosmo".to_string(),
+            amount_scaled: Uint128::new(100_000),
+            amount: Uint128::zero(), // doesn't matter for this test
+            enabled: true,
+        },
+    );
+    deps.querier.set_red_bank_user_debt(
+        &user_addr,
+        UserDebtResponse {
+            denom: "uosmo".to_string(),
+            amount_scaled: Uint128::new(50_000),
+            amount: Uint128::zero(), // doesn't matter for this test
+            uncollateralized: false,
+        },
+    );
+    let env = mars_testing::mock_env(MockEnvParams {
+        block_time: Timestamp::from_seconds(102_000),
+        ..Default::default()
+    });
+    let rewards = query_user_unclaimed_rewards(deps.as_ref(), env, user_addr.to_string()).unwrap();
+
+    // collateral: 2000 seconds * 100 per second, all of the market's collateral being the user's
+    // debt: 1000 seconds * 50 per second since the user's index was set
+    assert_eq!(rewards, Uint128::new(250_000));
+}
+
+#[test]
+fn late_deposit_does_not_dilute_prior_holders() {
+    let mut deps = th_setup();
+    let env_at = |seconds: u64| {
+        mars_testing::mock_env(MockEnvParams {
+            block_time: Timestamp::from_seconds(seconds),
+            ..Default::default()
+        })
+    };
+
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &AssetIncentive {
+                emission: Emission::PerSecond(Uint128::new(10)),
+                start_time: 1_000_000,
+                duration: 86400,
+                index: Decimal::zero(),
+                last_updated: 1_000_000,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
+            },
+        )
+        .unwrap();
+
+    // alice is the only depositor when the incentive starts
+    let balance_change =
+        |user: &str, user_before: u128, total_before: u128| ExecuteMsg::BalanceChange {
+            user_addr: Addr::unchecked(user),
+            denom: "uosmo".to_string(),
+            user_amount_scaled_before: Uint128::new(user_before),
+            total_amount_scaled_before: Uint128::new(total_before),
+            kind: None,
+        };
+    execute(
+        deps.as_mut(),
+        env_at(1_000_000),
+        mock_info("red_bank", &[]),
+        balance_change("alice", 0, 0),
+    )
+    .unwrap();
+
+    // bob deposits nine times as much after 1000 seconds. The Red Bank reports the total from
+    // before his deposit, so the elapsed window is distributed over alice's deposit only
+    execute(
+        deps.as_mut(),
+        env_at(1_001_000),
+        mock_info("red_bank", &[]),
+        balance_change("bob", 0, 100),
+    )
+    .unwrap();
+
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: Uint128::new(1_000),
+        ..Default::default()
+    });
+    for (user, amount_scaled) in [("alice", 100u128), ("bob", 900)] {
+        deps.querier.set_red_bank_user_collateral(
+            &Addr::unchecked(user),
+            UserCollateralResponse {
+                denom: "uosmo".to_string(),
+                amount_scaled: Uint128::new(amount_scaled),
+                amount: Uint128::zero(), // doesn't matter for this test
+                enabled: true,
+            },
+        );
+    }
+
+    // all the rewards emitted before bob's deposit go to alice
+    let alice_rewards =
+        query_user_unclaimed_rewards(deps.as_ref(), env_at(1_001_000), "alice".to_string())
+            .unwrap();
+    assert_eq!(alice_rewards, Uint128::new(10_000));
+    let bob_rewards =
+        query_user_unclaimed_rewards(deps.as_ref(), env_at(1_001_000), "bob".to_string()).unwrap();
+    assert_eq!(bob_rewards, Uint128::zero());
+
+    // afterwards, rewards are shared pro rata
+    let alice_rewards =
+        query_user_unclaimed_rewards(deps.as_ref(), env_at(1_002_000), "alice".to_string())
+            .unwrap();
+    assert_eq!(alice_rewards, Uint128::new(11_000));
+    let bob_rewards =
+        query_user_unclaimed_rewards(deps.as_ref(), env_at(1_002_000), "bob".to_string()).unwrap();
+    assert_eq!(bob_rewards, Uint128::new(9_000));
+}
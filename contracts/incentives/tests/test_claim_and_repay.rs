@@ -0,0 +1,173 @@
+use cosmwasm_std::{
+    attr, coin, coins,
+    testing::{mock_env, mock_info},
+    to_binary, Addr, BankMsg, CosmosMsg, Empty, Reply, SubMsg, SubMsgResponse, SubMsgResult,
+    Uint128, WasmMsg,
+};
+use mars_incentives::{
+    contract::{execute, reply, REPAY_REPLY_ID},
+    state::USER_UNCLAIMED_REWARDS,
+};
+use mars_red_bank_types::{
+    incentives::ExecuteMsg,
+    red_bank::{self, UserDebtResponse},
+    rewards_collector,
+};
+
+use crate::helpers::th_setup;
+
+mod helpers;
+
+#[test]
+fn claim_and_repay_same_denom() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    USER_UNCLAIMED_REWARDS.save(deps.as_mut().storage, &user_addr, &Uint128::new(1_000)).unwrap();
+    deps.querier.set_red_bank_user_debt(
+        &user_addr,
+        UserDebtResponse {
+            denom: "umars".to_string(),
+            amount_scaled: Uint128::zero(), // doesn't matter for this test
+            amount: Uint128::new(600),
+            uncollateralized: false,
+        },
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[]),
+        ExecuteMsg::ClaimAndRepay {
+            debt_denom: "umars".to_string(),
+        },
+    )
+    .unwrap();
+
+    // the debt is fully repaid and the rest is sent to the user
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: "red_bank".to_string(),
+                msg: to_binary(&red_bank::ExecuteMsg::Repay {
+                    on_behalf_of: Some("user".to_string()),
+                })
+                .unwrap(),
+                funds: coins(600, "umars"),
+            }),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "user".to_string(),
+                amount: coins(400, "umars"),
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_and_repay"),
+            attr("user", "user"),
+            attr("mars_rewards", "1000"),
+            attr("debt_denom", "umars"),
+            attr("repaid_amount", "600"),
+        ]
+    );
+}
+
+#[test]
+fn claim_and_repay_with_swap() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    USER_UNCLAIMED_REWARDS.save(deps.as_mut().storage, &user_addr, &Uint128::new(1_000)).unwrap();
+    deps.querier.set_red_bank_user_debt(
+        &user_addr,
+        UserDebtResponse {
+            denom: "uusdc".to_string(),
+            amount_scaled: Uint128::zero(), // doesn't matter for this test
+            amount: Uint128::new(300),
+            uncollateralized: false,
+        },
+    );
+    deps.querier.set_contract_balances(&[coin(1_000_000, "umars"), coin(50, "uusdc")]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[]),
+        ExecuteMsg::ClaimAndRepay {
+            debt_denom: "uusdc".to_string(),
+        },
+    )
+    .unwrap();
+
+    // the rewards are swapped into the debt denom by the rewards collector first
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: "rewards_collector".to_string(),
+                msg: to_binary(&rewards_collector::ExecuteMsg::<Empty>::SwapFunds {
+                    denom_out: "uusdc".to_string(),
+                })
+                .unwrap(),
+                funds: coins(1_000, "umars"),
+            },
+            REPAY_REPLY_ID
+        )]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_and_repay"),
+            attr("user", "user"),
+            attr("mars_rewards", "1000"),
+            attr("debt_denom", "uusdc"),
+        ]
+    );
+
+    // the swap yields 500 uusdc on top of the 50 uusdc the contract already held
+    deps.querier.set_contract_balances(&[coin(999_000, "umars"), coin(550, "uusdc")]);
+
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: REPAY_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+
+    // only the swap output is used: the debt is fully repaid and the rest is sent to the user
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: "red_bank".to_string(),
+                msg: to_binary(&red_bank::ExecuteMsg::Repay {
+                    on_behalf_of: Some("user".to_string()),
+                })
+                .unwrap(),
+                funds: coins(300, "uusdc"),
+            }),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "user".to_string(),
+                amount: coins(200, "uusdc"),
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_with_swapped_rewards"),
+            attr("user", "user"),
+            attr("debt_denom", "uusdc"),
+            attr("swapped_amount", "500"),
+            attr("repaid_amount", "300"),
+        ]
+    );
+}
@@ -1,15 +1,23 @@
 use cosmwasm_std::{
     attr, coins,
     testing::{mock_env, mock_info},
-    Addr, BankMsg, CosmosMsg, Decimal, SubMsg, Timestamp, Uint128,
+    to_binary, Addr, BankMsg, CosmosMsg, Decimal, SubMsg, Timestamp, Uint128, WasmMsg,
 };
 use mars_incentives::{
-    contract::{execute, query_user_unclaimed_rewards},
+    contract::{
+        execute, query_total_unclaimed_rewards, query_user_claimed_totals,
+        query_user_unclaimed_rewards,
+    },
+    error::ContractError,
     helpers::{compute_asset_incentive_index, compute_user_accrued_rewards},
-    state::{ASSET_INCENTIVES, USER_ASSET_INDICES, USER_UNCLAIMED_REWARDS},
+    state::{
+        ASSET_INCENTIVES, USER_ASSET_INDICES, USER_ASSET_UNCLAIMED_REWARDS, USER_UNCLAIMED_REWARDS,
+        USER_VESTING,
+    },
 };
+use mars_owner::OwnerError::NotOwner;
 use mars_red_bank_types::{
-    incentives::{AssetIncentive, ExecuteMsg},
+    incentives::{AssetIncentive, Emission, ExecuteMsg, VaultCallbackMsg},
     red_bank::{Market, UserCollateralResponse},
 };
 use mars_testing::MockEnvParams;
@@ -93,11 +101,14 @@ fn execute_claim_rewards() {
             deps.as_mut().storage,
             asset_denom,
             &AssetIncentive {
-                emission_per_second: Uint128::new(100),
+                emission: Emission::PerSecond(Uint128::new(100)),
                 start_time: time_start,
                 duration: 8640000,
                 index: Decimal::one(),
                 last_updated: time_start,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
             },
         )
         .unwrap();
@@ -106,11 +117,14 @@ fn execute_claim_rewards() {
             deps.as_mut().storage,
             zero_denom,
             &AssetIncentive {
-                emission_per_second: Uint128::zero(),
+                emission: Emission::PerSecond(Uint128::zero()),
                 start_time: env.block.time.seconds(),
                 duration: 86400,
                 index: Decimal::one(),
                 last_updated: time_start,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
             },
         )
         .unwrap();
@@ -119,11 +133,14 @@ fn execute_claim_rewards() {
             deps.as_mut().storage,
             no_user_denom,
             &AssetIncentive {
-                emission_per_second: Uint128::new(200),
+                emission: Emission::PerSecond(Uint128::new(200)),
                 start_time: env.block.time.seconds(),
                 duration: 86400,
                 index: Decimal::one(),
                 last_updated: time_start,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
             },
         )
         .unwrap();
@@ -174,7 +191,9 @@ fn execute_claim_rewards() {
         block_time: Timestamp::from_seconds(time_contract_call),
         ..Default::default()
     });
-    let msg = ExecuteMsg::ClaimRewards {};
+    let msg = ExecuteMsg::ClaimRewards {
+        recipient: None,
+    };
 
     // query a bit before gives less rewards
     let env_before = mars_testing::mock_env(MockEnvParams {
@@ -212,6 +231,7 @@ fn execute_claim_rewards() {
         vec![
             attr("action", "claim_rewards"),
             attr("user", "user"),
+            attr("recipient", "user"),
             attr("mars_rewards", expected_accrued_rewards),
         ]
     );
@@ -255,12 +275,614 @@ fn claim_zero_rewards() {
     let mut deps = th_setup();
 
     let info = mock_info("user", &[]);
-    let msg = ExecuteMsg::ClaimRewards {};
+    let msg = ExecuteMsg::ClaimRewards {
+        recipient: None,
+    };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(res.messages.len(), 0);
     assert_eq!(
         res.attributes,
-        vec![attr("action", "claim_rewards"), attr("user", "user"), attr("mars_rewards", "0"),]
+        vec![
+            attr("action", "claim_rewards"),
+            attr("user", "user"),
+            attr("recipient", "user"),
+            attr("mars_rewards", "0"),
+        ]
+    );
+}
+
+#[test]
+fn claim_rewards_to_recipient() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    USER_UNCLAIMED_REWARDS.save(deps.as_mut().storage, &user_addr, &Uint128::new(1_000)).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[]),
+        ExecuteMsg::ClaimRewards {
+            recipient: Some("friend".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "friend".to_string(),
+            amount: coins(1_000, "umars"),
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_rewards"),
+            attr("user", "user"),
+            attr("recipient", "friend"),
+            attr("mars_rewards", "1000"),
+        ]
+    );
+
+    // the rewards are claimed from the sender's balance
+    let user_unclaimed_rewards =
+        USER_UNCLAIMED_REWARDS.load(deps.as_ref().storage, &user_addr).unwrap();
+    assert_eq!(user_unclaimed_rewards, Uint128::zero());
+}
+
+#[test]
+fn total_unclaimed_rewards_follows_balance_changes_and_claims() {
+    let mut deps = th_setup();
+
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: Uint128::new(120_000),
+        ..Default::default()
+    });
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &AssetIncentive {
+                emission: Emission::PerSecond(Uint128::zero()),
+                start_time: 0,
+                duration: 86400,
+                index: Decimal::from_ratio(1_u128, 2_u128),
+                last_updated: 0,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
+            },
+        )
+        .unwrap();
+    assert_eq!(query_total_unclaimed_rewards(deps.as_ref()).unwrap(), Uint128::zero());
+
+    // rewards accrued on balance changes are added to the total
+    for (user, user_amount_scaled_before) in [("alice", 100_000u128), ("bob", 20_000)] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("red_bank", &[]),
+            ExecuteMsg::BalanceChange {
+                user_addr: Addr::unchecked(user),
+                denom: "uosmo".to_string(),
+                user_amount_scaled_before: Uint128::new(user_amount_scaled_before),
+                total_amount_scaled_before: Uint128::new(120_000),
+                kind: None,
+            },
+        )
+        .unwrap();
+    }
+    assert_eq!(query_total_unclaimed_rewards(deps.as_ref()).unwrap(), Uint128::new(60_000));
+
+    // claimed rewards are removed from the total
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::ClaimRewards {
+            recipient: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(query_total_unclaimed_rewards(deps.as_ref()).unwrap(), Uint128::new(10_000));
+}
+
+#[test]
+fn claimed_totals_accumulate() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    // nothing claimed yet
+    let totals = query_user_claimed_totals(deps.as_ref(), user_addr.to_string()).unwrap();
+    assert!(totals.is_empty());
+
+    for unclaimed_rewards in [1_000u128, 2_500] {
+        USER_UNCLAIMED_REWARDS
+            .save(deps.as_mut().storage, &user_addr, &Uint128::new(unclaimed_rewards))
+            .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("user", &[]),
+            ExecuteMsg::ClaimRewards {
+                recipient: None,
+            },
+        )
+        .unwrap();
+    }
+
+    let totals = query_user_claimed_totals(deps.as_ref(), user_addr.to_string()).unwrap();
+    assert_eq!(totals, coins(3_500, "umars"));
+
+    // claiming nothing doesn't change the total
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[]),
+        ExecuteMsg::ClaimRewards {
+            recipient: None,
+        },
+    )
+    .unwrap();
+    let totals = query_user_claimed_totals(deps.as_ref(), user_addr.to_string()).unwrap();
+    assert_eq!(totals, coins(3_500, "umars"));
+}
+
+#[test]
+fn claiming_rewards_notifies_compounding_vault() {
+    let mut deps = th_setup();
+    let vault_addr = Addr::unchecked("vault");
+
+    let register_msg = |registered: bool| ExecuteMsg::UpdateCompoundingVault {
+        vault: vault_addr.to_string(),
+        registered,
+    };
+
+    // only the owner can register vaults
+    let err = execute(deps.as_mut(), mock_env(), mock_info("somebody", &[]), register_msg(true))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Owner(NotOwner {}));
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), register_msg(true)).unwrap();
+
+    USER_UNCLAIMED_REWARDS.save(deps.as_mut().storage, &vault_addr, &Uint128::new(1_234)).unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("vault", &[]),
+        ExecuteMsg::ClaimRewards {
+            recipient: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: vault_addr.to_string(),
+                amount: coins(1_234, "umars"),
+            })),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: vault_addr.to_string(),
+                msg: to_binary(&VaultCallbackMsg::RewardsClaimed {
+                    rewards: coins(1_234, "umars"),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    // once deregistered, the vault only receives the rewards
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), register_msg(false)).unwrap();
+
+    USER_UNCLAIMED_REWARDS.save(deps.as_mut().storage, &vault_addr, &Uint128::new(100)).unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("vault", &[]),
+        ExecuteMsg::ClaimRewards {
+            recipient: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: vault_addr.to_string(),
+            amount: coins(100, "umars"),
+        }))]
+    );
+}
+
+#[test]
+fn claiming_vesting_rewards() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+    let env_at = |seconds: u64| {
+        mars_testing::mock_env(MockEnvParams {
+            block_time: Timestamp::from_seconds(seconds),
+            ..Default::default()
+        })
+    };
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            address_provider: None,
+            mars_denom: None,
+            vesting_duration: Some(1_000),
+            funder: None,
+        },
+    )
+    .unwrap();
+
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: Uint128::new(100_000),
+        ..Default::default()
+    });
+    deps.querier.set_red_bank_user_collateral(
+        &user_addr,
+        UserCollateralResponse {
+            denom: "uosmo".to_string(),
+            amount_scaled: Uint128::new(10_000),
+            amount: Uint128::zero(), // doesn't matter for this test
+            enabled: true,
+        },
+    );
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &AssetIncentive {
+                emission: Emission::PerSecond(Uint128::zero()),
+                start_time: 0,
+                duration: 10_000_000,
+                index: Decimal::one(),
+                last_updated: 0,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
+            },
+        )
+        .unwrap();
+
+    // rewards accrued before vesting was enabled are released in full
+    USER_UNCLAIMED_REWARDS.save(deps.as_mut().storage, &user_addr, &Uint128::new(500)).unwrap();
+
+    // 10_000 * (1 - 0) rewards accrued on a balance change start vesting
+    execute(
+        deps.as_mut(),
+        env_at(1_000_000),
+        mock_info("red_bank", &[]),
+        ExecuteMsg::BalanceChange {
+            user_addr: user_addr.clone(),
+            denom: "uosmo".to_string(),
+            user_amount_scaled_before: Uint128::new(10_000),
+            total_amount_scaled_before: Uint128::new(100_000),
+            kind: None,
+        },
+    )
+    .unwrap();
+
+    // a quarter of the way through the vesting window: 500 + 10_000 * 250 / 1_000 = 3_000
+    let unclaimed_rewards =
+        query_user_unclaimed_rewards(deps.as_ref(), env_at(1_000_250), user_addr.to_string())
+            .unwrap();
+    assert_eq!(unclaimed_rewards, Uint128::new(3_000));
+
+    let res = execute(
+        deps.as_mut(),
+        env_at(1_000_250),
+        mock_info("user", &[]),
+        ExecuteMsg::ClaimRewards {
+            recipient: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: user_addr.to_string(),
+            amount: coins(3_000, "umars"),
+        }))]
+    );
+
+    // the rest is still vesting, so nothing more can be claimed yet
+    let unclaimed_rewards =
+        query_user_unclaimed_rewards(deps.as_ref(), env_at(1_000_250), user_addr.to_string())
+            .unwrap();
+    assert_eq!(unclaimed_rewards, Uint128::zero());
+
+    // half of the way through: 10_000 * 500 / 1_000 - 2_500 = 2_500
+    let unclaimed_rewards =
+        query_user_unclaimed_rewards(deps.as_ref(), env_at(1_000_500), user_addr.to_string())
+            .unwrap();
+    assert_eq!(unclaimed_rewards, Uint128::new(2_500));
+
+    // once fully vested, the remainder is released and the tranche removed
+    let res = execute(
+        deps.as_mut(),
+        env_at(1_001_500),
+        mock_info("user", &[]),
+        ExecuteMsg::ClaimRewards {
+            recipient: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: user_addr.to_string(),
+            amount: coins(7_500, "umars"),
+        }))]
     );
+    assert!(USER_VESTING.may_load(deps.as_ref().storage, &user_addr).unwrap().is_none());
+
+    let totals = query_user_claimed_totals(deps.as_ref(), user_addr.to_string()).unwrap();
+    assert_eq!(totals, coins(10_500, "umars"));
+}
+
+#[test]
+fn claiming_rewards_capped_to_balance() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    // the contract is slightly under-funded
+    deps.querier.set_contract_balances(&coins(999, "umars"));
+    USER_UNCLAIMED_REWARDS.save(deps.as_mut().storage, &user_addr, &Uint128::new(1_000)).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[]),
+        ExecuteMsg::ClaimRewards {
+            recipient: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: user_addr.to_string(),
+            amount: coins(999, "umars"),
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_rewards"),
+            attr("user", "user"),
+            attr("recipient", "user"),
+            attr("mars_rewards", "999"),
+            attr("capped", "1"),
+        ]
+    );
+
+    // the shortfall can be claimed later
+    let unclaimed_rewards = USER_UNCLAIMED_REWARDS.load(deps.as_ref().storage, &user_addr).unwrap();
+    assert_eq!(unclaimed_rewards, Uint128::new(1));
+}
+
+#[test]
+fn claiming_rewards_skips_failing_market() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    // only the uosmo market can be queried from the Red Bank
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: Uint128::new(100_000),
+        ..Default::default()
+    });
+    deps.querier.set_red_bank_user_collateral(
+        &user_addr,
+        UserCollateralResponse {
+            denom: "uosmo".to_string(),
+            amount_scaled: Uint128::new(10_000),
+            amount: Uint128::zero(), // doesn't matter for this test
+            enabled: true,
+        },
+    );
+
+    let asset_incentive = AssetIncentive {
+        emission: Emission::PerSecond(Uint128::new(100)),
+        start_time: 0,
+        duration: 8640000,
+        index: Decimal::zero(),
+        last_updated: 500_000,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
+    };
+    for denom in ["uatom", "uosmo"] {
+        ASSET_INCENTIVES.save(deps.as_mut().storage, denom, &asset_incentive).unwrap();
+        USER_ASSET_INDICES
+            .save(deps.as_mut().storage, (&user_addr, denom), &Decimal::zero())
+            .unwrap();
+    }
+
+    let time_contract_call = 600_000_u64;
+    let env = mars_testing::mock_env(MockEnvParams {
+        block_time: Timestamp::from_seconds(time_contract_call),
+        ..Default::default()
+    });
+
+    // 100 * 100_000 seconds emitted, of which the user gets 10%
+    let expected_rewards = Uint128::new(1_000_000);
+    let rewards_query =
+        query_user_unclaimed_rewards(deps.as_ref(), env.clone(), user_addr.to_string()).unwrap();
+    assert_eq!(rewards_query, expected_rewards);
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("user", &[]),
+        ExecuteMsg::ClaimRewards {
+            recipient: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: user_addr.to_string(),
+            amount: coins(expected_rewards.u128(), "umars"),
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_rewards"),
+            attr("user", "user"),
+            attr("recipient", "user"),
+            attr("mars_rewards", expected_rewards),
+            attr("skipped_denoms", "uatom"),
+        ]
+    );
+
+    // the failing market's incentive and user index are left as is, to be settled later
+    let uatom_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uatom").unwrap();
+    assert_eq!(uatom_incentive, asset_incentive);
+    let user_uatom_index =
+        USER_ASSET_INDICES.load(deps.as_ref().storage, (&user_addr, "uatom")).unwrap();
+    assert_eq!(user_uatom_index, Decimal::zero());
+
+    let uosmo_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
+    assert_eq!(uosmo_incentive.last_updated, time_contract_call);
+    let user_uosmo_index =
+        USER_ASSET_INDICES.load(deps.as_ref().storage, (&user_addr, "uosmo")).unwrap();
+    assert_eq!(user_uosmo_index, uosmo_incentive.index);
+}
+
+#[test]
+fn claim_rewards_for_denoms() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    // three markets with the same incentive, in which the user holds a tenth of the collateral
+    for denom in ["uatom", "uosmo", "uusdc"] {
+        deps.querier.set_redbank_market(Market {
+            denom: denom.to_string(),
+            collateral_total_scaled: Uint128::new(100_000),
+            ..Default::default()
+        });
+        deps.querier.set_red_bank_user_collateral(
+            &user_addr,
+            UserCollateralResponse {
+                denom: denom.to_string(),
+                amount_scaled: Uint128::new(10_000),
+                amount: Uint128::zero(), // doesn't matter for this test
+                enabled: true,
+            },
+        );
+        ASSET_INCENTIVES
+            .save(
+                deps.as_mut().storage,
+                denom,
+                &AssetIncentive {
+                    emission: Emission::PerSecond(Uint128::new(100)),
+                    start_time: 1_000_000,
+                    duration: 86400,
+                    index: Decimal::zero(),
+                    last_updated: 1_000_000,
+                    last_updated_height: 0,
+                    unemitted: Uint128::zero(),
+                    schedules: vec![],
+                },
+            )
+            .unwrap();
+        USER_ASSET_INDICES
+            .save(deps.as_mut().storage, (&user_addr, denom), &Decimal::zero())
+            .unwrap();
+    }
+    USER_UNCLAIMED_REWARDS.save(deps.as_mut().storage, &user_addr, &Uint128::new(500)).unwrap();
+
+    // after 500 seconds, the user's balance changes in two of the markets, setting aside the 5_000
+    // MARS accrued in each of them
+    let env = mars_testing::mock_env(MockEnvParams {
+        block_time: Timestamp::from_seconds(1_000_500),
+        ..Default::default()
+    });
+    for denom in ["uatom", "uosmo"] {
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("red_bank", &[]),
+            ExecuteMsg::BalanceChange {
+                user_addr: user_addr.clone(),
+                denom: denom.to_string(),
+                user_amount_scaled_before: Uint128::new(10_000),
+                total_amount_scaled_before: Uint128::new(100_000),
+                kind: None,
+            },
+        )
+        .unwrap();
+        let unclaimed_rewards =
+            USER_ASSET_UNCLAIMED_REWARDS.load(deps.as_ref().storage, (&user_addr, denom)).unwrap();
+        assert_eq!(unclaimed_rewards, Uint128::new(5_000));
+    }
+
+    // after 1000 seconds, the user accrued 10_000 MARS in each market, half of it set aside in the
+    // markets whose balance changed
+    let env = mars_testing::mock_env(MockEnvParams {
+        block_time: Timestamp::from_seconds(1_001_000),
+        ..Default::default()
+    });
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("user", &[]),
+        ExecuteMsg::ClaimRewardsForDenoms {
+            collateral_denoms: vec!["uosmo".to_string()],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: user_addr.to_string(),
+            amount: coins(10_000, "umars"),
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_rewards"),
+            attr("user", "user"),
+            attr("recipient", "user"),
+            attr("mars_rewards", "10000"),
+            attr("collateral_denoms", "uosmo"),
+        ]
+    );
+
+    // only the claimed denom's user index is updated
+    let user_index = USER_ASSET_INDICES.load(deps.as_ref().storage, (&user_addr, "uosmo")).unwrap();
+    assert_eq!(user_index, Decimal::one());
+    let user_index = USER_ASSET_INDICES.load(deps.as_ref().storage, (&user_addr, "uatom")).unwrap();
+    assert_eq!(user_index, Decimal::from_ratio(1_u128, 2_u128));
+    let user_index = USER_ASSET_INDICES.load(deps.as_ref().storage, (&user_addr, "uusdc")).unwrap();
+    assert_eq!(user_index, Decimal::zero());
+
+    // only the claimed denom's set aside rewards are cleared
+    let unclaimed_rewards = USER_ASSET_UNCLAIMED_REWARDS
+        .may_load(deps.as_ref().storage, (&user_addr, "uosmo"))
+        .unwrap();
+    assert_eq!(unclaimed_rewards, None);
+    let unclaimed_rewards =
+        USER_ASSET_UNCLAIMED_REWARDS.load(deps.as_ref().storage, (&user_addr, "uatom")).unwrap();
+    assert_eq!(unclaimed_rewards, Uint128::new(5_000));
+
+    // the other two denoms' rewards and the rewards not attributed to an asset remain unclaimed
+    let user_unclaimed_rewards =
+        USER_UNCLAIMED_REWARDS.load(deps.as_ref().storage, &user_addr).unwrap();
+    assert_eq!(user_unclaimed_rewards, Uint128::new(500));
+    let rewards_query =
+        query_user_unclaimed_rewards(deps.as_ref(), env, user_addr.to_string()).unwrap();
+    assert_eq!(rewards_query, Uint128::new(20_500));
 }
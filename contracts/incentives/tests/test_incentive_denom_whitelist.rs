@@ -0,0 +1,163 @@
+use cosmwasm_std::{
+    attr,
+    testing::{mock_env, mock_info},
+    Addr, Decimal, Timestamp, Uint128,
+};
+use mars_incentives::{
+    contract::{execute, query_incentive_denom_whitelist},
+    state::{ASSET_INCENTIVES, USER_UNCLAIMED_REWARDS},
+    ContractError,
+};
+use mars_owner::OwnerError::NotOwner;
+use mars_red_bank_types::{
+    incentives::{AssetIncentive, Emission, ExecuteMsg},
+    red_bank::Market,
+};
+use mars_testing::MockEnvParams;
+use mars_utils::error::ValidationError;
+
+use crate::helpers::th_setup;
+
+mod helpers;
+
+fn update_whitelist_msg(add: &[&str], remove: &[&str]) -> ExecuteMsg {
+    ExecuteMsg::UpdateIncentiveDenomWhitelist {
+        add: add.iter().map(|denom| denom.to_string()).collect(),
+        remove: remove.iter().map(|denom| denom.to_string()).collect(),
+    }
+}
+
+#[test]
+fn update_incentive_denom_whitelist() {
+    let mut deps = th_setup();
+
+    // the MARS denom is whitelisted on instantiation
+    let whitelist = query_incentive_denom_whitelist(deps.as_ref()).unwrap();
+    assert_eq!(whitelist, vec!["umars".to_string()]);
+
+    // only the owner can update the whitelist
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        update_whitelist_msg(&["uosmo"], &[]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Owner(NotOwner {}));
+
+    // added denoms must be valid
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_whitelist_msg(&["!*jadfaefc"], &[]),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Validation(ValidationError::InvalidDenom { .. })));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_whitelist_msg(&["uosmo", "umars"], &["umars"]),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_incentive_denom_whitelist"),
+            attr("added", "uosmo,umars"),
+            attr("removed", "umars"),
+        ]
+    );
+
+    let whitelist = query_incentive_denom_whitelist(deps.as_ref()).unwrap();
+    assert_eq!(whitelist, vec!["uosmo".to_string()]);
+}
+
+#[test]
+fn incentives_in_removed_denom_are_claimable_but_not_extendable() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: Uint128::new(100_000),
+        ..Default::default()
+    });
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &AssetIncentive {
+                emission: Emission::PerSecond(Uint128::new(100)),
+                start_time: 1_000_000,
+                duration: 86400,
+                index: Decimal::zero(),
+                last_updated: 1_000_000,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
+            },
+        )
+        .unwrap();
+    USER_UNCLAIMED_REWARDS.save(deps.as_mut().storage, &user_addr, &Uint128::new(1_000)).unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_whitelist_msg(&[], &["umars"]),
+    )
+    .unwrap();
+
+    let env = mars_testing::mock_env(MockEnvParams {
+        block_time: Timestamp::from_seconds(1_001_000),
+        ..Default::default()
+    });
+    let expected_err = ContractError::IncentiveDenomNotWhitelisted {
+        denom: "umars".to_string(),
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetAssetIncentive {
+            denom: "uosmo".to_string(),
+            kind: None,
+            emission: None,
+            start_time: None,
+            duration: Some(172800),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, expected_err);
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddIncentiveSchedule {
+            collateral_denom: "uosmo".to_string(),
+            incentive_denom: "umars".to_string(),
+            emission: Emission::PerSecond(Uint128::new(50)),
+            start_time: 1_001_000,
+            duration: 1000,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, expected_err);
+
+    // rewards can still be claimed
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("user", &[]),
+        ExecuteMsg::ClaimRewards {
+            recipient: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+}
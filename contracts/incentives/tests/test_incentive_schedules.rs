@@ -0,0 +1,154 @@
+use cosmwasm_std::{attr, testing::mock_info, Decimal, Env, StdError, Timestamp, Uint128};
+use mars_incentives::{
+    contract::{execute, query_incentive_schedules},
+    state::ASSET_INCENTIVES,
+    ContractError,
+};
+use mars_owner::OwnerError::NotOwner;
+use mars_red_bank_types::{
+    incentives::{AssetIncentive, Emission, ExecuteMsg, IncentiveSchedule},
+    red_bank::Market,
+};
+use mars_testing::MockEnvParams;
+
+use crate::helpers::th_setup;
+
+mod helpers;
+
+fn add_schedule_msg(collateral_denom: &str, start_time: u64) -> ExecuteMsg {
+    ExecuteMsg::AddIncentiveSchedule {
+        collateral_denom: collateral_denom.to_string(),
+        incentive_denom: "umars".to_string(),
+        emission: Emission::PerSecond(Uint128::new(50)),
+        start_time,
+        duration: 1000,
+    }
+}
+
+fn env_at(block_time: u64) -> Env {
+    mars_testing::mock_env(MockEnvParams {
+        block_time: Timestamp::from_seconds(block_time),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn add_incentive_schedule() {
+    let mut deps = th_setup();
+
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: Uint128::new(100_000),
+        ..Default::default()
+    });
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &AssetIncentive {
+                emission: Emission::PerSecond(Uint128::new(100)),
+                start_time: 1_000_000,
+                duration: 86400,
+                index: Decimal::zero(),
+                last_updated: 1_000_000,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
+            },
+        )
+        .unwrap();
+
+    // only the owner can add schedules
+    let err = execute(
+        deps.as_mut(),
+        env_at(1_001_000),
+        mock_info("jake", &[]),
+        add_schedule_msg("uosmo", 1_001_000),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Owner(NotOwner {}));
+
+    // the asset must already be incentivized
+    let err = execute(
+        deps.as_mut(),
+        env_at(1_001_000),
+        mock_info("owner", &[]),
+        add_schedule_msg("uatom", 1_001_000),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidIncentive {
+            reason: "no incentive is set for uatom".to_string(),
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env_at(1_001_000),
+        mock_info("owner", &[]),
+        add_schedule_msg("uosmo", 1_001_000),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "add_incentive_schedule"),
+            attr("collateral_denom", "uosmo"),
+            attr("schedule_id", "1"),
+            attr("emission_per_second", "50"),
+            attr("start_time", "1001000"),
+            attr("duration", "1000"),
+        ]
+    );
+
+    // the index is updated up to the time the schedule is added, with the main schedule only
+    let asset_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
+    assert_eq!(asset_incentive.index, Decimal::one());
+    assert_eq!(asset_incentive.last_updated, 1_001_000);
+
+    let schedules =
+        query_incentive_schedules(deps.as_ref(), "uosmo".to_string(), "umars".to_string()).unwrap();
+    assert_eq!(
+        schedules,
+        vec![
+            IncentiveSchedule {
+                id: 0,
+                emission: Emission::PerSecond(Uint128::new(100)),
+                start_time: 1_000_000,
+                duration: 86400,
+            },
+            IncentiveSchedule {
+                id: 1,
+                emission: Emission::PerSecond(Uint128::new(50)),
+                start_time: 1_001_000,
+                duration: 1000,
+            },
+        ]
+    );
+
+    // both schedules emit into the index afterwards
+    execute(
+        deps.as_mut(),
+        env_at(1_002_000),
+        mock_info("owner", &[]),
+        add_schedule_msg("uosmo", 1_003_000),
+    )
+    .unwrap();
+    let asset_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
+    assert_eq!(asset_incentive.index, Decimal::from_ratio(25u128, 10u128));
+    assert_eq!(asset_incentive.schedules.len(), 2);
+}
+
+#[test]
+fn query_incentive_schedules_of_asset_without_incentive() {
+    let deps = th_setup();
+
+    let schedules =
+        query_incentive_schedules(deps.as_ref(), "uosmo".to_string(), "umars".to_string()).unwrap();
+    assert!(schedules.is_empty());
+
+    let err = query_incentive_schedules(deps.as_ref(), "uosmo".to_string(), "uatom".to_string())
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err("no incentives are distributed in uatom"));
+}
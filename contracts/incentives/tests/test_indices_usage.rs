@@ -1,28 +1,44 @@
-use cosmwasm_std::{Decimal, OverflowError, OverflowOperation, StdError, Uint128};
+use cosmwasm_std::{
+    BlockInfo, Decimal, OverflowError, OverflowOperation, StdError, Timestamp, Uint128,
+};
 use mars_incentives::helpers::{
     compute_asset_incentive_index, compute_user_accrued_rewards, update_asset_incentive_index,
 };
-use mars_red_bank_types::incentives::AssetIncentive;
+use mars_red_bank_types::incentives::{AssetIncentive, Emission, IncentiveSchedule};
 
 mod helpers;
 
+/// Block at the given time, one block having been produced per second since the UNIX epoch
+fn block_at(time: u64) -> BlockInfo {
+    BlockInfo {
+        height: time,
+        time: Timestamp::from_seconds(time),
+        chain_id: "cosmos-testnet-14002".to_string(),
+    }
+}
+
 #[test]
 fn update_asset_incentive_index_if_zero_emission() {
     let start_time = 0;
     let mut ai = AssetIncentive {
-        emission_per_second: Uint128::zero(),
+        emission: Emission::PerSecond(Uint128::zero()),
         start_time,
         duration: 300, // 5 min
         index: Decimal::one(),
         last_updated: 0,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
 
     let current_block_time = start_time + 1;
     let mut expected_ai = ai.clone();
     expected_ai.last_updated = current_block_time;
+    expected_ai.last_updated_height = current_block_time;
 
-    // only last_updated should be changed to current_block_time
-    update_asset_incentive_index(&mut ai, Uint128::new(100), current_block_time).unwrap();
+    // only last_updated and last_updated_height should be changed to current_block_time
+    update_asset_incentive_index(&mut ai, Uint128::new(100), &block_at(current_block_time))
+        .unwrap();
     assert_eq!(ai, expected_ai);
 }
 
@@ -30,19 +46,59 @@ fn update_asset_incentive_index_if_zero_emission() {
 fn update_asset_incentive_index_if_zero_amount() {
     let start_time = 0;
     let mut ai = AssetIncentive {
-        emission_per_second: Uint128::new(50),
+        emission: Emission::PerSecond(Uint128::new(50)),
         start_time,
         duration: 300, // 5 min
         index: Decimal::one(),
         last_updated: 0,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
 
     let current_block_time = start_time + 1;
     let mut expected_ai = ai.clone();
     expected_ai.last_updated = current_block_time;
+    expected_ai.last_updated_height = current_block_time;
+    expected_ai.unemitted = Uint128::new(50);
+
+    // the index isn't changed, the emission of the elapsed second being set aside instead
+    update_asset_incentive_index(&mut ai, Uint128::zero(), &block_at(current_block_time)).unwrap();
+    assert_eq!(ai, expected_ai);
+
+    // once something is supplied again, what was set aside is distributed along with the emission
+    // since the last update: (50 + 2 * 50) / 100
+    let current_block_time = current_block_time + 2;
+    let mut expected_ai = ai.clone();
+    expected_ai.index = Decimal::from_ratio(25u128, 10u128);
+    expected_ai.last_updated = current_block_time;
+    expected_ai.last_updated_height = current_block_time;
+    expected_ai.unemitted = Uint128::zero();
+
+    update_asset_incentive_index(&mut ai, Uint128::new(100), &block_at(current_block_time))
+        .unwrap();
+    assert_eq!(ai, expected_ai);
+}
+
+#[test]
+fn update_asset_incentive_index_keeps_unemitted_after_end() {
+    let mut ai = AssetIncentive {
+        emission: Emission::PerSecond(Uint128::new(50)),
+        start_time: 0,
+        duration: 300,
+        index: Decimal::one(),
+        last_updated: 300,
+        last_updated_height: 300,
+        unemitted: Uint128::new(200),
+        schedules: vec![],
+    };
 
-    // only last_updated should be changed to current_block_time
-    update_asset_incentive_index(&mut ai, Uint128::zero(), current_block_time).unwrap();
+    // nothing is emitted anymore, so what's unemitted is left for the funder to reclaim
+    let mut expected_ai = ai.clone();
+    expected_ai.last_updated = 400;
+    expected_ai.last_updated_height = 400;
+
+    update_asset_incentive_index(&mut ai, Uint128::new(100), &block_at(400)).unwrap();
     assert_eq!(ai, expected_ai);
 }
 
@@ -50,19 +106,24 @@ fn update_asset_incentive_index_if_zero_amount() {
 fn update_asset_incentive_index_if_current_block_lt_start_time() {
     let start_time = 10;
     let mut ai = AssetIncentive {
-        emission_per_second: Uint128::new(50),
+        emission: Emission::PerSecond(Uint128::new(50)),
         start_time,
         duration: 300, // 5 min
         index: Decimal::one(),
         last_updated: 0,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
 
     let current_block_time = start_time - 1;
     let mut expected_ai = ai.clone();
     expected_ai.last_updated = current_block_time;
+    expected_ai.last_updated_height = current_block_time;
 
-    // only last_updated should be changed to current_block_time
-    update_asset_incentive_index(&mut ai, Uint128::new(100), current_block_time).unwrap();
+    // only last_updated and last_updated_height should be changed to current_block_time
+    update_asset_incentive_index(&mut ai, Uint128::new(100), &block_at(current_block_time))
+        .unwrap();
     assert_eq!(ai, expected_ai);
 }
 
@@ -70,19 +131,24 @@ fn update_asset_incentive_index_if_current_block_lt_start_time() {
 fn update_asset_incentive_index_if_current_block_eq_start_time() {
     let start_time = 10;
     let mut ai = AssetIncentive {
-        emission_per_second: Uint128::new(50),
+        emission: Emission::PerSecond(Uint128::new(50)),
         start_time,
         duration: 300, // 5 min
         index: Decimal::one(),
         last_updated: 0,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
 
     let current_block_time = start_time;
     let mut expected_ai = ai.clone();
     expected_ai.last_updated = current_block_time;
+    expected_ai.last_updated_height = current_block_time;
 
-    // only last_updated should be changed to current_block_time
-    update_asset_incentive_index(&mut ai, Uint128::new(100), current_block_time).unwrap();
+    // only last_updated and last_updated_height should be changed to current_block_time
+    update_asset_incentive_index(&mut ai, Uint128::new(100), &block_at(current_block_time))
+        .unwrap();
     assert_eq!(ai, expected_ai);
 }
 
@@ -93,26 +159,31 @@ fn update_asset_incentive_index_if_current_block_gt_start_time() {
     let start_time = 10;
     let eps = Uint128::new(20);
     let mut ai = AssetIncentive {
-        emission_per_second: eps,
+        emission: Emission::PerSecond(eps),
         start_time,
         duration: 300, // 5 min
         index: Decimal::one(),
         last_updated: 0,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
 
     let current_block_time = start_time + 1;
     let mut expected_ai = ai.clone();
     expected_ai.index = Decimal::from_ratio(12u128, 10u128);
     expected_ai.last_updated = current_block_time;
+    expected_ai.last_updated_height = current_block_time;
 
-    update_asset_incentive_index(&mut ai, total_amount, current_block_time).unwrap();
+    update_asset_incentive_index(&mut ai, total_amount, &block_at(current_block_time)).unwrap();
     assert_eq!(ai, expected_ai);
 
     let current_block_time = current_block_time + 2;
     let mut expected_ai = ai.clone();
     expected_ai.index = Decimal::from_ratio(16u128, 10u128);
     expected_ai.last_updated = current_block_time;
-    update_asset_incentive_index(&mut ai, total_amount, current_block_time).unwrap();
+    expected_ai.last_updated_height = current_block_time;
+    update_asset_incentive_index(&mut ai, total_amount, &block_at(current_block_time)).unwrap();
     assert_eq!(ai, expected_ai);
 }
 
@@ -122,19 +193,24 @@ fn update_asset_incentive_index_if_last_updated_eq_end_time() {
     let duration = 300; // 5 min
     let end_time = start_time + duration;
     let mut ai = AssetIncentive {
-        emission_per_second: Uint128::new(50),
+        emission: Emission::PerSecond(Uint128::new(50)),
         start_time,
         duration,
         index: Decimal::one(),
         last_updated: end_time,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
 
     let current_block_time = end_time + 1;
     let mut expected_ai = ai.clone();
     expected_ai.last_updated = current_block_time;
+    expected_ai.last_updated_height = current_block_time;
 
-    // only last_updated should be changed to current_block_time
-    update_asset_incentive_index(&mut ai, Uint128::new(100), current_block_time).unwrap();
+    // only last_updated and last_updated_height should be changed to current_block_time
+    update_asset_incentive_index(&mut ai, Uint128::new(100), &block_at(current_block_time))
+        .unwrap();
     assert_eq!(ai, expected_ai);
 }
 
@@ -145,19 +221,24 @@ fn update_asset_incentive_index_if_last_updated_gt_end_time() {
     let end_time = start_time + duration;
     let last_updated = end_time + 1;
     let mut ai = AssetIncentive {
-        emission_per_second: Uint128::new(50),
+        emission: Emission::PerSecond(Uint128::new(50)),
         start_time,
         duration,
         index: Decimal::one(),
         last_updated,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
 
     let current_block_time = last_updated + 1;
     let mut expected_ai = ai.clone();
     expected_ai.last_updated = current_block_time;
+    expected_ai.last_updated_height = current_block_time;
 
-    // only last_updated should be changed to current_block_time
-    update_asset_incentive_index(&mut ai, Uint128::new(100), current_block_time).unwrap();
+    // only last_updated and last_updated_height should be changed to current_block_time
+    update_asset_incentive_index(&mut ai, Uint128::new(100), &block_at(current_block_time))
+        .unwrap();
     assert_eq!(ai, expected_ai);
 }
 
@@ -168,19 +249,24 @@ fn update_asset_incentive_index_if_last_updated_lt_end_time() {
     let end_time = start_time + duration;
     let last_updated = end_time - 1;
     let mut ai = AssetIncentive {
-        emission_per_second: Uint128::new(20),
+        emission: Emission::PerSecond(Uint128::new(20)),
         start_time,
         duration,
         index: Decimal::one(),
         last_updated,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
 
     let current_block_time = end_time;
     let mut expected_ai = ai.clone();
     expected_ai.index = Decimal::from_ratio(12u128, 10u128);
     expected_ai.last_updated = current_block_time;
+    expected_ai.last_updated_height = current_block_time;
 
-    update_asset_incentive_index(&mut ai, Uint128::new(100), current_block_time).unwrap();
+    update_asset_incentive_index(&mut ai, Uint128::new(100), &block_at(current_block_time))
+        .unwrap();
     assert_eq!(ai, expected_ai);
 }
 
@@ -190,19 +276,111 @@ fn update_asset_incentive_index_if_not_updated_till_finished() {
     let duration = 300; // 5 min
     let end_time = start_time + duration;
     let mut ai = AssetIncentive {
-        emission_per_second: Uint128::new(20),
+        emission: Emission::PerSecond(Uint128::new(20)),
         start_time,
         duration,
         index: Decimal::one(),
         last_updated: 0,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
 
     let current_block_time = end_time + 10;
     let mut expected_ai = ai.clone();
     expected_ai.index = Decimal::from_ratio(610u128, 10u128);
     expected_ai.last_updated = current_block_time;
+    expected_ai.last_updated_height = current_block_time;
+
+    update_asset_incentive_index(&mut ai, Uint128::new(100), &block_at(current_block_time))
+        .unwrap();
+    assert_eq!(ai, expected_ai);
+}
+
+#[test]
+fn update_asset_incentive_index_per_block() {
+    let total_amount = Uint128::new(100);
+
+    let start_time = 10;
+    let duration = 300; // 5 min
+    let end_time = start_time + duration;
+    let mut ai = AssetIncentive {
+        emission: Emission::PerBlock(Uint128::new(20)),
+        start_time,
+        duration,
+        index: Decimal::one(),
+        last_updated: start_time,
+        last_updated_height: 100,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
+    };
+
+    // 5 blocks produced in 10 seconds: the emission depends on the blocks only
+    let block = BlockInfo {
+        height: 105,
+        ..block_at(start_time + 10)
+    };
+    let mut expected_ai = ai.clone();
+    // 1 + 20 * 5 / 100 = 2
+    expected_ai.index = Decimal::from_ratio(2u128, 1u128);
+    expected_ai.last_updated = start_time + 10;
+    expected_ai.last_updated_height = 105;
+    update_asset_incentive_index(&mut ai, total_amount, &block).unwrap();
+    assert_eq!(ai, expected_ai);
+
+    // 10 blocks produced in 20 seconds, only the first 10 of which are within the incentive, so
+    // only half of the blocks are paid for
+    ai.last_updated = end_time - 10;
+    ai.last_updated_height = 200;
+    let block = BlockInfo {
+        height: 210,
+        ..block_at(end_time + 10)
+    };
+    let mut expected_ai = ai.clone();
+    // 2 + 20 * 5 / 100 = 3
+    expected_ai.index = Decimal::from_ratio(3u128, 1u128);
+    expected_ai.last_updated = end_time + 10;
+    expected_ai.last_updated_height = 210;
+    update_asset_incentive_index(&mut ai, total_amount, &block).unwrap();
+    assert_eq!(ai, expected_ai);
+}
+
+#[test]
+fn update_asset_incentive_index_sums_schedules() {
+    let total_amount = Uint128::new(100);
+
+    let mut ai = AssetIncentive {
+        emission: Emission::PerSecond(Uint128::new(20)),
+        start_time: 10,
+        duration: 300,
+        index: Decimal::one(),
+        last_updated: 10,
+        last_updated_height: 10,
+        unemitted: Uint128::zero(),
+        // a bonus emitting for 5 seconds, and another that hasn't started yet
+        schedules: vec![
+            IncentiveSchedule {
+                id: 1,
+                emission: Emission::PerSecond(Uint128::new(10)),
+                start_time: 20,
+                duration: 5,
+            },
+            IncentiveSchedule {
+                id: 2,
+                emission: Emission::PerBlock(Uint128::new(1000)),
+                start_time: 50,
+                duration: 100,
+            },
+        ],
+    };
+
+    // 20 * 20 emitted by the main schedule and 10 * 5 by the first bonus
+    let mut expected_ai = ai.clone();
+    expected_ai.index = Decimal::from_ratio(55u128, 10u128);
+    expected_ai.last_updated = 30;
+    expected_ai.last_updated_height = 30;
 
-    update_asset_incentive_index(&mut ai, Uint128::new(100), current_block_time).unwrap();
+    update_asset_incentive_index(&mut ai, total_amount, &block_at(30)).unwrap();
     assert_eq!(ai, expected_ai);
 }
 
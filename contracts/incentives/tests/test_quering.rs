@@ -1,6 +1,18 @@
-use cosmwasm_std::{Decimal, Uint128};
-use mars_incentives::state::ASSET_INCENTIVES;
-use mars_red_bank_types::incentives::{AssetIncentive, AssetIncentiveResponse, QueryMsg};
+use cosmwasm_std::{from_binary, testing::mock_env, Addr, Decimal, Timestamp, Uint128};
+use mars_incentives::{
+    contract::query,
+    helpers::{compute_asset_incentive_index, compute_user_accrued_rewards},
+    state::{
+        ASSET_INCENTIVES, DEBT_ASSET_INCENTIVES, USER_ASSET_INDICES, USER_ASSET_UNCLAIMED_REWARDS,
+    },
+};
+use mars_red_bank_types::{
+    incentives::{
+        AssetIncentive, AssetIncentiveResponse, Emission, IncentiveSchedule, PositionKind,
+        QueryMsg, RewardDebugInfoResponse, UserActiveIncentiveResponse, UserRewardStateResponse,
+    },
+    red_bank::{Market, UserCollateralResponse, UserDebtResponse},
+};
 
 use crate::helpers::th_setup;
 
@@ -12,27 +24,36 @@ fn query_asset_incentive() {
 
     // incentives
     let uosmo_incentive = AssetIncentive {
-        emission_per_second: Uint128::new(100),
+        emission: Emission::PerSecond(Uint128::new(100)),
         start_time: 120,
         duration: 8640000,
         index: Decimal::one(),
         last_updated: 150,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
     ASSET_INCENTIVES.save(deps.as_mut().storage, "uosmo", &uosmo_incentive).unwrap();
     let uatom_incentive = AssetIncentive {
-        emission_per_second: Uint128::zero(),
+        emission: Emission::PerSecond(Uint128::zero()),
         start_time: 0,
         duration: 1200,
         index: Decimal::one(),
         last_updated: 1000,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
     ASSET_INCENTIVES.save(deps.as_mut().storage, "uatom", &uatom_incentive).unwrap();
     let uusdc_incentive = AssetIncentive {
-        emission_per_second: Uint128::new(200),
+        emission: Emission::PerSecond(Uint128::new(200)),
         start_time: 12000,
         duration: 86400,
         index: Decimal::from_ratio(120u128, 50u128),
         last_updated: 120000,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
     ASSET_INCENTIVES.save(deps.as_mut().storage, "uusdc", &uusdc_incentive).unwrap();
 
@@ -40,9 +61,17 @@ fn query_asset_incentive() {
         deps.as_ref(),
         QueryMsg::AssetIncentive {
             denom: "uatom".to_string(),
+            kind: None,
         },
     );
-    assert_eq!(res, AssetIncentiveResponse::from("uatom".to_string(), uatom_incentive));
+    assert_eq!(
+        res,
+        AssetIncentiveResponse::from(
+            "uatom".to_string(),
+            PositionKind::Collateral,
+            uatom_incentive
+        )
+    );
 }
 
 #[test]
@@ -51,27 +80,36 @@ fn query_asset_incentives() {
 
     // incentives
     let uosmo_incentive = AssetIncentive {
-        emission_per_second: Uint128::new(100),
+        emission: Emission::PerSecond(Uint128::new(100)),
         start_time: 120,
         duration: 8640000,
         index: Decimal::one(),
         last_updated: 150,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
     ASSET_INCENTIVES.save(deps.as_mut().storage, "uosmo", &uosmo_incentive).unwrap();
     let uatom_incentive = AssetIncentive {
-        emission_per_second: Uint128::zero(),
+        emission: Emission::PerSecond(Uint128::zero()),
         start_time: 0,
         duration: 1200,
         index: Decimal::one(),
         last_updated: 1000,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
     ASSET_INCENTIVES.save(deps.as_mut().storage, "uatom", &uatom_incentive).unwrap();
     let uusdc_incentive = AssetIncentive {
-        emission_per_second: Uint128::new(200),
+        emission: Emission::PerSecond(Uint128::new(200)),
         start_time: 12000,
         duration: 86400,
         index: Decimal::from_ratio(120u128, 50u128),
         last_updated: 120000,
+        last_updated_height: 0,
+        unemitted: Uint128::zero(),
+        schedules: vec![],
     };
     ASSET_INCENTIVES.save(deps.as_mut().storage, "uusdc", &uusdc_incentive).unwrap();
 
@@ -79,6 +117,7 @@ fn query_asset_incentives() {
     let res: Vec<AssetIncentiveResponse> = helpers::th_query(
         deps.as_ref(),
         QueryMsg::AssetIncentives {
+            kind: None,
             start_after: None,
             limit: None,
         },
@@ -86,9 +125,21 @@ fn query_asset_incentives() {
     assert_eq!(
         res,
         vec![
-            AssetIncentiveResponse::from("uatom".to_string(), uatom_incentive),
-            AssetIncentiveResponse::from("uosmo".to_string(), uosmo_incentive.clone()),
-            AssetIncentiveResponse::from("uusdc".to_string(), uusdc_incentive),
+            AssetIncentiveResponse::from(
+                "uatom".to_string(),
+                PositionKind::Collateral,
+                uatom_incentive
+            ),
+            AssetIncentiveResponse::from(
+                "uosmo".to_string(),
+                PositionKind::Collateral,
+                uosmo_incentive.clone()
+            ),
+            AssetIncentiveResponse::from(
+                "uusdc".to_string(),
+                PositionKind::Collateral,
+                uusdc_incentive
+            ),
         ]
     );
 
@@ -96,9 +147,649 @@ fn query_asset_incentives() {
     let res: Vec<AssetIncentiveResponse> = helpers::th_query(
         deps.as_ref(),
         QueryMsg::AssetIncentives {
+            kind: None,
             start_after: Some("uatom".to_string()),
             limit: Some(1),
         },
     );
-    assert_eq!(res, vec![AssetIncentiveResponse::from("uosmo".to_string(), uosmo_incentive)]);
+    assert_eq!(
+        res,
+        vec![AssetIncentiveResponse::from(
+            "uosmo".to_string(),
+            PositionKind::Collateral,
+            uosmo_incentive
+        )]
+    );
+}
+
+#[test]
+fn query_reward_debug_info() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    let total_supply = Uint128::new(100_000);
+    let user_balance = Uint128::new(10_000);
+    let user_index = Decimal::from_ratio(1u128, 2u128);
+    let time_start = 500_000_u64;
+    let time_query = 600_000_u64;
+
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: total_supply,
+        ..Default::default()
+    });
+    deps.querier.set_red_bank_user_collateral(
+        &user_addr,
+        UserCollateralResponse {
+            denom: "uosmo".to_string(),
+            amount_scaled: user_balance,
+            amount: Uint128::zero(), // doesn't matter for this test
+            enabled: true,
+        },
+    );
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &AssetIncentive {
+                emission: Emission::PerSecond(Uint128::new(100)),
+                start_time: time_start,
+                duration: 8640000,
+                index: Decimal::one(),
+                last_updated: time_start,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
+            },
+        )
+        .unwrap();
+    USER_ASSET_INDICES.save(deps.as_mut().storage, (&user_addr, "uosmo"), &user_index).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(time_query);
+    let res: RewardDebugInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::RewardDebugInfo {
+                user: user_addr.to_string(),
+                collateral_denom: "uosmo".to_string(),
+                incentive_denom: "umars".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let expected_asset_index = compute_asset_incentive_index(
+        Decimal::one(),
+        Uint128::new(100),
+        total_supply,
+        time_start,
+        time_query,
+    )
+    .unwrap();
+    let expected_accrued =
+        compute_user_accrued_rewards(user_balance, user_index, expected_asset_index).unwrap();
+
+    // 1 + 100 * 100_000 / 100_000 = 101
+    assert_eq!(expected_asset_index, Decimal::from_ratio(101u128, 1u128));
+    // 10_000 * 101 - 10_000 * 0.5
+    assert_eq!(expected_accrued, Uint128::new(1_005_000));
+
+    assert_eq!(
+        res,
+        RewardDebugInfoResponse {
+            asset_index_now: expected_asset_index,
+            user_index_stored: user_index,
+            user_collateral_scaled: user_balance,
+            accrued_since: expected_accrued,
+        }
+    );
+
+    // only the MARS denom is distributed as incentives
+    query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::RewardDebugInfo {
+            user: user_addr.to_string(),
+            collateral_denom: "uosmo".to_string(),
+            incentive_denom: "uatom".to_string(),
+        },
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn query_user_reward_state() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    let previous_unclaimed_rewards = Uint128::new(50_000);
+    let total_supply = Uint128::new(100_000);
+    let user_balance = Uint128::new(10_000);
+    let time_start = 500_000_u64;
+    let time_query = 600_000_u64;
+
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: total_supply,
+        ..Default::default()
+    });
+    deps.querier.set_red_bank_user_collateral(
+        &user_addr,
+        UserCollateralResponse {
+            denom: "uosmo".to_string(),
+            amount_scaled: user_balance,
+            amount: Uint128::zero(), // doesn't matter for this test
+            enabled: true,
+        },
+    );
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &AssetIncentive {
+                emission: Emission::PerSecond(Uint128::new(100)),
+                start_time: time_start,
+                duration: 8640000,
+                index: Decimal::one(),
+                last_updated: time_start,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
+            },
+        )
+        .unwrap();
+    USER_ASSET_INDICES.save(deps.as_mut().storage, (&user_addr, "uosmo"), &Decimal::one()).unwrap();
+    USER_ASSET_UNCLAIMED_REWARDS
+        .save(deps.as_mut().storage, (&user_addr, "uosmo"), &previous_unclaimed_rewards)
+        .unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(time_query);
+    let res: UserRewardStateResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::UserRewardState {
+                user: user_addr.to_string(),
+                collateral_denom: "uosmo".to_string(),
+                incentive_denom: "umars".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let expected_asset_index = compute_asset_incentive_index(
+        Decimal::one(),
+        Uint128::new(100),
+        total_supply,
+        time_start,
+        time_query,
+    )
+    .unwrap();
+    let expected_pending =
+        compute_user_accrued_rewards(user_balance, Decimal::one(), expected_asset_index).unwrap();
+
+    assert_eq!(
+        res,
+        UserRewardStateResponse {
+            user_index: Decimal::one(),
+            asset_index: expected_asset_index,
+            unclaimed: previous_unclaimed_rewards,
+            pending: expected_pending,
+        }
+    );
+    // 10_000 * (101 - 1)
+    assert_eq!(res.pending, Uint128::new(1_000_000));
+}
+
+#[test]
+fn query_user_active_incentives() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+
+    let time_start = 500_000_u64;
+    let time_query = 600_000_u64;
+
+    // (denom, emission per second, start time, duration, user's collateral, enabled)
+    let incentives = [
+        ("uosmo", 100, time_start, 8640000, 10_000, true),
+        ("uatom", 200, time_start, 8640000, 10_000, false),
+        ("ujuno", 300, time_start, 8640000, 0, true),
+        ("ustars", 400, time_start, 50_000, 10_000, true),
+        ("uusdc", 500, time_query + 1, 8640000, 10_000, true),
+        ("uweth", 0, time_start, 8640000, 10_000, true),
+        ("uaxl", 600, time_start, 8640000, 5_000, true),
+    ];
+    for (denom, emission, start_time, duration, collateral, enabled) in incentives {
+        ASSET_INCENTIVES
+            .save(
+                deps.as_mut().storage,
+                denom,
+                &AssetIncentive {
+                    emission: Emission::PerSecond(Uint128::new(emission)),
+                    start_time,
+                    duration,
+                    index: Decimal::zero(),
+                    last_updated: start_time,
+                    last_updated_height: 0,
+                    unemitted: Uint128::zero(),
+                    schedules: vec![],
+                },
+            )
+            .unwrap();
+        deps.querier.set_red_bank_user_collateral(
+            &user_addr,
+            UserCollateralResponse {
+                denom: denom.to_string(),
+                amount_scaled: Uint128::new(collateral),
+                amount: Uint128::new(collateral),
+                enabled,
+            },
+        );
+    }
+
+    // (denom, user's debt, uncollateralized)
+    let debt_incentives = [("uosmo", 2_000, false), ("uatom", 2_000, true), ("ujuno", 0, false)];
+    for (denom, debt, uncollateralized) in debt_incentives {
+        DEBT_ASSET_INCENTIVES
+            .save(
+                deps.as_mut().storage,
+                denom,
+                &AssetIncentive {
+                    emission: Emission::PerSecond(Uint128::new(50)),
+                    start_time: time_start,
+                    duration: 8640000,
+                    index: Decimal::zero(),
+                    last_updated: time_start,
+                    last_updated_height: 0,
+                    unemitted: Uint128::zero(),
+                    schedules: vec![],
+                },
+            )
+            .unwrap();
+        deps.querier.set_redbank_market(Market {
+            denom: denom.to_string(),
+            uncollateralized_debt_accrues_interest: false,
+            ..Default::default()
+        });
+        deps.querier.set_red_bank_user_debt(
+            &user_addr,
+            UserDebtResponse {
+                denom: denom.to_string(),
+                amount_scaled: Uint128::new(debt),
+                amount: Uint128::new(debt),
+                uncollateralized,
+            },
+        );
+    }
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(time_query);
+    let res: Vec<UserActiveIncentiveResponse> = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::UserActiveIncentives {
+                user: user_addr.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    // disabled, empty, ended, not yet started and zero emission incentives are left out, as is debt
+    // that doesn't accrue interest
+    assert_eq!(
+        res,
+        vec![
+            UserActiveIncentiveResponse {
+                collateral_denom: "uaxl".to_string(),
+                kind: PositionKind::Collateral,
+                incentive_denom: "umars".to_string(),
+                emission: Emission::PerSecond(Uint128::new(600)),
+            },
+            UserActiveIncentiveResponse {
+                collateral_denom: "uosmo".to_string(),
+                kind: PositionKind::Collateral,
+                incentive_denom: "umars".to_string(),
+                emission: Emission::PerSecond(Uint128::new(100)),
+            },
+            UserActiveIncentiveResponse {
+                collateral_denom: "uosmo".to_string(),
+                kind: PositionKind::Debt,
+                incentive_denom: "umars".to_string(),
+                emission: Emission::PerSecond(Uint128::new(50)),
+            },
+        ]
+    );
+}
+
+#[test]
+fn query_projected_user_rewards() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+    let now = mock_env().block.time.seconds();
+
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: Uint128::new(100_000),
+        ..Default::default()
+    });
+    deps.querier.set_red_bank_user_collateral(
+        &user_addr,
+        UserCollateralResponse {
+            denom: "uosmo".to_string(),
+            amount_scaled: Uint128::new(10_000),
+            amount: Uint128::zero(), // doesn't matter for this test
+            enabled: true,
+        },
+    );
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &AssetIncentive {
+                emission: Emission::PerSecond(Uint128::new(100)),
+                start_time: now,
+                duration: 86400,
+                index: Decimal::zero(),
+                last_updated: now,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
+            },
+        )
+        .unwrap();
+    USER_ASSET_INDICES
+        .save(deps.as_mut().storage, (&user_addr, "uosmo"), &Decimal::zero())
+        .unwrap();
+
+    let query_projected = |future_timestamp: u64| {
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ProjectedUserRewards {
+                user: user_addr.to_string(),
+                future_timestamp,
+            },
+        )
+        .map(|res| from_binary::<Uint128>(&res).unwrap())
+    };
+
+    // before the incentive ends, the user gets 10% of the emissions up to the future timestamp
+    assert_eq!(query_projected(now + 43200).unwrap(), Uint128::new(432_000));
+
+    // after the incentive ends, the projection stops at its end
+    assert_eq!(query_projected(now + 86400).unwrap(), Uint128::new(864_000));
+    assert_eq!(query_projected(now + 200_000).unwrap(), Uint128::new(864_000));
+
+    // the timestamp can't be in the past
+    query_projected(now - 1).unwrap_err();
+}
+
+#[test]
+fn query_reward_rate_per_unit() {
+    let mut deps = th_setup();
+    let now = mock_env().block.time.seconds();
+
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: Uint128::new(400_000),
+        ..Default::default()
+    });
+    deps.querier.set_redbank_market(Market {
+        denom: "uatom".to_string(),
+        collateral_total_scaled: Uint128::zero(),
+        ..Default::default()
+    });
+
+    // (denom, emission, start time, duration)
+    let incentives = [
+        ("uosmo", Emission::PerSecond(Uint128::new(100)), now - 100, 86400),
+        ("uatom", Emission::PerSecond(Uint128::new(100)), now - 100, 86400),
+        ("ujuno", Emission::PerSecond(Uint128::new(100)), now - 100, 50),
+        ("uaxl", Emission::PerBlock(Uint128::new(100)), now - 100, 86400),
+    ];
+    for (denom, emission, start_time, duration) in incentives {
+        ASSET_INCENTIVES
+            .save(
+                deps.as_mut().storage,
+                denom,
+                &AssetIncentive {
+                    emission,
+                    start_time,
+                    duration,
+                    index: Decimal::zero(),
+                    last_updated: start_time,
+                    last_updated_height: 0,
+                    unemitted: Uint128::zero(),
+                    schedules: vec![],
+                },
+            )
+            .unwrap();
+    }
+
+    let query_rate = |collateral_denom: &str, incentive_denom: &str| {
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RewardRatePerUnit {
+                collateral_denom: collateral_denom.to_string(),
+                incentive_denom: incentive_denom.to_string(),
+            },
+        )
+        .map(|res| from_binary::<Decimal>(&res).unwrap())
+    };
+
+    // 100 / 400_000 per scaled unit per second
+    assert_eq!(query_rate("uosmo", "umars").unwrap(), Decimal::from_ratio(1u128, 4000u128));
+
+    // nothing supplied, ended incentive, no incentive at all
+    assert_eq!(query_rate("uatom", "umars").unwrap(), Decimal::zero());
+    assert_eq!(query_rate("ujuno", "umars").unwrap(), Decimal::zero());
+    assert_eq!(query_rate("ustars", "umars").unwrap(), Decimal::zero());
+
+    // the time blocks take isn't known
+    query_rate("uaxl", "umars").unwrap_err();
+
+    // rewards are only distributed in MARS
+    query_rate("uosmo", "uosmo").unwrap_err();
+}
+
+#[test]
+fn query_asset_incentive_apr() {
+    let mut deps = th_setup();
+    let now = mock_env().block.time.seconds();
+
+    // 1_000_000 uosmo supplied
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        liquidity_index: Decimal::from_ratio(2u128, 1u128),
+        collateral_total_scaled: Uint128::new(500_000_000_000),
+        ..Default::default()
+    });
+    deps.querier.set_redbank_market(Market {
+        denom: "uatom".to_string(),
+        liquidity_index: Decimal::one(),
+        collateral_total_scaled: Uint128::zero(),
+        ..Default::default()
+    });
+    deps.querier.set_oracle_price("umars", Decimal::percent(50));
+    deps.querier.set_oracle_price("uosmo", Decimal::percent(200));
+    deps.querier.set_oracle_price("uatom", Decimal::percent(1000));
+
+    // (denom, emission, start time, duration)
+    let incentives = [
+        ("uosmo", Emission::PerSecond(Uint128::new(10)), now - 100, 86400),
+        ("uatom", Emission::PerSecond(Uint128::new(10)), now - 100, 86400),
+        ("ujuno", Emission::PerSecond(Uint128::new(10)), now - 100, 50),
+        ("uaxl", Emission::PerBlock(Uint128::new(10)), now - 100, 86400),
+    ];
+    for (denom, emission, start_time, duration) in incentives {
+        ASSET_INCENTIVES
+            .save(
+                deps.as_mut().storage,
+                denom,
+                &AssetIncentive {
+                    emission,
+                    start_time,
+                    duration,
+                    index: Decimal::zero(),
+                    last_updated: start_time,
+                    last_updated_height: 0,
+                    unemitted: Uint128::zero(),
+                    schedules: vec![],
+                },
+            )
+            .unwrap();
+    }
+
+    let query_apr = |collateral_denom: &str, incentive_denom: &str| {
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AssetIncentiveApr {
+                collateral_denom: collateral_denom.to_string(),
+                incentive_denom: incentive_denom.to_string(),
+            },
+        )
+        .map(|res| from_binary::<Decimal>(&res).unwrap())
+    };
+
+    // a year of emissions is 315_360_000 umars worth 157_680_000, the collateral being worth
+    // 2_000_000
+    assert_eq!(query_apr("uosmo", "umars").unwrap(), Decimal::from_ratio(7884u128, 100u128));
+
+    // nothing supplied, ended incentive, no incentive at all
+    assert_eq!(query_apr("uatom", "umars").unwrap(), Decimal::zero());
+    assert_eq!(query_apr("ujuno", "umars").unwrap(), Decimal::zero());
+    assert_eq!(query_apr("ustars", "umars").unwrap(), Decimal::zero());
+
+    // the time blocks take isn't known
+    query_apr("uaxl", "umars").unwrap_err();
+
+    // rewards are only distributed in MARS
+    query_apr("uosmo", "uosmo").unwrap_err();
+}
+
+#[test]
+fn query_incentive_with_overlapping_schedules() {
+    let mut deps = th_setup();
+    let user_addr = Addr::unchecked("user");
+    let now = mock_env().block.time.seconds();
+
+    // 1_000_000 uosmo supplied
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        liquidity_index: Decimal::from_ratio(2u128, 1u128),
+        collateral_total_scaled: Uint128::new(500_000_000_000),
+        ..Default::default()
+    });
+    deps.querier.set_oracle_price("umars", Decimal::percent(50));
+    deps.querier.set_oracle_price("uosmo", Decimal::percent(200));
+    deps.querier.set_red_bank_user_collateral(
+        &user_addr,
+        UserCollateralResponse {
+            denom: "uosmo".to_string(),
+            amount_scaled: Uint128::new(10_000),
+            amount: Uint128::new(10_000),
+            enabled: true,
+        },
+    );
+
+    // the main schedule and the first additional one are running, the others have ended or not
+    // yet started
+    let schedule = |id: u64, emission: u128, start_time: u64, duration: u64| IncentiveSchedule {
+        id,
+        emission: Emission::PerSecond(Uint128::new(emission)),
+        start_time,
+        duration,
+    };
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &AssetIncentive {
+                emission: Emission::PerSecond(Uint128::new(100)),
+                start_time: now - 100,
+                duration: 86400,
+                index: Decimal::zero(),
+                last_updated: now - 100,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![
+                    schedule(1, 50, now - 50, 1000),
+                    schedule(2, 1000, now - 5000, 100),
+                    schedule(3, 1000, now + 1, 1000),
+                ],
+            },
+        )
+        .unwrap();
+
+    let query_decimal = |msg: QueryMsg| {
+        from_binary::<Decimal>(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+    };
+
+    // 150 / 500_000_000_000 per scaled unit per second
+    let rate = query_decimal(QueryMsg::RewardRatePerUnit {
+        collateral_denom: "uosmo".to_string(),
+        incentive_denom: "umars".to_string(),
+    });
+    assert_eq!(rate, Decimal::from_ratio(150u128, 500_000_000_000u128));
+
+    // a year of emissions is 4_730_400_000 umars worth 2_365_200_000, the collateral being worth
+    // 2_000_000
+    let apr = query_decimal(QueryMsg::AssetIncentiveApr {
+        collateral_denom: "uosmo".to_string(),
+        incentive_denom: "umars".to_string(),
+    });
+    assert_eq!(apr, Decimal::from_ratio(11826u128, 10u128));
+
+    // the user earns from both running schedules
+    let res: Vec<UserActiveIncentiveResponse> = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::UserActiveIncentives {
+                user: user_addr.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        vec![
+            UserActiveIncentiveResponse {
+                collateral_denom: "uosmo".to_string(),
+                kind: PositionKind::Collateral,
+                incentive_denom: "umars".to_string(),
+                emission: Emission::PerSecond(Uint128::new(100)),
+            },
+            UserActiveIncentiveResponse {
+                collateral_denom: "uosmo".to_string(),
+                kind: PositionKind::Collateral,
+                incentive_denom: "umars".to_string(),
+                emission: Emission::PerSecond(Uint128::new(50)),
+            },
+        ]
+    );
+
+    // a running schedule emitting per block can't be given per second
+    let mut asset_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
+    asset_incentive.schedules[0].emission = Emission::PerBlock(Uint128::new(50));
+    ASSET_INCENTIVES.save(deps.as_mut().storage, "uosmo", &asset_incentive).unwrap();
+    query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::RewardRatePerUnit {
+            collateral_denom: "uosmo".to_string(),
+            incentive_denom: "umars".to_string(),
+        },
+    )
+    .unwrap_err();
 }
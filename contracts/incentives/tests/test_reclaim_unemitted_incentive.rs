@@ -0,0 +1,241 @@
+use cosmwasm_std::{
+    attr, coins,
+    testing::{mock_env, mock_info},
+    Addr, BankMsg, CosmosMsg, Decimal, Env, SubMsg, Timestamp, Uint128,
+};
+use mars_incentives::{contract::execute, state::ASSET_INCENTIVES, ContractError};
+use mars_red_bank_types::{
+    error::MarsError,
+    incentives::{AssetIncentive, Emission, ExecuteMsg, IncentiveSchedule},
+    red_bank::Market,
+};
+use mars_testing::MockEnvParams;
+
+use crate::helpers::th_setup;
+
+mod helpers;
+
+fn reclaim_msg() -> ExecuteMsg {
+    ExecuteMsg::ReclaimUnemittedIncentive {
+        collateral_denom: "uosmo".to_string(),
+        incentive_denom: "umars".to_string(),
+    }
+}
+
+fn env_at(block_time: u64) -> Env {
+    mars_testing::mock_env(MockEnvParams {
+        block_time: Timestamp::from_seconds(block_time),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn cannot_reclaim_without_funder() {
+    let mut deps = th_setup();
+
+    let err =
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), reclaim_msg()).unwrap_err();
+    assert_eq!(err, ContractError::FunderNotSet {});
+}
+
+#[test]
+fn reclaim_unemitted_incentive() {
+    let mut deps = th_setup();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            address_provider: None,
+            mars_denom: None,
+            vesting_duration: None,
+            funder: Some("funder".to_string()),
+        },
+    )
+    .unwrap();
+
+    // 100 MARS per second for 1000 seconds
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &AssetIncentive {
+                emission: Emission::PerSecond(Uint128::new(100)),
+                start_time: 1_000_000,
+                duration: 1000,
+                index: Decimal::zero(),
+                last_updated: 1_000_000,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
+            },
+        )
+        .unwrap();
+
+    // the only depositor withdraws everything after 600 seconds
+    execute(
+        deps.as_mut(),
+        env_at(1_000_600),
+        mock_info("red_bank", &[]),
+        ExecuteMsg::BalanceChange {
+            user_addr: Addr::unchecked("user"),
+            denom: "uosmo".to_string(),
+            user_amount_scaled_before: Uint128::new(100_000),
+            total_amount_scaled_before: Uint128::new(100_000),
+            kind: None,
+        },
+    )
+    .unwrap();
+    let asset_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
+    assert_eq!(asset_incentive.unemitted, Uint128::zero());
+    assert_eq!(asset_incentive.index, Decimal::from_ratio(60_000u128, 100_000u128));
+
+    // nothing is supplied until the incentive ends
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: Uint128::zero(),
+        ..Default::default()
+    });
+
+    // only the owner or the funder can reclaim
+    let err = execute(deps.as_mut(), env_at(1_002_000), mock_info("jake", &[]), reclaim_msg())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Mars(MarsError::Unauthorized {}));
+
+    // the incentive must have ended
+    let err = execute(deps.as_mut(), env_at(1_000_999), mock_info("funder", &[]), reclaim_msg())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::IncentiveNotEnded {
+            denom: "uosmo".to_string(),
+            end_time: 1_001_000,
+        }
+    );
+
+    // only the MARS denom is distributed as incentives
+    let err = execute(
+        deps.as_mut(),
+        env_at(1_002_000),
+        mock_info("funder", &[]),
+        ExecuteMsg::ReclaimUnemittedIncentive {
+            collateral_denom: "uosmo".to_string(),
+            incentive_denom: "uatom".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidIncentive {
+            reason: "no incentives are distributed in uatom".to_string(),
+        }
+    );
+
+    // the emissions of the last 400 seconds are refunded to the funder
+    let res =
+        execute(deps.as_mut(), env_at(1_002_000), mock_info("owner", &[]), reclaim_msg()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "funder".to_string(),
+            amount: coins(40_000, "umars"),
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "reclaim_unemitted_incentive"),
+            attr("collateral_denom", "uosmo"),
+            attr("incentive_denom", "umars"),
+            attr("funder", "funder"),
+            attr("amount", "40000"),
+        ]
+    );
+
+    // the index is left as is
+    let asset_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
+    assert_eq!(asset_incentive.unemitted, Uint128::zero());
+    assert_eq!(asset_incentive.index, Decimal::from_ratio(60_000u128, 100_000u128));
+    assert_eq!(asset_incentive.last_updated, 1_002_000);
+
+    // nothing is refunded twice
+    let err = execute(deps.as_mut(), env_at(1_003_000), mock_info("funder", &[]), reclaim_msg())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NothingToReclaim {
+            denom: "uosmo".to_string(),
+        }
+    );
+}
+
+#[test]
+fn cannot_reclaim_before_all_schedules_end() {
+    let mut deps = th_setup();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            address_provider: None,
+            mars_denom: None,
+            vesting_duration: None,
+            funder: Some("funder".to_string()),
+        },
+    )
+    .unwrap();
+
+    // nothing is supplied while the incentive emits
+    deps.querier.set_redbank_market(Market {
+        denom: "uosmo".to_string(),
+        collateral_total_scaled: Uint128::zero(),
+        ..Default::default()
+    });
+
+    // the main schedule ends before the additional one, which overlaps it
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &AssetIncentive {
+                emission: Emission::PerSecond(Uint128::new(100)),
+                start_time: 1_000_000,
+                duration: 1000,
+                index: Decimal::zero(),
+                last_updated: 1_000_000,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![IncentiveSchedule {
+                    id: 1,
+                    emission: Emission::PerSecond(Uint128::new(50)),
+                    start_time: 1_000_500,
+                    duration: 1500,
+                }],
+            },
+        )
+        .unwrap();
+
+    // the incentive is still running after its main schedule ended
+    let err = execute(deps.as_mut(), env_at(1_001_500), mock_info("funder", &[]), reclaim_msg())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::IncentiveNotEnded {
+            denom: "uosmo".to_string(),
+            end_time: 1_002_000,
+        }
+    );
+
+    // the emissions of both schedules are refunded once they have ended
+    let res =
+        execute(deps.as_mut(), env_at(1_002_500), mock_info("funder", &[]), reclaim_msg()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "funder".to_string(),
+            amount: coins(175_000, "umars"),
+        }))]
+    );
+}
@@ -9,7 +9,7 @@ use mars_incentives::{
 };
 use mars_owner::OwnerError::NotOwner;
 use mars_red_bank_types::{
-    incentives::{AssetIncentive, ExecuteMsg},
+    incentives::{AssetIncentive, Emission, ExecuteMsg},
     red_bank::Market,
 };
 use mars_testing::MockEnvParams;
@@ -26,7 +26,8 @@ fn only_owner_can_set_asset_incentive() {
     let info = mock_info("sender", &[]);
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: Some(Uint128::new(100)),
+        kind: None,
+        emission: Some(Emission::PerSecond(Uint128::new(100))),
         start_time: None,
         duration: Some(86400),
     };
@@ -42,7 +43,8 @@ fn invalid_denom_for_incentives() {
     let info = mock_info("owner", &[]);
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "adfnjg&akjsfn!".to_string(),
-        emission_per_second: Some(Uint128::new(100)),
+        kind: None,
+        emission: Some(Emission::PerSecond(Uint128::new(100))),
         start_time: None,
         duration: Some(2400u64),
     };
@@ -65,7 +67,8 @@ fn cannot_set_new_asset_incentive_with_empty_params() {
 
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: None,
+        kind: None,
+        emission: None,
         start_time: None,
         duration: None,
     };
@@ -79,7 +82,8 @@ fn cannot_set_new_asset_incentive_with_empty_params() {
 
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: Some(Uint128::from(100u32)),
+        kind: None,
+        emission: Some(Emission::PerSecond(Uint128::from(100u32))),
         start_time: Some(100),
         duration: None,
     };
@@ -93,7 +97,8 @@ fn cannot_set_new_asset_incentive_with_empty_params() {
 
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: None,
+        kind: None,
+        emission: None,
         start_time: Some(100),
         duration: Some(2400u64),
     };
@@ -107,7 +112,8 @@ fn cannot_set_new_asset_incentive_with_empty_params() {
 
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: Some(Uint128::from(100u32)),
+        kind: None,
+        emission: Some(Emission::PerSecond(Uint128::from(100u32))),
         start_time: None,
         duration: Some(2400u64),
     };
@@ -132,7 +138,8 @@ fn cannot_set_new_asset_incentive_with_invalid_params() {
 
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: Some(Uint128::from(100u32)),
+        kind: None,
+        emission: Some(Emission::PerSecond(Uint128::from(100u32))),
         start_time: Some(block_time.seconds()),
         duration: Some(0u64),
     };
@@ -146,7 +153,8 @@ fn cannot_set_new_asset_incentive_with_invalid_params() {
 
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: Some(Uint128::from(100u32)),
+        kind: None,
+        emission: Some(Emission::PerSecond(Uint128::from(100u32))),
         start_time: Some(block_time.minus_seconds(1u64).seconds()),
         duration: Some(100u64),
     };
@@ -171,7 +179,8 @@ fn set_new_asset_incentive() {
     });
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: Some(Uint128::new(100)),
+        kind: None,
+        emission: Some(Emission::PerSecond(Uint128::new(100))),
         start_time: Some(block_time.seconds()),
         duration: Some(86400),
     };
@@ -182,6 +191,7 @@ fn set_new_asset_incentive() {
         vec![
             attr("action", "set_asset_incentive"),
             attr("denom", "uosmo"),
+            attr("kind", "collateral"),
             attr("emission_per_second", "100"),
             attr("start_time", block_time.seconds().to_string()),
             attr("duration", "86400"),
@@ -190,13 +200,52 @@ fn set_new_asset_incentive() {
 
     let asset_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
 
-    assert_eq!(asset_incentive.emission_per_second, Uint128::new(100));
+    assert_eq!(asset_incentive.emission, Emission::PerSecond(Uint128::new(100)));
     assert_eq!(asset_incentive.index, Decimal::zero());
     assert_eq!(asset_incentive.last_updated, 1_000_000);
     assert_eq!(asset_incentive.start_time, block_time.seconds());
     assert_eq!(asset_incentive.duration, 86400);
 }
 
+#[test]
+fn set_new_asset_incentive_per_block() {
+    let mut deps = th_setup();
+
+    let info = mock_info("owner", &[]);
+    let block_time = Timestamp::from_seconds(1_000_000);
+    let env = mars_testing::mock_env(MockEnvParams {
+        block_time,
+        block_height: 5_000,
+    });
+    let msg = ExecuteMsg::SetAssetIncentive {
+        denom: "uosmo".to_string(),
+        kind: None,
+        emission: Some(Emission::PerBlock(Uint128::new(600))),
+        start_time: Some(block_time.seconds()),
+        duration: Some(86400),
+    };
+
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "set_asset_incentive"),
+            attr("denom", "uosmo"),
+            attr("kind", "collateral"),
+            attr("emission_per_block", "600"),
+            attr("start_time", block_time.seconds().to_string()),
+            attr("duration", "86400"),
+        ]
+    );
+
+    let asset_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
+
+    assert_eq!(asset_incentive.emission, Emission::PerBlock(Uint128::new(600)));
+    assert_eq!(asset_incentive.index, Decimal::zero());
+    assert_eq!(asset_incentive.last_updated, 1_000_000);
+    assert_eq!(asset_incentive.last_updated_height, 5_000);
+}
+
 #[test]
 fn set_existing_asset_incentive_with_different_start_time() {
     let mut deps = th_setup();
@@ -216,11 +265,14 @@ fn set_existing_asset_incentive_with_different_start_time() {
             deps.as_mut().storage,
             "uosmo",
             &AssetIncentive {
-                emission_per_second: Uint128::new(124),
+                emission: Emission::PerSecond(Uint128::new(124)),
                 start_time,
                 duration,
                 index: Decimal::zero(),
                 last_updated: start_time,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
             },
         )
         .unwrap();
@@ -233,7 +285,8 @@ fn set_existing_asset_incentive_with_different_start_time() {
     });
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: None,
+        kind: None,
+        emission: None,
         start_time: Some(block_time.seconds() + 10),
         duration: None,
     };
@@ -253,7 +306,8 @@ fn set_existing_asset_incentive_with_different_start_time() {
     });
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: None,
+        kind: None,
+        emission: None,
         start_time: Some(block_time.seconds() - 1),
         duration: None,
     };
@@ -274,7 +328,8 @@ fn set_existing_asset_incentive_with_different_start_time() {
     });
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: None,
+        kind: None,
+        emission: None,
         start_time: Some(start_time),
         duration: None,
     };
@@ -291,7 +346,8 @@ fn set_existing_asset_incentive_with_different_start_time() {
     });
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: None,
+        kind: None,
+        emission: None,
         start_time: None,
         duration: None,
     };
@@ -311,7 +367,8 @@ fn set_existing_asset_incentive_with_different_start_time() {
     });
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: None,
+        kind: None,
+        emission: None,
         start_time: None,
         duration: None,
     };
@@ -341,11 +398,14 @@ fn set_existing_asset_incentive_with_different_duration() {
             deps.as_mut().storage,
             "uosmo",
             &AssetIncentive {
-                emission_per_second: Uint128::new(124),
+                emission: Emission::PerSecond(Uint128::new(124)),
                 start_time,
                 duration,
                 index: Decimal::zero(),
                 last_updated: start_time,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
             },
         )
         .unwrap();
@@ -358,7 +418,8 @@ fn set_existing_asset_incentive_with_different_duration() {
     });
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: None,
+        kind: None,
+        emission: None,
         start_time: None,
         duration: Some(0),
     };
@@ -378,7 +439,8 @@ fn set_existing_asset_incentive_with_different_duration() {
     });
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: None,
+        kind: None,
+        emission: None,
         start_time: None,
         duration: Some(duration - 1),
     };
@@ -399,7 +461,8 @@ fn set_existing_asset_incentive_with_different_duration() {
     });
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: None,
+        kind: None,
+        emission: None,
         start_time: None,
         duration: Some(duration),
     };
@@ -418,14 +481,15 @@ fn set_existing_asset_incentive_with_different_duration() {
     });
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: "uosmo".to_string(),
-        emission_per_second: Some(Uint128::new(300)),
+        kind: None,
+        emission: Some(Emission::PerSecond(Uint128::new(300))),
         start_time: None,
         duration: None,
     };
     let prev_asset_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
     execute(deps.as_mut(), env, info, msg).unwrap();
     let asset_incentive = ASSET_INCENTIVES.load(deps.as_ref().storage, "uosmo").unwrap();
-    assert_eq!(asset_incentive.emission_per_second, Uint128::new(300));
+    assert_eq!(asset_incentive.emission, Emission::PerSecond(Uint128::new(300)));
     assert_eq!(asset_incentive.start_time, prev_asset_incentive.start_time);
     assert_eq!(asset_incentive.duration, prev_asset_incentive.duration);
     assert_eq!(asset_incentive.last_updated, block_time.seconds());
@@ -453,11 +517,14 @@ fn set_existing_asset_incentive_with_index_updated_during_incentive() {
             deps.as_mut().storage,
             denom,
             &AssetIncentive {
-                emission_per_second: Uint128::new(100),
+                emission: Emission::PerSecond(Uint128::new(100)),
                 start_time,
                 duration,
                 index: Decimal::from_ratio(1_u128, 2_u128),
                 last_updated,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
             },
         )
         .unwrap();
@@ -471,7 +538,8 @@ fn set_existing_asset_incentive_with_index_updated_during_incentive() {
     });
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: denom.to_string(),
-        emission_per_second: Some(Uint128::new(200)),
+        kind: None,
+        emission: Some(Emission::PerSecond(Uint128::new(200))),
         start_time: None,
         duration: None,
     };
@@ -483,6 +551,7 @@ fn set_existing_asset_incentive_with_index_updated_during_incentive() {
         vec![
             attr("action", "set_asset_incentive"),
             attr("denom", denom),
+            attr("kind", "collateral"),
             attr("emission_per_second", "200"),
             attr("start_time", start_time.to_string()),
             attr("duration", duration.to_string()),
@@ -500,7 +569,7 @@ fn set_existing_asset_incentive_with_index_updated_during_incentive() {
     )
     .unwrap();
 
-    assert_eq!(asset_incentive.emission_per_second, Uint128::new(200));
+    assert_eq!(asset_incentive.emission, Emission::PerSecond(Uint128::new(200)));
     assert_eq!(asset_incentive.start_time, start_time);
     assert_eq!(asset_incentive.duration, duration);
     assert_eq!(asset_incentive.index, expected_index);
@@ -529,11 +598,14 @@ fn set_existing_asset_incentive_with_index_updated_after_incentive() {
             deps.as_mut().storage,
             denom,
             &AssetIncentive {
-                emission_per_second: Uint128::new(120),
+                emission: Emission::PerSecond(Uint128::new(120)),
                 start_time,
                 duration,
                 index: Decimal::from_ratio(1_u128, 4_u128),
                 last_updated,
+                last_updated_height: 0,
+                unemitted: Uint128::zero(),
+                schedules: vec![],
             },
         )
         .unwrap();
@@ -547,7 +619,8 @@ fn set_existing_asset_incentive_with_index_updated_after_incentive() {
     });
     let msg = ExecuteMsg::SetAssetIncentive {
         denom: denom.to_string(),
-        emission_per_second: Some(Uint128::new(215)),
+        kind: None,
+        emission: Some(Emission::PerSecond(Uint128::new(215))),
         start_time: Some(block_time.seconds()),
         duration: None,
     };
@@ -559,6 +632,7 @@ fn set_existing_asset_incentive_with_index_updated_after_incentive() {
         vec![
             attr("action", "set_asset_incentive"),
             attr("denom", denom),
+            attr("kind", "collateral"),
             attr("emission_per_second", "215"),
             attr("start_time", block_time.seconds().to_string()),
             attr("duration", duration.to_string()),
@@ -576,7 +650,7 @@ fn set_existing_asset_incentive_with_index_updated_after_incentive() {
     )
     .unwrap();
 
-    assert_eq!(asset_incentive.emission_per_second, Uint128::new(215));
+    assert_eq!(asset_incentive.emission, Emission::PerSecond(Uint128::new(215)));
     assert_eq!(asset_incentive.start_time, block_time.seconds());
     assert_eq!(asset_incentive.duration, duration);
     assert_eq!(asset_incentive.index, expected_index);
@@ -7,12 +7,16 @@ use cosmwasm_std::{
 use cw_storage_plus::{Bound, Item, Map};
 use mars_owner::{Owner, OwnerInit::SetInitialOwner, OwnerUpdate};
 use mars_red_bank_types::oracle::{
-    Config, ConfigResponse, ExecuteMsg, InstantiateMsg, PriceResponse, PriceSourceResponse,
-    QueryMsg,
+    Config, ConfigResponse, ExecuteMsg, InstantiateMsg, PriceResponse, PriceSnapshot,
+    PriceSnapshotResponse, PriceSourceResponse, PriceWithTimestampResponse, QueryMsg,
+    TwapWindowResponse,
 };
 use mars_utils::helpers::validate_native_denom;
 
-use crate::{error::ContractResult, PriceSourceChecked, PriceSourceUnchecked};
+use crate::{
+    error::{ContractError, ContractResult},
+    PriceSourceChecked, PriceSourceUnchecked,
+};
 
 const DEFAULT_LIMIT: u32 = 10;
 const MAX_LIMIT: u32 = 30;
@@ -29,6 +33,8 @@ where
     pub config: Item<'a, Config>,
     /// The price source of each coin denom
     pub price_sources: Map<'a, &'a str, P>,
+    /// The most recent price snapshot of each coin denom
+    pub price_snapshots: Map<'a, &'a str, PriceSnapshot>,
     /// Phantom data holds the unchecked price source type
     pub unchecked_price_source: PhantomData<PU>,
     /// Phantom data holds the custom query type
@@ -46,6 +52,7 @@ where
             owner: Owner::new("owner"),
             config: Item::new("config"),
             price_sources: Map::new("price_sources"),
+            price_snapshots: Map::new("price_snapshots"),
             unchecked_price_source: PhantomData,
             custom_query: PhantomData,
         }
@@ -82,6 +89,7 @@ where
     pub fn execute(
         &self,
         deps: DepsMut<C>,
+        env: Env,
         info: MessageInfo,
         msg: ExecuteMsg<PU>,
     ) -> ContractResult<Response> {
@@ -97,6 +105,9 @@ where
             ExecuteMsg::UpdateConfig {
                 base_denom,
             } => self.update_config(deps, info.sender, base_denom),
+            ExecuteMsg::SnapshotPrice {
+                denom,
+            } => self.snapshot_price(deps, env, info.sender, denom),
         }
     }
 
@@ -117,6 +128,19 @@ where
                 start_after,
                 limit,
             } => to_binary(&self.query_prices(deps, env, start_after, limit)?),
+            QueryMsg::PriceWithTimestamp {
+                denom,
+            } => to_binary(&self.query_price_with_timestamp(deps, env, denom)?),
+            QueryMsg::PriceSnapshot {
+                denom,
+                max_age,
+            } => to_binary(&self.query_price_snapshot(deps, env, denom, max_age)?),
+            QueryMsg::PriceSourceCount {
+                denom,
+            } => to_binary(&self.query_price_source_count(deps, denom)?),
+            QueryMsg::TwapWindow {
+                denom,
+            } => to_binary(&self.query_twap_window(deps, env, denom)?),
         };
         res.map_err(Into::into)
     }
@@ -191,6 +215,49 @@ where
         Ok(response)
     }
 
+    fn snapshot_price(
+        &self,
+        deps: DepsMut<C>,
+        env: Env,
+        sender_addr: Addr,
+        denom: String,
+    ) -> ContractResult<Response> {
+        // anyone being able to snapshot would let them pick the block whose price is then used
+        // for the whole freshness window
+        self.owner.assert_owner(deps.storage, &sender_addr)?;
+
+        // a spot price snapshot would only freeze the price of the block it's taken at, so only
+        // prices averaged over a TWAP window can be snapshotted
+        let cfg = self.config.load(deps.storage)?;
+        let price_source = self.price_sources.load(deps.storage, &denom)?;
+        if price_source
+            .twap_window(&deps.as_ref(), &env, &denom, &cfg, &self.price_sources)?
+            .is_none()
+        {
+            return Err(ContractError::InvalidPriceSource {
+                reason: format!("price of {denom} is not TWAP-based"),
+            });
+        }
+
+        let price = self.query_price(deps.as_ref(), env.clone(), denom.clone())?.price;
+        let timestamp = env.block.time.seconds();
+
+        self.price_snapshots.save(
+            deps.storage,
+            &denom,
+            &PriceSnapshot {
+                price,
+                timestamp,
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "snapshot_price")
+            .add_attribute("denom", denom)
+            .add_attribute("price", price.to_string())
+            .add_attribute("timestamp", timestamp.to_string()))
+    }
+
     fn query_config(&self, deps: Deps<C>) -> StdResult<ConfigResponse> {
         let owner_state = self.owner.query(deps.storage)?;
         let cfg = self.config.load(deps.storage)?;
@@ -212,6 +279,10 @@ where
         })
     }
 
+    fn query_price_source_count(&self, deps: Deps<C>, denom: String) -> StdResult<u32> {
+        Ok(self.price_sources.load(deps.storage, &denom)?.source_count())
+    }
+
     fn query_price_sources(
         &self,
         deps: Deps<C>,
@@ -243,6 +314,81 @@ where
         })
     }
 
+    fn query_price_with_timestamp(
+        &self,
+        deps: Deps<C>,
+        env: Env,
+        denom: String,
+    ) -> ContractResult<PriceWithTimestampResponse> {
+        let cfg = self.config.load(deps.storage)?;
+        let price_source = self.price_sources.load(deps.storage, &denom)?;
+        Ok(PriceWithTimestampResponse {
+            price: price_source.query_price(&deps, &env, &denom, &cfg, &self.price_sources)?,
+            timestamp: price_source.price_timestamp(
+                &deps,
+                &env,
+                &denom,
+                &cfg,
+                &self.price_sources,
+            )?,
+            denom,
+        })
+    }
+
+    fn query_twap_window(
+        &self,
+        deps: Deps<C>,
+        env: Env,
+        denom: String,
+    ) -> ContractResult<TwapWindowResponse> {
+        let cfg = self.config.load(deps.storage)?;
+        let price_source = self.price_sources.load(deps.storage, &denom)?;
+
+        let window_size =
+            match price_source.twap_window(&deps, &env, &denom, &cfg, &self.price_sources)? {
+                Some(window_size) => window_size,
+                None => {
+                    return Err(ContractError::InvalidPriceSource {
+                        reason: format!("price of {denom} is not TWAP-based"),
+                    })
+                }
+            };
+
+        Ok(TwapWindowResponse {
+            start_time: env.block.time.seconds().saturating_sub(window_size),
+            denom,
+            window_size,
+        })
+    }
+
+    fn query_price_snapshot(
+        &self,
+        deps: Deps<C>,
+        env: Env,
+        denom: String,
+        max_age: Option<u64>,
+    ) -> ContractResult<PriceSnapshotResponse> {
+        let snapshot = self.price_snapshots.load(deps.storage, &denom)?;
+
+        let now = env.block.time.seconds();
+        if let Some(max_age) = max_age {
+            if now.saturating_sub(snapshot.timestamp) > max_age {
+                return Err(ContractError::PriceSnapshotStale {
+                    denom,
+                    timestamp: snapshot.timestamp,
+                    now,
+                    max_age,
+                });
+            }
+        }
+
+        Ok(PriceSnapshotResponse {
+            denom,
+            price: snapshot.price,
+            timestamp: snapshot.timestamp,
+        })
+    }
+
     fn query_prices(
         &self,
         deps: Deps<C>,
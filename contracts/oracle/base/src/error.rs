@@ -48,6 +48,23 @@ pub enum ContractError {
     InvalidPrice {
         reason: String,
     },
+
+    #[error("Redemption rate is stale: last updated: {last_updated}, now: {now}, max staleness: {max_staleness}")]
+    RedemptionRateStale {
+        last_updated: u64,
+        now: u64,
+        max_staleness: u64,
+    },
+
+    #[error(
+        "Price snapshot of {denom} is stale: taken at: {timestamp}, now: {now}, max age: {max_age}"
+    )]
+    PriceSnapshotStale {
+        denom: String,
+        timestamp: u64,
+        now: u64,
+        max_age: u64,
+    },
 }
 
 pub type ContractResult<T> = Result<T, ContractError>;
@@ -45,4 +45,37 @@ where
         config: &Config,
         price_sources: &Map<&str, Self>,
     ) -> ContractResult<Decimal>;
+
+    /// Number of independent sources the price can be obtained from, which is one unless the
+    /// price source falls back to others
+    fn source_count(&self) -> u32 {
+        1
+    }
+
+    /// Window size in seconds of the TWAP the current price is computed with, or `None` if the
+    /// price isn't TWAP-based. For price sources falling back to others, this is the window of the
+    /// source the price is currently obtained from.
+    fn twap_window(
+        &self,
+        _deps: &Deps<C>,
+        _env: &Env,
+        _denom: &str,
+        _config: &Config,
+        _price_sources: &Map<&str, Self>,
+    ) -> ContractResult<Option<u64>> {
+        Ok(None)
+    }
+
+    /// Time (in seconds) of the oldest data the price is computed from. Defaults to the current
+    /// block time, which is right for prices computed from on-chain state when queried.
+    fn price_timestamp(
+        &self,
+        _deps: &Deps<C>,
+        env: &Env,
+        _denom: &str,
+        _config: &Config,
+        _price_sources: &Map<&str, Self>,
+    ) -> ContractResult<u64> {
+        Ok(env.block.time.seconds())
+    }
 }
@@ -34,11 +34,11 @@ pub mod entry {
     #[entry_point]
     pub fn execute(
         deps: DepsMut,
-        _env: Env,
+        env: Env,
         info: MessageInfo,
         msg: ExecuteMsg<OsmosisPriceSourceUnchecked>,
     ) -> ContractResult<Response> {
-        OsmosisOracle::default().execute(deps, info, msg)
+        OsmosisOracle::default().execute(deps, env, info, msg)
     }
 
     #[entry_point]
@@ -1,15 +1,17 @@
-use std::{cmp::min, fmt};
+use std::{cmp::min, collections::HashSet, fmt};
 
 use cosmwasm_std::{Addr, Decimal, Decimal256, Deps, Empty, Env, Isqrt, Uint128, Uint256};
 use cw_storage_plus::Map;
 use mars_oracle_base::{
-    ContractError::InvalidPrice, ContractResult, PriceSourceChecked, PriceSourceUnchecked,
+    ContractError::{self, InvalidPrice, RedemptionRateStale},
+    ContractResult, PriceSourceChecked, PriceSourceUnchecked,
 };
 use mars_osmosis::helpers::{
     query_arithmetic_twap_price, query_geometric_twap_price, query_pool, query_spot_price,
     recovered_since_downtime_of_length, Pool,
 };
 use mars_red_bank_types::oracle::Config;
+use mars_utils::helpers::validate_native_denom;
 use pyth_sdk_cw::{query_price_feed, PriceIdentifier};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -205,6 +207,31 @@ pub enum OsmosisPriceSource<T> {
         /// Params to query redemption rate
         redemption_rate: RedemptionRate<T>,
     },
+    /// Liquid Staking Derivatives (LSD) price based on the Stride redemption rate alone.
+    ///
+    /// Equation to calculate the price:
+    /// stAsset/USD = stAsset/Asset Redemption Rate * Asset/USD
+    ///
+    /// Unlike `Lsd`, this doesn't need a stAsset/Asset Osmosis pool, but it doesn't guard against
+    /// the stAsset trading below its redemption rate either.
+    ///
+    /// NOTE: Asset/USD price source should be available in the Mars Oracle contract.
+    StrideRedemptionRate {
+        /// Transitive denom for which we query price in USD. It refers to 'Asset' in the equation:
+        /// stAsset/USD = stAsset/Asset Redemption Rate * Asset/USD
+        transitive_denom: String,
+
+        /// Params to query redemption rate
+        redemption_rate: RedemptionRate<T>,
+    },
+    /// Tries each price source in order and returns the price of the first one that succeeds.
+    /// Any failure of a source, including staleness and confidence rejections, falls through to
+    /// the next one. Fails only if all of them fail.
+    ///
+    /// NOTE: Chains can't be nested.
+    Chain {
+        sources: Vec<OsmosisPriceSource<T>>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -297,6 +324,22 @@ impl fmt::Display for OsmosisPriceSourceChecked {
                 } = redemption_rate;
                 format!("lsd:{transitive_denom}:{pool_id}:{window_size}:{dd_fmt}:{contract_addr}:{max_staleness}")
             }
+            OsmosisPriceSource::StrideRedemptionRate {
+                transitive_denom,
+                redemption_rate,
+            } => {
+                let RedemptionRate {
+                    contract_addr,
+                    max_staleness,
+                } = redemption_rate;
+                format!("stride_redemption_rate:{transitive_denom}:{contract_addr}:{max_staleness}")
+            }
+            OsmosisPriceSource::Chain {
+                sources,
+            } => {
+                let sources_fmt: Vec<_> = sources.iter().map(ToString::to_string).collect();
+                format!("chain:[{}]", sources_fmt.join(","))
+            }
         };
         write!(f, "{label}")
     }
@@ -408,6 +451,44 @@ impl PriceSourceUnchecked<OsmosisPriceSourceChecked, Empty> for OsmosisPriceSour
                     },
                 })
             }
+            OsmosisPriceSourceUnchecked::StrideRedemptionRate {
+                transitive_denom,
+                redemption_rate,
+            } => {
+                if transitive_denom == denom {
+                    return Err(ContractError::InvalidPriceSource {
+                        reason: "transitive denom must differ from the denom".to_string(),
+                    });
+                }
+                validate_native_denom(transitive_denom)?;
+                Ok(OsmosisPriceSourceChecked::StrideRedemptionRate {
+                    transitive_denom: transitive_denom.to_string(),
+                    redemption_rate: RedemptionRate {
+                        contract_addr: deps.api.addr_validate(&redemption_rate.contract_addr)?,
+                        max_staleness: redemption_rate.max_staleness,
+                    },
+                })
+            }
+            OsmosisPriceSourceUnchecked::Chain {
+                sources,
+            } => {
+                if sources.is_empty() {
+                    return Err(ContractError::InvalidPriceSource {
+                        reason: "chain must contain at least one price source".to_string(),
+                    });
+                }
+                if sources.iter().any(|source| matches!(source, OsmosisPriceSource::Chain { .. })) {
+                    return Err(ContractError::InvalidPriceSource {
+                        reason: "chains can't be nested".to_string(),
+                    });
+                }
+                Ok(OsmosisPriceSourceChecked::Chain {
+                    sources: sources
+                        .iter()
+                        .map(|source| source.clone().validate(deps, denom, base_denom))
+                        .collect::<ContractResult<_>>()?,
+                })
+            }
         }
     }
 }
@@ -518,6 +599,183 @@ impl PriceSourceChecked<Empty> for OsmosisPriceSourceChecked {
                     price_sources,
                 )
             }
+            OsmosisPriceSourceChecked::StrideRedemptionRate {
+                transitive_denom,
+                redemption_rate,
+            } => Self::query_stride_redemption_rate_price(
+                deps,
+                env,
+                denom,
+                transitive_denom,
+                redemption_rate,
+                config,
+                price_sources,
+            ),
+            OsmosisPriceSourceChecked::Chain {
+                sources,
+            } => {
+                let mut errors = vec![];
+                for source in sources {
+                    match source.query_price(deps, env, denom, config, price_sources) {
+                        Ok(price) => return Ok(price),
+                        Err(err) => errors.push(format!("{source}: {err}")),
+                    }
+                }
+                Err(InvalidPrice {
+                    reason: format!("all price sources in the chain failed: {}", errors.join("; ")),
+                })
+            }
+        }
+    }
+
+    fn source_count(&self) -> u32 {
+        match self {
+            // sources reading the same pool or feed, e.g. a spot price and a TWAP of one pool,
+            // aren't independent of each other so they count once
+            OsmosisPriceSourceChecked::Chain {
+                sources,
+            } => sources.iter().map(Self::feed).collect::<HashSet<_>>().len() as u32,
+            _ => 1,
+        }
+    }
+
+    fn twap_window(
+        &self,
+        deps: &Deps,
+        env: &Env,
+        denom: &str,
+        config: &Config,
+        price_sources: &Map<&str, Self>,
+    ) -> ContractResult<Option<u64>> {
+        match self {
+            OsmosisPriceSourceChecked::ArithmeticTwap {
+                window_size,
+                ..
+            }
+            | OsmosisPriceSourceChecked::GeometricTwap {
+                window_size,
+                ..
+            }
+            | OsmosisPriceSourceChecked::StakedGeometricTwap {
+                window_size,
+                ..
+            } => Ok(Some(*window_size)),
+            OsmosisPriceSourceChecked::Lsd {
+                geometric_twap,
+                ..
+            } => Ok(Some(geometric_twap.window_size)),
+            OsmosisPriceSourceChecked::Chain {
+                sources,
+            } => {
+                // the price comes from the first source that succeeds
+                for source in sources {
+                    if source.query_price(deps, env, denom, config, price_sources).is_ok() {
+                        return source.twap_window(deps, env, denom, config, price_sources);
+                    }
+                }
+                Err(InvalidPrice {
+                    reason: "all price sources in the chain failed".to_string(),
+                })
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn price_timestamp(
+        &self,
+        deps: &Deps,
+        env: &Env,
+        denom: &str,
+        config: &Config,
+        price_sources: &Map<&str, Self>,
+    ) -> ContractResult<u64> {
+        match self {
+            OsmosisPriceSourceChecked::XykLiquidityToken {
+                pool_id,
+            } => {
+                let pool = query_pool(&deps.querier, *pool_id)?;
+                let coin0 = Pool::unwrap_coin(&pool.pool_assets[0].token)?;
+                let coin1 = Pool::unwrap_coin(&pool.pool_assets[1].token)?;
+
+                let coin0_timestamp = Self::transitive_price_timestamp(
+                    deps,
+                    env,
+                    &coin0.denom,
+                    config,
+                    price_sources,
+                )?;
+                let coin1_timestamp = Self::transitive_price_timestamp(
+                    deps,
+                    env,
+                    &coin1.denom,
+                    config,
+                    price_sources,
+                )?;
+
+                Ok(min(coin0_timestamp, coin1_timestamp))
+            }
+            OsmosisPriceSourceChecked::StakedGeometricTwap {
+                transitive_denom,
+                ..
+            } => {
+                Self::transitive_price_timestamp(deps, env, transitive_denom, config, price_sources)
+            }
+            OsmosisPriceSourceChecked::Pyth {
+                contract_addr,
+                price_feed_id,
+                ..
+            } => {
+                let price_feed =
+                    query_price_feed(&deps.querier, contract_addr.clone(), *price_feed_id)?
+                        .price_feed;
+                let publish_time = price_feed.get_price_unchecked().publish_time.max(0) as u64;
+
+                Ok(min(
+                    publish_time,
+                    Self::transitive_price_timestamp(deps, env, "usd", config, price_sources)?,
+                ))
+            }
+            OsmosisPriceSourceChecked::Lsd {
+                transitive_denom,
+                redemption_rate,
+                ..
+            }
+            | OsmosisPriceSourceChecked::StrideRedemptionRate {
+                transitive_denom,
+                redemption_rate,
+            } => {
+                let rr = query_redemption_rate(
+                    &deps.querier,
+                    redemption_rate.contract_addr.clone(),
+                    denom.to_string(),
+                    transitive_denom.to_string(),
+                )?;
+
+                Ok(min(
+                    rr.last_updated,
+                    Self::transitive_price_timestamp(
+                        deps,
+                        env,
+                        transitive_denom,
+                        config,
+                        price_sources,
+                    )?,
+                ))
+            }
+            OsmosisPriceSourceChecked::Chain {
+                sources,
+            } => {
+                // the price comes from the first source that succeeds
+                for source in sources {
+                    if source.query_price(deps, env, denom, config, price_sources).is_ok() {
+                        return source.price_timestamp(deps, env, denom, config, price_sources);
+                    }
+                }
+                Err(InvalidPrice {
+                    reason: "all price sources in the chain failed".to_string(),
+                })
+            }
+            _ => Ok(env.block.time.seconds()),
         }
     }
 }
@@ -543,6 +801,70 @@ impl OsmosisPriceSourceChecked {
         Ok(())
     }
 
+    /// Identifies the pool or feed a price source reads its price from
+    fn feed(&self) -> String {
+        match self {
+            OsmosisPriceSourceChecked::Fixed {
+                ..
+            } => "fixed".to_string(),
+            OsmosisPriceSourceChecked::Spot {
+                pool_id,
+            }
+            | OsmosisPriceSourceChecked::ArithmeticTwap {
+                pool_id,
+                ..
+            }
+            | OsmosisPriceSourceChecked::GeometricTwap {
+                pool_id,
+                ..
+            }
+            | OsmosisPriceSourceChecked::XykLiquidityToken {
+                pool_id,
+            }
+            | OsmosisPriceSourceChecked::StakedGeometricTwap {
+                pool_id,
+                ..
+            } => format!("pool:{pool_id}"),
+            OsmosisPriceSourceChecked::Pyth {
+                contract_addr,
+                price_feed_id,
+                ..
+            } => format!("pyth:{contract_addr}:{price_feed_id}"),
+            OsmosisPriceSourceChecked::Lsd {
+                geometric_twap,
+                redemption_rate,
+                ..
+            } => format!("lsd:{}:{}", geometric_twap.pool_id, redemption_rate.contract_addr),
+            OsmosisPriceSourceChecked::StrideRedemptionRate {
+                redemption_rate,
+                ..
+            } => format!("redemption_rate:{}", redemption_rate.contract_addr),
+            OsmosisPriceSourceChecked::Chain {
+                sources,
+            } => {
+                let feeds: Vec<_> = sources.iter().map(Self::feed).collect();
+                format!("chain:[{}]", feeds.join(","))
+            }
+        }
+    }
+
+    /// Timestamp of the price of another denom that a price is derived from
+    fn transitive_price_timestamp(
+        deps: &Deps,
+        env: &Env,
+        denom: &str,
+        config: &Config,
+        price_sources: &Map<&str, Self>,
+    ) -> ContractResult<u64> {
+        price_sources.load(deps.storage, denom)?.price_timestamp(
+            deps,
+            env,
+            denom,
+            config,
+            price_sources,
+        )
+    }
+
     /// The calculation of the value of liquidity token, see: https://blog.alphafinance.io/fair-lp-token-pricing/.
     /// This formulation avoids a potential sandwich attack that distorts asset prices by a flashloan.
     ///
@@ -651,25 +973,16 @@ impl OsmosisPriceSourceChecked {
             start_time,
         )?;
 
-        // query redemption rate
-        let rr = query_redemption_rate(
-            &deps.querier,
-            redemption_rate.contract_addr.clone(),
-            denom.to_string(),
-            transitive_denom.to_string(),
+        let exchange_rate = Self::query_checked_redemption_rate(
+            deps,
+            env,
+            denom,
+            transitive_denom,
+            &redemption_rate,
         )?;
-        // Check if the redemption rate is not too old
-        if (current_time - rr.last_updated) > redemption_rate.max_staleness {
-            return Err(InvalidPrice {
-                reason: format!(
-                    "redemption rate update time is too old/stale. last updated: {}, now: {}",
-                    rr.last_updated, current_time
-                ),
-            });
-        }
 
         // min from geometric TWAP and exchange rate
-        let min_price = min(staked_price, rr.exchange_rate);
+        let min_price = min(staked_price, exchange_rate);
 
         // use current price source
         let transitive_price = price_sources.load(deps.storage, transitive_denom)?.query_price(
@@ -683,6 +996,74 @@ impl OsmosisPriceSourceChecked {
         min_price.checked_mul(transitive_price).map_err(Into::into)
     }
 
+    /// Staked asset price quoted in USD, based on the redemption rate alone.
+    ///
+    /// stAsset/USD = stAsset/Asset Redemption Rate * Asset/USD
+    fn query_stride_redemption_rate_price(
+        deps: &Deps,
+        env: &Env,
+        denom: &str,
+        transitive_denom: &str,
+        redemption_rate: &RedemptionRate<Addr>,
+        config: &Config,
+        price_sources: &Map<&str, OsmosisPriceSourceChecked>,
+    ) -> ContractResult<Decimal> {
+        let exchange_rate = Self::query_checked_redemption_rate(
+            deps,
+            env,
+            denom,
+            transitive_denom,
+            redemption_rate,
+        )?;
+
+        // use current price source
+        let transitive_price = price_sources.load(deps.storage, transitive_denom)?.query_price(
+            deps,
+            env,
+            transitive_denom,
+            config,
+            price_sources,
+        )?;
+
+        exchange_rate.checked_mul(transitive_price).map_err(Into::into)
+    }
+
+    /// Query the stAsset/Asset redemption rate from Stride, rejecting it if it's too old or zero
+    fn query_checked_redemption_rate(
+        deps: &Deps,
+        env: &Env,
+        denom: &str,
+        transitive_denom: &str,
+        redemption_rate: &RedemptionRate<Addr>,
+    ) -> ContractResult<Decimal> {
+        let current_time = env.block.time.seconds();
+
+        // query redemption rate
+        let rr = query_redemption_rate(
+            &deps.querier,
+            redemption_rate.contract_addr.clone(),
+            denom.to_string(),
+            transitive_denom.to_string(),
+        )?;
+        // Check if the redemption rate is not too old
+        if current_time.saturating_sub(rr.last_updated) > redemption_rate.max_staleness {
+            return Err(RedemptionRateStale {
+                last_updated: rr.last_updated,
+                now: current_time,
+                max_staleness: redemption_rate.max_staleness,
+            });
+        }
+
+        // A zero rate would make the stAsset worthless, which is never a valid price
+        if rr.exchange_rate.is_zero() {
+            return Err(InvalidPrice {
+                reason: format!("redemption rate of {denom} to {transitive_denom} is zero"),
+            });
+        }
+
+        Ok(rr.exchange_rate)
+    }
+
     fn query_pyth_price(
         deps: &Deps,
         env: &Env,
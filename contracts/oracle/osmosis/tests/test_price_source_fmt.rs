@@ -155,3 +155,20 @@ fn display_lsd_price_source() {
     };
     assert_eq!(ps.to_string(), "lsd:transitive:456:380:Some(Duration30m:552):osmo1zw4fxj4pt0pu0jdd7cs6gecdj3pvfxhhtgkm4w2y44jp60hywzvssud6uc:1234");
 }
+
+#[test]
+fn display_stride_redemption_rate_price_source() {
+    let ps = OsmosisPriceSourceChecked::StrideRedemptionRate {
+        transitive_denom: "transitive".to_string(),
+        redemption_rate: RedemptionRate {
+            contract_addr: Addr::unchecked(
+                "osmo1zw4fxj4pt0pu0jdd7cs6gecdj3pvfxhhtgkm4w2y44jp60hywzvssud6uc",
+            ),
+            max_staleness: 1234,
+        },
+    };
+    assert_eq!(
+        ps.to_string(),
+        "stride_redemption_rate:transitive:osmo1zw4fxj4pt0pu0jdd7cs6gecdj3pvfxhhtgkm4w2y44jp60hywzvssud6uc:1234"
+    );
+}
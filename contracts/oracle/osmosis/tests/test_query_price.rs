@@ -10,7 +10,9 @@ use mars_oracle_osmosis::{
     contract::entry, scale_pyth_price, stride::RedemptionRateResponse, Downtime, DowntimeDetector,
     GeometricTwap, OsmosisPriceSourceUnchecked, RedemptionRate,
 };
-use mars_red_bank_types::oracle::{PriceResponse, QueryMsg};
+use mars_red_bank_types::oracle::{
+    PriceResponse, PriceWithTimestampResponse, QueryMsg, TwapWindowResponse,
+};
 use mars_testing::{mock_env_at_block_time, MarsMockQuerier};
 use osmosis_std::types::osmosis::{
     poolmanager::v1beta1::SpotPriceResponse,
@@ -237,6 +239,122 @@ fn querying_geometric_twap_price_with_downtime_detector() {
     assert_eq!(res.price, Decimal::from_ratio(77777u128, 12345u128));
 }
 
+#[test]
+fn querying_price_source_count() {
+    let mut deps = helpers::setup_test_with_pools();
+
+    // both TWAPs read the same pool, so they're counted once
+    helpers::set_price_source(
+        deps.as_mut(),
+        "umars",
+        OsmosisPriceSourceUnchecked::Chain {
+            sources: vec![
+                OsmosisPriceSourceUnchecked::ArithmeticTwap {
+                    pool_id: 89,
+                    window_size: 86400,
+                    downtime_detector: None,
+                },
+                OsmosisPriceSourceUnchecked::GeometricTwap {
+                    pool_id: 89,
+                    window_size: 1800,
+                    downtime_detector: None,
+                },
+                OsmosisPriceSourceUnchecked::Fixed {
+                    price: Decimal::one(),
+                },
+            ],
+        },
+    );
+    helpers::set_price_source(
+        deps.as_mut(),
+        "uosmo",
+        OsmosisPriceSourceUnchecked::Fixed {
+            price: Decimal::one(),
+        },
+    );
+
+    let query_count = |denom: &str| -> u32 {
+        helpers::query(
+            deps.as_ref(),
+            QueryMsg::PriceSourceCount {
+                denom: denom.to_string(),
+            },
+        )
+    };
+    assert_eq!(query_count("umars"), 2);
+    assert_eq!(query_count("uosmo"), 1);
+}
+
+#[test]
+fn querying_twap_window() {
+    let mut deps = helpers::setup_test_with_pools();
+
+    helpers::set_price_source(
+        deps.as_mut(),
+        "umars",
+        OsmosisPriceSourceUnchecked::Chain {
+            sources: vec![
+                OsmosisPriceSourceUnchecked::ArithmeticTwap {
+                    pool_id: 89,
+                    window_size: 86400,
+                    downtime_detector: None,
+                },
+                OsmosisPriceSourceUnchecked::GeometricTwap {
+                    pool_id: 89,
+                    window_size: 1800,
+                    downtime_detector: None,
+                },
+            ],
+        },
+    );
+    helpers::set_price_source(
+        deps.as_mut(),
+        "uosmo",
+        OsmosisPriceSourceUnchecked::Fixed {
+            price: Decimal::one(),
+        },
+    );
+
+    // the arithmetic twap has no price, so the geometric twap's window is the one used
+    deps.querier.set_geometric_twap_price(
+        89,
+        "umars",
+        "uosmo",
+        GeometricTwapToNowResponse {
+            geometric_twap: Decimal::from_ratio(66666u128, 12345u128).to_string(),
+        },
+    );
+
+    let res: TwapWindowResponse = from_binary(
+        &entry::query(
+            deps.as_ref(),
+            mock_env_at_block_time(100_000),
+            QueryMsg::TwapWindow {
+                denom: "umars".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        TwapWindowResponse {
+            denom: "umars".to_string(),
+            window_size: 1800,
+            start_time: 98_200,
+        }
+    );
+
+    // fixed prices have no twap window
+    let err = helpers::query_err(
+        deps.as_ref(),
+        QueryMsg::TwapWindow {
+            denom: "uosmo".to_string(),
+        },
+    );
+    assert!(matches!(err, ContractError::InvalidPriceSource { .. }));
+}
+
 #[test]
 fn querying_staked_geometric_twap_price() {
     let mut deps = helpers::setup_test_with_pools();
@@ -554,6 +672,79 @@ fn setup_pyth_and_geometric_twap_for_lsd(
     (pyth_price, ustatom_uatom_price)
 }
 
+#[test]
+fn querying_price_with_timestamp() {
+    let mut deps = helpers::setup_test_with_pools();
+
+    // price source used to convert USD to base_denom
+    helpers::set_price_source(
+        deps.as_mut(),
+        "usd",
+        OsmosisPriceSourceUnchecked::Fixed {
+            price: Decimal::from_str("1000000").unwrap(),
+        },
+    );
+
+    let publish_time = 1677157333u64;
+    let (pyth_price, _) = setup_pyth_and_geometric_twap_for_lsd(&mut deps, publish_time);
+
+    let ustatom_uatom_redemption_rate = Decimal::from_ratio(1054u128, 1000u128);
+    deps.querier.set_redemption_rate(
+        "ustatom",
+        "uatom",
+        RedemptionRateResponse {
+            exchange_rate: ustatom_uatom_redemption_rate,
+            last_updated: publish_time - 100,
+        },
+    );
+    helpers::set_price_source(
+        deps.as_mut(),
+        "ustatom",
+        OsmosisPriceSourceUnchecked::StrideRedemptionRate {
+            transitive_denom: "uatom".to_string(),
+            redemption_rate: RedemptionRate {
+                contract_addr: "dummy_addr".to_string(),
+                max_staleness: 21600,
+            },
+        },
+    );
+
+    let query_price_with_timestamp = |denom: &str| -> PriceWithTimestampResponse {
+        let res = entry::query(
+            deps.as_ref(),
+            mock_env_at_block_time(publish_time + 60),
+            QueryMsg::PriceWithTimestamp {
+                denom: denom.to_string(),
+            },
+        )
+        .unwrap();
+        from_binary(&res).unwrap()
+    };
+
+    // a fixed price is computed when queried
+    assert_eq!(query_price_with_timestamp("usd").timestamp, publish_time + 60);
+
+    // a Pyth price is as old as its publish time
+    assert_eq!(
+        query_price_with_timestamp("uatom"),
+        PriceWithTimestampResponse {
+            denom: "uatom".to_string(),
+            price: pyth_price,
+            timestamp: publish_time,
+        }
+    );
+
+    // a price derived from other data is as old as the oldest of it, here the redemption rate
+    assert_eq!(
+        query_price_with_timestamp("ustatom"),
+        PriceWithTimestampResponse {
+            denom: "ustatom".to_string(),
+            price: ustatom_uatom_redemption_rate * pyth_price,
+            timestamp: publish_time - 100,
+        }
+    );
+}
+
 #[test]
 fn querying_lsd_price_if_no_transitive_denom_price_source() {
     let mut deps = helpers::setup_test_with_pools();
@@ -662,8 +853,112 @@ fn querying_lsd_price_if_redemption_rate_too_old() {
     .unwrap_err();
     assert_eq!(
         res_err,
+        ContractError::RedemptionRateStale {
+            last_updated: 1677135732,
+            now: 1677157333,
+            max_staleness,
+        }
+    );
+
+    // a redemption rate exactly `max_staleness` old is still accepted
+    deps.querier.set_redemption_rate(
+        "ustatom",
+        "uatom",
+        RedemptionRateResponse {
+            exchange_rate: ustatom_uatom_price + Decimal::one(),
+            last_updated: publish_time - max_staleness,
+        },
+    );
+    entry::query(
+        deps.as_ref(),
+        mock_env_at_block_time(publish_time),
+        QueryMsg::Price {
+            denom: "ustatom".to_string(),
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn querying_stride_redemption_rate_price() {
+    let mut deps = helpers::setup_test_with_pools();
+
+    let max_staleness = 21600u64;
+    let publish_time = 1677157333u64;
+
+    helpers::set_price_source(
+        deps.as_mut(),
+        "uatom",
+        OsmosisPriceSourceUnchecked::Fixed {
+            price: Decimal::from_ratio(85u128, 10u128),
+        },
+    );
+    helpers::set_price_source(
+        deps.as_mut(),
+        "ustatom",
+        OsmosisPriceSourceUnchecked::StrideRedemptionRate {
+            transitive_denom: "uatom".to_string(),
+            redemption_rate: RedemptionRate {
+                contract_addr: "dummy_addr".to_string(),
+                max_staleness,
+            },
+        },
+    );
+
+    let query_price = |deps: &OwnedDeps<MockStorage, MockApi, MarsMockQuerier>| {
+        entry::query(
+            deps.as_ref(),
+            mock_env_at_block_time(publish_time),
+            QueryMsg::Price {
+                denom: "ustatom".to_string(),
+            },
+        )
+    };
+
+    // setup redemption rate: stAtom/Atom
+    let ustatom_uatom_redemption_rate = Decimal::from_ratio(1054u128, 1000u128);
+    deps.querier.set_redemption_rate(
+        "ustatom",
+        "uatom",
+        RedemptionRateResponse {
+            exchange_rate: ustatom_uatom_redemption_rate,
+            last_updated: publish_time - max_staleness,
+        },
+    );
+    let res: PriceResponse = from_binary(&query_price(&deps).unwrap()).unwrap();
+    assert_eq!(res.price, ustatom_uatom_redemption_rate * Decimal::from_ratio(85u128, 10u128));
+
+    // stale redemption rate
+    deps.querier.set_redemption_rate(
+        "ustatom",
+        "uatom",
+        RedemptionRateResponse {
+            exchange_rate: ustatom_uatom_redemption_rate,
+            last_updated: publish_time - max_staleness - 1,
+        },
+    );
+    assert_eq!(
+        query_price(&deps).unwrap_err(),
+        ContractError::RedemptionRateStale {
+            last_updated: publish_time - max_staleness - 1,
+            now: publish_time,
+            max_staleness,
+        }
+    );
+
+    // zero redemption rate
+    deps.querier.set_redemption_rate(
+        "ustatom",
+        "uatom",
+        RedemptionRateResponse {
+            exchange_rate: Decimal::zero(),
+            last_updated: publish_time,
+        },
+    );
+    assert_eq!(
+        query_price(&deps).unwrap_err(),
         ContractError::InvalidPrice {
-            reason: "redemption rate update time is too old/stale. last updated: 1677135732, now: 1677157333".to_string()
+            reason: "redemption rate of ustatom to uatom is zero".to_string(),
         }
     );
 }
@@ -1168,3 +1463,57 @@ fn querying_all_prices() {
         ]
     );
 }
+
+#[test]
+fn querying_chain_price() {
+    let mut deps = helpers::setup_test_with_pools();
+
+    helpers::set_price_source(
+        deps.as_mut(),
+        "umars",
+        OsmosisPriceSourceUnchecked::Chain {
+            sources: vec![
+                OsmosisPriceSourceUnchecked::ArithmeticTwap {
+                    pool_id: 89,
+                    window_size: 86400,
+                    downtime_detector: None,
+                },
+                OsmosisPriceSourceUnchecked::GeometricTwap {
+                    pool_id: 89,
+                    window_size: 86400,
+                    downtime_detector: None,
+                },
+                OsmosisPriceSourceUnchecked::Spot {
+                    pool_id: 89,
+                },
+            ],
+        },
+    );
+
+    // none of the sources have a price
+    let err = helpers::query_err(
+        deps.as_ref(),
+        QueryMsg::Price {
+            denom: "umars".to_string(),
+        },
+    );
+    assert!(matches!(err, ContractError::InvalidPrice { .. }));
+
+    // the twap sources still fail, so the spot price is used
+    deps.querier.set_spot_price(
+        89,
+        "umars",
+        "uosmo",
+        SpotPriceResponse {
+            spot_price: Decimal::from_ratio(88888u128, 12345u128).to_string(),
+        },
+    );
+
+    let res: PriceResponse = helpers::query(
+        deps.as_ref(),
+        QueryMsg::Price {
+            denom: "umars".to_string(),
+        },
+    );
+    assert_eq!(res.price, Decimal::from_ratio(88888u128, 12345u128));
+}
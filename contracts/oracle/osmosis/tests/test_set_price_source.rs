@@ -889,6 +889,87 @@ fn setting_price_source_lsd_successfully() {
     );
 }
 
+#[test]
+fn setting_price_source_stride_redemption_rate_with_invalid_params() {
+    let mut deps = helpers::setup_test();
+
+    let mut set_price_source_stride = |transitive_denom: &str| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner"),
+            ExecuteMsg::SetPriceSource {
+                denom: "ustatom".to_string(),
+                price_source: OsmosisPriceSourceUnchecked::StrideRedemptionRate {
+                    transitive_denom: transitive_denom.to_string(),
+                    redemption_rate: RedemptionRate {
+                        contract_addr: "dummy_addr".to_string(),
+                        max_staleness: 21600,
+                    },
+                },
+            },
+        )
+    };
+
+    // transitive denom is the same as the denom
+    let err = set_price_source_stride("ustatom").unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidPriceSource {
+            reason: "transitive denom must differ from the denom".to_string()
+        }
+    );
+
+    // transitive denom is not a valid denom
+    let err = set_price_source_stride("!*jadfaefc").unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Validation(ValidationError::InvalidDenom {
+            reason: "First character is not ASCII alphabetic".to_string()
+        })
+    );
+}
+
+#[test]
+fn setting_price_source_stride_redemption_rate_successfully() {
+    let mut deps = helpers::setup_test();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::SetPriceSource {
+            denom: "ustatom".to_string(),
+            price_source: OsmosisPriceSourceUnchecked::StrideRedemptionRate {
+                transitive_denom: "uatom".to_string(),
+                redemption_rate: RedemptionRate {
+                    contract_addr: "dummy_addr".to_string(),
+                    max_staleness: 21600,
+                },
+            },
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 0);
+
+    let res: PriceSourceResponse = helpers::query(
+        deps.as_ref(),
+        QueryMsg::PriceSource {
+            denom: "ustatom".to_string(),
+        },
+    );
+    assert_eq!(
+        res.price_source,
+        OsmosisPriceSourceChecked::StrideRedemptionRate {
+            transitive_denom: "uatom".to_string(),
+            redemption_rate: RedemptionRate {
+                contract_addr: Addr::unchecked("dummy_addr"),
+                max_staleness: 21600,
+            },
+        }
+    );
+}
+
 #[test]
 fn setting_price_source_xyk_lp() {
     let mut deps = helpers::setup_test_with_pools();
@@ -0,0 +1,125 @@
+use cosmwasm_std::{from_binary, Decimal};
+use mars_oracle_base::ContractError;
+use mars_oracle_osmosis::{contract::entry, msg::ExecuteMsg, OsmosisPriceSourceUnchecked};
+use mars_owner::OwnerError::NotOwner;
+use mars_red_bank_types::oracle::{PriceSnapshotResponse, QueryMsg};
+use mars_testing::{mock_env_at_block_time, mock_info};
+use osmosis_std::types::osmosis::twap::v1beta1::ArithmeticTwapToNowResponse;
+
+mod helpers;
+
+#[test]
+fn snapshotting_price() {
+    let mut deps = helpers::setup_test_with_pools();
+
+    helpers::set_price_source(
+        deps.as_mut(),
+        "umars",
+        OsmosisPriceSourceUnchecked::ArithmeticTwap {
+            pool_id: 89,
+            window_size: 86400,
+            downtime_detector: None,
+        },
+    );
+
+    let snapshot_query = |max_age: Option<u64>| QueryMsg::PriceSnapshot {
+        denom: "umars".to_string(),
+        max_age,
+    };
+
+    // no snapshot has been taken yet
+    entry::query(deps.as_ref(), mock_env_at_block_time(10000), snapshot_query(None)).unwrap_err();
+
+    deps.querier.set_arithmetic_twap_price(
+        89,
+        "umars",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(77777u128, 12345u128).to_string(),
+        },
+    );
+    let snapshot_msg = ExecuteMsg::SnapshotPrice {
+        denom: "umars".to_string(),
+    };
+
+    // only the owner can snapshot, so a liquidator can't pick the block whose price is used
+    let err = entry::execute(
+        deps.as_mut(),
+        mock_env_at_block_time(10000),
+        mock_info("jake"),
+        snapshot_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Owner(NotOwner {}));
+
+    entry::execute(deps.as_mut(), mock_env_at_block_time(10000), mock_info("owner"), snapshot_msg)
+        .unwrap();
+
+    // the snapshot keeps the price it was taken at, even if the live price moves
+    deps.querier.set_arithmetic_twap_price(
+        89,
+        "umars",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(88888u128, 12345u128).to_string(),
+        },
+    );
+    let res: PriceSnapshotResponse = from_binary(
+        &entry::query(deps.as_ref(), mock_env_at_block_time(10300), snapshot_query(Some(300)))
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        PriceSnapshotResponse {
+            denom: "umars".to_string(),
+            price: Decimal::from_ratio(77777u128, 12345u128),
+            timestamp: 10000,
+        }
+    );
+
+    // the snapshot is too old for the requested freshness window
+    let err = entry::query(deps.as_ref(), mock_env_at_block_time(10301), snapshot_query(Some(300)))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PriceSnapshotStale {
+            denom: "umars".to_string(),
+            timestamp: 10000,
+            now: 10301,
+            max_age: 300,
+        }
+    );
+
+    // without a freshness window, any snapshot is returned
+    entry::query(deps.as_ref(), mock_env_at_block_time(10301), snapshot_query(None)).unwrap();
+}
+
+#[test]
+fn snapshotting_price_not_twap_based() {
+    let mut deps = helpers::setup_test_with_pools();
+
+    helpers::set_price_source(
+        deps.as_mut(),
+        "uatom",
+        OsmosisPriceSourceUnchecked::Spot {
+            pool_id: 1,
+        },
+    );
+
+    let err = entry::execute(
+        deps.as_mut(),
+        mock_env_at_block_time(10000),
+        mock_info("owner"),
+        ExecuteMsg::SnapshotPrice {
+            denom: "uatom".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidPriceSource {
+            reason: "price of uatom is not TWAP-based".to_string()
+        }
+    );
+}
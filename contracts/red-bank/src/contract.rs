@@ -1,7 +1,10 @@
-use cosmwasm_std::{entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{
+    entry_point, to_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Reply, Response,
+    StdResult,
+};
 use mars_red_bank_types::red_bank::{ExecuteMsg, InstantiateMsg, QueryMsg};
 
-use crate::{error::ContractError, execute, query};
+use crate::{error::ContractError, execute, migrations, query};
 
 #[entry_point]
 pub fn instantiate(
@@ -39,7 +42,9 @@ pub fn execute(
             new_limit,
         } => {
             let user_addr = deps.api.addr_validate(&user)?;
-            execute::update_uncollateralized_loan_limit(deps, info, user_addr, denom, new_limit)
+            execute::update_uncollateralized_loan_limit(
+                deps, env, info, user_addr, denom, new_limit,
+            )
         }
         ExecuteMsg::Deposit {
             on_behalf_of,
@@ -69,6 +74,12 @@ pub fn execute(
             let sent_coin = cw_utils::one_coin(&info)?;
             execute::repay(deps, env, info, on_behalf_of, sent_coin.denom, sent_coin.amount)
         }
+        ExecuteMsg::CloseDustDebt {
+            denom,
+        } => {
+            let sent_amount = cw_utils::must_pay(&info, &denom)?;
+            execute::close_dust_debt(deps, env, info, denom, sent_amount)
+        }
         ExecuteMsg::Liquidate {
             user,
             collateral_denom,
@@ -94,6 +105,28 @@ pub fn execute(
             cw_utils::nonpayable(&info)?;
             execute::update_asset_collateral_status(deps, env, info, denom, enable)
         }
+        ExecuteMsg::SweepReserves {
+            denom,
+        } => {
+            cw_utils::nonpayable(&info)?;
+            execute::sweep_reserves(deps, env, denom)
+        }
+        ExecuteMsg::WithdrawAllAndConsolidate {
+            target_denom,
+        } => {
+            cw_utils::nonpayable(&info)?;
+            execute::withdraw_all_and_consolidate(deps, env, info, target_denom)
+        }
+    }
+}
+
+#[entry_point]
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
+    match reply.id {
+        execute::CONSOLIDATE_REPLY_ID => execute::send_consolidated_amount(deps, env),
+        id => Err(ContractError::UnknownReplyId {
+            id,
+        }),
     }
 }
 
@@ -108,6 +141,10 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
             start_after,
             limit,
         } => to_binary(&query::query_markets(deps, start_after, limit)?),
+        QueryMsg::UserCounts {} => to_binary(&query::query_user_counts(deps)?),
+        QueryMsg::MarketScaledTotals {
+            denom,
+        } => to_binary(&query::query_market_scaled_totals(deps, env, denom)?),
         QueryMsg::UncollateralizedLoanLimit {
             user,
             denom,
@@ -164,12 +201,147 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
                 limit,
             )?)
         }
+        QueryMsg::UserMarkets {
+            user,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_user_markets(deps, user_addr)?)
+        }
+        QueryMsg::UserLiquidationHistory {
+            user,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_user_liquidation_history(deps, user_addr)?)
+        }
         QueryMsg::UserPosition {
             user,
         } => {
             let user_addr = deps.api.addr_validate(&user)?;
             to_binary(&query::query_user_position(deps, env, user_addr)?)
         }
+        QueryMsg::HealthDecomposition {
+            user,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_health_decomposition(deps, env, user_addr)?)
+        }
+        QueryMsg::HealthSensitivity {
+            user,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_health_sensitivity(deps, env, user_addr)?)
+        }
+        QueryMsg::UserHealthBreakdown {
+            user,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_user_health_breakdown(deps, env, user_addr)?)
+        }
+        QueryMsg::CurrentLtv {
+            user,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_current_ltv(deps, env, user_addr)?)
+        }
+        QueryMsg::AggregateHealth {
+            users,
+        } => {
+            let user_addrs = users
+                .iter()
+                .map(|user| deps.api.addr_validate(user))
+                .collect::<StdResult<Vec<_>>>()?;
+            to_binary(&query::query_aggregate_health(deps, env, user_addrs)?)
+        }
+        QueryMsg::BadDebt {
+            users,
+        } => {
+            let user_addrs = users
+                .iter()
+                .map(|user| deps.api.addr_validate(user))
+                .collect::<StdResult<Vec<_>>>()?;
+            to_binary(&query::query_bad_debt(deps, env, user_addrs)?)
+        }
+        QueryMsg::HaircutCollateralValue {
+            user,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_haircut_collateral_value(deps, env, user_addr)?)
+        }
+        QueryMsg::ProjectedInterest {
+            user,
+            denom,
+            seconds,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_projected_interest(
+                deps, &env.block, user_addr, denom, seconds,
+            )?)
+        }
+        QueryMsg::RescueDeposit {
+            user,
+            denom,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_rescue_deposit(deps, env, user_addr, denom)?)
+        }
+        QueryMsg::MaxWithdrawable {
+            user,
+            denom,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_max_withdrawable(deps, env, user_addr, denom)?)
+        }
+        QueryMsg::MaxBorrowable {
+            user,
+            denom,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_max_borrowable(deps, env, user_addr, denom)?)
+        }
+        QueryMsg::EstimateLiquidationProfit {
+            user,
+            debt_denom,
+            collateral_denom,
+            repay_amount,
+            gas_cost,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_estimate_liquidation_profit(
+                deps,
+                env,
+                user_addr,
+                debt_denom,
+                collateral_denom,
+                repay_amount,
+                gas_cost,
+            )?)
+        }
+        QueryMsg::SeizableCollaterals {
+            user,
+            debt_denom,
+            repay_amount,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_seizable_collaterals(
+                deps,
+                env,
+                user_addr,
+                debt_denom,
+                repay_amount,
+            )?)
+        }
+        QueryMsg::ValidateNewMarket {
+            denom,
+            max_ltv,
+            liquidation_threshold,
+            liquidation_bonus,
+        } => to_binary(&query::query_validate_new_market(
+            deps,
+            denom,
+            max_ltv,
+            liquidation_threshold,
+            liquidation_bonus,
+        )?),
         QueryMsg::ScaledLiquidityAmount {
             denom,
             amount,
@@ -189,3 +361,8 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
     };
     res.map_err(Into::into)
 }
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> Result<Response, ContractError> {
+    migrations::v1_0_1::migrate(deps)
+}
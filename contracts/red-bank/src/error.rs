@@ -1,4 +1,6 @@
-use cosmwasm_std::{OverflowError, StdError};
+use cosmwasm_std::{
+    CheckedFromRatioError, CheckedMultiplyRatioError, OverflowError, StdError, Uint128,
+};
 use cw_utils::PaymentError;
 use mars_health::error::HealthError;
 use mars_owner::OwnerError;
@@ -17,6 +19,9 @@ pub enum ContractError {
     #[error("{0}")]
     Validation(#[from] ValidationError),
 
+    #[error("{0}")]
+    Version(#[from] cw2::VersionError),
+
     #[error("{0}")]
     Owner(#[from] OwnerError),
 
@@ -26,6 +31,12 @@ pub enum ContractError {
     #[error("{0}")]
     Overflow(#[from] OverflowError),
 
+    #[error("{0}")]
+    CheckedMultiplyRatio(#[from] CheckedMultiplyRatioError),
+
+    #[error("{0}")]
+    CheckedFromRatio(#[from] CheckedFromRatioError),
+
     #[error("{0}")]
     Health(#[from] HealthError),
 
@@ -135,6 +146,81 @@ pub enum ContractError {
     #[error("Cannot update uncollateralized loan limit because user has uncollateralized debt")]
     UserHasUncollateralizedDebt {},
 
+    #[error(
+        "Uncollateralized loan limit {new_limit} is below the user's outstanding uncollateralized debt {debt}"
+    )]
+    UncollateralizedLoanLimitBelowDebt {
+        new_limit: Uint128,
+        debt: Uint128,
+    },
+
     #[error("Cannot repay uncollateralized loan on behalf of another user")]
     CannotRepayUncollateralizedLoanOnBehalfOf {},
+
+    #[error("Liquidation repays a base denom value of {value}, below the minimum of {min_value}")]
+    LiquidationTooSmall {
+        min_value: Uint128,
+        value: Uint128,
+    },
+
+    #[error("No reserves to sweep for {denom:?}")]
+    NoReservesToSweep {
+        denom: String,
+    },
+
+    #[error("Deposit for {denom:?} is below the minimum amount of {min_amount}")]
+    DepositTooSmall {
+        denom: String,
+        min_amount: Uint128,
+    },
+
+    #[error("Debt of {amount} {denom} is not below the dust threshold of {threshold}")]
+    DebtNotDust {
+        denom: String,
+        amount: Uint128,
+        threshold: Uint128,
+    },
+
+    #[error("Closing the debt requires {required}, but only {sent} was sent")]
+    InsufficientFundsToCloseDebt {
+        required: Uint128,
+        sent: Uint128,
+    },
+
+    #[error("Cannot query more than {max} users at once")]
+    TooManyUsers {
+        max: usize,
+    },
+
+    #[error("Oracle price for {denom:?} is zero")]
+    ZeroPrice {
+        denom: String,
+    },
+
+    #[error(
+        "Whether uncollateralized debt accrues interest can only be set on market initialization"
+    )]
+    UncollateralizedDebtInterestNotUpdatable {},
+
+    #[error("Price of {denom:?} has {sources} oracle sources, below the minimum of {min_sources}")]
+    InsufficientOracleSources {
+        denom: String,
+        sources: u32,
+        min_sources: u32,
+    },
+
+    #[error("Price of {denom:?} was recorded at {timestamp}, more than {max_age} seconds ago")]
+    StalePrice {
+        denom: String,
+        timestamp: u64,
+        max_age: u64,
+    },
+
+    #[error("User has no enabled collateral to consolidate")]
+    NoCollateralToConsolidate {},
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId {
+        id: u64,
+    },
 }
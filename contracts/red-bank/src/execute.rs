@@ -1,15 +1,19 @@
 use std::{cmp::min, str};
 
 use cosmwasm_std::{
-    Addr, Decimal, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+    coins, to_binary, Addr, Api, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response,
+    StdError, StdResult, SubMsg, Uint128, WasmMsg,
 };
 use mars_owner::{OwnerError, OwnerInit::SetInitialOwner, OwnerUpdate};
 use mars_red_bank_types::{
     address_provider::{self, MarsAddressType},
     error::MarsError,
+    oracle,
     red_bank::{
-        Config, CreateOrUpdateConfig, Debt, InitOrUpdateAssetParams, InstantiateMsg, Market,
+        Config, CreateOrUpdateConfig, InitOrUpdateAssetParams, InstantiateMsg, LiquidationRecord,
+        Market,
     },
+    rewards_collector,
 };
 use mars_utils::{
     helpers::{build_send_asset_msg, option_string_to_addr, validate_native_denom, zero_address},
@@ -20,19 +24,27 @@ use crate::{
     error::ContractError,
     health::{
         assert_below_liq_threshold_after_withdraw, assert_below_max_ltv_after_borrow,
-        assert_liquidatable,
+        assert_liquidatable, compute_position_health, get_user_positions_map,
     },
     interest_rates::{
-        apply_accumulated_interests, get_scaled_debt_amount, get_scaled_liquidity_amount,
-        get_underlying_debt_amount, get_underlying_liquidity_amount, update_interest_rates,
+        apply_accumulated_interests, debt_accrues_interest, get_scaled_debt_amount,
+        get_scaled_liquidity_amount, get_scaled_user_debt_amount, get_underlying_debt_amount,
+        get_underlying_liquidity_amount, get_underlying_user_debt_amount, update_interest_rates,
+    },
+    state::{
+        COLLATERALS, CONFIG, DEBTS, LIQUIDATION_HISTORY, MARKETS, MAX_LIQUIDATION_HISTORY, OWNER,
+        PENDING_CONSOLIDATION, UNCOLLATERALIZED_LOAN_LIMITS,
     },
-    state::{COLLATERALS, CONFIG, DEBTS, MARKETS, OWNER, UNCOLLATERALIZED_LOAN_LIMITS},
     user::User,
 };
 
 pub const CONTRACT_NAME: &str = "crates.io:mars-red-bank";
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Reply ID of the last swap of a `WithdrawAllAndConsolidate`, after which the consolidated amount
+/// is sent to the user
+pub const CONSOLIDATE_REPLY_ID: u64 = 1;
+
 pub fn instantiate(deps: DepsMut, msg: InstantiateMsg) -> Result<Response, ContractError> {
     cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
@@ -41,6 +53,11 @@ pub fn instantiate(deps: DepsMut, msg: InstantiateMsg) -> Result<Response, Contr
     let CreateOrUpdateConfig {
         address_provider,
         close_factor,
+        apply_value_haircut,
+        min_liquidation_value,
+        exclude_paused_collateral,
+        min_oracle_sources,
+        max_price_age,
     } = msg.config;
 
     // All fields should be available
@@ -53,6 +70,11 @@ pub fn instantiate(deps: DepsMut, msg: InstantiateMsg) -> Result<Response, Contr
     let config = Config {
         address_provider: option_string_to_addr(deps.api, address_provider, zero_address())?,
         close_factor: close_factor.unwrap(),
+        apply_value_haircut: apply_value_haircut.unwrap_or(false),
+        min_liquidation_value: min_liquidation_value.unwrap_or_default(),
+        exclude_paused_collateral: exclude_paused_collateral.unwrap_or(false),
+        min_oracle_sources: min_oracle_sources.unwrap_or_default(),
+        max_price_age: max_price_age.unwrap_or_default(),
     };
 
     config.validate()?;
@@ -93,12 +115,23 @@ pub fn update_config(
     let CreateOrUpdateConfig {
         address_provider,
         close_factor,
+        apply_value_haircut,
+        min_liquidation_value,
+        exclude_paused_collateral,
+        min_oracle_sources,
+        max_price_age,
     } = new_config;
 
     // Update config
     config.address_provider =
         option_string_to_addr(deps.api, address_provider, config.address_provider)?;
     config.close_factor = close_factor.unwrap_or(config.close_factor);
+    config.apply_value_haircut = apply_value_haircut.unwrap_or(config.apply_value_haircut);
+    config.min_liquidation_value = min_liquidation_value.unwrap_or(config.min_liquidation_value);
+    config.exclude_paused_collateral =
+        exclude_paused_collateral.unwrap_or(config.exclude_paused_collateral);
+    config.min_oracle_sources = min_oracle_sources.unwrap_or(config.min_oracle_sources);
+    config.max_price_age = max_price_age.unwrap_or(config.max_price_age);
 
     // Validate config
     config.validate()?;
@@ -125,7 +158,12 @@ pub fn init_asset(
         return Err(ContractError::AssetAlreadyInitialized {});
     }
 
-    let new_market = create_market(env.block.time.seconds(), &denom, params)?;
+    let new_market = create_market(deps.api, env.block.time.seconds(), &denom, params)?;
+
+    if new_market.deposit_enabled || new_market.borrow_enabled {
+        assert_min_oracle_sources(deps.as_ref(), &denom)?;
+    }
+
     MARKETS.save(deps.storage, &denom, &new_market)?;
 
     Ok(Response::new().add_attribute("action", "init_asset").add_attribute("denom", denom))
@@ -133,6 +171,7 @@ pub fn init_asset(
 
 /// Initialize new market
 pub fn create_market(
+    api: &dyn Api,
     block_time: u64,
     denom: &str,
     params: InitOrUpdateAssetParams,
@@ -148,6 +187,13 @@ pub fn create_market(
         deposit_enabled,
         borrow_enabled,
         deposit_cap,
+        value_haircut,
+        min_deposit_amount,
+        reserve_recipient,
+        dust_threshold,
+        uncollateralized_debt_accrues_interest,
+        close_factor,
+        collateral_close_factor,
     } = params;
 
     // All fields should be available
@@ -181,6 +227,15 @@ pub fn create_market(
         borrow_enabled: borrow_enabled.unwrap(),
         // if not specified, deposit cap is set to unlimited
         deposit_cap: deposit_cap.unwrap_or(Uint128::MAX),
+        value_haircut: value_haircut.unwrap_or_else(Decimal::zero),
+        min_deposit_amount: min_deposit_amount.unwrap_or_default(),
+        reserve_recipient: reserve_recipient.map(|addr| api.addr_validate(&addr)).transpose()?,
+        unswept_reserves_scaled: Uint128::zero(),
+        dust_threshold: dust_threshold.unwrap_or_default(),
+        uncollateralized_debt_accrues_interest: uncollateralized_debt_accrues_interest
+            .unwrap_or(true),
+        close_factor,
+        collateral_close_factor,
     };
 
     new_market.validate()?;
@@ -226,8 +281,20 @@ fn update_asset_by_owner(
                 deposit_enabled,
                 borrow_enabled,
                 deposit_cap,
+                value_haircut,
+                min_deposit_amount,
+                reserve_recipient,
+                dust_threshold,
+                uncollateralized_debt_accrues_interest,
+                close_factor,
+                collateral_close_factor,
             } = params;
 
+            // existing uncollateralized debts are stored either scaled or not depending on it
+            if uncollateralized_debt_accrues_interest.is_some() {
+                return Err(ContractError::UncollateralizedDebtInterestNotUpdatable {});
+            }
+
             // If reserve factor or interest rates are updated we update indexes with
             // current values before applying the change to prevent applying this
             // new params to a period where they were not valid yet. Interests rates are
@@ -236,9 +303,14 @@ fn update_asset_by_owner(
                 && reserve_factor.unwrap() != market.reserve_factor)
                 || interest_rate_model.is_some();
 
+            let reserve_recipient =
+                reserve_recipient.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+            let reserve_recipient_changed =
+                reserve_recipient.is_some() && reserve_recipient != market.reserve_recipient;
+
             let mut response = Response::new();
 
-            if should_update_interest_rates {
+            if should_update_interest_rates || reserve_recipient_changed {
                 let config = CONFIG.load(deps.storage)?;
                 let addresses = address_provider::helpers::query_contract_addrs(
                     deps.as_ref(),
@@ -258,6 +330,19 @@ fn update_asset_by_owner(
                 )?;
             }
 
+            // Reserves accrued up to now are left to the rewards collector, so that a new
+            // recipient only gets the ones accrued from now on
+            if reserve_recipient_changed {
+                market.unswept_reserves_scaled = Uint128::zero();
+            }
+
+            // Deposits or borrows being turned on are subject to the oracle sources check
+            let enabling = (deposit_enabled == Some(true) && !market.deposit_enabled)
+                || (borrow_enabled == Some(true) && !market.borrow_enabled);
+            if enabling {
+                assert_min_oracle_sources(deps.as_ref(), denom)?;
+            }
+
             let mut updated_market = Market {
                 max_loan_to_value: max_loan_to_value.unwrap_or(market.max_loan_to_value),
                 reserve_factor: reserve_factor.unwrap_or(market.reserve_factor),
@@ -268,6 +353,12 @@ fn update_asset_by_owner(
                 deposit_enabled: deposit_enabled.unwrap_or(market.deposit_enabled),
                 borrow_enabled: borrow_enabled.unwrap_or(market.borrow_enabled),
                 deposit_cap: deposit_cap.unwrap_or(market.deposit_cap),
+                value_haircut: value_haircut.unwrap_or(market.value_haircut),
+                min_deposit_amount: min_deposit_amount.unwrap_or(market.min_deposit_amount),
+                reserve_recipient: reserve_recipient.or(market.reserve_recipient),
+                dust_threshold: dust_threshold.unwrap_or(market.dust_threshold),
+                close_factor: close_factor.or(market.close_factor),
+                collateral_close_factor: collateral_close_factor.or(market.collateral_close_factor),
                 ..market
             };
 
@@ -283,6 +374,37 @@ fn update_asset_by_owner(
     }
 }
 
+/// Assert that the oracle backs the price of the denom with at least the configured minimum
+/// number of price sources
+fn assert_min_oracle_sources(deps: Deps, denom: &str) -> Result<(), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.min_oracle_sources == 0 {
+        return Ok(());
+    }
+
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+    let sources: u32 = deps.querier.query_wasm_smart(
+        oracle_addr,
+        &oracle::QueryMsg::PriceSourceCount {
+            denom: denom.to_string(),
+        },
+    )?;
+
+    if sources < config.min_oracle_sources {
+        return Err(ContractError::InsufficientOracleSources {
+            denom: denom.to_string(),
+            sources,
+            min_sources: config.min_oracle_sources,
+        });
+    }
+
+    Ok(())
+}
+
 /// Emergency owner can only DISABLE BORROWING.
 fn update_asset_by_emergency_owner(
     deps: DepsMut,
@@ -309,6 +431,7 @@ fn update_asset_by_emergency_owner(
 /// Update uncollateralized loan limit by a given amount in base asset
 pub fn update_uncollateralized_loan_limit(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     user_addr: Addr,
     denom: String,
@@ -331,22 +454,30 @@ pub fn update_uncollateralized_loan_limit(
         return Err(ContractError::UserHasUncollateralizedDebt {});
     }
 
+    // a limit below the outstanding uncollateralized debt would leave the user over their credit
+    // line right away
+    if !new_limit.is_zero() && !current_debt.is_zero() {
+        let market = MARKETS.load(deps.storage, &denom)?;
+        let debt =
+            get_underlying_user_debt_amount(current_debt, true, &market, env.block.time.seconds())?;
+        if new_limit < debt {
+            return Err(ContractError::UncollateralizedLoanLimitBelowDebt {
+                new_limit,
+                debt,
+            });
+        }
+    }
+
     UNCOLLATERALIZED_LOAN_LIMITS.save(deps.storage, (&user_addr, &denom), &new_limit)?;
 
-    DEBTS.update(deps.storage, (&user_addr, &denom), |debt_opt: Option<Debt>| -> StdResult<_> {
-        let mut debt = debt_opt.unwrap_or(Debt {
-            amount_scaled: Uint128::zero(),
-            uncollateralized: false,
-        });
-        // if limit == 0 then uncollateralized = false, otherwise uncollateralized = true
-        debt.uncollateralized = !new_limit.is_zero();
-        Ok(debt)
-    })?;
+    // if limit == 0 then uncollateralized = false, otherwise uncollateralized = true
+    User(&user_addr).set_debt_uncollateralized(deps.storage, &denom, !new_limit.is_zero())?;
 
     Ok(Response::new()
         .add_attribute("action", "update_uncollateralized_loan_limit")
         .add_attribute("user", user_addr)
         .add_attribute("denom", denom)
+        .add_attribute("old_allowance", current_limit)
         .add_attribute("new_allowance", new_limit))
 }
 
@@ -383,6 +514,18 @@ pub fn deposit(
         });
     }
 
+    // topping up an existing position is always allowed, only new positions must meet the minimum
+    if deposit_amount < market.min_deposit_amount
+        && COLLATERALS
+            .may_load(deps.storage, (user.address(), &denom))?
+            .map_or(true, |collateral| collateral.amount_scaled.is_zero())
+    {
+        return Err(ContractError::DepositTooSmall {
+            denom,
+            min_amount: market.min_deposit_amount,
+        });
+    }
+
     let mut response = Response::new();
 
     let config = CONFIG.load(deps.storage)?;
@@ -555,6 +698,239 @@ pub fn withdraw(
         .add_attribute("amount_scaled", withdraw_amount_scaled))
 }
 
+/// Withdraw all of the user's enabled collateral, and swap each asset other than `target_denom` into
+/// it through the rewards collector. The withdrawn `target_denom` and the swap outputs are sent to
+/// the user once the swaps are done.
+pub fn withdraw_all_and_consolidate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    target_denom: String,
+) -> Result<Response, ContractError> {
+    let withdrawer = User(&info.sender);
+
+    let mut collaterals = vec![];
+    for item in
+        COLLATERALS.prefix(withdrawer.address()).range(deps.storage, None, None, Order::Ascending)
+    {
+        let (denom, collateral) = item?;
+        if collateral.enabled {
+            collaterals.push((denom, collateral));
+        }
+    }
+    if collaterals.is_empty() {
+        return Err(ContractError::NoCollateralToConsolidate {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps.as_ref(),
+        &config.address_provider,
+        vec![
+            MarsAddressType::Oracle,
+            MarsAddressType::Incentives,
+            MarsAddressType::RewardsCollector,
+        ],
+    )?;
+    let rewards_collector_addr = &addresses[&MarsAddressType::RewardsCollector];
+    let incentives_addr = &addresses[&MarsAddressType::Incentives];
+    let oracle_addr = &addresses[&MarsAddressType::Oracle];
+
+    // the remaining debt, if any, must stay healthy without any of the withdrawn collateral
+    if withdrawer.is_borrowing(deps.storage) {
+        let mut positions =
+            get_user_positions_map(&deps.as_ref(), &env, withdrawer.address(), oracle_addr)?;
+        for (denom, _) in &collaterals {
+            if let Some(position) = positions.get_mut(denom) {
+                position.collateral_amount = Uint128::zero();
+            }
+        }
+        if compute_position_health(&positions)?.is_liquidatable() {
+            return Err(ContractError::InvalidHealthFactorAfterWithdraw {});
+        }
+    }
+
+    let mut response = Response::new();
+    let mut target_amount = Uint128::zero();
+    let mut swap_msgs = vec![];
+
+    for (denom, collateral) in collaterals {
+        let mut market = MARKETS.load(deps.storage, &denom)?;
+
+        let withdraw_amount = get_underlying_liquidity_amount(
+            collateral.amount_scaled,
+            &market,
+            env.block.time.seconds(),
+        )?;
+
+        // update indexes and interest rates
+        response = apply_accumulated_interests(
+            deps.storage,
+            &env,
+            &mut market,
+            rewards_collector_addr,
+            incentives_addr,
+            response,
+        )?;
+
+        response = update_interest_rates(&env, &mut market, response)?;
+
+        // the whole collateral position is withdrawn
+        response = withdrawer.decrease_collateral(
+            deps.storage,
+            &market,
+            collateral.amount_scaled,
+            incentives_addr,
+            response,
+        )?;
+
+        market.decrease_collateral(collateral.amount_scaled)?;
+        MARKETS.save(deps.storage, &denom, &market)?;
+
+        if denom == target_denom {
+            target_amount = withdraw_amount;
+        } else if !withdraw_amount.is_zero() {
+            swap_msgs.push(WasmMsg::Execute {
+                contract_addr: rewards_collector_addr.to_string(),
+                msg: to_binary(&rewards_collector::ExecuteMsg::<Empty>::SwapFunds {
+                    denom_out: target_denom.clone(),
+                })?,
+                funds: coins(withdraw_amount.u128(), &denom),
+            });
+        }
+
+        response = response.add_attribute(format!("amount_{denom}"), withdraw_amount);
+    }
+
+    response = response
+        .add_attribute("action", "withdraw_all_and_consolidate")
+        .add_attribute("sender", withdrawer.address())
+        .add_attribute("target_denom", &target_denom);
+
+    // nothing to swap, so the withdrawn target denom can be sent right away
+    let Some(last_swap_msg) = swap_msgs.pop() else {
+        if !target_amount.is_zero() {
+            response = response.add_message(build_send_asset_msg(
+                withdrawer.address(),
+                &target_denom,
+                target_amount,
+            ));
+        }
+        return Ok(response.add_attribute("consolidated_amount", target_amount));
+    };
+
+    // the swap outputs are sent back to this contract, so the consolidated amount is only known
+    // once the last swap is done
+    let balance_before = deps.querier.query_balance(&env.contract.address, &target_denom)?.amount;
+    PENDING_CONSOLIDATION.save(
+        deps.storage,
+        &(withdrawer.address().clone(), target_denom, balance_before, target_amount),
+    )?;
+
+    Ok(response
+        .add_messages(swap_msgs)
+        .add_submessage(SubMsg::reply_on_success(last_swap_msg, CONSOLIDATE_REPLY_ID)))
+}
+
+/// Send the user the withdrawn target denom of a `WithdrawAllAndConsolidate` along with the output
+/// of the swaps of their other collateral
+pub fn send_consolidated_amount(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let (user_addr, target_denom, balance_before, target_amount) =
+        PENDING_CONSOLIDATION.load(deps.storage)?;
+    PENDING_CONSOLIDATION.remove(deps.storage);
+
+    let balance = deps.querier.query_balance(&env.contract.address, &target_denom)?.amount;
+    let swapped_amount = balance.checked_sub(balance_before)?;
+    let consolidated_amount = target_amount.checked_add(swapped_amount)?;
+
+    let mut response = Response::new();
+    if !consolidated_amount.is_zero() {
+        response = response.add_message(build_send_asset_msg(
+            &user_addr,
+            &target_denom,
+            consolidated_amount,
+        ));
+    }
+
+    Ok(response
+        .add_attribute("action", "send_consolidated_amount")
+        .add_attribute("user", user_addr)
+        .add_attribute("target_denom", target_denom)
+        .add_attribute("swapped_amount", swapped_amount)
+        .add_attribute("consolidated_amount", consolidated_amount))
+}
+
+/// Withdraw all collateral shares held by the rewards collector in the given market, i.e. the
+/// accrued reserves, and send the underlying coins to the market's reserve recipient, or to the
+/// rewards collector if it has none
+pub fn sweep_reserves(deps: DepsMut, env: Env, denom: String) -> Result<Response, ContractError> {
+    let mut market = MARKETS.load(deps.storage, &denom)?;
+
+    let config = CONFIG.load(deps.storage)?;
+
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps.as_ref(),
+        &config.address_provider,
+        vec![MarsAddressType::Incentives, MarsAddressType::RewardsCollector],
+    )?;
+    let rewards_collector_addr = &addresses[&MarsAddressType::RewardsCollector];
+    let incentives_addr = &addresses[&MarsAddressType::Incentives];
+
+    let mut response = Response::new();
+
+    // mint the reserves accrued up to now before sweeping them
+    response = apply_accumulated_interests(
+        deps.storage,
+        &env,
+        &mut market,
+        rewards_collector_addr,
+        incentives_addr,
+        response,
+    )?;
+
+    // only the reserves are swept, not the rest of the collector's collateral, which may also have
+    // been withdrawn from in the meantime
+    let rewards_collector = User(rewards_collector_addr);
+    let collector_collateral_scaled = COLLATERALS
+        .may_load(deps.storage, (rewards_collector_addr, &denom))?
+        .map(|collateral| collateral.amount_scaled)
+        .unwrap_or_default();
+    let reserves_scaled = min(market.unswept_reserves_scaled, collector_collateral_scaled);
+    if reserves_scaled.is_zero() {
+        return Err(ContractError::NoReservesToSweep {
+            denom,
+        });
+    }
+
+    let reserves =
+        get_underlying_liquidity_amount(reserves_scaled, &market, env.block.time.seconds())?;
+
+    response = rewards_collector.decrease_collateral(
+        deps.storage,
+        &market,
+        reserves_scaled,
+        incentives_addr,
+        response,
+    )?;
+
+    market.decrease_collateral(reserves_scaled)?;
+    market.unswept_reserves_scaled = Uint128::zero();
+
+    response = update_interest_rates(&env, &mut market, response)?;
+    MARKETS.save(deps.storage, &denom, &market)?;
+
+    let recipient_addr = market.reserve_recipient.as_ref().unwrap_or(rewards_collector_addr);
+
+    Ok(response
+        .add_message(build_send_asset_msg(recipient_addr, &denom, reserves))
+        .add_attribute("action", "sweep_reserves")
+        .add_attribute("recipient", recipient_addr)
+        .add_attribute("denom", denom)
+        .add_attribute("amount", reserves)
+        .add_attribute("amount_scaled", reserves_scaled))
+}
+
 /// Add debt for the borrower and send the borrowed funds
 pub fn borrow(
     deps: DepsMut,
@@ -619,8 +995,9 @@ pub fn borrow(
         let debt_amount_scaled = borrower.debt_amount_scaled(deps.storage, &denom)?;
 
         let asset_market = MARKETS.load(deps.storage, &denom)?;
-        let debt_amount = get_underlying_debt_amount(
+        let debt_amount = get_underlying_user_debt_amount(
             debt_amount_scaled,
+            true,
             &asset_market,
             env.block.time.seconds(),
         )?;
@@ -643,11 +1020,24 @@ pub fn borrow(
     )?;
 
     // Set new debt
-    let borrow_amount_scaled =
-        get_scaled_debt_amount(borrow_amount, &borrow_market, env.block.time.seconds())?;
+    let borrow_amount_scaled = get_scaled_user_debt_amount(
+        borrow_amount,
+        uncollateralized_debt,
+        &borrow_market,
+        env.block.time.seconds(),
+    )?;
 
-    borrow_market.increase_debt(borrow_amount_scaled)?;
-    borrower.increase_debt(deps.storage, &denom, borrow_amount_scaled, uncollateralized_debt)?;
+    response = borrower.increase_debt(
+        deps.storage,
+        &borrow_market,
+        borrow_amount_scaled,
+        uncollateralized_debt,
+        incentives_addr,
+        response,
+    )?;
+    if debt_accrues_interest(&borrow_market, uncollateralized_debt) {
+        borrow_market.increase_debt(borrow_amount_scaled)?;
+    }
 
     response = update_interest_rates(&env, &mut borrow_market, response)?;
     MARKETS.save(deps.storage, &denom, &borrow_market)?;
@@ -720,8 +1110,12 @@ pub fn repay(
     )?;
 
     let debt_amount_scaled_before = debt.amount_scaled;
-    let debt_amount_before =
-        get_underlying_debt_amount(debt.amount_scaled, &market, env.block.time.seconds())?;
+    let debt_amount_before = get_underlying_user_debt_amount(
+        debt.amount_scaled,
+        debt.uncollateralized,
+        &market,
+        env.block.time.seconds(),
+    )?;
 
     // If repay amount exceeds debt, refund any excess amounts
     let mut refund_amount = Uint128::zero();
@@ -734,14 +1128,26 @@ pub fn repay(
         debt_amount_after = debt_amount_before - repay_amount;
     }
 
-    let debt_amount_scaled_after =
-        get_scaled_debt_amount(debt_amount_after, &market, env.block.time.seconds())?;
+    let debt_amount_scaled_after = get_scaled_user_debt_amount(
+        debt_amount_after,
+        debt.uncollateralized,
+        &market,
+        env.block.time.seconds(),
+    )?;
 
     let debt_amount_scaled_delta =
         debt_amount_scaled_before.checked_sub(debt_amount_scaled_after)?;
 
-    market.decrease_debt(debt_amount_scaled_delta)?;
-    user.decrease_debt(deps.storage, &denom, debt_amount_scaled_delta)?;
+    response = user.decrease_debt(
+        deps.storage,
+        &market,
+        debt_amount_scaled_delta,
+        incentives_addr,
+        response,
+    )?;
+    if debt_accrues_interest(&market, debt.uncollateralized) {
+        market.decrease_debt(debt_amount_scaled_delta)?;
+    }
 
     response = update_interest_rates(&env, &mut market, response)?;
     MARKETS.save(deps.storage, &denom, &market)?;
@@ -755,6 +1161,87 @@ pub fn repay(
         .add_attribute("amount_scaled", debt_amount_scaled_delta))
 }
 
+/// Repay a user's debt below the market's dust threshold in full, removing the debt position even
+/// if rounding would otherwise leave a residual scaled amount
+pub fn close_dust_debt(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    sent_amount: Uint128,
+) -> Result<Response, ContractError> {
+    let user = User(&info.sender);
+
+    let debt = DEBTS
+        .may_load(deps.storage, (user.address(), &denom))?
+        .ok_or(ContractError::CannotRepayZeroDebt {})?;
+
+    let config = CONFIG.load(deps.storage)?;
+
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps.as_ref(),
+        &config.address_provider,
+        vec![MarsAddressType::Incentives, MarsAddressType::RewardsCollector],
+    )?;
+    let rewards_collector_addr = &addresses[&MarsAddressType::RewardsCollector];
+    let incentives_addr = &addresses[&MarsAddressType::Incentives];
+
+    let mut market = MARKETS.load(deps.storage, &denom)?;
+
+    let mut response = Response::new();
+
+    response = apply_accumulated_interests(
+        deps.storage,
+        &env,
+        &mut market,
+        rewards_collector_addr,
+        incentives_addr,
+        response,
+    )?;
+
+    let debt_amount = get_underlying_user_debt_amount(
+        debt.amount_scaled,
+        debt.uncollateralized,
+        &market,
+        env.block.time.seconds(),
+    )?;
+    if debt_amount >= market.dust_threshold {
+        return Err(ContractError::DebtNotDust {
+            denom,
+            amount: debt_amount,
+            threshold: market.dust_threshold,
+        });
+    }
+    if sent_amount < debt_amount {
+        return Err(ContractError::InsufficientFundsToCloseDebt {
+            required: debt_amount,
+            sent: sent_amount,
+        });
+    }
+
+    let refund_amount = sent_amount - debt_amount;
+    if !refund_amount.is_zero() {
+        response = response.add_message(build_send_asset_msg(&info.sender, &denom, refund_amount));
+    }
+
+    // the whole scaled amount is removed, not only the part the repaid amount converts to
+    response =
+        user.decrease_debt(deps.storage, &market, debt.amount_scaled, incentives_addr, response)?;
+    if debt_accrues_interest(&market, debt.uncollateralized) {
+        market.decrease_debt(debt.amount_scaled)?;
+    }
+
+    response = update_interest_rates(&env, &mut market, response)?;
+    MARKETS.save(deps.storage, &denom, &market)?;
+
+    Ok(response
+        .add_attribute("action", "close_dust_debt")
+        .add_attribute("user", user)
+        .add_attribute("denom", denom)
+        .add_attribute("amount", debt_amount)
+        .add_attribute("amount_scaled", debt.amount_scaled))
+}
+
 /// Execute loan liquidations on under-collateralized loans
 pub fn liquidate(
     deps: DepsMut,
@@ -838,6 +1325,15 @@ pub fn liquidate(
         .ok_or(ContractError::CannotLiquidateWhenNoDebtBalance {})?
         .asset_price;
 
+    // the amounts to liquidate are derived from the ratio of the two prices
+    for (denom, price) in [(&collateral_denom, collateral_price), (&debt_denom, debt_price)] {
+        if price.is_zero() {
+            return Err(ContractError::ZeroPrice {
+                denom: denom.clone(),
+            });
+        }
+    }
+
     let mut response = Response::new();
 
     let user_debt_amount =
@@ -856,9 +1352,22 @@ pub fn liquidate(
         collateral_price,
         debt_price,
         block_time,
-        config.close_factor,
+        liquidation_close_factor(config.close_factor, &collateral_market, &debt_market),
     )?;
 
+    // Dust liquidations are rejected unless they close the user's debt or collateral position, as
+    // there would be nothing left worth liquidating afterwards. The minimum is a value in the
+    // oracle's base denom, same as the debt value it's compared to
+    let closes_position = debt_amount_to_repay == user_debt_amount
+        || collateral_amount_to_liquidate_scaled == user_collateral.amount_scaled;
+    let debt_value_to_repay = debt_amount_to_repay * debt_price;
+    if !closes_position && debt_value_to_repay < config.min_liquidation_value {
+        return Err(ContractError::LiquidationTooSmall {
+            min_value: config.min_liquidation_value,
+            value: debt_value_to_repay,
+        });
+    }
+
     // 4. Transfer collateral shares from the user to the liquidator
     response = user.decrease_collateral(
         deps.storage,
@@ -884,54 +1393,54 @@ pub fn liquidate(
     let debt_amount_scaled_delta =
         user_debt.amount_scaled.checked_sub(user_debt_amount_scaled_after)?;
 
-    user.decrease_debt(deps.storage, &debt_denom, debt_amount_scaled_delta)?;
+    response = user.decrease_debt(
+        deps.storage,
+        &debt_market,
+        debt_amount_scaled_delta,
+        incentives_addr,
+        response,
+    )?;
 
     let debt_market_debt_total_scaled_after =
         debt_market.debt_total_scaled.checked_sub(debt_amount_scaled_delta)?;
 
-    // 6. Update markets depending on whether the collateral and debt markets are the same
-    // and whether the liquidator receives coins (no change in liquidity) or underlying asset
-    // (changes liquidity)
-    if collateral_and_debt_are_the_same_asset {
-        // NOTE: for the sake of clarity copy attributes from collateral market and
-        // give generic naming. Debt market could have been used as well
-        let mut asset_market_after = collateral_market;
-        let denom = &collateral_denom;
+    // 6. Update the debt market.
+    // If the collateral and debt are the same asset, this is also the collateral market. The
+    // collateral shares only move from the user to the recipient and the market's total collateral
+    // is untouched, so a single update of the debt total is the net adjustment for both sides.
+    let mut debt_market_after = debt_market;
 
-        response = apply_accumulated_interests(
-            deps.storage,
-            &env,
-            &mut asset_market_after,
-            rewards_collector_addr,
-            incentives_addr,
-            response,
-        )?;
-
-        asset_market_after.debt_total_scaled = debt_market_debt_total_scaled_after;
-
-        response = update_interest_rates(&env, &mut asset_market_after, response)?;
-
-        MARKETS.save(deps.storage, denom, &asset_market_after)?;
-    } else {
-        let mut debt_market_after = debt_market;
+    response = apply_accumulated_interests(
+        deps.storage,
+        &env,
+        &mut debt_market_after,
+        rewards_collector_addr,
+        incentives_addr,
+        response,
+    )?;
 
-        response = apply_accumulated_interests(
-            deps.storage,
-            &env,
-            &mut debt_market_after,
-            rewards_collector_addr,
-            incentives_addr,
-            response,
-        )?;
+    debt_market_after.debt_total_scaled = debt_market_debt_total_scaled_after;
 
-        debt_market_after.debt_total_scaled = debt_market_debt_total_scaled_after;
+    response = update_interest_rates(&env, &mut debt_market_after, response)?;
 
-        response = update_interest_rates(&env, &mut debt_market_after, response)?;
+    MARKETS.save(deps.storage, &debt_denom, &debt_market_after)?;
 
-        MARKETS.save(deps.storage, &debt_denom, &debt_market_after)?;
+    // 7. Record the liquidation in the user's history, dropping the oldest entry once it is full
+    let mut history = LIQUIDATION_HISTORY.may_load(deps.storage, &user_addr)?.unwrap_or_default();
+    if history.len() >= MAX_LIQUIDATION_HISTORY {
+        history.remove(0);
     }
-
-    // 7. Build response
+    history.push(LiquidationRecord {
+        liquidator: info.sender.to_string(),
+        debt_denom: debt_denom.clone(),
+        debt_amount: debt_amount_to_repay,
+        collateral_denom: collateral_denom.clone(),
+        collateral_amount: collateral_amount_to_liquidate,
+        timestamp: block_time,
+    });
+    LIQUIDATION_HISTORY.save(deps.storage, &user_addr, &history)?;
+
+    // 8. Build response
     // refund sent amount in excess of actual debt amount to liquidate
     if !refund_amount.is_zero() {
         response =
@@ -951,6 +1460,19 @@ pub fn liquidate(
         .add_attribute("debt_amount_scaled", debt_amount_scaled_delta))
 }
 
+/// Close factor of a liquidation: the seized collateral market's override if set, otherwise the
+/// debt market's, otherwise the global close factor
+pub fn liquidation_close_factor(
+    global_close_factor: Decimal,
+    collateral_market: &Market,
+    debt_market: &Market,
+) -> Decimal {
+    collateral_market
+        .collateral_close_factor
+        .or(debt_market.close_factor)
+        .unwrap_or(global_close_factor)
+}
+
 /// Computes debt to repay (in debt asset),
 /// collateral to liquidate (in collateral asset) and
 /// amount to refund the liquidator (in debt asset)
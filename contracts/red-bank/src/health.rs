@@ -1,13 +1,16 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{hash_map::Entry, BTreeSet, HashMap};
 
-use cosmwasm_std::{Addr, Deps, Env, Order, StdError, StdResult, Uint128};
+use cosmwasm_std::{Addr, Decimal, Deps, Env, Order, StdError, StdResult, Uint128};
 use mars_health::health::{Health, Position as HealthPosition};
-use mars_red_bank_types::{oracle, red_bank::Position};
+use mars_red_bank_types::{
+    oracle,
+    red_bank::{Config, Position},
+};
 
 use crate::{
     error::ContractError,
-    interest_rates::{get_underlying_debt_amount, get_underlying_liquidity_amount},
-    state::{COLLATERALS, DEBTS, MARKETS},
+    interest_rates::{get_underlying_liquidity_amount, get_underlying_user_debt_amount},
+    state::{COLLATERALS, CONFIG, DEBTS, MARKETS},
 };
 
 /// Check the Health Factor for a given user
@@ -23,6 +26,45 @@ pub fn assert_liquidatable(
     Ok((health.is_liquidatable(), positions))
 }
 
+/// A user's positions a liquidator can act on, each with the asset's price
+#[derive(Debug, Default, PartialEq)]
+pub struct LiquidationCandidates {
+    /// Whether the user can be liquidated
+    pub liquidatable: bool,
+    /// Positions with enabled collateral that can be seized, sorted by denom
+    pub collaterals: Vec<Position>,
+    /// Positions with collateralized debt that can be repaid, sorted by denom
+    pub debts: Vec<Position>,
+}
+
+/// Like `assert_liquidatable`, but split the user's positions into the collaterals that can be
+/// seized and the debts that can be repaid, so that a liquidator can pick a pair from them directly
+pub fn get_liquidation_candidates(
+    deps: &Deps,
+    env: &Env,
+    user_addr: &Addr,
+    oracle_addr: &Addr,
+) -> Result<LiquidationCandidates, ContractError> {
+    let positions = get_user_positions_map(deps, env, user_addr, oracle_addr)?;
+    let health = compute_position_health(&positions)?;
+
+    // disabled collateral isn't part of the positions' collateral amounts
+    let mut positions = positions.into_values().collect::<Vec<_>>();
+    positions.sort_by(|a, b| a.denom.cmp(&b.denom));
+    let collaterals =
+        positions.iter().filter(|p| !p.collateral_amount.is_zero()).cloned().collect();
+    let debts = positions
+        .into_iter()
+        .filter(|p| !p.debt_amount.is_zero() && !p.uncollateralized_debt)
+        .collect();
+
+    Ok(LiquidationCandidates {
+        liquidatable: health.is_liquidatable(),
+        collaterals,
+        debts,
+    })
+}
+
 /// Check the Health Factor for a given user after a withdraw
 pub fn assert_below_liq_threshold_after_withdraw(
     deps: &Deps,
@@ -63,59 +105,61 @@ pub fn assert_below_max_ltv_after_borrow(
     let mut positions = get_user_positions_map(deps, env, user_addr, oracle_addr)?;
 
     // Update position to compute health factor after borrow
-    positions
-        .entry(denom.to_string())
-        .or_insert(Position {
+    let position = get_borrow_position(deps, env, oracle_addr, &mut positions, denom)?;
+    position.debt_amount += borrow_amount;
+
+    let health = compute_position_health(&positions)?;
+    Ok(!health.is_above_max_ltv())
+}
+
+/// Get the position in the denom a user is about to borrow, inserting an empty one if the user
+/// doesn't have a position in that denom yet
+pub fn get_borrow_position<'a>(
+    deps: &Deps,
+    env: &Env,
+    oracle_addr: &Addr,
+    positions: &'a mut HashMap<String, Position>,
+    denom: &str,
+) -> Result<&'a mut Position, ContractError> {
+    let position = match positions.entry(denom.to_string()) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(Position {
             denom: denom.to_string(),
             debt_amount: Uint128::zero(),
-            asset_price: oracle::helpers::query_price(&deps.querier, oracle_addr, denom)?,
+            asset_price: query_asset_price(
+                deps,
+                env,
+                &CONFIG.load(deps.storage)?,
+                oracle_addr,
+                denom,
+            )?,
             ..Default::default()
-        })
-        .debt_amount += borrow_amount;
+        }),
+    };
 
-    let health = compute_position_health(&positions)?;
-    Ok(!health.is_above_max_ltv())
+    // A debt in a zero priced asset wouldn't count towards the user's health at all
+    if position.asset_price.is_zero() {
+        return Err(ContractError::ZeroPrice {
+            denom: denom.to_string(),
+        });
+    }
+
+    Ok(position)
 }
 
 /// Compute Health of a given User Position
 pub fn compute_position_health(
     positions: &HashMap<String, Position>,
 ) -> Result<Health, ContractError> {
-    let positions = positions
-        .values()
-        .map(|p| {
-            // if it is an "uncollateralized" debt, then it won't count towards their health factor
-            let debt_amount = if p.uncollateralized_debt {
-                Uint128::zero()
-            } else {
-                p.debt_amount
-            };
-
-            HealthPosition {
-                denom: p.denom.clone(),
-                collateral_amount: p.collateral_amount,
-                debt_amount,
-                price: p.asset_price,
-                max_ltv: p.max_ltv,
-                liquidation_threshold: p.liquidation_threshold,
-            }
-        })
-        .collect::<Vec<_>>();
+    let positions = positions.values().map(Into::into).collect::<Vec<HealthPosition>>();
 
     Health::compute_health(&positions).map_err(Into::into)
 }
 
 /// Goes through assets user has a position in and returns a HashMap mapping the asset denoms to the
 /// scaled amounts, and some metadata to be used by the caller.
-pub fn get_user_positions_map(
-    deps: &Deps,
-    env: &Env,
-    user_addr: &Addr,
-    oracle_addr: &Addr,
-) -> StdResult<HashMap<String, Position>> {
-    let block_time = env.block.time.seconds();
-
-    // Find all denoms that the user has a collateral or debt position in
+/// Find all denoms that the user has a collateral or debt position in
+pub fn get_user_denoms(deps: &Deps, user_addr: &Addr) -> StdResult<BTreeSet<String>> {
     let collateral_denoms = COLLATERALS
         .prefix(user_addr)
         .keys(deps.storage, None, None, Order::Ascending)
@@ -125,11 +169,53 @@ pub fn get_user_positions_map(
         .keys(deps.storage, None, None, Order::Ascending)
         .collect::<StdResult<Vec<_>>>()?;
 
-    // Collect the denoms into a hashset so that there are no dups
-    let mut denoms = HashSet::new();
+    // Collect the denoms into a set so that there are no dups
+    let mut denoms = BTreeSet::new();
     denoms.extend(collateral_denoms);
     denoms.extend(debt_denoms);
 
+    Ok(denoms)
+}
+
+/// Query the price of an asset to compute health with.
+///
+/// If the config sets a maximum price age, the price is queried along with the time of the data it
+/// is computed from, and an error is returned if that is older than the maximum age, so that health
+/// is never computed (and users never liquidated) based on stale prices.
+pub fn query_asset_price(
+    deps: &Deps,
+    env: &Env,
+    config: &Config<Addr>,
+    oracle_addr: &Addr,
+    denom: &str,
+) -> Result<Decimal, ContractError> {
+    if config.max_price_age == 0 {
+        return Ok(oracle::helpers::query_price(&deps.querier, oracle_addr, denom)?);
+    }
+
+    let res = oracle::helpers::query_price_with_timestamp(&deps.querier, oracle_addr, denom)?;
+    if env.block.time.seconds().saturating_sub(res.timestamp) > config.max_price_age {
+        return Err(ContractError::StalePrice {
+            denom: denom.to_string(),
+            timestamp: res.timestamp,
+            max_age: config.max_price_age,
+        });
+    }
+
+    Ok(res.price)
+}
+
+pub fn get_user_positions_map(
+    deps: &Deps,
+    env: &Env,
+    user_addr: &Addr,
+    oracle_addr: &Addr,
+) -> Result<HashMap<String, Position>, ContractError> {
+    let block_time = env.block.time.seconds();
+    let config = CONFIG.load(deps.storage)?;
+
+    let denoms = get_user_denoms(deps, user_addr)?;
+
     // Enumerate the denoms, compute underlying debt and collateral amount, and query the prices.
     // Finally, collect the results into a hashmap indexed by the denoms.
     denoms
@@ -137,8 +223,12 @@ pub fn get_user_positions_map(
         .map(|denom| {
             let market = MARKETS.load(deps.storage, &denom)?;
 
+            // collateral in a paused market can't be liquidated, so it may optionally not count
+            // towards the user's health either
+            let excluded = config.exclude_paused_collateral && market.is_paused();
+
             let collateral_amount = match COLLATERALS.may_load(deps.storage, (user_addr, &denom))? {
-                Some(collateral) if collateral.enabled => {
+                Some(collateral) if collateral.enabled && !excluded => {
                     let amount_scaled = collateral.amount_scaled;
                     get_underlying_liquidity_amount(amount_scaled, &market, block_time)?
                 }
@@ -148,14 +238,24 @@ pub fn get_user_positions_map(
             let (debt_amount, uncollateralized_debt) =
                 match DEBTS.may_load(deps.storage, (user_addr, &denom))? {
                     Some(debt) => {
-                        let debt_amount =
-                            get_underlying_debt_amount(debt.amount_scaled, &market, block_time)?;
+                        let debt_amount = get_underlying_user_debt_amount(
+                            debt.amount_scaled,
+                            debt.uncollateralized,
+                            &market,
+                            block_time,
+                        )?;
                         (debt_amount, debt.uncollateralized)
                     }
                     None => (Uint128::zero(), false),
                 };
 
-            let asset_price = oracle::helpers::query_price(&deps.querier, oracle_addr, &denom)?;
+            let asset_price = query_asset_price(deps, env, &config, oracle_addr, &denom)?;
+
+            let value_haircut = if config.apply_value_haircut {
+                market.value_haircut
+            } else {
+                Decimal::zero()
+            };
 
             let position = Position {
                 denom: denom.clone(),
@@ -165,6 +265,7 @@ pub fn get_user_positions_map(
                 max_ltv: market.max_loan_to_value,
                 liquidation_threshold: market.liquidation_threshold,
                 asset_price,
+                value_haircut,
             };
 
             Ok((denom, position))
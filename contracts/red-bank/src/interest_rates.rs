@@ -34,22 +34,8 @@ pub fn apply_accumulated_interests(
 
     // Update market indices
     if market.indexes_last_updated < current_timestamp {
-        let time_elapsed = current_timestamp - market.indexes_last_updated;
-
-        if !market.borrow_rate.is_zero() {
-            market.borrow_index = calculate_applied_linear_interest_rate(
-                market.borrow_index,
-                market.borrow_rate,
-                time_elapsed,
-            )?;
-        }
-        if !market.liquidity_rate.is_zero() {
-            market.liquidity_index = calculate_applied_linear_interest_rate(
-                market.liquidity_index,
-                market.liquidity_rate,
-                time_elapsed,
-            )?;
-        }
+        market.borrow_index = get_updated_borrow_index(market, current_timestamp)?;
+        market.liquidity_index = get_updated_liquidity_index(market, current_timestamp)?;
         market.indexes_last_updated = current_timestamp;
     }
 
@@ -90,6 +76,8 @@ pub fn apply_accumulated_interests(
             response,
         )?;
         market.increase_collateral(reward_amount_scaled)?;
+        market.unswept_reserves_scaled =
+            market.unswept_reserves_scaled.checked_add(reward_amount_scaled)?;
     }
 
     Ok(response)
@@ -181,6 +169,42 @@ pub fn get_underlying_debt_amount(
     )
 }
 
+/// Whether a user's debt accrues interest. Uncollateralized debt doesn't in markets that waive
+/// interest on it, and is then stored unscaled.
+pub fn debt_accrues_interest(market: &Market, uncollateralized: bool) -> bool {
+    !uncollateralized || market.uncollateralized_debt_accrues_interest
+}
+
+/// Get the amount a user's debt is stored as from its underlying amount, same as
+/// `get_scaled_debt_amount` unless the debt doesn't accrue interest
+pub fn get_scaled_user_debt_amount(
+    amount: Uint128,
+    uncollateralized: bool,
+    market: &Market,
+    timestamp: u64,
+) -> StdResult<Uint128> {
+    if debt_accrues_interest(market, uncollateralized) {
+        get_scaled_debt_amount(amount, market, timestamp)
+    } else {
+        Ok(amount)
+    }
+}
+
+/// Get the underlying amount of a user's debt from its stored amount, same as
+/// `get_underlying_debt_amount` unless the debt doesn't accrue interest
+pub fn get_underlying_user_debt_amount(
+    amount_scaled: Uint128,
+    uncollateralized: bool,
+    market: &Market,
+    timestamp: u64,
+) -> StdResult<Uint128> {
+    if debt_accrues_interest(market, uncollateralized) {
+        get_underlying_debt_amount(amount_scaled, market, timestamp)
+    } else {
+        Ok(amount_scaled)
+    }
+}
+
 pub enum ScalingOperation {
     Truncate,
     Ceil,
@@ -236,8 +260,9 @@ pub fn get_updated_borrow_index(market: &Market, timestamp: u64) -> StdResult<De
                 market.borrow_index,
                 market.borrow_rate,
                 time_elapsed,
-            );
-            return updated_index;
+            )?;
+            assert_index_not_decreasing(market.borrow_index, updated_index)?;
+            return Ok(updated_index);
         }
     }
 
@@ -262,14 +287,27 @@ pub fn get_updated_liquidity_index(market: &Market, timestamp: u64) -> StdResult
                 market.liquidity_index,
                 market.liquidity_rate,
                 time_elapsed,
-            );
-            return updated_index;
+            )?;
+            assert_index_not_decreasing(market.liquidity_index, updated_index)?;
+            return Ok(updated_index);
         }
     }
 
     Ok(market.liquidity_index)
 }
 
+/// Rejects an index update that would move the index backward. Underlying amounts are computed
+/// assuming indexes never decrease, so applying such an update would shrink user balances.
+pub fn assert_index_not_decreasing(previous_index: Decimal, new_index: Decimal) -> StdResult<()> {
+    if new_index < previous_index {
+        return Err(StdError::generic_err(format!(
+            "Index cannot decrease: updated index {} is smaller than previous index {}",
+            new_index, previous_index
+        )));
+    }
+    Ok(())
+}
+
 /// Update interest rates for current liquidity and debt levels
 /// Note it does not save the market to the store (that is left to the caller)
 /// Returns response with appended interest rates updated event
@@ -309,12 +347,13 @@ pub fn build_interests_updated_event(denom: &str, market: &Market) -> Event {
 
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::{Decimal, Uint128};
+    use cosmwasm_std::{Decimal, StdError, Uint128};
     use mars_red_bank_types::red_bank::Market;
 
     use crate::interest_rates::{
-        calculate_applied_linear_interest_rate, get_scaled_debt_amount,
-        get_scaled_liquidity_amount, get_underlying_debt_amount, get_underlying_liquidity_amount,
+        assert_index_not_decreasing, calculate_applied_linear_interest_rate,
+        get_scaled_debt_amount, get_scaled_liquidity_amount, get_underlying_debt_amount,
+        get_underlying_liquidity_amount,
     };
 
     #[test]
@@ -328,6 +367,24 @@ mod tests {
         assert_eq!(accumulated, Decimal::from_ratio(11u128, 100u128));
     }
 
+    #[test]
+    fn backward_index_movement() {
+        let previous_index = Decimal::from_ratio(11u128, 10u128);
+
+        // e.g. a clawback reducing the index by 10%
+        let new_index = previous_index * Decimal::percent(90);
+        let err = assert_index_not_decreasing(previous_index, new_index).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(
+                "Index cannot decrease: updated index 0.99 is smaller than previous index 1.1"
+            )
+        );
+
+        assert_index_not_decreasing(previous_index, previous_index).unwrap();
+        assert_index_not_decreasing(previous_index, Decimal::from_ratio(12u128, 10u128)).unwrap();
+    }
+
     #[test]
     fn liquidity_and_debt_rounding() {
         let start = Uint128::from(100_000_000_000_u128);
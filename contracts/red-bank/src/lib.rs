@@ -4,6 +4,7 @@ pub mod error;
 pub mod execute;
 pub mod health;
 pub mod interest_rates;
+pub mod migrations;
 pub mod query;
 pub mod state;
 pub mod user;
@@ -0,0 +1,313 @@
+/// Migration logic for Red Bank contract with version: 1.0.1
+pub mod v1_0_1 {
+    use cosmwasm_std::{Addr, Decimal, DepsMut, Order, Response, StdResult, Uint128};
+    use mars_red_bank_types::red_bank::{Config, Market};
+
+    use crate::{
+        error::ContractError,
+        execute::{CONTRACT_NAME, CONTRACT_VERSION},
+        state::{BORROWER_COUNT, COLLATERALS, CONFIG, DEBTS, MARKETS, SUPPLIER_COUNT},
+    };
+
+    const FROM_VERSION: &str = "1.0.1";
+
+    pub fn migrate(deps: DepsMut) -> Result<Response, ContractError> {
+        // make sure we're migrating the correct contract and from the correct version
+        cw2::assert_contract_version(deps.as_ref().storage, CONTRACT_NAME, FROM_VERSION)?;
+
+        // none of the checks added since are enabled until configured by the owner
+        let old_config = old_state::CONFIG.load(deps.storage)?;
+        let config = Config {
+            address_provider: old_config.address_provider,
+            close_factor: old_config.close_factor,
+            apply_value_haircut: false,
+            min_liquidation_value: Uint128::zero(),
+            exclude_paused_collateral: false,
+            min_oracle_sources: 0,
+            max_price_age: 0,
+        };
+        CONFIG.save(deps.storage, &config)?;
+
+        // existing markets get no haircut, minimum deposit or dust threshold and fall back to the
+        // global close factor. Reserves accrued so far weren't tracked apart from the rewards
+        // collector's own deposits, so none are sweepable. Existing uncollateralized debt is
+        // stored scaled, so it has to keep accruing interest
+        let old_markets = old_state::MARKETS
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (denom, old_market) in old_markets {
+            let market = Market {
+                denom: old_market.denom,
+                max_loan_to_value: old_market.max_loan_to_value,
+                liquidation_threshold: old_market.liquidation_threshold,
+                liquidation_bonus: old_market.liquidation_bonus,
+                reserve_factor: old_market.reserve_factor,
+                interest_rate_model: old_market.interest_rate_model,
+                borrow_index: old_market.borrow_index,
+                liquidity_index: old_market.liquidity_index,
+                borrow_rate: old_market.borrow_rate,
+                liquidity_rate: old_market.liquidity_rate,
+                indexes_last_updated: old_market.indexes_last_updated,
+                collateral_total_scaled: old_market.collateral_total_scaled,
+                debt_total_scaled: old_market.debt_total_scaled,
+                deposit_enabled: old_market.deposit_enabled,
+                borrow_enabled: old_market.borrow_enabled,
+                deposit_cap: old_market.deposit_cap,
+                value_haircut: Decimal::zero(),
+                min_deposit_amount: Uint128::zero(),
+                reserve_recipient: None,
+                unswept_reserves_scaled: Uint128::zero(),
+                dust_threshold: Uint128::zero(),
+                uncollateralized_debt_accrues_interest: true,
+                close_factor: None,
+                collateral_close_factor: None,
+            };
+            // the new market is stored under the same key, replacing the old one
+            MARKETS.save(deps.storage, &denom, &market)?;
+        }
+
+        // the user counters weren't maintained before, so they're seeded from the existing
+        // positions
+        let suppliers = count_users(
+            COLLATERALS.range(deps.storage, None, None, Order::Ascending).map(|item| {
+                let ((user, _), collateral) = item?;
+                Ok((user, collateral.amount_scaled))
+            }),
+        )?;
+        let borrowers =
+            count_users(DEBTS.range(deps.storage, None, None, Order::Ascending).map(|item| {
+                let ((user, _), debt) = item?;
+                Ok((user, debt.amount_scaled))
+            }))?;
+        SUPPLIER_COUNT.save(deps.storage, &suppliers)?;
+        BORROWER_COUNT.save(deps.storage, &borrowers)?;
+
+        // update contract version
+        cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", FROM_VERSION)
+            .add_attribute("to_version", CONTRACT_VERSION)
+            .add_attribute("suppliers", suppliers.to_string())
+            .add_attribute("borrowers", borrowers.to_string()))
+    }
+
+    /// Count the distinct users with a non-zero position, given the positions ordered by user
+    fn count_users(positions: impl Iterator<Item = StdResult<(Addr, Uint128)>>) -> StdResult<u64> {
+        let mut count = 0;
+        let mut last_user = None;
+        for item in positions {
+            let (user, amount_scaled) = item?;
+            if amount_scaled.is_zero() || last_user.as_ref() == Some(&user) {
+                continue;
+            }
+            count += 1;
+            last_user = Some(user);
+        }
+        Ok(count)
+    }
+
+    pub mod old_state {
+        use cosmwasm_schema::cw_serde;
+        use cosmwasm_std::{Addr, Decimal, Uint128};
+        use cw_storage_plus::{Item, Map};
+        use mars_red_bank_types::red_bank::InterestRateModel;
+
+        pub const CONFIG: Item<Config> = Item::new("config");
+        pub const MARKETS: Map<&str, Market> = Map::new("markets");
+
+        #[cw_serde]
+        pub struct Config {
+            pub address_provider: Addr,
+            pub close_factor: Decimal,
+        }
+
+        #[cw_serde]
+        pub struct Market {
+            pub denom: String,
+            pub max_loan_to_value: Decimal,
+            pub liquidation_threshold: Decimal,
+            pub liquidation_bonus: Decimal,
+            pub reserve_factor: Decimal,
+            pub interest_rate_model: InterestRateModel,
+            pub borrow_index: Decimal,
+            pub liquidity_index: Decimal,
+            pub borrow_rate: Decimal,
+            pub liquidity_rate: Decimal,
+            pub indexes_last_updated: u64,
+            pub collateral_total_scaled: Uint128,
+            pub debt_total_scaled: Uint128,
+            pub deposit_enabled: bool,
+            pub borrow_enabled: bool,
+            pub deposit_cap: Uint128,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use cosmwasm_std::{attr, testing::mock_dependencies};
+        use mars_red_bank_types::red_bank::{Collateral, Debt, InterestRateModel};
+
+        use super::*;
+
+        fn save_old_config(deps: DepsMut) {
+            old_state::CONFIG
+                .save(
+                    deps.storage,
+                    &old_state::Config {
+                        address_provider: Addr::unchecked("address_provider"),
+                        close_factor: Decimal::percent(50),
+                    },
+                )
+                .unwrap();
+        }
+
+        #[test]
+        fn migrating_config_and_markets() {
+            let mut deps = mock_dependencies();
+
+            cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, FROM_VERSION).unwrap();
+            save_old_config(deps.as_mut());
+            let interest_rate_model = InterestRateModel {
+                optimal_utilization_rate: Decimal::percent(80),
+                base: Decimal::zero(),
+                slope_1: Decimal::percent(7),
+                slope_2: Decimal::percent(45),
+            };
+            old_state::MARKETS
+                .save(
+                    deps.as_mut().storage,
+                    "uosmo",
+                    &old_state::Market {
+                        denom: "uosmo".to_string(),
+                        max_loan_to_value: Decimal::percent(60),
+                        liquidation_threshold: Decimal::percent(65),
+                        liquidation_bonus: Decimal::percent(5),
+                        reserve_factor: Decimal::percent(10),
+                        interest_rate_model: interest_rate_model.clone(),
+                        borrow_index: Decimal::percent(120),
+                        liquidity_index: Decimal::percent(110),
+                        borrow_rate: Decimal::percent(8),
+                        liquidity_rate: Decimal::percent(5),
+                        indexes_last_updated: 1_000_000,
+                        collateral_total_scaled: Uint128::new(1_000_000),
+                        debt_total_scaled: Uint128::new(500_000),
+                        deposit_enabled: true,
+                        borrow_enabled: false,
+                        deposit_cap: Uint128::new(10_000_000),
+                    },
+                )
+                .unwrap();
+
+            migrate(deps.as_mut()).unwrap();
+
+            assert_eq!(
+                CONFIG.load(&deps.storage).unwrap(),
+                Config {
+                    address_provider: Addr::unchecked("address_provider"),
+                    close_factor: Decimal::percent(50),
+                    apply_value_haircut: false,
+                    min_liquidation_value: Uint128::zero(),
+                    exclude_paused_collateral: false,
+                    min_oracle_sources: 0,
+                    max_price_age: 0,
+                }
+            );
+            assert_eq!(
+                MARKETS.load(&deps.storage, "uosmo").unwrap(),
+                Market {
+                    denom: "uosmo".to_string(),
+                    max_loan_to_value: Decimal::percent(60),
+                    liquidation_threshold: Decimal::percent(65),
+                    liquidation_bonus: Decimal::percent(5),
+                    reserve_factor: Decimal::percent(10),
+                    interest_rate_model,
+                    borrow_index: Decimal::percent(120),
+                    liquidity_index: Decimal::percent(110),
+                    borrow_rate: Decimal::percent(8),
+                    liquidity_rate: Decimal::percent(5),
+                    indexes_last_updated: 1_000_000,
+                    collateral_total_scaled: Uint128::new(1_000_000),
+                    debt_total_scaled: Uint128::new(500_000),
+                    deposit_enabled: true,
+                    borrow_enabled: false,
+                    deposit_cap: Uint128::new(10_000_000),
+                    value_haircut: Decimal::zero(),
+                    min_deposit_amount: Uint128::zero(),
+                    reserve_recipient: None,
+                    unswept_reserves_scaled: Uint128::zero(),
+                    dust_threshold: Uint128::zero(),
+                    uncollateralized_debt_accrues_interest: true,
+                    close_factor: None,
+                    collateral_close_factor: None,
+                }
+            );
+        }
+
+        #[test]
+        fn migrating_seeds_user_counts() {
+            let mut deps = mock_dependencies();
+
+            cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, FROM_VERSION).unwrap();
+            save_old_config(deps.as_mut());
+
+            // alice supplies in two markets, bob in one and carol's position is empty
+            let collaterals =
+                [("alice", "uatom", 100), ("alice", "uosmo", 50), ("bob", "uosmo", 10)];
+            for (user, denom, amount_scaled) in collaterals {
+                COLLATERALS
+                    .save(
+                        deps.as_mut().storage,
+                        (&Addr::unchecked(user), denom),
+                        &Collateral {
+                            amount_scaled: Uint128::new(amount_scaled),
+                            enabled: true,
+                        },
+                    )
+                    .unwrap();
+            }
+            COLLATERALS
+                .save(
+                    deps.as_mut().storage,
+                    (&Addr::unchecked("carol"), "uatom"),
+                    &Collateral {
+                        amount_scaled: Uint128::zero(),
+                        enabled: true,
+                    },
+                )
+                .unwrap();
+
+            // bob borrows, while carol was only given an uncollateralized loan limit
+            let debts = [("bob", "uatom", 20), ("carol", "uatom", 0), ("carol", "uosmo", 0)];
+            for (user, denom, amount_scaled) in debts {
+                DEBTS
+                    .save(
+                        deps.as_mut().storage,
+                        (&Addr::unchecked(user), denom),
+                        &Debt {
+                            amount_scaled: Uint128::new(amount_scaled),
+                            uncollateralized: user == "carol",
+                        },
+                    )
+                    .unwrap();
+            }
+
+            let res = migrate(deps.as_mut()).unwrap();
+            assert_eq!(res.messages, vec![]);
+            assert_eq!(
+                res.attributes,
+                vec![
+                    attr("action", "migrate"),
+                    attr("from_version", "1.0.1"),
+                    attr("to_version", "1.1.0"),
+                    attr("suppliers", "2"),
+                    attr("borrowers", "1"),
+                ]
+            );
+
+            assert_eq!(SUPPLIER_COUNT.load(&deps.storage).unwrap(), 2);
+            assert_eq!(BORROWER_COUNT.load(&deps.storage).unwrap(), 1);
+        }
+    }
+}
@@ -1,25 +1,43 @@
-use cosmwasm_std::{Addr, BlockInfo, Deps, Env, Order, StdError, StdResult, Uint128};
+use std::collections::HashMap;
+
+use cosmwasm_std::{
+    Addr, BlockInfo, Decimal, Deps, Env, Fraction, Order, StdError, StdResult, Uint128,
+};
 use cw_storage_plus::Bound;
 use mars_red_bank_types::{
     address_provider::{self, MarsAddressType},
+    oracle,
     red_bank::{
-        Collateral, ConfigResponse, Debt, Market, UncollateralizedLoanLimitResponse,
-        UserCollateralResponse, UserDebtResponse, UserHealthStatus, UserPositionResponse,
+        AggregateHealthResponse, BadDebtResponse, Collateral, CollateralHealthContribution,
+        ConfigResponse, CurrentLtvResponse, Debt, EstimateLiquidationProfitResponse,
+        HaircutCollateralValueResponse, HealthDecompositionResponse, HealthSensitivityResponse,
+        LiquidationRecord, Market, MarketScaledTotalsResponse, Position, ProjectedInterestResponse,
+        RescueDepositResponse, SeizableCollateral, UncollateralizedLoanLimitResponse, UserBadDebt,
+        UserCollateralResponse, UserCountsResponse, UserDebtResponse, UserHealthBreakdownResponse,
+        UserHealthStatus, UserPositionResponse, ValidateNewMarketResponse,
     },
 };
 
 use crate::{
     error::ContractError,
+    execute::{liquidation_close_factor, liquidation_compute_amounts},
     health,
     interest_rates::{
+        calculate_applied_linear_interest_rate, compute_underlying_amount, debt_accrues_interest,
         get_scaled_debt_amount, get_scaled_liquidity_amount, get_underlying_debt_amount,
-        get_underlying_liquidity_amount,
+        get_underlying_liquidity_amount, get_underlying_user_debt_amount, get_updated_borrow_index,
+        get_updated_liquidity_index, ScalingOperation,
+    },
+    state::{
+        BORROWER_COUNT, COLLATERALS, CONFIG, DEBTS, LIQUIDATION_HISTORY, MARKETS, OWNER,
+        SUPPLIER_COUNT, UNCOLLATERALIZED_LOAN_LIMITS,
     },
-    state::{COLLATERALS, CONFIG, DEBTS, MARKETS, OWNER, UNCOLLATERALIZED_LOAN_LIMITS},
+    user::User,
 };
 
 const DEFAULT_LIMIT: u32 = 5;
 const MAX_LIMIT: u32 = 10;
+const MAX_AGGREGATE_HEALTH_USERS: usize = 30;
 
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let owner_state = OWNER.query(deps.storage)?;
@@ -30,6 +48,11 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         emergency_owner: owner_state.emergency_owner,
         address_provider: config.address_provider.to_string(),
         close_factor: config.close_factor,
+        apply_value_haircut: config.apply_value_haircut,
+        min_liquidation_value: config.min_liquidation_value,
+        exclude_paused_collateral: config.exclude_paused_collateral,
+        min_oracle_sources: config.min_oracle_sources,
+        max_price_age: config.max_price_age,
     })
 }
 
@@ -57,6 +80,33 @@ pub fn query_markets(
         .collect()
 }
 
+pub fn query_market_scaled_totals(
+    deps: Deps,
+    env: Env,
+    denom: String,
+) -> StdResult<MarketScaledTotalsResponse> {
+    let market = query_market(deps, denom)?;
+    let block_time = env.block.time.seconds();
+
+    Ok(MarketScaledTotalsResponse {
+        collateral_total_amount: get_underlying_liquidity_amount(
+            market.collateral_total_scaled,
+            &market,
+            block_time,
+        )?,
+        debt_total_amount: get_underlying_debt_amount(
+            market.debt_total_scaled,
+            &market,
+            block_time,
+        )?,
+        liquidity_index: get_updated_liquidity_index(&market, block_time)?,
+        borrow_index: get_updated_borrow_index(&market, block_time)?,
+        collateral_total_scaled: market.collateral_total_scaled,
+        debt_total_scaled: market.debt_total_scaled,
+        denom: market.denom,
+    })
+}
+
 pub fn query_uncollateralized_loan_limit(
     deps: Deps,
     user_addr: Addr,
@@ -105,7 +155,8 @@ pub fn query_user_debt(
 
     let block_time = block.time.seconds();
     let market = MARKETS.load(deps.storage, &denom)?;
-    let amount = get_underlying_debt_amount(amount_scaled, &market, block_time)?;
+    let amount =
+        get_underlying_user_debt_amount(amount_scaled, uncollateralized, &market, block_time)?;
 
     Ok(UserDebtResponse {
         denom,
@@ -137,7 +188,12 @@ pub fn query_user_debts(
             let market = MARKETS.load(deps.storage, &denom)?;
 
             let amount_scaled = debt.amount_scaled;
-            let amount = get_underlying_debt_amount(amount_scaled, &market, block_time)?;
+            let amount = get_underlying_user_debt_amount(
+                amount_scaled,
+                debt.uncollateralized,
+                &market,
+                block_time,
+            )?;
 
             Ok(UserDebtResponse {
                 denom,
@@ -206,6 +262,244 @@ pub fn query_user_collaterals(
         .collect()
 }
 
+pub fn query_estimate_liquidation_profit(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+    debt_denom: String,
+    collateral_denom: String,
+    repay_amount: Uint128,
+    gas_cost: Uint128,
+) -> Result<EstimateLiquidationProfitResponse, ContractError> {
+    let block_time = env.block.time.seconds();
+
+    let user_collateral = COLLATERALS
+        .may_load(deps.storage, (&user_addr, &collateral_denom))?
+        .ok_or(ContractError::CannotLiquidateWhenNoCollateralBalance {})?;
+    if !user_collateral.enabled {
+        return Err(ContractError::CannotLiquidateWhenCollateralUnset {
+            denom: collateral_denom,
+        });
+    }
+    let user_debt = DEBTS
+        .may_load(deps.storage, (&user_addr, &debt_denom))?
+        .ok_or(ContractError::CannotLiquidateWhenNoDebtBalance {})?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+    if !health::compute_position_health(&positions)?.is_liquidatable() {
+        return Err(ContractError::CannotLiquidateHealthyPosition {});
+    }
+
+    let debt_market = MARKETS.load(deps.storage, &debt_denom)?;
+    let user_debt_amount = get_underlying_user_debt_amount(
+        user_debt.amount_scaled,
+        user_debt.uncollateralized,
+        &debt_market,
+        block_time,
+    )?;
+
+    let (debt_amount, collateral_amount, collateral_price) = compute_seize_amounts(
+        deps,
+        block_time,
+        config.close_factor,
+        &positions,
+        &collateral_denom,
+        user_collateral.amount_scaled,
+        &debt_market,
+        user_debt_amount,
+        repay_amount,
+    )?;
+    let debt_price = positions
+        .get(&debt_denom)
+        .ok_or(ContractError::CannotLiquidateWhenNoDebtBalance {})?
+        .asset_price;
+
+    let debt_value = debt_amount * debt_price;
+    let collateral_value = collateral_amount * collateral_price;
+    // the gas cost is supplied in the oracle's base denom, same as the values
+    let cost = debt_value.checked_add(gas_cost)?;
+
+    Ok(EstimateLiquidationProfitResponse {
+        debt_amount,
+        collateral_amount,
+        debt_value,
+        collateral_value,
+        net_profit: collateral_value.saturating_sub(cost),
+        net_loss: cost.saturating_sub(collateral_value),
+    })
+}
+
+pub fn query_seizable_collaterals(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+    debt_denom: String,
+    repay_amount: Uint128,
+) -> Result<Vec<SeizableCollateral>, ContractError> {
+    let block_time = env.block.time.seconds();
+
+    let user_debt = DEBTS
+        .may_load(deps.storage, (&user_addr, &debt_denom))?
+        .ok_or(ContractError::CannotLiquidateWhenNoDebtBalance {})?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+    if !health::compute_position_health(&positions)?.is_liquidatable() {
+        return Err(ContractError::CannotLiquidateHealthyPosition {});
+    }
+
+    let debt_market = MARKETS.load(deps.storage, &debt_denom)?;
+    let user_debt_amount = get_underlying_user_debt_amount(
+        user_debt.amount_scaled,
+        user_debt.uncollateralized,
+        &debt_market,
+        block_time,
+    )?;
+
+    let mut seizable = COLLATERALS
+        .prefix(&user_addr)
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref().map_or(true, |(_, collateral)| {
+                collateral.enabled && !collateral.amount_scaled.is_zero()
+            })
+        })
+        .map(|item| {
+            let (denom, collateral) = item?;
+            let (debt_amount, collateral_amount, collateral_price) = compute_seize_amounts(
+                deps,
+                block_time,
+                config.close_factor,
+                &positions,
+                &denom,
+                collateral.amount_scaled,
+                &debt_market,
+                user_debt_amount,
+                repay_amount,
+            )?;
+            Ok(SeizableCollateral {
+                denom,
+                debt_amount,
+                collateral_amount,
+                collateral_value: collateral_amount * collateral_price,
+            })
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+
+    seizable.sort_by(|a, b| b.collateral_value.cmp(&a.collateral_value));
+
+    Ok(seizable)
+}
+
+/// Amount of debt repaid and of collateral seized, including the liquidation bonus, when repaying
+/// the given amount of the user's debt against one of their collaterals. Also returns the price
+/// of the collateral.
+#[allow(clippy::too_many_arguments)]
+fn compute_seize_amounts(
+    deps: Deps,
+    block_time: u64,
+    global_close_factor: Decimal,
+    positions: &HashMap<String, Position>,
+    collateral_denom: &str,
+    user_collateral_amount_scaled: Uint128,
+    debt_market: &Market,
+    user_debt_amount: Uint128,
+    repay_amount: Uint128,
+) -> Result<(Uint128, Uint128, Decimal), ContractError> {
+    let collateral_price = positions
+        .get(collateral_denom)
+        .ok_or(ContractError::CannotLiquidateWhenNoCollateralBalance {})?
+        .asset_price;
+    let debt_price = positions
+        .get(&debt_market.denom)
+        .ok_or(ContractError::CannotLiquidateWhenNoDebtBalance {})?
+        .asset_price;
+
+    let collateral_market = MARKETS.load(deps.storage, collateral_denom)?;
+
+    let (debt_amount, collateral_amount, _, _) = liquidation_compute_amounts(
+        user_collateral_amount_scaled,
+        user_debt_amount,
+        repay_amount,
+        &collateral_market,
+        collateral_price,
+        debt_price,
+        block_time,
+        liquidation_close_factor(global_close_factor, &collateral_market, debt_market),
+    )?;
+
+    Ok((debt_amount, collateral_amount, collateral_price))
+}
+
+pub fn query_validate_new_market(
+    deps: Deps,
+    denom: String,
+    max_ltv: Decimal,
+    liquidation_threshold: Decimal,
+    liquidation_bonus: Decimal,
+) -> Result<ValidateNewMarketResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let mut errors = vec![];
+    let mut warnings = vec![];
+
+    match oracle::helpers::query_price(&deps.querier, &oracle_addr, &denom) {
+        Ok(price) if price.is_zero() => {
+            errors.push(format!("oracle returns a zero price for {denom}"))
+        }
+        Ok(_) => {}
+        Err(err) => errors.push(format!("oracle has no working price source for {denom}: {err}")),
+    }
+
+    if max_ltv >= liquidation_threshold {
+        errors.push(format!(
+            "max_ltv ({max_ltv}) must be less than liquidation_threshold ({liquidation_threshold})"
+        ));
+    }
+    if liquidation_threshold > Decimal::one() {
+        errors.push(format!("liquidation_threshold ({liquidation_threshold}) must be <= 1"));
+    }
+    if liquidation_bonus > Decimal::one() {
+        errors.push(format!("liquidation_bonus ({liquidation_bonus}) must be <= 1"));
+    }
+
+    if MARKETS.has(deps.storage, &denom) {
+        warnings.push(format!("a market for {denom} already exists"));
+    }
+
+    // a position at the liquidation threshold can't pay the full bonus out of its collateral
+    if liquidation_threshold * (Decimal::one() + liquidation_bonus) > Decimal::one() {
+        warnings.push(format!(
+            "liquidation_threshold ({liquidation_threshold}) with liquidation_bonus \
+             ({liquidation_bonus}) can leave bad debt when liquidating"
+        ));
+    }
+
+    Ok(ValidateNewMarketResponse {
+        denom,
+        errors,
+        warnings,
+    })
+}
+
 pub fn query_scaled_liquidity_amount(
     deps: Deps,
     env: Env,
@@ -246,6 +540,24 @@ pub fn query_underlying_debt_amount(
     get_underlying_debt_amount(amount_scaled, &market, env.block.time.seconds())
 }
 
+pub fn query_user_markets(deps: Deps, user_addr: Addr) -> StdResult<Vec<String>> {
+    Ok(health::get_user_denoms(&deps, &user_addr)?.into_iter().collect())
+}
+
+pub fn query_user_liquidation_history(
+    deps: Deps,
+    user_addr: Addr,
+) -> StdResult<Vec<LiquidationRecord>> {
+    Ok(LIQUIDATION_HISTORY.may_load(deps.storage, &user_addr)?.unwrap_or_default())
+}
+
+pub fn query_user_counts(deps: Deps) -> StdResult<UserCountsResponse> {
+    Ok(UserCountsResponse {
+        suppliers: SUPPLIER_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+        borrowers: BORROWER_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
 pub fn query_user_position(
     deps: Deps,
     env: Env,
@@ -280,3 +592,565 @@ pub fn query_user_position(
         health_status,
     })
 }
+
+pub fn query_health_decomposition(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+) -> Result<HealthDecompositionResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+    let health = health::compute_position_health(&positions)?;
+
+    // the health is a sum over positions, so each collateral's contribution is the health of its
+    // position alone
+    let mut collaterals = positions
+        .into_iter()
+        .filter(|(_, p)| !p.collateral_amount.is_zero())
+        .map(|(denom, p)| {
+            let position_health =
+                health::compute_position_health(&HashMap::from([(denom.clone(), p)]))?;
+            Ok(CollateralHealthContribution {
+                denom,
+                value: position_health.total_collateral_value,
+                weighted_value: position_health.liquidation_threshold_adjusted_collateral,
+            })
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    collaterals.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+    Ok(HealthDecompositionResponse {
+        collaterals,
+        total_weighted_collateral: health.liquidation_threshold_adjusted_collateral,
+        total_debt_value: health.total_debt_value,
+        liquidation_health_factor: health.liquidation_health_factor,
+    })
+}
+
+pub fn query_health_sensitivity(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+) -> Result<Vec<HealthSensitivityResponse>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let mut positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+    let liquidation_hf =
+        match health::compute_position_health(&positions)?.liquidation_health_factor {
+            Some(hf) => hf,
+            None => return Ok(vec![]),
+        };
+
+    let mut collateral_denoms: Vec<_> = positions
+        .values()
+        .filter(|p| !p.collateral_amount.is_zero())
+        .map(|p| p.denom.clone())
+        .collect();
+    collateral_denoms.sort();
+
+    collateral_denoms
+        .into_iter()
+        .map(|denom| {
+            // decrease the asset's price by 1% and compute the health factor again
+            let price = positions[&denom].asset_price;
+            positions.get_mut(&denom).unwrap().asset_price = price * Decimal::percent(99);
+            let perturbed_hf =
+                health::compute_position_health(&positions)?.liquidation_health_factor;
+            positions.get_mut(&denom).unwrap().asset_price = price;
+
+            Ok(HealthSensitivityResponse {
+                denom,
+                liquidation_health_factor_change: liquidation_hf
+                    .saturating_sub(perturbed_hf.unwrap_or_default()),
+            })
+        })
+        .collect()
+}
+
+pub fn query_user_health_breakdown(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+) -> Result<Vec<UserHealthBreakdownResponse>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+
+    // the health is a sum over positions, so each asset's contribution is the health of its
+    // position alone
+    let mut breakdown = positions
+        .into_iter()
+        .map(|(denom, p)| {
+            let position_health =
+                health::compute_position_health(&HashMap::from([(denom.clone(), p)]))?;
+            Ok(UserHealthBreakdownResponse {
+                denom,
+                collateral_value: position_health.total_collateral_value,
+                debt_value: position_health.total_debt_value,
+                weighted_max_ltv_collateral: position_health.max_ltv_adjusted_collateral,
+                weighted_liquidation_threshold_collateral: position_health
+                    .liquidation_threshold_adjusted_collateral,
+            })
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    breakdown.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+    Ok(breakdown)
+}
+
+pub fn query_current_ltv(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+) -> Result<CurrentLtvResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+    let health = health::compute_position_health(&positions)?;
+
+    if health.total_collateral_value.is_zero() {
+        return Ok(CurrentLtvResponse {
+            ltv: if health.total_debt_value.is_zero() {
+                Some(Decimal::zero())
+            } else {
+                None
+            },
+            max_ltv: Decimal::zero(),
+            liquidation_threshold: Decimal::zero(),
+        });
+    }
+
+    Ok(CurrentLtvResponse {
+        ltv: Some(Decimal::checked_from_ratio(
+            health.total_debt_value,
+            health.total_collateral_value,
+        )?),
+        max_ltv: Decimal::checked_from_ratio(
+            health.max_ltv_adjusted_collateral,
+            health.total_collateral_value,
+        )?,
+        liquidation_threshold: Decimal::checked_from_ratio(
+            health.liquidation_threshold_adjusted_collateral,
+            health.total_collateral_value,
+        )?,
+    })
+}
+
+pub fn query_aggregate_health(
+    deps: Deps,
+    env: Env,
+    user_addrs: Vec<Addr>,
+) -> Result<AggregateHealthResponse, ContractError> {
+    if user_addrs.len() > MAX_AGGREGATE_HEALTH_USERS {
+        return Err(ContractError::TooManyUsers {
+            max: MAX_AGGREGATE_HEALTH_USERS,
+        });
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let mut total_collateral_value = Uint128::zero();
+    let mut total_weighted_liquidation_threshold_collateral = Uint128::zero();
+    let mut total_debt_value = Uint128::zero();
+    let mut liquidatable_users = 0;
+    for user_addr in user_addrs {
+        let positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+        let health = health::compute_position_health(&positions)?;
+
+        total_collateral_value =
+            total_collateral_value.checked_add(health.total_collateral_value)?;
+        total_weighted_liquidation_threshold_collateral =
+            total_weighted_liquidation_threshold_collateral
+                .checked_add(health.liquidation_threshold_adjusted_collateral)?;
+        total_debt_value = total_debt_value.checked_add(health.total_debt_value)?;
+        if health.is_liquidatable() {
+            liquidatable_users += 1;
+        }
+    }
+
+    let liquidation_health_factor = if total_debt_value.is_zero() {
+        None
+    } else {
+        Some(Decimal::checked_from_ratio(
+            total_weighted_liquidation_threshold_collateral,
+            total_debt_value,
+        )?)
+    };
+
+    Ok(AggregateHealthResponse {
+        total_collateral_value,
+        total_weighted_liquidation_threshold_collateral,
+        total_debt_value,
+        liquidation_health_factor,
+        liquidatable_users,
+    })
+}
+
+pub fn query_bad_debt(
+    deps: Deps,
+    env: Env,
+    user_addrs: Vec<Addr>,
+) -> Result<BadDebtResponse, ContractError> {
+    if user_addrs.len() > MAX_AGGREGATE_HEALTH_USERS {
+        return Err(ContractError::TooManyUsers {
+            max: MAX_AGGREGATE_HEALTH_USERS,
+        });
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let mut users = vec![];
+    let mut total_shortfall = Uint128::zero();
+    for user_addr in user_addrs {
+        let positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+        let health = health::compute_position_health(&positions)?;
+
+        if health.total_debt_value > health.total_collateral_value {
+            let shortfall = health.total_debt_value - health.total_collateral_value;
+            total_shortfall = total_shortfall.checked_add(shortfall)?;
+            users.push(UserBadDebt {
+                user: user_addr.into(),
+                shortfall,
+            });
+        }
+    }
+
+    Ok(BadDebtResponse {
+        users,
+        total_shortfall,
+    })
+}
+
+pub fn query_haircut_collateral_value(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+) -> Result<HaircutCollateralValueResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+
+    let mut total_enabled_collateral = Uint128::zero();
+    let mut haircut_collateral_value = Uint128::zero();
+    for p in positions.values().filter(|p| !p.collateral_amount.is_zero()) {
+        // the haircut is always applied here, regardless of whether it is used for health
+        let market = MARKETS.load(deps.storage, &p.denom)?;
+
+        let collateral_value = p
+            .collateral_amount
+            .checked_multiply_ratio(p.asset_price.numerator(), p.asset_price.denominator())?;
+
+        total_enabled_collateral = total_enabled_collateral.checked_add(collateral_value)?;
+        haircut_collateral_value = haircut_collateral_value
+            .checked_add(collateral_value * (Decimal::one() - market.value_haircut))?;
+    }
+
+    Ok(HaircutCollateralValueResponse {
+        total_enabled_collateral,
+        haircut_collateral_value,
+    })
+}
+
+pub fn query_projected_interest(
+    deps: Deps,
+    block: &BlockInfo,
+    user_addr: Addr,
+    denom: String,
+    seconds: u64,
+) -> StdResult<ProjectedInterestResponse> {
+    let Debt {
+        amount_scaled,
+        uncollateralized,
+    } = DEBTS.may_load(deps.storage, (&user_addr, &denom))?.unwrap_or_default();
+
+    let market = MARKETS.load(deps.storage, &denom)?;
+
+    // interest-free debt is stored unscaled
+    if !debt_accrues_interest(&market, uncollateralized) {
+        return Ok(ProjectedInterestResponse {
+            denom,
+            debt: amount_scaled,
+            borrow_rate: market.borrow_rate,
+            projected_interest: Uint128::zero(),
+        });
+    }
+
+    let borrow_index = get_updated_borrow_index(&market, block.time.seconds())?;
+    let debt = compute_underlying_amount(amount_scaled, borrow_index, ScalingOperation::Ceil)?;
+
+    // keep applying the current borrow rate until the end of the period
+    let projected_borrow_index =
+        calculate_applied_linear_interest_rate(borrow_index, market.borrow_rate, seconds)?;
+    let projected_debt =
+        compute_underlying_amount(amount_scaled, projected_borrow_index, ScalingOperation::Ceil)?;
+
+    Ok(ProjectedInterestResponse {
+        denom,
+        debt,
+        borrow_rate: market.borrow_rate,
+        projected_interest: projected_debt.checked_sub(debt)?,
+    })
+}
+
+pub fn query_rescue_deposit(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+    denom: String,
+) -> Result<RescueDepositResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let mut positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+    let health = health::compute_position_health(&positions)?;
+    if !health.is_liquidatable() {
+        return Ok(RescueDepositResponse {
+            denom,
+            amount: Some(Uint128::zero()),
+        });
+    }
+
+    // depositing doesn't enable a collateral the user has disabled, so it can't help either
+    let market = MARKETS.load(deps.storage, &denom)?;
+    let collateral_disabled = COLLATERALS
+        .may_load(deps.storage, (&user_addr, &denom))?
+        .map_or(false, |collateral| !collateral.enabled);
+    if !market.deposit_enabled || collateral_disabled {
+        return Ok(RescueDepositResponse {
+            denom,
+            amount: None,
+        });
+    }
+
+    let position = match positions.remove(&denom) {
+        Some(p) => p,
+        None => Position {
+            denom: denom.clone(),
+            max_ltv: market.max_loan_to_value,
+            liquidation_threshold: market.liquidation_threshold,
+            asset_price: health::query_asset_price(&deps, &env, &config, &oracle_addr, &denom)?,
+            value_haircut: if config.apply_value_haircut {
+                market.value_haircut
+            } else {
+                Decimal::zero()
+            },
+            ..Default::default()
+        },
+    };
+
+    if position.liquidation_threshold.is_zero()
+        || position.asset_price.is_zero()
+        || position.value_haircut >= Decimal::one()
+    {
+        return Ok(RescueDepositResponse {
+            denom,
+            amount: None,
+        });
+    }
+
+    let collateral_amount = position.collateral_amount;
+
+    // health of the position without any collateral of this asset; the collateral of this asset
+    // then has to make up for the whole shortfall on its own
+    positions.insert(
+        denom.clone(),
+        Position {
+            collateral_amount: Uint128::zero(),
+            ..position.clone()
+        },
+    );
+    let health = health::compute_position_health(&positions)?;
+    let shortfall =
+        health.total_debt_value.checked_sub(health.liquidation_threshold_adjusted_collateral)?;
+    let amount = collateral_to_cover(shortfall, &position)?.checked_sub(collateral_amount)?;
+
+    let total_deposits = get_underlying_liquidity_amount(
+        market.collateral_total_scaled,
+        &market,
+        env.block.time.seconds(),
+    )?;
+    if total_deposits.checked_add(amount)? > market.deposit_cap {
+        return Ok(RescueDepositResponse {
+            denom,
+            amount: None,
+        });
+    }
+
+    Ok(RescueDepositResponse {
+        denom,
+        amount: Some(amount),
+    })
+}
+
+pub fn query_max_withdrawable(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+    denom: String,
+) -> Result<Uint128, ContractError> {
+    let market = MARKETS.load(deps.storage, &denom)?;
+    let collateral = match COLLATERALS.may_load(deps.storage, (&user_addr, &denom))? {
+        Some(collateral) => collateral,
+        None => return Ok(Uint128::zero()),
+    };
+    let collateral_amount = get_underlying_liquidity_amount(
+        collateral.amount_scaled,
+        &market,
+        env.block.time.seconds(),
+    )?;
+
+    // same as in `withdraw`, health is only checked for enabled collateral of a borrowing user
+    if !collateral.enabled || !User(&user_addr).is_borrowing(deps.storage) {
+        return Ok(collateral_amount);
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let mut positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+    if health::compute_position_health(&positions)?.is_liquidatable() {
+        return Ok(Uint128::zero());
+    }
+
+    // health of the position without any collateral of this asset. If the user is healthy even
+    // then, all of it can be withdrawn; otherwise what remains has to cover the shortfall
+    let position = match positions.remove(&denom) {
+        Some(p) => p,
+        None => return Ok(collateral_amount),
+    };
+    positions.insert(
+        denom.clone(),
+        Position {
+            collateral_amount: Uint128::zero(),
+            ..position.clone()
+        },
+    );
+    let health = health::compute_position_health(&positions)?;
+    if !health.is_liquidatable() {
+        return Ok(collateral_amount);
+    }
+
+    let shortfall =
+        health.total_debt_value.checked_sub(health.liquidation_threshold_adjusted_collateral)?;
+    Ok(position.collateral_amount.saturating_sub(collateral_to_cover(shortfall, &position)?))
+}
+
+pub fn query_max_borrowable(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+    denom: String,
+) -> Result<Uint128, ContractError> {
+    let market = MARKETS.load(deps.storage, &denom)?;
+    if !market.borrow_enabled {
+        return Ok(Uint128::zero());
+    }
+
+    let user = User(&user_addr);
+    let uncollateralized_loan_limit = user.uncollateralized_loan_limit(deps.storage, &denom)?;
+    if !uncollateralized_loan_limit.is_zero() {
+        let debt_amount = get_underlying_user_debt_amount(
+            user.debt_amount_scaled(deps.storage, &denom)?,
+            true,
+            &market,
+            env.block.time.seconds(),
+        )?;
+        return Ok(uncollateralized_loan_limit.saturating_sub(debt_amount));
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let mut positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+    let max_ltv_health_factor = health::compute_position_health(&positions)?.max_ltv_health_factor;
+    if max_ltv_health_factor.map_or(false, |hf| hf <= Decimal::one()) {
+        return Ok(Uint128::zero());
+    }
+
+    // health of the position without any debt in this asset; the borrowed asset's debt value then
+    // has to fit in whatever max LTV adjusted collateral is left
+    let position = health::get_borrow_position(&deps, &env, &oracle_addr, &mut positions, &denom)?;
+    let debt_amount = position.debt_amount;
+    let asset_price = position.asset_price;
+    position.debt_amount = Uint128::zero();
+
+    let health = health::compute_position_health(&positions)?;
+    let headroom = match health.max_ltv_adjusted_collateral.checked_sub(health.total_debt_value) {
+        Ok(headroom) => headroom,
+        Err(_) => return Ok(Uint128::zero()),
+    };
+
+    // largest debt amount whose (rounded down) value is at most the headroom
+    let max_debt_amount =
+        div_ceil(headroom.checked_add(Uint128::one())?, asset_price)?.checked_sub(Uint128::one())?;
+    Ok(max_debt_amount.saturating_sub(debt_amount))
+}
+
+/// Smallest collateral amount of the position's asset whose liquidation threshold adjusted value
+/// covers the given value, inverting each step of the (rounded down) health computation by
+/// rounding up instead.
+///
+/// The position's liquidation threshold and price must be non-zero and its haircut below one.
+fn collateral_to_cover(value: Uint128, position: &Position) -> Result<Uint128, ContractError> {
+    let threshold_value = div_ceil(value, position.liquidation_threshold)?;
+    let haircut_amount = div_ceil(threshold_value, position.asset_price)?;
+    div_ceil(haircut_amount, Decimal::one() - position.value_haircut)
+}
+
+/// Smallest amount `x` such that `x * ratio` (rounded down) is at least `value`
+fn div_ceil(value: Uint128, ratio: Decimal) -> Result<Uint128, ContractError> {
+    let amount = value.checked_multiply_ratio(ratio.denominator(), ratio.numerator())?;
+    if amount.checked_multiply_ratio(ratio.numerator(), ratio.denominator())? < value {
+        Ok(amount.checked_add(Uint128::one())?)
+    } else {
+        Ok(amount)
+    }
+}
@@ -1,7 +1,7 @@
 use cosmwasm_std::{Addr, Uint128};
 use cw_storage_plus::{Item, Map};
 use mars_owner::Owner;
-use mars_red_bank_types::red_bank::{Collateral, Config, Debt, Market};
+use mars_red_bank_types::red_bank::{Collateral, Config, Debt, LiquidationRecord, Market};
 
 pub const OWNER: Owner = Owner::new("owner");
 pub const CONFIG: Item<Config<Addr>> = Item::new("config");
@@ -9,3 +9,16 @@ pub const MARKETS: Map<&str, Market> = Map::new("markets");
 pub const COLLATERALS: Map<(&Addr, &str), Collateral> = Map::new("collaterals");
 pub const DEBTS: Map<(&Addr, &str), Debt> = Map::new("debts");
 pub const UNCOLLATERALIZED_LOAN_LIMITS: Map<(&Addr, &str), Uint128> = Map::new("limits");
+/// User, target denom, the contract's balance of it before the swaps, and the amount of it withdrawn
+/// as is, during a `WithdrawAllAndConsolidate`
+pub const PENDING_CONSOLIDATION: Item<(Addr, String, Uint128, Uint128)> =
+    Item::new("pending_consolidation");
+
+/// Number of users with at least one non-zero entry in `COLLATERALS`
+pub const SUPPLIER_COUNT: Item<u64> = Item::new("supplier_count");
+/// Number of users with at least one non-zero entry in `DEBTS`
+pub const BORROWER_COUNT: Item<u64> = Item::new("borrower_count");
+
+/// Maximum number of liquidations kept in each user's history; older ones are dropped
+pub const MAX_LIQUIDATION_HISTORY: usize = 10;
+pub const LIQUIDATION_HISTORY: Map<&Addr, Vec<LiquidationRecord>> = Map::new("liquidations");
@@ -1,12 +1,17 @@
 use cosmwasm_std::{
-    to_binary, Addr, CosmosMsg, Order, Response, StdResult, Storage, Uint128, WasmMsg,
+    to_binary, Addr, CosmosMsg, Order, OverflowError, OverflowOperation, Response, StdError,
+    StdResult, Storage, Uint128, WasmMsg,
 };
+use cw_storage_plus::Item;
 use mars_red_bank_types::{
-    incentives,
+    incentives::{self, PositionKind},
     red_bank::{Collateral, Debt, Market},
 };
 
-use crate::state::{COLLATERALS, DEBTS, UNCOLLATERALIZED_LOAN_LIMITS};
+use crate::{
+    interest_rates::debt_accrues_interest,
+    state::{BORROWER_COUNT, COLLATERALS, DEBTS, SUPPLIER_COUNT, UNCOLLATERALIZED_LOAN_LIMITS},
+};
 
 /// A helper class providing an intuitive API for managing user positions in the contract store.
 ///
@@ -24,7 +29,7 @@ use crate::state::{COLLATERALS, DEBTS, UNCOLLATERALIZED_LOAN_LIMITS};
 ///
 /// ```rust
 /// let user = User(&user_addr);
-/// user.increase_debt(deps.storage, new_debt)?;
+/// user.increase_debt(deps.storage, &market, new_debt, false, &incentives_addr, response)?;
 /// ```
 #[derive(Clone, Copy)]
 pub struct User<'a>(pub &'a Addr);
@@ -88,6 +93,30 @@ impl<'a> User<'a> {
         DEBTS.prefix(self.0).range(store, None, None, Order::Ascending).next().is_some()
     }
 
+    /// Return `true` if the user has a non-zero collateral position in _any_ asset.
+    pub fn is_supplying(&self, store: &dyn Storage) -> StdResult<bool> {
+        for item in COLLATERALS.prefix(self.0).range(store, None, None, Order::Ascending) {
+            let (_, collateral) = item?;
+            if !collateral.amount_scaled.is_zero() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Return `true` if the user has a non-zero debt position in _any_ asset. Unlike
+    /// `is_borrowing`, the empty debt positions of users given an uncollateralized loan limit
+    /// aren't taken into account.
+    fn has_debt(&self, store: &dyn Storage) -> StdResult<bool> {
+        for item in DEBTS.prefix(self.0).range(store, None, None, Order::Ascending) {
+            let (_, debt) = item?;
+            if !debt.amount_scaled.is_zero() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     /// Increase a user's collateral shares by the specified amount. Returns a message to inform the
     /// incentives contract to update the user's index.
     ///
@@ -103,6 +132,10 @@ impl<'a> User<'a> {
         incentives_addr: &Addr,
         response: Response,
     ) -> StdResult<Response> {
+        if !amount_scaled.is_zero() && !self.is_supplying(store)? {
+            increment_count(store, &SUPPLIER_COUNT)?;
+        }
+
         let mut amount_scaled_before = Uint128::zero();
 
         COLLATERALS.update(store, (self.0, &market.denom), |opt| -> StdResult<_> {
@@ -122,6 +155,7 @@ impl<'a> User<'a> {
         let msg = self.build_incentives_balance_changed_msg(
             incentives_addr,
             market,
+            PositionKind::Collateral,
             amount_scaled_before,
         )?;
 
@@ -149,6 +183,9 @@ impl<'a> User<'a> {
 
         if collateral.amount_scaled.is_zero() {
             COLLATERALS.remove(store, (self.0, &market.denom));
+            if !amount_scaled_before.is_zero() && !self.is_supplying(store)? {
+                decrement_count(store, &SUPPLIER_COUNT)?;
+            }
         } else {
             COLLATERALS.save(store, (self.0, &market.denom), &collateral)?;
         }
@@ -156,6 +193,7 @@ impl<'a> User<'a> {
         let msg = self.build_incentives_balance_changed_msg(
             incentives_addr,
             market,
+            PositionKind::Collateral,
             amount_scaled_before,
         )?;
 
@@ -165,71 +203,156 @@ impl<'a> User<'a> {
     /// For internal use by the struct only.
     ///
     /// Create an execute message to inform the incentive contract to update the user's index upon a
-    /// change in the user's scaled collateral amount.
+    /// change in the user's scaled collateral or debt amount. The market's totals must be the ones
+    /// before the change.
     fn build_incentives_balance_changed_msg(
         &self,
         incentives_addr: &Addr,
         market: &Market,
+        kind: PositionKind,
         user_amount_scaled_before: Uint128,
     ) -> StdResult<CosmosMsg> {
+        let total_amount_scaled_before = match kind {
+            PositionKind::Collateral => market.collateral_total_scaled,
+            PositionKind::Debt => market.debt_total_scaled,
+        };
         Ok(WasmMsg::Execute {
             contract_addr: incentives_addr.into(),
             msg: to_binary(&incentives::ExecuteMsg::BalanceChange {
                 user_addr: self.address().clone(),
                 denom: market.denom.clone(),
                 user_amount_scaled_before,
-                total_amount_scaled_before: market.collateral_total_scaled,
+                total_amount_scaled_before,
+                kind: Some(kind),
             })?,
             funds: vec![],
         }
         .into())
     }
 
-    /// Increase a user's debt shares by the specified amount.
+    /// Increase a user's debt shares by the specified amount. Returns a message to inform the
+    /// incentives contract to update the user's index, unless the debt doesn't accrue interest and
+    /// so isn't part of the market's total debt.
     ///
     /// This may be invoked if a user makes a new borrowing.
     pub fn increase_debt(
         &self,
         store: &mut dyn Storage,
-        denom: &str,
+        market: &Market,
         amount_scaled: Uint128,
         uncollateralized: bool,
-    ) -> StdResult<()> {
-        DEBTS.update(store, (self.0, denom), |opt| -> StdResult<_> {
+        incentives_addr: &Addr,
+        response: Response,
+    ) -> StdResult<Response> {
+        if !amount_scaled.is_zero() && !self.has_debt(store)? {
+            increment_count(store, &BORROWER_COUNT)?;
+        }
+
+        let mut amount_scaled_before = Uint128::zero();
+
+        DEBTS.update(store, (self.0, &market.denom), |opt| -> StdResult<_> {
             match opt {
-                Some(debt) => Ok(Debt {
-                    amount_scaled: debt.amount_scaled.checked_add(amount_scaled)?,
-                    uncollateralized,
-                }),
+                Some(debt) => {
+                    amount_scaled_before = debt.amount_scaled;
+                    Ok(Debt {
+                        amount_scaled: debt.amount_scaled.checked_add(amount_scaled)?,
+                        uncollateralized,
+                    })
+                }
                 None => Ok(Debt {
                     amount_scaled,
                     uncollateralized,
                 }),
             }
         })?;
-        Ok(())
+
+        if !debt_accrues_interest(market, uncollateralized) {
+            return Ok(response);
+        }
+
+        let msg = self.build_incentives_balance_changed_msg(
+            incentives_addr,
+            market,
+            PositionKind::Debt,
+            amount_scaled_before,
+        )?;
+
+        Ok(response.add_message(msg))
     }
 
     /// Decrease a user's debt shares by the specified amount. If reduced to zero, delete the debt
-    /// position from contract storage.
+    /// position from contract storage. Returns a message to inform the incentives contract to
+    /// update the user's index, unless the debt doesn't accrue interest.
     ///
     /// This may be invoked if a user makes a repayment, or gets liquidated.
     pub fn decrease_debt(
         &self,
         store: &mut dyn Storage,
-        denom: &str,
+        market: &Market,
         amount_scaled: Uint128,
-    ) -> StdResult<()> {
-        let mut debt = DEBTS.load(store, (self.0, denom))?;
+        incentives_addr: &Addr,
+        response: Response,
+    ) -> StdResult<Response> {
+        let mut debt = DEBTS.load(store, (self.0, &market.denom))?;
 
+        let amount_scaled_before = debt.amount_scaled;
         debt.amount_scaled = debt.amount_scaled.checked_sub(amount_scaled)?;
 
         if debt.amount_scaled.is_zero() {
-            DEBTS.remove(store, (self.0, denom));
+            DEBTS.remove(store, (self.0, &market.denom));
+            if !amount_scaled_before.is_zero() && !self.has_debt(store)? {
+                decrement_count(store, &BORROWER_COUNT)?;
+            }
         } else {
-            DEBTS.save(store, (self.0, denom), &debt)?;
+            DEBTS.save(store, (self.0, &market.denom), &debt)?;
+        }
+
+        if !debt_accrues_interest(market, debt.uncollateralized) {
+            return Ok(response);
         }
 
+        let msg = self.build_incentives_balance_changed_msg(
+            incentives_addr,
+            market,
+            PositionKind::Debt,
+            amount_scaled_before,
+        )?;
+
+        Ok(response.add_message(msg))
+    }
+
+    /// Mark the user's debt in the given denom as collateralized or uncollateralized, creating an
+    /// empty debt position if the user doesn't have one. An empty position doesn't make the user a
+    /// borrower.
+    ///
+    /// This is invoked when the owner updates the user's uncollateralized loan limit.
+    pub fn set_debt_uncollateralized(
+        &self,
+        store: &mut dyn Storage,
+        denom: &str,
+        uncollateralized: bool,
+    ) -> StdResult<()> {
+        DEBTS.update(store, (self.0, denom), |opt| -> StdResult<_> {
+            let mut debt = opt.unwrap_or(Debt {
+                amount_scaled: Uint128::zero(),
+                uncollateralized: false,
+            });
+            debt.uncollateralized = uncollateralized;
+            Ok(debt)
+        })?;
         Ok(())
     }
 }
+
+fn increment_count(store: &mut dyn Storage, count: &Item<u64>) -> StdResult<()> {
+    let value = count.may_load(store)?.unwrap_or_default();
+    count.save(store, &(value + 1))
+}
+
+fn decrement_count(store: &mut dyn Storage, count: &Item<u64>) -> StdResult<()> {
+    let value = count.may_load(store)?.unwrap_or_default();
+    let value = value
+        .checked_sub(1)
+        .ok_or_else(|| StdError::overflow(OverflowError::new(OverflowOperation::Sub, value, 1)))?;
+    count.save(store, &value)
+}
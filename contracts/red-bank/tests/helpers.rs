@@ -4,7 +4,7 @@ use cosmwasm_schema::serde;
 use cosmwasm_std::{
     from_binary,
     testing::{MockApi, MockStorage},
-    Addr, Coin, Decimal, Deps, DepsMut, Event, OwnedDeps, Uint128,
+    to_binary, Addr, Coin, Decimal, Deps, DepsMut, Event, OwnedDeps, SubMsg, Uint128, WasmMsg,
 };
 use mars_red_bank::{
     contract::{instantiate, query},
@@ -14,8 +14,10 @@ use mars_red_bank::{
     },
     state::{COLLATERALS, DEBTS, MARKETS},
 };
-use mars_red_bank_types::red_bank::{
-    Collateral, CreateOrUpdateConfig, Debt, InstantiateMsg, Market, QueryMsg,
+use mars_red_bank_types::{
+    address_provider::MarsAddressType,
+    incentives::{self, PositionKind},
+    red_bank::{Collateral, CreateOrUpdateConfig, Debt, InstantiateMsg, Market, QueryMsg},
 };
 use mars_testing::{mock_dependencies, mock_env, mock_info, MarsMockQuerier, MockEnvParams};
 
@@ -77,6 +79,11 @@ pub fn th_setup(contract_balances: &[Coin]) -> OwnedDeps<MockStorage, MockApi, M
     let config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: Some(Decimal::from_ratio(1u128, 2u128)),
+        apply_value_haircut: None,
+        min_liquidation_value: None,
+        exclude_paused_collateral: None,
+        min_oracle_sources: None,
+        max_price_age: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -123,6 +130,27 @@ pub fn th_build_interests_updated_event(denom: &str, ir: &TestInterestResults) -
         .add_attribute("liquidity_rate", ir.liquidity_rate.to_string())
 }
 
+/// Message informing the incentives contract of a change in the user's scaled debt
+pub fn th_build_debt_balance_changed_msg(
+    user_addr: &Addr,
+    denom: &str,
+    user_amount_scaled_before: Uint128,
+    total_amount_scaled_before: Uint128,
+) -> SubMsg {
+    SubMsg::new(WasmMsg::Execute {
+        contract_addr: MarsAddressType::Incentives.to_string(),
+        msg: to_binary(&incentives::ExecuteMsg::BalanceChange {
+            user_addr: user_addr.clone(),
+            denom: denom.to_string(),
+            user_amount_scaled_before,
+            total_amount_scaled_before,
+            kind: Some(PositionKind::Debt),
+        })
+        .unwrap(),
+        funds: vec![],
+    })
+}
+
 /// Deltas to be using in expected indices/rates results
 #[derive(Default, Debug)]
 pub struct TestUtilizationDeltaInfo {
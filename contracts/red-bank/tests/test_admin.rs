@@ -30,6 +30,11 @@ fn proper_initialization() {
     let base_config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: None,
+        apply_value_haircut: None,
+        min_liquidation_value: None,
+        exclude_paused_collateral: None,
+        min_oracle_sources: None,
+        max_price_age: None,
     };
 
     // *
@@ -38,6 +43,11 @@ fn proper_initialization() {
     let empty_config = CreateOrUpdateConfig {
         address_provider: None,
         close_factor: None,
+        apply_value_haircut: None,
+        min_liquidation_value: None,
+        exclude_paused_collateral: None,
+        min_oracle_sources: None,
+        max_price_age: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -108,6 +118,11 @@ fn update_config() {
     let init_config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: Some(close_factor),
+        apply_value_haircut: None,
+        min_liquidation_value: None,
+        exclude_paused_collateral: None,
+        min_oracle_sources: None,
+        max_price_age: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -157,6 +172,11 @@ fn update_config() {
     let config = CreateOrUpdateConfig {
         address_provider: Some("new_address_provider".to_string()),
         close_factor: Some(close_factor),
+        apply_value_haircut: None,
+        min_liquidation_value: None,
+        exclude_paused_collateral: None,
+        min_oracle_sources: None,
+        max_price_age: None,
     };
     let msg = ExecuteMsg::UpdateConfig {
         config: config.clone(),
@@ -184,6 +204,11 @@ fn init_asset() {
     let config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: Some(Decimal::from_ratio(1u128, 2u128)),
+        apply_value_haircut: None,
+        min_liquidation_value: None,
+        exclude_paused_collateral: None,
+        min_oracle_sources: None,
+        max_price_age: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -208,6 +233,13 @@ fn init_asset() {
         deposit_enabled: Some(true),
         borrow_enabled: Some(true),
         deposit_cap: None,
+        value_haircut: None,
+        min_deposit_amount: None,
+        reserve_recipient: None,
+        dust_threshold: None,
+        uncollateralized_debt_accrues_interest: None,
+        close_factor: None,
+        collateral_close_factor: None,
     };
 
     // non owner is not authorized
@@ -469,6 +501,11 @@ fn update_asset() {
     let config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: Some(Decimal::from_ratio(1u128, 2u128)),
+        apply_value_haircut: None,
+        min_liquidation_value: None,
+        exclude_paused_collateral: None,
+        min_oracle_sources: None,
+        max_price_age: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -493,6 +530,13 @@ fn update_asset() {
         deposit_enabled: Some(true),
         borrow_enabled: Some(true),
         deposit_cap: None,
+        value_haircut: None,
+        min_deposit_amount: None,
+        reserve_recipient: None,
+        dust_threshold: None,
+        uncollateralized_debt_accrues_interest: None,
+        close_factor: None,
+        collateral_close_factor: None,
     };
 
     // non owner is not authorized
@@ -657,6 +701,13 @@ fn update_asset() {
             deposit_enabled: Some(true),
             borrow_enabled: Some(true),
             deposit_cap: Some(Uint128::new(10_000_000)),
+            value_haircut: None,
+            min_deposit_amount: None,
+            reserve_recipient: None,
+            dust_threshold: None,
+            uncollateralized_debt_accrues_interest: None,
+            close_factor: None,
+            collateral_close_factor: None,
         };
         let msg = ExecuteMsg::UpdateAsset {
             denom: "someasset".to_string(),
@@ -692,6 +743,13 @@ fn update_asset() {
             deposit_enabled: None,
             borrow_enabled: None,
             deposit_cap: None,
+            value_haircut: None,
+            min_deposit_amount: None,
+            reserve_recipient: None,
+            dust_threshold: None,
+            uncollateralized_debt_accrues_interest: None,
+            close_factor: None,
+            collateral_close_factor: None,
         };
         let msg = ExecuteMsg::UpdateAsset {
             denom: "someasset".to_string(),
@@ -722,6 +780,11 @@ fn update_asset_with_new_interest_rate_model_params() {
     let config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: Some(Decimal::from_ratio(1u128, 2u128)),
+        apply_value_haircut: None,
+        min_liquidation_value: None,
+        exclude_paused_collateral: None,
+        min_oracle_sources: None,
+        max_price_age: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -747,6 +810,13 @@ fn update_asset_with_new_interest_rate_model_params() {
         deposit_enabled: Some(true),
         borrow_enabled: Some(true),
         deposit_cap: None,
+        value_haircut: None,
+        min_deposit_amount: None,
+        reserve_recipient: None,
+        dust_threshold: None,
+        uncollateralized_debt_accrues_interest: None,
+        close_factor: None,
+        collateral_close_factor: None,
     };
 
     let msg = ExecuteMsg::InitAsset {
@@ -865,6 +935,13 @@ fn update_asset_new_reserve_factor_accrues_interest_rate() {
         deposit_enabled: None,
         borrow_enabled: None,
         deposit_cap: None,
+        value_haircut: None,
+        min_deposit_amount: None,
+        reserve_recipient: None,
+        dust_threshold: None,
+        uncollateralized_debt_accrues_interest: None,
+        close_factor: None,
+        collateral_close_factor: None,
     };
     let msg = ExecuteMsg::UpdateAsset {
         denom: "somecoin".to_string(),
@@ -954,6 +1031,11 @@ fn update_asset_by_emergency_owner() {
     let config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: Some(Decimal::from_ratio(1u128, 2u128)),
+        apply_value_haircut: None,
+        min_liquidation_value: None,
+        exclude_paused_collateral: None,
+        min_oracle_sources: None,
+        max_price_age: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -978,6 +1060,13 @@ fn update_asset_by_emergency_owner() {
         deposit_enabled: Some(true),
         borrow_enabled: Some(true),
         deposit_cap: None,
+        value_haircut: None,
+        min_deposit_amount: None,
+        reserve_recipient: None,
+        dust_threshold: None,
+        uncollateralized_debt_accrues_interest: None,
+        close_factor: None,
+        collateral_close_factor: None,
     };
 
     execute(
@@ -1044,6 +1133,13 @@ fn update_asset_by_emergency_owner() {
             deposit_enabled: Some(false),
             borrow_enabled: Some(false),
             deposit_cap: Some(Uint128::new(10_000_000)),
+            value_haircut: None,
+            min_deposit_amount: None,
+            reserve_recipient: None,
+            dust_threshold: None,
+            uncollateralized_debt_accrues_interest: None,
+            close_factor: None,
+            collateral_close_factor: None,
         };
         let msg = ExecuteMsg::UpdateAsset {
             denom: "someasset".to_string(),
@@ -1063,3 +1159,124 @@ fn update_asset_by_emergency_owner() {
         assert_eq!(old_market, new_market);
     }
 }
+
+#[test]
+fn enabling_market_requires_min_oracle_sources() {
+    let mut deps = th_setup(&[]);
+    let env = mock_env(MockEnvParams::default());
+
+    let msg = ExecuteMsg::UpdateConfig {
+        config: CreateOrUpdateConfig {
+            address_provider: None,
+            close_factor: None,
+            apply_value_haircut: None,
+            min_liquidation_value: None,
+            exclude_paused_collateral: None,
+            min_oracle_sources: Some(2),
+            max_price_age: None,
+        },
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    deps.querier.set_oracle_price_source_count("uosmo", 1);
+    deps.querier.set_oracle_price_source_count("uatom", 2);
+
+    let params = InitOrUpdateAssetParams {
+        max_loan_to_value: Some(Decimal::percent(50)),
+        reserve_factor: Some(Decimal::percent(1)),
+        liquidation_threshold: Some(Decimal::percent(80)),
+        liquidation_bonus: Some(Decimal::percent(10)),
+        interest_rate_model: Some(InterestRateModel {
+            optimal_utilization_rate: Decimal::one(),
+            base: Decimal::percent(5),
+            slope_1: Decimal::zero(),
+            slope_2: Decimal::zero(),
+        }),
+        deposit_enabled: Some(true),
+        borrow_enabled: Some(true),
+        deposit_cap: None,
+        value_haircut: None,
+        min_deposit_amount: None,
+        reserve_recipient: None,
+        dust_threshold: None,
+        uncollateralized_debt_accrues_interest: None,
+        close_factor: None,
+        collateral_close_factor: None,
+    };
+
+    // a single-source market can't be initialized as enabled
+    let msg = ExecuteMsg::InitAsset {
+        denom: "uosmo".to_string(),
+        params: params.clone(),
+    };
+    let error_res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::InsufficientOracleSources {
+            denom: "uosmo".to_string(),
+            sources: 1,
+            min_sources: 2,
+        }
+    );
+
+    // a market with enough sources can
+    let msg = ExecuteMsg::InitAsset {
+        denom: "uatom".to_string(),
+        params: params.clone(),
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    // a disabled single-source market can be initialized, but not enabled later
+    let msg = ExecuteMsg::InitAsset {
+        denom: "uosmo".to_string(),
+        params: InitOrUpdateAssetParams {
+            deposit_enabled: Some(false),
+            borrow_enabled: Some(false),
+            ..params
+        },
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    let enable_params = InitOrUpdateAssetParams {
+        max_loan_to_value: None,
+        reserve_factor: None,
+        liquidation_threshold: None,
+        liquidation_bonus: None,
+        interest_rate_model: None,
+        deposit_enabled: Some(true),
+        borrow_enabled: None,
+        deposit_cap: None,
+        value_haircut: None,
+        min_deposit_amount: None,
+        reserve_recipient: None,
+        dust_threshold: None,
+        uncollateralized_debt_accrues_interest: None,
+        close_factor: None,
+        collateral_close_factor: None,
+    };
+    let msg = ExecuteMsg::UpdateAsset {
+        denom: "uosmo".to_string(),
+        params: enable_params.clone(),
+    };
+    let error_res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::InsufficientOracleSources {
+            denom: "uosmo".to_string(),
+            sources: 1,
+            min_sources: 2,
+        }
+    );
+
+    // once the oracle has a fallback source, the market can be enabled
+    deps.querier.set_oracle_price_source_count("uosmo", 2);
+    let msg = ExecuteMsg::UpdateAsset {
+        denom: "uosmo".to_string(),
+        params: enable_params,
+    };
+    execute(deps.as_mut(), env, mock_info("owner", &[]), msg).unwrap();
+
+    let market = MARKETS.load(&deps.storage, "uosmo").unwrap();
+    assert!(market.deposit_enabled);
+    assert!(!market.borrow_enabled);
+}
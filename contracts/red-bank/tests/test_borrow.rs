@@ -1,13 +1,15 @@
 use cosmwasm_std::{
-    attr, coin, coins, testing::mock_info, Addr, BankMsg, CosmosMsg, Decimal, SubMsg, Uint128,
+    attr, coin, coins, from_binary, testing::mock_info, Addr, BankMsg, CosmosMsg, Decimal, Deps,
+    SubMsg, Uint128,
 };
 use cw_utils::PaymentError;
 use helpers::{
-    has_collateral_position, has_debt_position, set_collateral, th_build_interests_updated_event,
+    has_collateral_position, has_debt_position, set_collateral, set_debt,
+    th_build_debt_balance_changed_msg, th_build_interests_updated_event,
     th_get_expected_indices_and_rates, th_init_market, th_setup, TestUtilizationDeltaInfo,
 };
 use mars_red_bank::{
-    contract::execute,
+    contract::{execute, query},
     error::ContractError,
     interest_rates::{
         calculate_applied_linear_interest_rate, compute_scaled_amount, compute_underlying_amount,
@@ -15,7 +17,9 @@ use mars_red_bank::{
     },
     state::{DEBTS, MARKETS, UNCOLLATERALIZED_LOAN_LIMITS},
 };
-use mars_red_bank_types::red_bank::{ExecuteMsg, Market};
+use mars_red_bank_types::red_bank::{
+    ExecuteMsg, InterestRateModel, Market, QueryMsg, UserDebtResponse,
+};
 use mars_testing::{mock_env, mock_env_at_block_time, MockEnvParams};
 use mars_utils::math;
 
@@ -119,10 +123,18 @@ fn borrow_and_repay() {
     // check correct messages and logging
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: borrower_addr.to_string(),
-            amount: coins(borrow_amount.u128(), "uosmo")
-        }))]
+        vec![
+            th_build_debt_balance_changed_msg(
+                &borrower_addr,
+                "uosmo",
+                Uint128::zero(),
+                market_1_initial.debt_total_scaled
+            ),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: borrower_addr.to_string(),
+                amount: coins(borrow_amount.u128(), "uosmo")
+            }))
+        ]
     );
     assert_eq!(
         res.attributes,
@@ -236,10 +248,18 @@ fn borrow_and_repay() {
     // check correct messages and logging
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "borrower".to_string(),
-            amount: coins(borrow_amount.u128(), "uusd")
-        }))]
+        vec![
+            th_build_debt_balance_changed_msg(
+                &borrower_addr,
+                "uusd",
+                Uint128::zero(),
+                market_2_initial.debt_total_scaled
+            ),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "borrower".to_string(),
+                amount: coins(borrow_amount.u128(), "uusd")
+            }))
+        ]
     );
     assert_eq!(
         res.attributes,
@@ -315,7 +335,15 @@ fn borrow_and_repay() {
     )
     .unwrap();
 
-    assert_eq!(res.messages, vec![]);
+    assert_eq!(
+        res.messages,
+        vec![th_build_debt_balance_changed_msg(
+            &borrower_addr,
+            "uusd",
+            expected_debt_scaled_2_after_borrow_2,
+            market_2_after_borrow_2.debt_total_scaled
+        )]
+    );
     assert_eq!(
         res.attributes,
         vec![
@@ -376,7 +404,15 @@ fn borrow_and_repay() {
     };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
 
-    assert_eq!(res.messages, vec![]);
+    assert_eq!(
+        res.messages,
+        vec![th_build_debt_balance_changed_msg(
+            &borrower_addr,
+            "uusd",
+            expected_debt_scaled_2_after_repay_some_2,
+            market_2_after_repay_some_2.debt_total_scaled
+        )]
+    );
     assert_eq!(
         res.attributes,
         vec![
@@ -441,10 +477,18 @@ fn borrow_and_repay() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: borrower_addr.to_string(),
-            amount: coins(expected_refund_amount.u128(), "uosmo")
-        }))]
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: borrower_addr.to_string(),
+                amount: coins(expected_refund_amount.u128(), "uosmo")
+            })),
+            th_build_debt_balance_changed_msg(
+                &borrower_addr,
+                "uosmo",
+                expected_debt_scaled_1_after_borrow_again,
+                market_1_after_borrow_again.debt_total_scaled
+            )
+        ]
     );
     assert_eq!(
         res.attributes,
@@ -536,7 +580,16 @@ fn repay_without_refund_on_behalf_of() {
     assert!(!has_debt_position(deps.as_ref(), &borrower_addr, &market_2_initial.denom));
 
     // Check msgs and attributes
-    assert_eq!(res.messages, vec![]);
+    let borrow_amount_scaled = Uint128::new(borrow_amount) * SCALING_FACTOR;
+    assert_eq!(
+        res.messages,
+        vec![th_build_debt_balance_changed_msg(
+            &borrower_addr,
+            "borrowedcoinnative",
+            borrow_amount_scaled,
+            borrow_amount_scaled
+        )]
+    );
     assert_eq!(
         res.attributes,
         vec![
@@ -619,10 +672,18 @@ fn repay_with_refund_on_behalf_of() {
     // Check msgs and attributes
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: user_addr.to_string(),
-            amount: coins(refund_amount, "borrowedcoinnative")
-        }))]
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: user_addr.to_string(),
+                amount: coins(refund_amount, "borrowedcoinnative")
+            })),
+            th_build_debt_balance_changed_msg(
+                &borrower_addr,
+                "borrowedcoinnative",
+                Uint128::new(borrow_amount) * SCALING_FACTOR,
+                Uint128::new(borrow_amount) * SCALING_FACTOR
+            )
+        ]
     );
     assert_eq!(
         res.attributes,
@@ -942,6 +1003,51 @@ fn cannot_borrow_if_market_not_enabled() {
     );
 }
 
+#[test]
+fn cannot_borrow_zero_priced_asset() {
+    let mut deps = th_setup(&[coin(1_000_000, "uzero")]);
+
+    th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            max_loan_to_value: Decimal::percent(50),
+            ..Default::default()
+        },
+    );
+    th_init_market(deps.as_mut(), "uzero", &Default::default());
+    deps.querier.set_oracle_price("uosmo", Decimal::one());
+    deps.querier.set_oracle_price("uzero", Decimal::zero());
+
+    let borrower_addr = Addr::unchecked("borrower");
+    set_collateral(
+        deps.as_mut(),
+        &borrower_addr,
+        "uosmo",
+        Uint128::new(1_000_000) * SCALING_FACTOR,
+        true,
+    );
+
+    // the debt would be worth nothing, so any amount would pass the LTV check
+    let error_res = execute(
+        deps.as_mut(),
+        mock_env(MockEnvParams::default()),
+        mock_info(borrower_addr.as_str(), &[]),
+        ExecuteMsg::Borrow {
+            denom: "uzero".to_string(),
+            amount: Uint128::new(1_000_000),
+            recipient: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::ZeroPrice {
+            denom: "uzero".to_string()
+        }
+    );
+}
+
 #[test]
 fn borrow_and_send_funds_to_another_user() {
     let initial_liquidity = 10000000;
@@ -997,10 +1103,18 @@ fn borrow_and_send_funds_to_another_user() {
     // Check msgs and attributes (funds should be sent to 'another_user')
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: another_user_addr.to_string(),
-            amount: coins(borrow_amount.u128(), "uusd")
-        }))]
+        vec![
+            th_build_debt_balance_changed_msg(
+                &borrower_addr,
+                "uusd",
+                Uint128::zero(),
+                Uint128::zero()
+            ),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: another_user_addr.to_string(),
+                amount: coins(borrow_amount.u128(), "uusd")
+            }))
+        ]
     );
     assert_eq!(
         res.attributes,
@@ -1014,3 +1128,187 @@ fn borrow_and_send_funds_to_another_user() {
         ]
     );
 }
+
+#[test]
+fn closing_dust_debt() {
+    let mut deps = th_setup(&[]);
+
+    let borrower_addr = Addr::unchecked("borrower");
+
+    // a scaled amount slightly above 500 units, so repaying the underlying amount alone could
+    // leave a sub-unit residue behind
+    let debt_amount_scaled = Uint128::new(500) * SCALING_FACTOR + Uint128::one();
+    let mock_market = Market {
+        liquidity_index: Decimal::one(),
+        borrow_index: Decimal::one(),
+        debt_total_scaled: debt_amount_scaled,
+        dust_threshold: Uint128::new(400),
+        ..Default::default()
+    };
+    th_init_market(deps.as_mut(), "uusd", &mock_market);
+    set_debt(deps.as_mut(), &borrower_addr, "uusd", debt_amount_scaled, false);
+
+    let env = mock_env(MockEnvParams::default());
+    let msg = ExecuteMsg::CloseDustDebt {
+        denom: "uusd".to_string(),
+    };
+
+    // the debt is not below the dust threshold
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("borrower", &coins(600, "uusd")),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DebtNotDust {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(501),
+            threshold: Uint128::new(400),
+        }
+    );
+
+    let mut market = MARKETS.load(&deps.storage, "uusd").unwrap();
+    market.dust_threshold = Uint128::new(1000);
+    MARKETS.save(deps.as_mut().storage, "uusd", &market).unwrap();
+
+    // not enough funds to cover the whole debt
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("borrower", &coins(500, "uusd")),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientFundsToCloseDebt {
+            required: Uint128::new(501),
+            sent: Uint128::new(500),
+        }
+    );
+
+    let res = execute(deps.as_mut(), env, mock_info("borrower", &coins(600, "uusd")), msg).unwrap();
+
+    // the excess is refunded
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: borrower_addr.to_string(),
+                amount: coins(99, "uusd")
+            })),
+            th_build_debt_balance_changed_msg(
+                &borrower_addr,
+                "uusd",
+                debt_amount_scaled,
+                debt_amount_scaled
+            )
+        ]
+    );
+
+    // the debt entry is removed entirely
+    assert!(!has_debt_position(deps.as_ref(), &borrower_addr, "uusd"));
+    let market_after = MARKETS.load(&deps.storage, "uusd").unwrap();
+    assert_eq!(market_after.debt_total_scaled, Uint128::zero());
+}
+
+#[test]
+fn uncollateralized_debt_without_interest() {
+    let mut deps = th_setup(&[coin(1_000_000_000, "uusd")]);
+
+    let mock_market = Market {
+        max_loan_to_value: Decimal::percent(50),
+        interest_rate_model: InterestRateModel {
+            optimal_utilization_rate: Decimal::percent(80),
+            base: Decimal::percent(10),
+            slope_1: Decimal::zero(),
+            slope_2: Decimal::zero(),
+        },
+        borrow_rate: Decimal::percent(10),
+        indexes_last_updated: 0,
+        uncollateralized_debt_accrues_interest: false,
+        ..Default::default()
+    };
+    th_init_market(deps.as_mut(), "uusd", &mock_market);
+
+    // one user borrows against collateral, the other on an uncollateralized loan limit
+    let collateralized_addr = Addr::unchecked("collateralized_borrower");
+    let uncollateralized_addr = Addr::unchecked("uncollateralized_borrower");
+    set_collateral(
+        deps.as_mut(),
+        &collateralized_addr,
+        "uusd",
+        Uint128::new(10_000) * SCALING_FACTOR,
+        true,
+    );
+    execute(
+        deps.as_mut(),
+        mock_env_at_block_time(0),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateUncollateralizedLoanLimit {
+            user: uncollateralized_addr.to_string(),
+            denom: "uusd".to_string(),
+            new_limit: Uint128::new(5_000),
+        },
+    )
+    .unwrap();
+
+    for borrower_addr in [&collateralized_addr, &uncollateralized_addr] {
+        execute(
+            deps.as_mut(),
+            mock_env_at_block_time(0),
+            mock_info(borrower_addr.as_str(), &[]),
+            ExecuteMsg::Borrow {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(1_000),
+                recipient: None,
+            },
+        )
+        .unwrap();
+    }
+
+    // only the collateralized debt counts towards the market's interest-bearing debt
+    let market = MARKETS.load(&deps.storage, "uusd").unwrap();
+    assert_eq!(market.debt_total_scaled, Uint128::new(1_000) * SCALING_FACTOR);
+    let debt = DEBTS.load(&deps.storage, (&uncollateralized_addr, "uusd")).unwrap();
+    assert_eq!(debt.amount_scaled, Uint128::new(1_000));
+
+    // after a year at a 10% borrow rate, only the collateralized debt has accrued interest
+    let one_year_later = mock_env_at_block_time(31_536_000);
+    let query_debt = |deps: Deps, user: &Addr| -> UserDebtResponse {
+        from_binary(
+            &query(
+                deps,
+                one_year_later.clone(),
+                QueryMsg::UserDebt {
+                    user: user.to_string(),
+                    denom: "uusd".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    assert_eq!(query_debt(deps.as_ref(), &collateralized_addr).amount, Uint128::new(1_100));
+    assert_eq!(query_debt(deps.as_ref(), &uncollateralized_addr).amount, Uint128::new(1_000));
+
+    // the uncollateralized debt is closed by repaying the borrowed amount, without informing the
+    // incentives contract as the debt isn't part of the market's total
+    let res = execute(
+        deps.as_mut(),
+        one_year_later.clone(),
+        mock_info(uncollateralized_addr.as_str(), &coins(1_000, "uusd")),
+        ExecuteMsg::Repay {
+            on_behalf_of: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![]);
+    assert!(!has_debt_position(deps.as_ref(), &uncollateralized_addr, "uusd"));
+
+    let market = MARKETS.load(&deps.storage, "uusd").unwrap();
+    assert_eq!(market.debt_total_scaled, Uint128::new(1_000) * SCALING_FACTOR);
+}
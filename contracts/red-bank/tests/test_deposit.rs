@@ -17,7 +17,7 @@ use mars_red_bank::{
 };
 use mars_red_bank_types::{
     address_provider::MarsAddressType,
-    incentives,
+    incentives::{self, PositionKind},
     red_bank::{Collateral, ExecuteMsg, Market},
 };
 use mars_testing::{mock_env_at_block_time, MarsMockQuerier};
@@ -210,6 +210,55 @@ fn depositing_above_cap() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn depositing_below_min_amount() {
+    let TestSuite {
+        mut deps,
+        denom,
+        depositor_addr,
+        ..
+    } = setup_test();
+
+    // set a minimum deposit amount
+    MARKETS
+        .update(deps.as_mut().storage, denom, |opt| -> StdResult<_> {
+            let mut market = opt.unwrap();
+            market.min_deposit_amount = Uint128::new(1_000);
+            Ok(market)
+        })
+        .unwrap();
+
+    // opening a new position with a small amount should fail
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_block_time(10000100),
+        mock_info(depositor_addr.as_str(), &coins(999, denom)),
+        ExecuteMsg::Deposit {
+            on_behalf_of: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DepositTooSmall {
+            denom: denom.to_string(),
+            min_amount: Uint128::new(1_000)
+        }
+    );
+
+    // topping up an existing position with a small amount should work
+    set_collateral(deps.as_mut(), &depositor_addr, denom, Uint128::new(123456), true);
+    let result = execute(
+        deps.as_mut(),
+        mock_env_at_block_time(10000100),
+        mock_info(depositor_addr.as_str(), &coins(999, denom)),
+        ExecuteMsg::Deposit {
+            on_behalf_of: None,
+        },
+    );
+    assert!(result.is_ok());
+}
+
 #[test]
 fn depositing_without_existing_position() {
     let TestSuite {
@@ -255,6 +304,7 @@ fn depositing_without_existing_position() {
                 user_amount_scaled_before: Uint128::zero(),
                 // NOTE: Protocol rewards accrued is zero, so here it's initial total supply
                 total_amount_scaled_before: initial_market.collateral_total_scaled,
+                kind: Some(PositionKind::Collateral),
             })
             .unwrap(),
             funds: vec![]
@@ -345,6 +395,7 @@ fn depositing_with_existing_position() {
                 user_amount_scaled_before: collateral_amount_scaled,
                 // NOTE: Protocol rewards accrued is zero, so here it's initial total supply
                 total_amount_scaled_before: initial_market.collateral_total_scaled,
+                kind: Some(PositionKind::Collateral),
             })
             .unwrap(),
             funds: vec![]
@@ -415,6 +466,7 @@ fn depositing_on_behalf_of() {
                     denom: initial_market.denom.clone(),
                     user_amount_scaled_before: Uint128::zero(),
                     total_amount_scaled_before: initial_market.collateral_total_scaled,
+                    kind: Some(PositionKind::Collateral),
                 })
                 .unwrap(),
                 funds: vec![]
@@ -429,6 +481,7 @@ fn depositing_on_behalf_of() {
                     // for the depositor this should be initial total supply + rewards shares minted
                     total_amount_scaled_before: initial_market.collateral_total_scaled
                         + expected_reward_amount_scaled,
+                    kind: Some(PositionKind::Collateral),
                 })
                 .unwrap(),
                 funds: vec![]
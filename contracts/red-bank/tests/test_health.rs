@@ -1,9 +1,21 @@
 use std::collections::HashMap;
 
-use cosmwasm_std::{CheckedMultiplyRatioError, Decimal, Uint128};
+use cosmwasm_std::{Addr, CheckedMultiplyRatioError, Decimal, StdResult, Timestamp, Uint128};
+use helpers::{set_collateral, set_debt, th_init_market, th_setup};
 use mars_health::error::HealthError;
-use mars_red_bank::{error::ContractError, health::compute_position_health};
-use mars_red_bank_types::red_bank::Position;
+use mars_red_bank::{
+    error::ContractError,
+    health::{
+        assert_liquidatable, compute_position_health, get_liquidation_candidates,
+        LiquidationCandidates,
+    },
+    interest_rates::SCALING_FACTOR,
+    state::CONFIG,
+};
+use mars_red_bank_types::red_bank::{Market, Position};
+use mars_testing::{mock_env, MockEnvParams};
+
+mod helpers;
 
 #[test]
 fn health_position() {
@@ -70,6 +82,38 @@ fn health_position() {
     assert!(health.is_above_max_ltv());
 }
 
+#[test]
+fn health_position_with_zero_price() {
+    // a zero priced collateral doesn't count towards the user's health
+    let mut osmo_position = default_osmo_position();
+    osmo_position.collateral_amount = Uint128::from(1_000_000u128);
+    osmo_position.asset_price = Decimal::zero();
+
+    let mut atom_position = default_atom_position();
+    atom_position.debt_amount = Uint128::from(10u128);
+
+    let positions =
+        HashMap::from([("osmo".to_string(), osmo_position), ("atom".to_string(), atom_position)]);
+    let health = compute_position_health(&positions).unwrap();
+
+    assert_eq!(health.total_collateral_value, Uint128::zero());
+    assert_eq!(health.total_debt_value, Uint128::from(102u128));
+    assert_eq!(health.liquidation_health_factor, Some(Decimal::zero()));
+    assert!(health.is_liquidatable());
+    assert!(health.is_above_max_ltv());
+
+    // neither does a zero priced debt
+    let mut osmo_position = default_osmo_position();
+    osmo_position.debt_amount = Uint128::from(1_000_000u128);
+    osmo_position.asset_price = Decimal::zero();
+
+    let positions = HashMap::from([("osmo".to_string(), osmo_position)]);
+    let health = compute_position_health(&positions).unwrap();
+
+    assert_eq!(health.total_debt_value, Uint128::zero());
+    assert_eq!(health.liquidation_health_factor, None);
+}
+
 #[test]
 fn health_error_if_overflow() {
     let mut osmo_position = default_osmo_position();
@@ -85,6 +129,125 @@ fn health_error_if_overflow() {
     );
 }
 
+#[test]
+fn liquidation_candidates() {
+    let mut deps = th_setup(&[]);
+    let user_addr = Addr::unchecked("user");
+
+    let osmo_market = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            max_loan_to_value: Decimal::percent(60),
+            liquidation_threshold: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    th_init_market(
+        deps.as_mut(),
+        "uatom",
+        &Market {
+            max_loan_to_value: Decimal::percent(50),
+            liquidation_threshold: Decimal::percent(70),
+            ..Default::default()
+        },
+    );
+    let usd_market = th_init_market(deps.as_mut(), "uusd", &Default::default());
+
+    deps.querier.set_oracle_price("uosmo", Decimal::one());
+    deps.querier.set_oracle_price("uatom", Decimal::from_ratio(10u128, 1u128));
+    deps.querier.set_oracle_price("uusd", Decimal::one());
+
+    // health factor: 100 * 0.8 / 100 = 0.8. Disabled collateral can't be seized and
+    // uncollateralized debt can't be repaid by liquidators
+    set_collateral(deps.as_mut(), &user_addr, "uosmo", Uint128::new(100) * SCALING_FACTOR, true);
+    set_collateral(deps.as_mut(), &user_addr, "uatom", Uint128::new(50) * SCALING_FACTOR, false);
+    set_debt(deps.as_mut(), &user_addr, "uusd", Uint128::new(100) * SCALING_FACTOR, false);
+    set_debt(deps.as_mut(), &user_addr, "uatom", Uint128::new(10) * SCALING_FACTOR, true);
+
+    let candidates = get_liquidation_candidates(
+        &deps.as_ref(),
+        &mock_env(MockEnvParams::default()),
+        &user_addr,
+        &Addr::unchecked("oracle"),
+    )
+    .unwrap();
+    assert_eq!(
+        candidates,
+        LiquidationCandidates {
+            liquidatable: true,
+            collaterals: vec![Position {
+                denom: "uosmo".to_string(),
+                collateral_amount: Uint128::new(100),
+                max_ltv: osmo_market.max_loan_to_value,
+                liquidation_threshold: osmo_market.liquidation_threshold,
+                asset_price: Decimal::one(),
+                ..Default::default()
+            }],
+            debts: vec![Position {
+                denom: "uusd".to_string(),
+                debt_amount: Uint128::new(100),
+                max_ltv: usd_market.max_loan_to_value,
+                liquidation_threshold: usd_market.liquidation_threshold,
+                asset_price: Decimal::one(),
+                ..Default::default()
+            }],
+        }
+    );
+}
+
+#[test]
+fn liquidation_check_fails_on_stale_price() {
+    let mut deps = th_setup(&[]);
+    let user_addr = Addr::unchecked("user");
+    let oracle_addr = Addr::unchecked("oracle");
+
+    CONFIG
+        .update(deps.as_mut().storage, |mut config| -> StdResult<_> {
+            config.max_price_age = 60;
+            Ok(config)
+        })
+        .unwrap();
+
+    let osmo_market = Market {
+        liquidation_threshold: Decimal::percent(80),
+        ..Default::default()
+    };
+    th_init_market(deps.as_mut(), "uosmo", &osmo_market);
+    th_init_market(deps.as_mut(), "uusd", &Default::default());
+
+    set_collateral(deps.as_mut(), &user_addr, "uosmo", Uint128::new(100) * SCALING_FACTOR, true);
+    set_debt(deps.as_mut(), &user_addr, "uusd", Uint128::new(100) * SCALING_FACTOR, false);
+
+    // health factor: 100 * 1 * 0.8 / 100 = 0.8, but the debt's price is based on stale data
+    deps.querier.set_oracle_price("uosmo", Decimal::one());
+    deps.querier.set_oracle_price("uusd", Decimal::one());
+    deps.querier.set_oracle_price_timestamp("uosmo", 990);
+    deps.querier.set_oracle_price_timestamp("uusd", 900);
+
+    let env = mock_env(MockEnvParams {
+        block_time: Timestamp::from_seconds(1000),
+        ..Default::default()
+    });
+
+    // rather than a possibly false liquidatable verdict, the check fails
+    let err = assert_liquidatable(&deps.as_ref(), &env, &user_addr, &oracle_addr).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::StalePrice {
+            denom: "uusd".to_string(),
+            timestamp: 900,
+            max_age: 60,
+        }
+    );
+
+    // once the price is fresh again, the verdict is returned
+    deps.querier.set_oracle_price_timestamp("uusd", 940);
+    let (liquidatable, _) =
+        assert_liquidatable(&deps.as_ref(), &env, &user_addr, &oracle_addr).unwrap();
+    assert!(liquidatable);
+}
+
 fn default_osmo_position() -> Position {
     Position {
         denom: "osmo".to_string(),
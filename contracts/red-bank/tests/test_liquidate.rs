@@ -15,22 +15,23 @@ use helpers::{
 use mars_red_bank::{
     contract::execute,
     error::ContractError,
-    execute::liquidation_compute_amounts,
+    execute::{liquidation_close_factor, liquidation_compute_amounts},
     interest_rates::{
         compute_scaled_amount, compute_underlying_amount, get_scaled_liquidity_amount,
         ScalingOperation, SCALING_FACTOR,
     },
+    query::query_user_liquidation_history,
     state::{COLLATERALS, CONFIG, DEBTS, MARKETS},
 };
 use mars_red_bank_types::{
     address_provider::MarsAddressType,
-    incentives,
-    red_bank::{Collateral, Debt, ExecuteMsg, InterestRateModel, Market},
+    incentives::{self, PositionKind},
+    red_bank::{Collateral, Debt, ExecuteMsg, InterestRateModel, LiquidationRecord, Market},
 };
 use mars_testing::{mock_env, mock_env_at_block_time, MarsMockQuerier, MockEnvParams};
 use mars_utils::math;
 
-use crate::helpers::{set_debt, TestInterestResults};
+use crate::helpers::{set_debt, th_build_debt_balance_changed_msg, TestInterestResults};
 
 mod helpers;
 
@@ -228,13 +229,15 @@ fn expected_messages(
     recipient_addr: &Addr,
     user_collateral_scaled: Uint128,
     recipient_collateral_scaled: Uint128,
+    user_debt_scaled: Uint128,
     collateral_market: &Market,
     debt_market: &Market,
 ) -> Vec<SubMsg> {
-    // there should be up to three messages updating indices at the incentives contract, in the
+    // there should be up to four messages updating indices at the incentives contract, in the
     // order:
     // - collateral denom, user
     // - collateral denom, liquidator
+    // - debt denom, user's debt
     // - debt denom, rewards collector (if rewards accrued > 0)
     //
     // NOTE that we don't expect a message to update rewards collector's index of the
@@ -248,6 +251,7 @@ fn expected_messages(
                 denom: collateral_market.denom.clone(),
                 user_amount_scaled_before: user_collateral_scaled,
                 total_amount_scaled_before: collateral_market.collateral_total_scaled,
+                kind: Some(PositionKind::Collateral),
             })
             .unwrap(),
             funds: vec![],
@@ -259,10 +263,17 @@ fn expected_messages(
                 denom: collateral_market.denom.clone(),
                 user_amount_scaled_before: recipient_collateral_scaled,
                 total_amount_scaled_before: collateral_market.collateral_total_scaled,
+                kind: Some(PositionKind::Collateral),
             })
             .unwrap(),
             funds: vec![],
         }),
+        th_build_debt_balance_changed_msg(
+            user_addr,
+            &debt_market.denom,
+            user_debt_scaled,
+            debt_market.debt_total_scaled,
+        ),
         SubMsg::new(WasmMsg::Execute {
             contract_addr: MarsAddressType::Incentives.to_string(),
             msg: to_binary(&incentives::ExecuteMsg::BalanceChange {
@@ -270,6 +281,7 @@ fn expected_messages(
                 denom: debt_market.denom.clone(),
                 user_amount_scaled_before: Uint128::zero(),
                 total_amount_scaled_before: debt_market.collateral_total_scaled,
+                kind: Some(PositionKind::Collateral),
             })
             .unwrap(),
             funds: vec![],
@@ -430,6 +442,7 @@ fn liquidate_partially() {
         &liquidator_addr,
         user_collateral_scaled_before,
         Uint128::zero(),
+        user_debt_scaled_before,
         &ts.collateral_market,
         &ts.debt_market,
     );
@@ -548,6 +561,7 @@ fn liquidate_up_to_close_factor_with_refund() {
         &liquidator_addr,
         user_collateral_scaled_before,
         Uint128::zero(),
+        user_debt_scaled_before,
         &ts.collateral_market,
         &ts.debt_market,
     );
@@ -697,6 +711,7 @@ fn liquidate_fully() {
         &liquidator_addr,
         user_collateral_scaled_before,
         Uint128::zero(),
+        user_debt_scaled_before,
         &collateral_market,
         &debt_market,
     );
@@ -833,6 +848,7 @@ fn liquidate_partially_if_same_asset_for_debt_and_collateral() {
         &liquidator_addr,
         user_collateral_scaled_before,
         Uint128::zero(),
+        user_debt_scaled_before,
         &collateral_market,
         &debt_market,
     );
@@ -1002,6 +1018,7 @@ fn liquidate_with_refund_if_same_asset_for_debt_and_collateral() {
         &liquidator_addr,
         user_collateral_scaled_before,
         Uint128::zero(),
+        user_debt_scaled_before,
         &collateral_market,
         &debt_market,
     );
@@ -1066,6 +1083,299 @@ fn liquidate_with_refund_if_same_asset_for_debt_and_collateral() {
     );
 }
 
+#[test]
+fn liquidate_same_asset_reconciles_balances() {
+    let TestSuite {
+        mut deps,
+        collateral_market,
+        ..
+    } = setup_test();
+    let denom = collateral_market.denom.clone();
+
+    let user_addr = Addr::unchecked("user");
+    let liquidator_addr = Addr::unchecked("liquidator");
+
+    let user_collateral_scaled_before = Uint128::from(2_000_000u64) * SCALING_FACTOR;
+    let user_debt_scaled_before = compute_scaled_amount(
+        Uint128::from(3_000_000u64),
+        collateral_market.borrow_index,
+        ScalingOperation::Ceil,
+    )
+    .unwrap();
+
+    set_collateral(deps.as_mut(), &user_addr, &denom, user_collateral_scaled_before, true);
+    set_debt(deps.as_mut(), &user_addr, &denom, user_debt_scaled_before, false);
+
+    let market_before = MARKETS.load(&deps.storage, &denom).unwrap();
+
+    let env = mock_env_at_block_time(15_000_000);
+    let info = mock_info(liquidator_addr.as_str(), &coins(1_000_000, denom.clone()));
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info,
+        ExecuteMsg::Liquidate {
+            user: user_addr.to_string(),
+            collateral_denom: denom.clone(),
+            recipient: None,
+        },
+    )
+    .unwrap();
+
+    // the repaid amount is below the close factor, so nothing is refunded and no coins are sent out
+    assert!(res
+        .messages
+        .iter()
+        .all(|msg| !matches!(msg.msg, CosmosMsg::Bank(BankMsg::Send { .. }))));
+
+    // the collateral shares seized from the user are exactly the ones received by the liquidator
+    let user_collateral = COLLATERALS.load(&deps.storage, (&user_addr, &denom)).unwrap();
+    let liquidator_collateral =
+        COLLATERALS.load(&deps.storage, (&liquidator_addr, &denom)).unwrap();
+    assert!(!liquidator_collateral.amount_scaled.is_zero());
+    assert_eq!(
+        user_collateral.amount_scaled + liquidator_collateral.amount_scaled,
+        user_collateral_scaled_before
+    );
+
+    // the market's debt total is reduced by exactly the user's debt reduction, and its collateral
+    // total only changes by the rewards minted to the rewards collector
+    let user_debt = DEBTS.load(&deps.storage, (&user_addr, &denom)).unwrap();
+    let rewards_collateral = rewards_collector_collateral(deps.as_ref(), &denom);
+    let market_after = MARKETS.load(&deps.storage, &denom).unwrap();
+    assert_eq!(
+        market_before.debt_total_scaled - market_after.debt_total_scaled,
+        user_debt_scaled_before - user_debt.amount_scaled
+    );
+    assert_eq!(
+        market_after.collateral_total_scaled,
+        market_before.collateral_total_scaled + rewards_collateral.amount_scaled
+    );
+}
+
+#[test]
+fn liquidate_partially_below_min_liquidation_value() {
+    let TestSuite {
+        mut deps,
+        collateral_market,
+        debt_market,
+        ..
+    } = setup_test();
+
+    CONFIG
+        .update(deps.as_mut().storage, |mut config| -> StdResult<_> {
+            config.min_liquidation_value = Uint128::new(1_000_000);
+            Ok(config)
+        })
+        .unwrap();
+
+    let user_addr = Addr::unchecked("user");
+    let liquidator_addr = Addr::unchecked("liquidator");
+
+    let user_collateral_scaled_before = Uint128::from(2_000_000u64) * SCALING_FACTOR;
+    let user_debt_scaled_before = compute_scaled_amount(
+        Uint128::from(3_000_000u64),
+        debt_market.borrow_index,
+        ScalingOperation::Ceil,
+    )
+    .unwrap();
+
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &collateral_market.denom,
+        user_collateral_scaled_before,
+        true,
+    );
+    set_debt(deps.as_mut(), &user_addr, &debt_market.denom, user_debt_scaled_before, false);
+
+    // repaying 1,000 debt at a price of 1.1 is worth 1,100, well below the minimum
+    let info = mock_info(liquidator_addr.as_str(), &coins(1_000, debt_market.denom.clone()));
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_block_time(16_000_000),
+        info,
+        ExecuteMsg::Liquidate {
+            user: user_addr.to_string(),
+            collateral_denom: collateral_market.denom.clone(),
+            recipient: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::LiquidationTooSmall {
+            min_value: Uint128::new(1_000_000),
+            value: Uint128::new(1_100),
+        }
+    );
+}
+
+#[test]
+fn recording_liquidation_history() {
+    let TestSuite {
+        mut deps,
+        collateral_market,
+        debt_market,
+        ..
+    } = setup_test();
+
+    let user_addr = Addr::unchecked("user");
+
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &collateral_market.denom,
+        Uint128::from(2_000_000u64) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(
+        deps.as_mut(),
+        &user_addr,
+        &debt_market.denom,
+        Uint128::from(3_000_000u64) * SCALING_FACTOR,
+        false,
+    );
+
+    assert!(query_user_liquidation_history(deps.as_ref(), user_addr.clone()).unwrap().is_empty());
+
+    let liquidations =
+        [("liquidator_1", 1_000u128, 16_000_000u64), ("liquidator_2", 2_000, 16_000_100)];
+    let mut expected_history = vec![];
+    for (liquidator, debt_amount, block_time) in liquidations {
+        let res = execute(
+            deps.as_mut(),
+            mock_env_at_block_time(block_time),
+            mock_info(liquidator, &coins(debt_amount, debt_market.denom.clone())),
+            ExecuteMsg::Liquidate {
+                user: user_addr.to_string(),
+                collateral_denom: collateral_market.denom.clone(),
+                recipient: None,
+            },
+        )
+        .unwrap();
+
+        let collateral_amount = res
+            .attributes
+            .iter()
+            .find(|attr| attr.key == "collateral_amount")
+            .map(|attr| attr.value.parse::<Uint128>().unwrap())
+            .unwrap();
+        expected_history.push(LiquidationRecord {
+            liquidator: liquidator.to_string(),
+            debt_denom: debt_market.denom.clone(),
+            debt_amount: Uint128::new(debt_amount),
+            collateral_denom: collateral_market.denom.clone(),
+            collateral_amount,
+            timestamp: block_time,
+        });
+    }
+
+    let history = query_user_liquidation_history(deps.as_ref(), user_addr).unwrap();
+    assert_eq!(history, expected_history);
+}
+
+#[test]
+fn liquidate_zero_priced_collateral() {
+    let TestSuite {
+        mut deps,
+        collateral_market,
+        debt_market,
+        ..
+    } = setup_test();
+
+    deps.querier.set_oracle_price(&collateral_market.denom, Decimal::zero());
+
+    let user_addr = Addr::unchecked("user");
+    let liquidator_addr = Addr::unchecked("liquidator");
+
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &collateral_market.denom,
+        Uint128::from(2_000_000u64) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(
+        deps.as_mut(),
+        &user_addr,
+        &debt_market.denom,
+        Uint128::from(1_000_000u64) * SCALING_FACTOR,
+        false,
+    );
+
+    // the collateral is worthless so the user is liquidatable, but there's nothing to seize
+    let info = mock_info(liquidator_addr.as_str(), &coins(1_000, debt_market.denom.clone()));
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_block_time(16_000_000),
+        info,
+        ExecuteMsg::Liquidate {
+            user: user_addr.to_string(),
+            collateral_denom: collateral_market.denom.clone(),
+            recipient: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ZeroPrice {
+            denom: collateral_market.denom
+        }
+    );
+}
+
+#[test]
+fn liquidate_fully_below_min_liquidation_value() {
+    let TestSuite {
+        mut deps,
+        collateral_market,
+        debt_market,
+        ..
+    } = setup_test();
+
+    CONFIG
+        .update(deps.as_mut().storage, |mut config| -> StdResult<_> {
+            config.min_liquidation_value = Uint128::new(1_000_000);
+            Ok(config)
+        })
+        .unwrap();
+
+    let user_addr = Addr::unchecked("user");
+    let liquidator_addr = Addr::unchecked("liquidator");
+
+    let user_collateral_scaled_before = Uint128::new(100) * SCALING_FACTOR;
+    let user_debt_scaled_before = Uint128::new(400) * SCALING_FACTOR;
+
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &collateral_market.denom,
+        user_collateral_scaled_before,
+        true,
+    );
+    set_debt(deps.as_mut(), &user_addr, &debt_market.denom, user_debt_scaled_before, false);
+
+    // the repaid value is below the minimum, but the user's whole collateral is liquidated
+    let info = mock_info(liquidator_addr.as_str(), &coins(300, debt_market.denom.clone()));
+    execute(
+        deps.as_mut(),
+        mock_env_at_block_time(16_000_000),
+        info,
+        ExecuteMsg::Liquidate {
+            user: user_addr.to_string(),
+            collateral_denom: collateral_market.denom.clone(),
+            recipient: None,
+        },
+    )
+    .unwrap();
+
+    assert!(!has_collateral_position(deps.as_ref(), &user_addr, &collateral_market.denom));
+    let liquidator_collateral =
+        COLLATERALS.load(&deps.storage, (&liquidator_addr, &collateral_market.denom)).unwrap();
+    assert_eq!(liquidator_collateral.amount_scaled, user_collateral_scaled_before);
+}
+
 #[test]
 fn liquidate_with_recipient_for_underlying_collateral() {
     let mut ts = setup_test();
@@ -1122,6 +1432,7 @@ fn liquidate_with_recipient_for_underlying_collateral() {
         &recipient_addr,
         user_collateral_scaled_before,
         Uint128::zero(),
+        user_debt_scaled_before,
         &ts.collateral_market,
         &ts.debt_market,
     );
@@ -1374,3 +1685,79 @@ fn cannot_liquidate_without_receiving_collaterals() {
     .unwrap_err();
     assert_eq!(res_err, StdError::generic_err("Can't process liquidation. Invalid collateral_amount_to_liquidate (0) and debt_amount_to_repay (10)"))
 }
+
+#[test]
+fn liquidate_with_collateral_close_factor() {
+    let mut ts = setup_test();
+
+    // the debt market's close factor overrides the global one, but the seized collateral's close
+    // factor takes precedence over both
+    ts.debt_market.close_factor = Some(Decimal::percent(30));
+    ts.collateral_market.collateral_close_factor = Some(Decimal::percent(80));
+    for market in [&ts.debt_market, &ts.collateral_market] {
+        MARKETS.save(ts.deps.as_mut().storage, &market.denom, market).unwrap();
+    }
+    assert_eq!(
+        liquidation_close_factor(ts.close_factor, &ts.collateral_market, &ts.debt_market),
+        Decimal::percent(80)
+    );
+    assert_eq!(
+        liquidation_close_factor(ts.close_factor, &Market::default(), &ts.debt_market),
+        Decimal::percent(30)
+    );
+    assert_eq!(
+        liquidation_close_factor(ts.close_factor, &Market::default(), &Market::default()),
+        ts.close_factor
+    );
+
+    let user_addr = Addr::unchecked("user");
+    let liquidator_addr = Addr::unchecked("liquidator");
+
+    let user_collateral_scaled_before = Uint128::from(2_000_000u64) * SCALING_FACTOR;
+    let user_debt_scaled_before = compute_scaled_amount(
+        Uint128::from(3_000_000u64),
+        ts.debt_market.borrow_index,
+        ScalingOperation::Ceil,
+    )
+    .unwrap();
+
+    set_collateral(
+        ts.deps.as_mut(),
+        &user_addr,
+        &ts.collateral_market.denom,
+        user_collateral_scaled_before,
+        true,
+    );
+    set_debt(ts.deps.as_mut(), &user_addr, &ts.debt_market.denom, user_debt_scaled_before, false);
+
+    let debt_to_repay = Uint128::from(10_000_000_u64);
+    let block_time = 16_000_000;
+    let res = execute(
+        ts.deps.as_mut(),
+        mock_env_at_block_time(block_time),
+        mock_info(liquidator_addr.as_str(), &coins(debt_to_repay.u128(), &ts.debt_market.denom)),
+        ExecuteMsg::Liquidate {
+            user: user_addr.to_string(),
+            collateral_denom: ts.collateral_market.denom.clone(),
+            recipient: None,
+        },
+    )
+    .unwrap();
+
+    // up to 80% of the debt is repaid, the rest of the sent coins being refunded
+    ts.close_factor = Decimal::percent(80);
+    let TestExpectedAmountResults {
+        user_debt_repayed,
+        expected_refund_amount,
+        ..
+    } = expected_amounts(block_time, user_debt_scaled_before, debt_to_repay, &ts);
+
+    assert!(res.attributes.contains(&attr("debt_amount", user_debt_repayed)));
+    assert_eq!(
+        res.messages.last().unwrap(),
+        &SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: liquidator_addr.to_string(),
+            amount: coins(expected_refund_amount.u128(), &ts.debt_market.denom),
+        }))
+    );
+}
@@ -3,8 +3,8 @@ use cosmwasm_std::{
 };
 use helpers::{
     has_collateral_enabled, has_collateral_position, has_debt_position, set_collateral, set_debt,
-    th_build_interests_updated_event, th_get_expected_indices_and_rates, th_init_market, th_setup,
-    TestUtilizationDeltaInfo,
+    th_build_debt_balance_changed_msg, th_build_interests_updated_event,
+    th_get_expected_indices_and_rates, th_init_market, th_setup, TestUtilizationDeltaInfo,
 };
 use mars_owner::OwnerError::NotOwner;
 use mars_red_bank::{
@@ -116,10 +116,18 @@ fn uncollateralized_loan_limits() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: borrower_addr.to_string(),
-            amount: coins(initial_borrow_amount.u128(), "somecoin")
-        }))]
+        vec![
+            th_build_debt_balance_changed_msg(
+                &borrower_addr,
+                "somecoin",
+                Uint128::zero(),
+                market_initial.debt_total_scaled
+            ),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: borrower_addr.to_string(),
+                amount: coins(initial_borrow_amount.u128(), "somecoin")
+            }))
+        ]
     );
 
     assert_eq!(
@@ -196,6 +204,65 @@ fn uncollateralized_loan_limits() {
     assert!(debt.uncollateralized);
 }
 
+#[test]
+fn updating_uncollateralized_loan_limit_with_outstanding_debt() {
+    let mut deps = th_setup(&[]);
+
+    // the uncollateralized debt is stored unscaled, i.e. as its underlying amount
+    th_init_market(
+        deps.as_mut(),
+        "somecoin",
+        &Market {
+            uncollateralized_debt_accrues_interest: false,
+            ..Default::default()
+        },
+    );
+
+    let borrower_addr = Addr::unchecked("borrower");
+    let update_limit = |new_limit: u128| ExecuteMsg::UpdateUncollateralizedLoanLimit {
+        user: borrower_addr.to_string(),
+        denom: "somecoin".to_string(),
+        new_limit: Uint128::new(new_limit),
+    };
+    let env = mock_env(MockEnvParams::default());
+    let info = mock_info("owner", &[]);
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), update_limit(2000)).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_uncollateralized_loan_limit"),
+            attr("user", "borrower"),
+            attr("denom", "somecoin"),
+            attr("old_allowance", "0"),
+            attr("new_allowance", "2000"),
+        ]
+    );
+
+    set_debt(deps.as_mut(), &borrower_addr, "somecoin", 1000u128, true);
+
+    // raising the limit
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), update_limit(3000)).unwrap();
+    assert_eq!(res.attributes[3], attr("old_allowance", "2000"));
+    assert_eq!(res.attributes[4], attr("new_allowance", "3000"));
+
+    // lowering the limit down to the outstanding debt
+    execute(deps.as_mut(), env.clone(), info.clone(), update_limit(1000)).unwrap();
+
+    // lowering the limit below the outstanding debt
+    let err = execute(deps.as_mut(), env, info, update_limit(999)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UncollateralizedLoanLimitBelowDebt {
+            new_limit: Uint128::new(999),
+            debt: Uint128::new(1000),
+        }
+    );
+    let limit =
+        UNCOLLATERALIZED_LOAN_LIMITS.load(&deps.storage, (&borrower_addr, "somecoin")).unwrap();
+    assert_eq!(limit, Uint128::new(1000));
+}
+
 #[test]
 fn update_asset_collateral() {
     let mut deps = th_setup(&[]);
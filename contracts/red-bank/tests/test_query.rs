@@ -1,11 +1,32 @@
-use cosmwasm_std::{testing::mock_env, Addr, Decimal, Uint128};
-use helpers::{set_collateral, th_init_market, th_setup};
+use cosmwasm_std::{
+    coin, coins,
+    testing::{mock_env, mock_info},
+    Addr, Decimal, OwnedDeps, StdResult, Uint128,
+};
+use helpers::{set_collateral, set_debt, th_init_market, th_setup};
 use mars_red_bank::{
+    contract::execute,
+    error::ContractError,
     interest_rates::{get_scaled_debt_amount, get_underlying_debt_amount, SCALING_FACTOR},
-    query::{query_user_collaterals, query_user_debt, query_user_debts},
-    state::DEBTS,
+    query::{
+        query_aggregate_health, query_bad_debt, query_current_ltv,
+        query_estimate_liquidation_profit, query_haircut_collateral_value,
+        query_health_decomposition, query_health_sensitivity, query_market_scaled_totals,
+        query_max_withdrawable, query_projected_interest, query_rescue_deposit,
+        query_seizable_collaterals, query_user_collaterals, query_user_counts, query_user_debt,
+        query_user_debts, query_user_health_breakdown, query_user_markets, query_user_position,
+        query_validate_new_market,
+    },
+    state::{CONFIG, DEBTS, UNCOLLATERALIZED_LOAN_LIMITS},
+};
+use mars_red_bank_types::red_bank::{
+    AggregateHealthResponse, BadDebtResponse, CollateralHealthContribution, CurrentLtvResponse,
+    Debt, EstimateLiquidationProfitResponse, ExecuteMsg, HaircutCollateralValueResponse,
+    HealthDecompositionResponse, HealthSensitivityResponse, Market, MarketScaledTotalsResponse,
+    ProjectedInterestResponse, RescueDepositResponse, SeizableCollateral, UserBadDebt,
+    UserCollateralResponse, UserCountsResponse, UserDebtResponse, UserHealthBreakdownResponse,
+    UserHealthStatus, ValidateNewMarketResponse,
 };
-use mars_red_bank_types::red_bank::{Debt, Market, UserCollateralResponse, UserDebtResponse};
 
 mod helpers;
 
@@ -233,3 +254,1356 @@ fn query_user_asset_debt() {
         );
     }
 }
+
+#[test]
+fn test_query_haircut_collateral_value() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    let market_1 = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            value_haircut: Decimal::percent(20),
+            ..Default::default()
+        },
+    );
+    let market_2 = th_init_market(deps.as_mut(), "uatom", &Default::default());
+
+    deps.querier.set_oracle_price(&market_1.denom, Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price(&market_2.denom, Decimal::from_ratio(10u128, 1u128));
+
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &market_1.denom,
+        Uint128::new(1000) * SCALING_FACTOR,
+        true,
+    );
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &market_2.denom,
+        Uint128::new(500) * SCALING_FACTOR,
+        true,
+    );
+
+    // osmo: 1000 * 2 = 2000, after a 20% haircut: 1600
+    // atom: 500 * 10 = 5000, no haircut
+    let res = query_haircut_collateral_value(deps.as_ref(), mock_env(), user_addr.clone()).unwrap();
+    assert_eq!(
+        res,
+        HaircutCollateralValueResponse {
+            total_enabled_collateral: Uint128::new(7000),
+            haircut_collateral_value: Uint128::new(6600),
+        }
+    );
+
+    // by default the haircut is not applied to the user's health
+    let position = query_user_position(deps.as_ref(), mock_env(), user_addr.clone()).unwrap();
+    assert_eq!(position.total_enabled_collateral, Uint128::new(7000));
+
+    // once enabled, the haircut collateral value is used for health
+    CONFIG
+        .update(deps.as_mut().storage, |mut config| -> StdResult<_> {
+            config.apply_value_haircut = true;
+            Ok(config)
+        })
+        .unwrap();
+    let position = query_user_position(deps.as_ref(), mock_env(), user_addr).unwrap();
+    assert_eq!(position.total_enabled_collateral, Uint128::new(6600));
+}
+
+#[test]
+fn test_query_user_position_with_paused_market() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    let osmo_market = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            liquidation_threshold: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    let atom_market = th_init_market(
+        deps.as_mut(),
+        "uatom",
+        &Market {
+            liquidation_threshold: Decimal::percent(70),
+            // paused
+            deposit_enabled: false,
+            borrow_enabled: false,
+            ..Default::default()
+        },
+    );
+    let usd_market = th_init_market(deps.as_mut(), "uusd", &Default::default());
+
+    deps.querier.set_oracle_price(&osmo_market.denom, Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price(&atom_market.denom, Decimal::from_ratio(10u128, 1u128));
+    deps.querier.set_oracle_price(&usd_market.denom, Decimal::one());
+
+    // collateral: 1000 osmo * 2 * 0.8 = 1600 + 100 atom * 10 * 0.7 = 700, debt: 1000 usd
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &osmo_market.denom,
+        Uint128::new(1000) * SCALING_FACTOR,
+        true,
+    );
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &atom_market.denom,
+        Uint128::new(100) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(
+        deps.as_mut(),
+        &user_addr,
+        &usd_market.denom,
+        Uint128::new(1000) * SCALING_FACTOR,
+        false,
+    );
+
+    let liq_threshold_hf = |deps: &OwnedDeps<_, _, _>| {
+        let position = query_user_position(deps.as_ref(), mock_env(), user_addr.clone()).unwrap();
+        match position.health_status {
+            UserHealthStatus::Borrowing {
+                liq_threshold_hf,
+                ..
+            } => liq_threshold_hf,
+            UserHealthStatus::NotBorrowing => panic!("user should be borrowing"),
+        }
+    };
+
+    // by default the paused collateral still counts towards the user's health
+    assert_eq!(liq_threshold_hf(&deps), Decimal::from_ratio(23u128, 10u128));
+
+    // once enabled, it is excluded
+    CONFIG
+        .update(deps.as_mut().storage, |mut config| -> StdResult<_> {
+            config.exclude_paused_collateral = true;
+            Ok(config)
+        })
+        .unwrap();
+    assert_eq!(liq_threshold_hf(&deps), Decimal::from_ratio(16u128, 10u128));
+}
+
+#[test]
+fn test_query_user_position_with_only_uncollateralized_debt() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    let osmo_market = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            liquidation_threshold: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    let usd_market = th_init_market(deps.as_mut(), "uusd", &Default::default());
+
+    deps.querier.set_oracle_price(&osmo_market.denom, Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price(&usd_market.denom, Decimal::one());
+
+    // the debt would make the user liquidatable if it counted: 100 osmo * 2 * 0.8 = 160 < 1000
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &osmo_market.denom,
+        Uint128::new(100) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(
+        deps.as_mut(),
+        &user_addr,
+        &usd_market.denom,
+        Uint128::new(1000) * SCALING_FACTOR,
+        true,
+    );
+
+    let position = query_user_position(deps.as_ref(), mock_env(), user_addr).unwrap();
+    assert_eq!(position.total_enabled_collateral, Uint128::new(200));
+    assert_eq!(position.total_collateralized_debt, Uint128::zero());
+    assert_eq!(position.health_status, UserHealthStatus::NotBorrowing);
+}
+
+#[test]
+fn test_query_projected_interest() {
+    let mut deps = th_setup(&[]);
+    let env = mock_env();
+
+    let user_addr = Addr::unchecked("user");
+
+    th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            borrow_index: Decimal::one(),
+            borrow_rate: Decimal::percent(10),
+            indexes_last_updated: env.block.time.seconds(),
+            ..Default::default()
+        },
+    );
+
+    let debt = Debt {
+        amount_scaled: Uint128::new(1_000_000) * SCALING_FACTOR,
+        uncollateralized: false,
+    };
+    DEBTS.save(deps.as_mut().storage, (&user_addr, "uosmo"), &debt).unwrap();
+
+    // half a year at 10% APR on a debt of 1,000,000
+    let res = query_projected_interest(
+        deps.as_ref(),
+        &env.block,
+        user_addr,
+        "uosmo".to_string(),
+        15_768_000,
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        ProjectedInterestResponse {
+            denom: "uosmo".to_string(),
+            debt: Uint128::new(1_000_000),
+            borrow_rate: Decimal::percent(10),
+            projected_interest: Uint128::new(50_000),
+        }
+    );
+}
+
+#[test]
+fn test_query_rescue_deposit() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    let osmo_market = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            liquidation_threshold: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    let atom_market = th_init_market(deps.as_mut(), "uatom", &Default::default());
+    let usd_market = th_init_market(
+        deps.as_mut(),
+        "uusd",
+        &Market {
+            liquidation_threshold: Decimal::percent(50),
+            ..Default::default()
+        },
+    );
+    let juno_market = th_init_market(
+        deps.as_mut(),
+        "ujuno",
+        &Market {
+            liquidation_threshold: Decimal::zero(),
+            ..Default::default()
+        },
+    );
+
+    deps.querier.set_oracle_price(&osmo_market.denom, Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price(&atom_market.denom, Decimal::from_ratio(10u128, 1u128));
+    deps.querier.set_oracle_price(&juno_market.denom, Decimal::one());
+
+    // collateral: 1000 osmo * 2 * 0.8 = 1600, debt: 170 atom * 10 = 1700
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &osmo_market.denom,
+        Uint128::new(1000) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(
+        deps.as_mut(),
+        &user_addr,
+        &atom_market.denom,
+        Uint128::new(170) * SCALING_FACTOR,
+        false,
+    );
+
+    let query = |denom: &str| {
+        query_rescue_deposit(deps.as_ref(), mock_env(), user_addr.clone(), denom.to_string())
+            .unwrap()
+    };
+
+    // osmo has to cover the whole debt: ceil(1700 / (2 * 0.8)) - 1000 = 63
+    assert_eq!(
+        query(&osmo_market.denom),
+        RescueDepositResponse {
+            denom: osmo_market.denom.clone(),
+            amount: Some(Uint128::new(63)),
+        }
+    );
+
+    // usd only has to cover the shortfall: ceil(100 / (1 * 0.5)) = 200
+    assert_eq!(query(&usd_market.denom).amount, Some(Uint128::new(200)));
+
+    // juno doesn't count towards the liquidation health factor at all
+    assert_eq!(query(&juno_market.denom).amount, None);
+}
+
+#[test]
+fn test_query_max_withdrawable() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+    let lender_addr = Addr::unchecked("lender");
+
+    let osmo_market = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            liquidation_threshold: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    let atom_market = th_init_market(deps.as_mut(), "uatom", &Default::default());
+    let usd_market = th_init_market(
+        deps.as_mut(),
+        "uusd",
+        &Market {
+            liquidation_threshold: Decimal::percent(50),
+            ..Default::default()
+        },
+    );
+
+    deps.querier.set_oracle_price(&osmo_market.denom, Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price(&atom_market.denom, Decimal::from_ratio(10u128, 1u128));
+    deps.querier.set_oracle_price(&usd_market.denom, Decimal::one());
+
+    // collateral: 1000 osmo * 2 * 0.8 + 400 usd * 1 * 0.5 = 1800, debt: 150 atom * 10 = 1500
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &osmo_market.denom,
+        Uint128::new(1000) * SCALING_FACTOR,
+        true,
+    );
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &usd_market.denom,
+        Uint128::new(400) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(
+        deps.as_mut(),
+        &user_addr,
+        &atom_market.denom,
+        Uint128::new(150) * SCALING_FACTOR,
+        false,
+    );
+    set_collateral(
+        deps.as_mut(),
+        &lender_addr,
+        &osmo_market.denom,
+        Uint128::new(500) * SCALING_FACTOR,
+        true,
+    );
+
+    let query = |user_addr: &Addr, denom: &str| {
+        query_max_withdrawable(deps.as_ref(), mock_env(), user_addr.clone(), denom.to_string())
+            .unwrap()
+    };
+
+    // osmo has to cover what usd doesn't: ceil((1500 - 200) / 0.8 / 2) = 813 must remain
+    assert_eq!(query(&user_addr, &osmo_market.denom), Uint128::new(187));
+
+    // osmo alone covers the debt, so all usd can be withdrawn
+    assert_eq!(query(&user_addr, &usd_market.denom), Uint128::new(400));
+
+    // no collateral to withdraw
+    assert_eq!(query(&user_addr, &atom_market.denom), Uint128::zero());
+
+    // not borrowing
+    assert_eq!(query(&lender_addr, &osmo_market.denom), Uint128::new(500));
+}
+
+#[test]
+fn test_query_max_borrowable() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+    let maxed_addr = Addr::unchecked("maxed");
+    let credit_addr = Addr::unchecked("credit");
+
+    let osmo_market = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            max_loan_to_value: Decimal::percent(50),
+            liquidation_threshold: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    let atom_market = th_init_market(deps.as_mut(), "uatom", &Default::default());
+    let usd_market = th_init_market(deps.as_mut(), "uusd", &Default::default());
+
+    deps.querier.set_oracle_price(&osmo_market.denom, Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price(&atom_market.denom, Decimal::from_ratio(10u128, 1u128));
+    deps.querier.set_oracle_price(&usd_market.denom, Decimal::percent(30));
+
+    // user: collateral 1000 osmo * 2 * 0.5 = 1000, debt: 50 atom * 10 = 500
+    // maxed: collateral 1000 osmo * 2 * 0.5 = 1000, debt: 100 atom * 10 = 1000
+    let users = [(&user_addr, 50), (&maxed_addr, 100)];
+    for (user_addr, debt) in users {
+        set_collateral(
+            deps.as_mut(),
+            user_addr,
+            &osmo_market.denom,
+            Uint128::new(1000) * SCALING_FACTOR,
+            true,
+        );
+        set_debt(
+            deps.as_mut(),
+            user_addr,
+            &atom_market.denom,
+            Uint128::new(debt) * SCALING_FACTOR,
+            false,
+        );
+    }
+
+    // credit: 300 of an uncollateralized loan limit of 1000 usd used
+    UNCOLLATERALIZED_LOAN_LIMITS
+        .save(deps.as_mut().storage, (&credit_addr, &usd_market.denom), &Uint128::new(1000))
+        .unwrap();
+    set_debt(
+        deps.as_mut(),
+        &credit_addr,
+        &usd_market.denom,
+        Uint128::new(300) * SCALING_FACTOR,
+        true,
+    );
+
+    let query = |user_addr: &Addr, denom: &str| {
+        query_max_borrowable(deps.as_ref(), mock_env(), user_addr.clone(), denom.to_string())
+            .unwrap()
+    };
+
+    // existing atom debt counts towards the max: 1000 / 10 - 50 = 50
+    assert_eq!(query(&user_addr, &atom_market.denom), Uint128::new(50));
+
+    // not borrowing usd yet: largest amount whose value rounds down to 1000 - 500 is 1669
+    assert_eq!(query(&user_addr, &usd_market.denom), Uint128::new(1669));
+
+    // already at max LTV
+    assert_eq!(query(&maxed_addr, &usd_market.denom), Uint128::zero());
+
+    // bounded by the uncollateralized loan limit instead
+    assert_eq!(query(&credit_addr, &usd_market.denom), Uint128::new(700));
+}
+
+#[test]
+fn test_query_aggregate_health() {
+    let mut deps = th_setup(&[]);
+
+    let osmo_market = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            liquidation_threshold: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    let atom_market = th_init_market(deps.as_mut(), "uatom", &Default::default());
+
+    deps.querier.set_oracle_price(&osmo_market.denom, Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price(&atom_market.denom, Decimal::from_ratio(10u128, 1u128));
+
+    // healthy: collateral 1000 osmo * 2 * 0.8 = 1600, debt: 100 atom * 10 = 1000
+    // liquidatable: collateral 1000 osmo * 2 * 0.8 = 1600, debt: 170 atom * 10 = 1700
+    // not borrowing: collateral 500 osmo * 2 * 0.8 = 800
+    let users = [("healthy", 1000, 100), ("liquidatable", 1000, 170), ("lender", 500, 0)];
+    for (user, collateral, debt) in users {
+        let user_addr = Addr::unchecked(user);
+        set_collateral(
+            deps.as_mut(),
+            &user_addr,
+            &osmo_market.denom,
+            Uint128::new(collateral) * SCALING_FACTOR,
+            true,
+        );
+        if debt > 0 {
+            set_debt(
+                deps.as_mut(),
+                &user_addr,
+                &atom_market.denom,
+                Uint128::new(debt) * SCALING_FACTOR,
+                false,
+            );
+        }
+    }
+
+    let user_addrs = users.iter().map(|(user, ..)| Addr::unchecked(*user)).collect();
+    let res = query_aggregate_health(deps.as_ref(), mock_env(), user_addrs).unwrap();
+    assert_eq!(
+        res,
+        AggregateHealthResponse {
+            total_collateral_value: Uint128::new(5000),
+            total_weighted_liquidation_threshold_collateral: Uint128::new(4000),
+            total_debt_value: Uint128::new(2700),
+            liquidation_health_factor: Some(Decimal::from_ratio(4000u128, 2700u128)),
+            liquidatable_users: 1,
+        }
+    );
+
+    // the number of users per query is bounded
+    let user_addrs = (0..31).map(|i| Addr::unchecked(format!("user_{i}"))).collect();
+    let err = query_aggregate_health(deps.as_ref(), mock_env(), user_addrs).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TooManyUsers {
+            max: 30
+        }
+    );
+}
+
+#[test]
+fn test_query_bad_debt() {
+    let mut deps = th_setup(&[]);
+
+    let osmo_market = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            liquidation_threshold: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    let atom_market = th_init_market(deps.as_mut(), "uatom", &Default::default());
+
+    deps.querier.set_oracle_price(&osmo_market.denom, Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price(&atom_market.denom, Decimal::from_ratio(10u128, 1u128));
+
+    // liquidatable but covered: collateral 1000 osmo * 2 = 2000, debt: 170 atom * 10 = 1700
+    // underwater: collateral 500 osmo * 2 = 1000, debt: 250 atom * 10 = 2500
+    // no collateral at all: debt: 30 atom * 10 = 300
+    let users = [("liquidatable", 1000, 170), ("underwater", 500, 250), ("wiped", 0, 30)];
+    for (user, collateral, debt) in users {
+        let user_addr = Addr::unchecked(user);
+        if collateral > 0 {
+            set_collateral(
+                deps.as_mut(),
+                &user_addr,
+                &osmo_market.denom,
+                Uint128::new(collateral) * SCALING_FACTOR,
+                true,
+            );
+        }
+        set_debt(
+            deps.as_mut(),
+            &user_addr,
+            &atom_market.denom,
+            Uint128::new(debt) * SCALING_FACTOR,
+            false,
+        );
+    }
+
+    let user_addrs = users.iter().map(|(user, ..)| Addr::unchecked(*user)).collect();
+    let res = query_bad_debt(deps.as_ref(), mock_env(), user_addrs).unwrap();
+    assert_eq!(
+        res,
+        BadDebtResponse {
+            users: vec![
+                UserBadDebt {
+                    user: "underwater".to_string(),
+                    shortfall: Uint128::new(1500),
+                },
+                UserBadDebt {
+                    user: "wiped".to_string(),
+                    shortfall: Uint128::new(300),
+                },
+            ],
+            total_shortfall: Uint128::new(1800),
+        }
+    );
+
+    // the number of users per query is bounded
+    let user_addrs = (0..31).map(|i| Addr::unchecked(format!("user_{i}"))).collect();
+    let err = query_bad_debt(deps.as_ref(), mock_env(), user_addrs).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TooManyUsers {
+            max: 30
+        }
+    );
+}
+
+#[test]
+fn test_query_current_ltv() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    let osmo_market = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            max_loan_to_value: Decimal::percent(60),
+            liquidation_threshold: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    let atom_market = th_init_market(
+        deps.as_mut(),
+        "uatom",
+        &Market {
+            max_loan_to_value: Decimal::percent(50),
+            liquidation_threshold: Decimal::percent(70),
+            ..Default::default()
+        },
+    );
+    let usd_market = th_init_market(deps.as_mut(), "uusd", &Default::default());
+
+    deps.querier.set_oracle_price(&osmo_market.denom, Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price(&atom_market.denom, Decimal::from_ratio(10u128, 1u128));
+    deps.querier.set_oracle_price(&usd_market.denom, Decimal::one());
+
+    // a user without any position isn't borrowing
+    let res = query_current_ltv(deps.as_ref(), mock_env(), user_addr.clone()).unwrap();
+    assert_eq!(
+        res,
+        CurrentLtvResponse {
+            ltv: Some(Decimal::zero()),
+            max_ltv: Decimal::zero(),
+            liquidation_threshold: Decimal::zero(),
+        }
+    );
+
+    // a debt without any collateral has no finite LTV
+    set_debt(
+        deps.as_mut(),
+        &user_addr,
+        &usd_market.denom,
+        Uint128::new(500) * SCALING_FACTOR,
+        false,
+    );
+    let res = query_current_ltv(deps.as_ref(), mock_env(), user_addr.clone()).unwrap();
+    assert_eq!(res.ltv, None);
+
+    // collateral: 1000 osmo * 2 = 2000 + 100 atom * 10 = 1000, debt: 500 usd
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &osmo_market.denom,
+        Uint128::new(1000) * SCALING_FACTOR,
+        true,
+    );
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &atom_market.denom,
+        Uint128::new(100) * SCALING_FACTOR,
+        true,
+    );
+
+    let res = query_current_ltv(deps.as_ref(), mock_env(), user_addr).unwrap();
+    assert_eq!(
+        res,
+        CurrentLtvResponse {
+            // 500 / 3000
+            ltv: Some(Decimal::from_ratio(1u128, 6u128)),
+            // (2000 * 0.6 + 1000 * 0.5) / 3000
+            max_ltv: Decimal::from_ratio(17u128, 30u128),
+            // (2000 * 0.8 + 1000 * 0.7) / 3000
+            liquidation_threshold: Decimal::from_ratio(23u128, 30u128),
+        }
+    );
+}
+
+#[test]
+fn test_query_health_decomposition() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            liquidation_threshold: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    th_init_market(
+        deps.as_mut(),
+        "uatom",
+        &Market {
+            liquidation_threshold: Decimal::percent(70),
+            ..Default::default()
+        },
+    );
+    th_init_market(deps.as_mut(), "uusd", &Default::default());
+
+    deps.querier.set_oracle_price("uosmo", Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price("uatom", Decimal::from_ratio(10u128, 1u128));
+
+    // collateral: 1000 osmo * 2 = 2000 + 100 atom * 10 = 1000, debt: 500 usd
+    set_collateral(deps.as_mut(), &user_addr, "uosmo", Uint128::new(1000) * SCALING_FACTOR, true);
+    set_collateral(deps.as_mut(), &user_addr, "uatom", Uint128::new(100) * SCALING_FACTOR, true);
+    set_debt(deps.as_mut(), &user_addr, "uusd", Uint128::new(500) * SCALING_FACTOR, false);
+
+    let res = query_health_decomposition(deps.as_ref(), mock_env(), user_addr).unwrap();
+    assert_eq!(
+        res,
+        HealthDecompositionResponse {
+            collaterals: vec![
+                CollateralHealthContribution {
+                    denom: "uatom".to_string(),
+                    value: Uint128::new(1000),
+                    weighted_value: Uint128::new(700),
+                },
+                CollateralHealthContribution {
+                    denom: "uosmo".to_string(),
+                    value: Uint128::new(2000),
+                    weighted_value: Uint128::new(1600),
+                },
+            ],
+            total_weighted_collateral: Uint128::new(2300),
+            total_debt_value: Uint128::new(500),
+            liquidation_health_factor: Some(Decimal::from_ratio(23u128, 5u128)),
+        }
+    );
+
+    let weighted_sum: Uint128 = res.collaterals.iter().map(|c| c.weighted_value).sum();
+    assert_eq!(weighted_sum, res.total_weighted_collateral);
+}
+
+#[test]
+fn test_query_user_health_breakdown() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            max_loan_to_value: Decimal::percent(60),
+            liquidation_threshold: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    th_init_market(
+        deps.as_mut(),
+        "uatom",
+        &Market {
+            max_loan_to_value: Decimal::percent(50),
+            liquidation_threshold: Decimal::percent(70),
+            ..Default::default()
+        },
+    );
+    th_init_market(deps.as_mut(), "uusd", &Default::default());
+
+    deps.querier.set_oracle_price("uosmo", Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price("uatom", Decimal::from_ratio(10u128, 1u128));
+    deps.querier.set_oracle_price("uusd", Decimal::one());
+
+    // collateral: 1000 osmo * 2 = 2000 + 100 atom * 10 = 1000, debt: 10 atom * 10 = 100 + 500 usd
+    set_collateral(deps.as_mut(), &user_addr, "uosmo", Uint128::new(1000) * SCALING_FACTOR, true);
+    set_collateral(deps.as_mut(), &user_addr, "uatom", Uint128::new(100) * SCALING_FACTOR, true);
+    set_debt(deps.as_mut(), &user_addr, "uatom", Uint128::new(10) * SCALING_FACTOR, false);
+    set_debt(deps.as_mut(), &user_addr, "uusd", Uint128::new(500) * SCALING_FACTOR, false);
+
+    let res = query_user_health_breakdown(deps.as_ref(), mock_env(), user_addr.clone()).unwrap();
+    assert_eq!(
+        res,
+        vec![
+            UserHealthBreakdownResponse {
+                denom: "uatom".to_string(),
+                collateral_value: Uint128::new(1000),
+                debt_value: Uint128::new(100),
+                weighted_max_ltv_collateral: Uint128::new(500),
+                weighted_liquidation_threshold_collateral: Uint128::new(700),
+            },
+            UserHealthBreakdownResponse {
+                denom: "uosmo".to_string(),
+                collateral_value: Uint128::new(2000),
+                debt_value: Uint128::zero(),
+                weighted_max_ltv_collateral: Uint128::new(1200),
+                weighted_liquidation_threshold_collateral: Uint128::new(1600),
+            },
+            UserHealthBreakdownResponse {
+                denom: "uusd".to_string(),
+                collateral_value: Uint128::zero(),
+                debt_value: Uint128::new(500),
+                weighted_max_ltv_collateral: Uint128::zero(),
+                weighted_liquidation_threshold_collateral: Uint128::zero(),
+            },
+        ]
+    );
+
+    // the contributions add up to the user's position
+    let position = query_user_position(deps.as_ref(), mock_env(), user_addr).unwrap();
+    let sum = |f: fn(&UserHealthBreakdownResponse) -> Uint128| res.iter().map(f).sum::<Uint128>();
+    assert_eq!(sum(|b| b.collateral_value), position.total_enabled_collateral);
+    assert_eq!(sum(|b| b.debt_value), position.total_collateralized_debt);
+    assert_eq!(sum(|b| b.weighted_max_ltv_collateral), position.weighted_max_ltv_collateral);
+    assert_eq!(
+        sum(|b| b.weighted_liquidation_threshold_collateral),
+        position.weighted_liquidation_threshold_collateral
+    );
+}
+
+#[test]
+fn test_query_health_sensitivity() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    let osmo_market = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            liquidation_threshold: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    let atom_market = th_init_market(
+        deps.as_mut(),
+        "uatom",
+        &Market {
+            liquidation_threshold: Decimal::percent(70),
+            ..Default::default()
+        },
+    );
+    let usd_market = th_init_market(deps.as_mut(), "uusd", &Default::default());
+
+    deps.querier.set_oracle_price(&osmo_market.denom, Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price(&atom_market.denom, Decimal::from_ratio(10u128, 1u128));
+    deps.querier.set_oracle_price(&usd_market.denom, Decimal::one());
+
+    // collateral: 1000 osmo * 2 * 0.8 = 1600 + 10 atom * 10 * 0.7 = 70
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &osmo_market.denom,
+        Uint128::new(1000) * SCALING_FACTOR,
+        true,
+    );
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &atom_market.denom,
+        Uint128::new(10) * SCALING_FACTOR,
+        true,
+    );
+
+    // not borrowing, so there is no health factor to move
+    let res = query_health_sensitivity(deps.as_ref(), mock_env(), user_addr.clone()).unwrap();
+    assert!(res.is_empty());
+
+    // debt: 1000 usd, health factor: 1670 / 1000 = 1.67
+    set_debt(
+        deps.as_mut(),
+        &user_addr,
+        &usd_market.denom,
+        Uint128::new(1000) * SCALING_FACTOR,
+        false,
+    );
+
+    let res = query_health_sensitivity(deps.as_ref(), mock_env(), user_addr).unwrap();
+    assert_eq!(
+        res,
+        vec![
+            // (99 * 0.7 + 1600) / 1000 = 1.669
+            HealthSensitivityResponse {
+                denom: atom_market.denom,
+                liquidation_health_factor_change: Decimal::permille(1),
+            },
+            // (1980 * 0.8 + 70) / 1000 = 1.654
+            HealthSensitivityResponse {
+                denom: osmo_market.denom,
+                liquidation_health_factor_change: Decimal::permille(16),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_query_validate_new_market() {
+    let mut deps = th_setup(&[]);
+
+    deps.querier.set_oracle_price("uatom", Decimal::from_ratio(10u128, 1u128));
+
+    // consistent params with a working price source
+    let res = query_validate_new_market(
+        deps.as_ref(),
+        "uatom".to_string(),
+        Decimal::percent(60),
+        Decimal::percent(70),
+        Decimal::percent(10),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        ValidateNewMarketResponse {
+            denom: "uatom".to_string(),
+            errors: vec![],
+            warnings: vec![],
+        }
+    );
+
+    // the oracle has no price source for the denom
+    let res = query_validate_new_market(
+        deps.as_ref(),
+        "ujuno".to_string(),
+        Decimal::percent(60),
+        Decimal::percent(70),
+        Decimal::percent(10),
+    )
+    .unwrap();
+    assert_eq!(res.errors.len(), 1);
+    assert!(res.errors[0].starts_with("oracle has no working price source for ujuno"));
+    assert!(res.warnings.is_empty());
+
+    // inconsistent params for an existing market
+    th_init_market(deps.as_mut(), "uatom", &Default::default());
+    let res = query_validate_new_market(
+        deps.as_ref(),
+        "uatom".to_string(),
+        Decimal::percent(80),
+        Decimal::percent(80),
+        Decimal::percent(150),
+    )
+    .unwrap();
+    assert_eq!(
+        res.errors,
+        vec![
+            "max_ltv (0.8) must be less than liquidation_threshold (0.8)".to_string(),
+            "liquidation_bonus (1.5) must be <= 1".to_string(),
+        ]
+    );
+    assert_eq!(
+        res.warnings,
+        vec![
+            "a market for uatom already exists".to_string(),
+            "liquidation_threshold (0.8) with liquidation_bonus (1.5) can leave bad debt when \
+             liquidating"
+                .to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_query_estimate_liquidation_profit() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    let osmo_market = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            liquidity_index: Decimal::one(),
+            borrow_index: Decimal::one(),
+            liquidation_threshold: Decimal::percent(50),
+            liquidation_bonus: Decimal::percent(10),
+            ..Default::default()
+        },
+    );
+    let usd_market = th_init_market(
+        deps.as_mut(),
+        "uusd",
+        &Market {
+            liquidity_index: Decimal::one(),
+            borrow_index: Decimal::one(),
+            ..Default::default()
+        },
+    );
+
+    deps.querier.set_oracle_price(&osmo_market.denom, Decimal::one());
+
+    // collateral: 1000 osmo * 1 * 0.5 = 500, debt: 600 usd * 1 = 600
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &osmo_market.denom,
+        Uint128::new(1000) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(
+        deps.as_mut(),
+        &user_addr,
+        &usd_market.denom,
+        Uint128::new(600) * SCALING_FACTOR,
+        false,
+    );
+
+    let query = |gas_cost: u128| {
+        query_estimate_liquidation_profit(
+            deps.as_ref(),
+            mock_env(),
+            user_addr.clone(),
+            usd_market.denom.clone(),
+            osmo_market.denom.clone(),
+            Uint128::new(100),
+            Uint128::new(gas_cost),
+        )
+        .unwrap()
+    };
+
+    // repaying 100 usd seizes 100 * 1.1 = 110 osmo, so the liquidation is profitable if gas costs
+    // less than 10
+    assert_eq!(
+        query(4),
+        EstimateLiquidationProfitResponse {
+            debt_amount: Uint128::new(100),
+            collateral_amount: Uint128::new(110),
+            debt_value: Uint128::new(100),
+            collateral_value: Uint128::new(110),
+            net_profit: Uint128::new(6),
+            net_loss: Uint128::zero(),
+        }
+    );
+
+    let res = query(15);
+    assert_eq!(res.net_profit, Uint128::zero());
+    assert_eq!(res.net_loss, Uint128::new(5));
+}
+
+#[test]
+fn test_query_estimate_liquidation_profit_of_uncollateralized_debt() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    let market = Market {
+        liquidity_index: Decimal::one(),
+        borrow_index: Decimal::one(),
+        liquidation_threshold: Decimal::percent(50),
+        liquidation_bonus: Decimal::percent(10),
+        ..Default::default()
+    };
+    th_init_market(deps.as_mut(), "uosmo", &market);
+    th_init_market(deps.as_mut(), "uatom", &market);
+    th_init_market(
+        deps.as_mut(),
+        "uusd",
+        &Market {
+            liquidity_index: Decimal::one(),
+            borrow_index: Decimal::from_ratio(2u128, 1u128),
+            uncollateralized_debt_accrues_interest: false,
+            ..Default::default()
+        },
+    );
+
+    deps.querier.set_oracle_price("uosmo", Decimal::one());
+    deps.querier.set_oracle_price("uatom", Decimal::one());
+    deps.querier.set_oracle_price("uusd", Decimal::one());
+
+    // collateral: 1000 osmo * 1 * 0.5 = 500, collateralized debt: 600 atom * 1 = 600
+    set_collateral(deps.as_mut(), &user_addr, "uosmo", Uint128::new(1000) * SCALING_FACTOR, true);
+    set_debt(deps.as_mut(), &user_addr, "uatom", Uint128::new(600) * SCALING_FACTOR, false);
+    // the uncollateralized debt doesn't accrue interest, so it's stored unscaled
+    set_debt(deps.as_mut(), &user_addr, "uusd", 700u128, true);
+
+    let res = query_estimate_liquidation_profit(
+        deps.as_ref(),
+        mock_env(),
+        user_addr,
+        "uusd".to_string(),
+        "uosmo".to_string(),
+        Uint128::new(500),
+        Uint128::new(5),
+    )
+    .unwrap();
+
+    // the repaid amount is capped by the close factor to 700 * 0.5 = 350 usd, which seizes
+    // 350 * 1.1 = 385 osmo
+    assert_eq!(
+        res,
+        EstimateLiquidationProfitResponse {
+            debt_amount: Uint128::new(350),
+            collateral_amount: Uint128::new(385),
+            debt_value: Uint128::new(350),
+            collateral_value: Uint128::new(385),
+            net_profit: Uint128::new(30),
+            net_loss: Uint128::zero(),
+        }
+    );
+}
+
+#[test]
+fn test_query_seizable_collaterals() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    let collateral_market = Market {
+        liquidity_index: Decimal::one(),
+        borrow_index: Decimal::one(),
+        liquidation_threshold: Decimal::percent(50),
+        liquidation_bonus: Decimal::percent(10),
+        ..Default::default()
+    };
+    th_init_market(deps.as_mut(), "uosmo", &collateral_market);
+    th_init_market(deps.as_mut(), "uatom", &collateral_market);
+    th_init_market(
+        deps.as_mut(),
+        "uusd",
+        &Market {
+            liquidity_index: Decimal::one(),
+            borrow_index: Decimal::one(),
+            ..Default::default()
+        },
+    );
+
+    deps.querier.set_oracle_price("uosmo", Decimal::one());
+    deps.querier.set_oracle_price("uatom", Decimal::from_ratio(2u128, 1u128));
+    deps.querier.set_oracle_price("uusd", Decimal::one());
+
+    // collateral: (1000 osmo * 1 + 50 atom * 2) * 0.5 = 550, debt: 700 usd * 1 = 700
+    set_collateral(deps.as_mut(), &user_addr, "uosmo", Uint128::new(1000) * SCALING_FACTOR, true);
+    set_collateral(deps.as_mut(), &user_addr, "uatom", Uint128::new(50) * SCALING_FACTOR, true);
+    // disabled collateral can't be seized
+    set_collateral(deps.as_mut(), &user_addr, "uusd", Uint128::new(10) * SCALING_FACTOR, false);
+    set_debt(deps.as_mut(), &user_addr, "uusd", Uint128::new(700) * SCALING_FACTOR, false);
+
+    let res = query_seizable_collaterals(
+        deps.as_ref(),
+        mock_env(),
+        user_addr,
+        "uusd".to_string(),
+        Uint128::new(200),
+    )
+    .unwrap();
+
+    // repaying 200 usd seizes 200 * 1.1 = 220 osmo, or 200 * 1.1 / 2 = 110 atom which is more than
+    // the user has. Then all 50 atom are seized for 50 * 2 / 1.1 = 90 usd.
+    assert_eq!(
+        res,
+        vec![
+            SeizableCollateral {
+                denom: "uosmo".to_string(),
+                debt_amount: Uint128::new(200),
+                collateral_amount: Uint128::new(220),
+                collateral_value: Uint128::new(220),
+            },
+            SeizableCollateral {
+                denom: "uatom".to_string(),
+                debt_amount: Uint128::new(90),
+                collateral_amount: Uint128::new(50),
+                collateral_value: Uint128::new(100),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_query_seizable_collaterals_of_uncollateralized_debt() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    let market = Market {
+        liquidity_index: Decimal::one(),
+        borrow_index: Decimal::one(),
+        liquidation_threshold: Decimal::percent(50),
+        liquidation_bonus: Decimal::percent(10),
+        ..Default::default()
+    };
+    th_init_market(deps.as_mut(), "uosmo", &market);
+    th_init_market(deps.as_mut(), "uatom", &market);
+    th_init_market(
+        deps.as_mut(),
+        "uusd",
+        &Market {
+            liquidity_index: Decimal::one(),
+            borrow_index: Decimal::from_ratio(2u128, 1u128),
+            uncollateralized_debt_accrues_interest: false,
+            ..Default::default()
+        },
+    );
+
+    deps.querier.set_oracle_price("uosmo", Decimal::one());
+    deps.querier.set_oracle_price("uatom", Decimal::one());
+    deps.querier.set_oracle_price("uusd", Decimal::one());
+
+    // collateral: 1000 osmo * 1 * 0.5 = 500, collateralized debt: 600 atom * 1 = 600
+    set_collateral(deps.as_mut(), &user_addr, "uosmo", Uint128::new(1000) * SCALING_FACTOR, true);
+    set_debt(deps.as_mut(), &user_addr, "uatom", Uint128::new(600) * SCALING_FACTOR, false);
+    // the uncollateralized debt doesn't accrue interest, so it's stored unscaled regardless of the
+    // borrow index
+    set_debt(deps.as_mut(), &user_addr, "uusd", 700u128, true);
+
+    let res = query_seizable_collaterals(
+        deps.as_ref(),
+        mock_env(),
+        user_addr,
+        "uusd".to_string(),
+        Uint128::new(500),
+    )
+    .unwrap();
+
+    // the repaid amount is capped by the close factor to 700 * 0.5 = 350 usd, which seizes
+    // 350 * 1.1 = 385 osmo
+    assert_eq!(
+        res,
+        vec![SeizableCollateral {
+            denom: "uosmo".to_string(),
+            debt_amount: Uint128::new(350),
+            collateral_amount: Uint128::new(385),
+            collateral_value: Uint128::new(385),
+        }]
+    );
+}
+
+#[test]
+fn test_query_market_scaled_totals() {
+    let mut deps = th_setup(&[]);
+    let env = mock_env();
+
+    th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            liquidity_index: Decimal::percent(110),
+            borrow_index: Decimal::percent(120),
+            indexes_last_updated: env.block.time.seconds(),
+            collateral_total_scaled: Uint128::new(1000) * SCALING_FACTOR,
+            debt_total_scaled: Uint128::new(500) * SCALING_FACTOR,
+            ..Default::default()
+        },
+    );
+
+    let res = query_market_scaled_totals(deps.as_ref(), env, "uosmo".to_string()).unwrap();
+    assert_eq!(
+        res,
+        MarketScaledTotalsResponse {
+            denom: "uosmo".to_string(),
+            collateral_total_scaled: Uint128::new(1000) * SCALING_FACTOR,
+            collateral_total_amount: Uint128::new(1100),
+            debt_total_scaled: Uint128::new(500) * SCALING_FACTOR,
+            debt_total_amount: Uint128::new(600),
+            liquidity_index: Decimal::percent(110),
+            borrow_index: Decimal::percent(120),
+        }
+    );
+
+    // with indices above one, the underlying amounts exceed the scaled amounts (once the scaling
+    // factor is taken out)
+    assert!(res.collateral_total_amount > res.collateral_total_scaled / SCALING_FACTOR);
+    assert!(res.debt_total_amount > res.debt_total_scaled / SCALING_FACTOR);
+}
+
+#[test]
+fn test_query_user_markets() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    // no positions
+    assert!(query_user_markets(deps.as_ref(), user_addr.clone()).unwrap().is_empty());
+
+    // osmo is both collateral and debt, atom only collateral, usd only debt
+    set_collateral(deps.as_mut(), &user_addr, "uosmo", Uint128::new(1000), true);
+    set_collateral(deps.as_mut(), &user_addr, "uatom", Uint128::new(1000), false);
+    set_debt(deps.as_mut(), &user_addr, "uosmo", Uint128::new(500), false);
+    set_debt(deps.as_mut(), &user_addr, "uusd", Uint128::new(500), false);
+
+    // another user's positions are not included
+    set_debt(deps.as_mut(), &Addr::unchecked("another_user"), "ujuno", Uint128::new(500), false);
+
+    let markets = query_user_markets(deps.as_ref(), user_addr).unwrap();
+    assert_eq!(markets, vec!["uatom".to_string(), "uosmo".to_string(), "uusd".to_string()]);
+}
+
+#[test]
+fn test_query_user_counts() {
+    let mut deps = th_setup(&[coin(1_000_000, "uusd")]);
+    let env = mock_env();
+
+    deps.querier.set_oracle_price("uatom", Decimal::one());
+    deps.querier.set_oracle_price("uosmo", Decimal::one());
+
+    let market = Market {
+        liquidity_index: Decimal::one(),
+        borrow_index: Decimal::one(),
+        max_loan_to_value: Decimal::percent(50),
+        indexes_last_updated: env.block.time.seconds(),
+        ..Default::default()
+    };
+    th_init_market(deps.as_mut(), "uatom", &market);
+    th_init_market(deps.as_mut(), "uosmo", &market);
+    th_init_market(deps.as_mut(), "uusd", &market);
+
+    let assert_counts = |deps: &OwnedDeps<_, _, _>, suppliers: u64, borrowers: u64| {
+        assert_eq!(
+            query_user_counts(deps.as_ref()).unwrap(),
+            UserCountsResponse {
+                suppliers,
+                borrowers,
+            }
+        );
+    };
+    let deposit = ExecuteMsg::Deposit {
+        on_behalf_of: None,
+    };
+
+    assert_counts(&deps, 0, 0);
+
+    // the empty debt position of a user given an uncollateralized loan limit isn't counted
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateUncollateralizedLoanLimit {
+            user: "carol".to_string(),
+            denom: "uusd".to_string(),
+            new_limit: Uint128::new(1000),
+        },
+    )
+    .unwrap();
+    assert_counts(&deps, 0, 0);
+
+    // two users supply, one of them in two markets
+    execute(deps.as_mut(), env.clone(), mock_info("alice", &coins(1000, "uatom")), deposit.clone())
+        .unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("alice", &coins(1000, "uosmo")), deposit.clone())
+        .unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("bob", &coins(1000, "uatom")), deposit).unwrap();
+    assert_counts(&deps, 2, 0);
+
+    // alice borrows twice from the same market
+    let borrow = ExecuteMsg::Borrow {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(100),
+        recipient: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), borrow.clone()).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), borrow).unwrap();
+    assert_counts(&deps, 2, 1);
+
+    // a partial repayment keeps alice a borrower, a full one doesn't
+    let repay = ExecuteMsg::Repay {
+        on_behalf_of: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("alice", &coins(150, "uusd")), repay.clone())
+        .unwrap();
+    assert_counts(&deps, 2, 1);
+    execute(deps.as_mut(), env.clone(), mock_info("alice", &coins(50, "uusd")), repay).unwrap();
+    assert_counts(&deps, 2, 0);
+
+    // closing one of two collateral positions keeps alice a supplier
+    let withdraw = |denom: &str| ExecuteMsg::Withdraw {
+        denom: denom.to_string(),
+        amount: None,
+        recipient: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), withdraw("uosmo")).unwrap();
+    assert_counts(&deps, 2, 0);
+    execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), withdraw("uatom")).unwrap();
+    assert_counts(&deps, 1, 0);
+    execute(deps.as_mut(), env, mock_info("bob", &[]), withdraw("uatom")).unwrap();
+    assert_counts(&deps, 0, 0);
+}
@@ -0,0 +1,278 @@
+use cosmwasm_std::{
+    attr, coin, coins,
+    testing::{mock_info, MockApi, MockStorage},
+    Addr, BankMsg, CosmosMsg, Decimal, OwnedDeps, SubMsg, Uint128,
+};
+use helpers::{
+    has_collateral_position, set_collateral, th_get_expected_indices_and_rates, th_setup,
+};
+use mars_red_bank::{
+    contract::execute,
+    error::ContractError,
+    interest_rates::{
+        compute_scaled_amount, compute_underlying_amount, ScalingOperation, SCALING_FACTOR,
+    },
+    state::{COLLATERALS, MARKETS},
+};
+use mars_red_bank_types::{
+    address_provider::MarsAddressType,
+    red_bank::{ExecuteMsg, InitOrUpdateAssetParams, Market},
+};
+use mars_testing::{mock_env_at_block_time, MarsMockQuerier};
+
+mod helpers;
+
+fn setup_test(
+    reserve_factor: Decimal,
+) -> (OwnedDeps<MockStorage, MockApi, MarsMockQuerier>, Market) {
+    let denom = "uosmo";
+
+    let mut deps = th_setup(&[coin(12_000_000, denom)]);
+
+    let market = Market {
+        denom: denom.to_string(),
+        reserve_factor,
+        borrow_index: Decimal::from_ratio(2u128, 1u128),
+        liquidity_index: Decimal::from_ratio(15u128, 10u128),
+        borrow_rate: Decimal::from_ratio(20u128, 100u128),
+        liquidity_rate: Decimal::from_ratio(10u128, 100u128),
+        indexes_last_updated: 10000000,
+        collateral_total_scaled: Uint128::new(2_000_000) * SCALING_FACTOR,
+        debt_total_scaled: Uint128::new(10_000_000) * SCALING_FACTOR,
+        unswept_reserves_scaled: Uint128::new(1_000) * SCALING_FACTOR,
+        ..Default::default()
+    };
+
+    MARKETS.save(deps.as_mut().storage, denom, &market).unwrap();
+
+    (deps, market)
+}
+
+#[test]
+fn sweeping_accrued_reserves() {
+    let (mut deps, market) = setup_test(Decimal::from_ratio(1u128, 10u128));
+
+    // besides the reserves, the rewards collector deposited some coins itself
+    let rewards_collector_addr = Addr::unchecked(MarsAddressType::RewardsCollector.to_string());
+    let reserves_scaled_before = market.unswept_reserves_scaled;
+    let own_deposit_scaled = Uint128::new(500) * SCALING_FACTOR;
+    set_collateral(
+        deps.as_mut(),
+        &rewards_collector_addr,
+        "uosmo",
+        reserves_scaled_before + own_deposit_scaled,
+        false,
+    );
+
+    let block_time = market.indexes_last_updated + 86400;
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_block_time(block_time),
+        mock_info("anyone", &[]),
+        ExecuteMsg::SweepReserves {
+            denom: "uosmo".to_string(),
+        },
+    )
+    .unwrap();
+
+    // reserves held before plus the ones accrued since the last index update
+    let expected_params =
+        th_get_expected_indices_and_rates(&market, block_time, Default::default());
+    let accrued_scaled = compute_scaled_amount(
+        expected_params.protocol_rewards_to_distribute,
+        expected_params.liquidity_index,
+        ScalingOperation::Truncate,
+    )
+    .unwrap();
+    let expected_reserves_scaled = reserves_scaled_before + accrued_scaled;
+    let expected_reserves = compute_underlying_amount(
+        expected_reserves_scaled,
+        expected_params.liquidity_index,
+        ScalingOperation::Truncate,
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.messages.last().unwrap(),
+        &SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: rewards_collector_addr.to_string(),
+            amount: coins(expected_reserves.u128(), "uosmo"),
+        }))
+    );
+
+    // only the collector's own deposit is left
+    let collateral = COLLATERALS.load(&deps.storage, (&rewards_collector_addr, "uosmo")).unwrap();
+    assert_eq!(collateral.amount_scaled, own_deposit_scaled);
+
+    let market_after = MARKETS.load(&deps.storage, "uosmo").unwrap();
+    assert_eq!(
+        market_after.collateral_total_scaled,
+        market.collateral_total_scaled - reserves_scaled_before
+    );
+    assert_eq!(market_after.unswept_reserves_scaled, Uint128::zero());
+}
+
+#[test]
+fn sweeping_reserves_to_market_recipient() {
+    let (mut deps, market) = setup_test(Decimal::from_ratio(1u128, 10u128));
+
+    let insurance_fund_addr = Addr::unchecked("insurance_fund");
+    MARKETS
+        .save(
+            deps.as_mut().storage,
+            "uosmo",
+            &Market {
+                reserve_recipient: Some(insurance_fund_addr.clone()),
+                ..market.clone()
+            },
+        )
+        .unwrap();
+
+    let rewards_collector_addr = Addr::unchecked(MarsAddressType::RewardsCollector.to_string());
+    set_collateral(
+        deps.as_mut(),
+        &rewards_collector_addr,
+        "uosmo",
+        Uint128::new(1_000) * SCALING_FACTOR,
+        false,
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_block_time(market.indexes_last_updated + 86400),
+        mock_info("anyone", &[]),
+        ExecuteMsg::SweepReserves {
+            denom: "uosmo".to_string(),
+        },
+    )
+    .unwrap();
+
+    // the reserves go to the market's recipient instead of the rewards collector
+    match &res.messages.last().unwrap().msg {
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address,
+            ..
+        }) => assert_eq!(to_address, insurance_fund_addr.as_str()),
+        msg => panic!("expected a bank send message, got {msg:?}"),
+    }
+    assert!(res.attributes.contains(&attr("recipient", insurance_fund_addr)));
+    assert!(!has_collateral_position(deps.as_ref(), &rewards_collector_addr, "uosmo"));
+}
+
+#[test]
+fn sweeping_reserves_accrued_since_recipient_was_set() {
+    let (mut deps, market) = setup_test(Decimal::from_ratio(1u128, 10u128));
+
+    let rewards_collector_addr = Addr::unchecked(MarsAddressType::RewardsCollector.to_string());
+    set_collateral(
+        deps.as_mut(),
+        &rewards_collector_addr,
+        "uosmo",
+        market.unswept_reserves_scaled,
+        false,
+    );
+
+    let set_at = market.indexes_last_updated + 86400;
+    execute(
+        deps.as_mut(),
+        mock_env_at_block_time(set_at),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateAsset {
+            denom: "uosmo".to_string(),
+            params: InitOrUpdateAssetParams {
+                max_loan_to_value: None,
+                reserve_factor: None,
+                liquidation_threshold: None,
+                liquidation_bonus: None,
+                interest_rate_model: None,
+                deposit_enabled: None,
+                borrow_enabled: None,
+                deposit_cap: None,
+                value_haircut: None,
+                min_deposit_amount: None,
+                reserve_recipient: Some("insurance_fund".to_string()),
+                dust_threshold: None,
+                uncollateralized_debt_accrues_interest: None,
+                close_factor: None,
+                collateral_close_factor: None,
+            },
+        },
+    )
+    .unwrap();
+
+    // the reserves accrued before the recipient was set stay with the rewards collector
+    let market_at_set = MARKETS.load(&deps.storage, "uosmo").unwrap();
+    assert_eq!(market_at_set.unswept_reserves_scaled, Uint128::zero());
+    let collector_scaled_at_set =
+        COLLATERALS.load(&deps.storage, (&rewards_collector_addr, "uosmo")).unwrap().amount_scaled;
+    assert!(collector_scaled_at_set > market.unswept_reserves_scaled);
+
+    let sweep = ExecuteMsg::SweepReserves {
+        denom: "uosmo".to_string(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_block_time(set_at),
+        mock_info("anyone", &[]),
+        sweep.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NoReservesToSweep {
+            denom: "uosmo".to_string()
+        }
+    );
+
+    // later on, only the reserves accrued since are sent to the new recipient
+    let block_time = set_at + 86400;
+    let res =
+        execute(deps.as_mut(), mock_env_at_block_time(block_time), mock_info("anyone", &[]), sweep)
+            .unwrap();
+
+    let expected_params =
+        th_get_expected_indices_and_rates(&market_at_set, block_time, Default::default());
+    let accrued_scaled = compute_scaled_amount(
+        expected_params.protocol_rewards_to_distribute,
+        expected_params.liquidity_index,
+        ScalingOperation::Truncate,
+    )
+    .unwrap();
+    let expected_reserves = compute_underlying_amount(
+        accrued_scaled,
+        expected_params.liquidity_index,
+        ScalingOperation::Truncate,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.last().unwrap(),
+        &SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "insurance_fund".to_string(),
+            amount: coins(expected_reserves.u128(), "uosmo"),
+        }))
+    );
+
+    let collateral = COLLATERALS.load(&deps.storage, (&rewards_collector_addr, "uosmo")).unwrap();
+    assert_eq!(collateral.amount_scaled, collector_scaled_at_set);
+}
+
+#[test]
+fn sweeping_without_reserves() {
+    let (mut deps, market) = setup_test(Decimal::zero());
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_block_time(market.indexes_last_updated + 86400),
+        mock_info("anyone", &[]),
+        ExecuteMsg::SweepReserves {
+            denom: "uosmo".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NoReservesToSweep {
+            denom: "uosmo".to_string()
+        }
+    );
+}
@@ -1,15 +1,17 @@
 use cosmwasm_std::{
     attr, coin, coins,
     testing::{mock_env, mock_info, MockApi, MockStorage},
-    to_binary, Addr, BankMsg, CosmosMsg, Decimal, OwnedDeps, SubMsg, Uint128, WasmMsg,
+    to_binary, Addr, BankMsg, CosmosMsg, Decimal, Empty, OwnedDeps, Reply, SubMsg, SubMsgResponse,
+    SubMsgResult, Uint128, WasmMsg,
 };
 use helpers::{
     has_collateral_position, set_collateral, th_build_interests_updated_event,
     th_get_expected_indices_and_rates, th_setup, TestUtilizationDeltaInfo,
 };
 use mars_red_bank::{
-    contract::execute,
+    contract::{execute, reply},
     error::ContractError,
+    execute::CONSOLIDATE_REPLY_ID,
     interest_rates::{
         compute_scaled_amount, compute_underlying_amount, get_scaled_liquidity_amount,
         get_updated_borrow_index, get_updated_liquidity_index, ScalingOperation, SCALING_FACTOR,
@@ -18,8 +20,9 @@ use mars_red_bank::{
 };
 use mars_red_bank_types::{
     address_provider::MarsAddressType,
-    incentives,
+    incentives::{self, PositionKind},
     red_bank::{Collateral, Debt, ExecuteMsg, Market},
+    rewards_collector,
 };
 use mars_testing::{mock_env_at_block_time, MarsMockQuerier};
 use mars_utils::math;
@@ -181,6 +184,7 @@ fn withdrawing_partially() {
                     denom: denom.to_string(),
                     user_amount_scaled_before: Uint128::zero(),
                     total_amount_scaled_before: initial_market.collateral_total_scaled,
+                    kind: Some(PositionKind::Collateral),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -193,6 +197,7 @@ fn withdrawing_partially() {
                     user_amount_scaled_before: initial_deposit_amount_scaled,
                     total_amount_scaled_before: initial_market.collateral_total_scaled
                         + expected_rewards_amount_scaled,
+                    kind: Some(PositionKind::Collateral),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -297,6 +302,7 @@ fn withdrawing_completely() {
                     denom: denom.to_string(),
                     user_amount_scaled_before: Uint128::zero(),
                     total_amount_scaled_before: initial_market.collateral_total_scaled,
+                    kind: Some(PositionKind::Collateral),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -309,6 +315,7 @@ fn withdrawing_completely() {
                     user_amount_scaled_before: withdrawer_balance_scaled,
                     total_amount_scaled_before: initial_market.collateral_total_scaled
                         + expected_rewards_amount_scaled,
+                    kind: Some(PositionKind::Collateral),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -405,6 +412,7 @@ fn withdrawing_to_another_user() {
                     denom: denom.to_string(),
                     user_amount_scaled_before: Uint128::zero(),
                     total_amount_scaled_before: initial_market.collateral_total_scaled,
+                    kind: Some(PositionKind::Collateral),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -417,6 +425,7 @@ fn withdrawing_to_another_user() {
                     user_amount_scaled_before: withdrawer_balance_scaled,
                     total_amount_scaled_before: initial_market.collateral_total_scaled
                         + expected_rewards_amount_scaled,
+                    kind: Some(PositionKind::Collateral),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -692,6 +701,7 @@ fn withdrawing_if_health_factor_met() {
                     user_amount_scaled_before: collaterals[2].amount_scaled,
                     // NOTE: Protocol rewards accrued is zero, so here it's initial total supply
                     total_amount_scaled_before: markets[2].collateral_total_scaled,
+                    kind: Some(PositionKind::Collateral),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -716,3 +726,211 @@ fn withdrawing_if_health_factor_met() {
     let market = MARKETS.load(deps.as_ref().storage, denoms[2]).unwrap();
     assert_eq!(market.collateral_total_scaled, expected_collateral_total_amount_scaled_after);
 }
+
+#[test]
+fn withdrawing_without_debt_skips_health_check() {
+    let HealthCheckTestSuite {
+        mut deps,
+        denoms,
+        withdrawer_addr,
+        ..
+    } = setup_health_check_test();
+
+    let withdraw = |denom: &str| ExecuteMsg::Withdraw {
+        denom: denom.to_string(),
+        amount: Some(Uint128::new(500_000)),
+        recipient: None,
+    };
+
+    // while borrowing, the health check prices the user's positions
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(withdrawer_addr.as_str(), &[]),
+        withdraw(denoms[2]),
+    )
+    .unwrap_err();
+    assert!(deps.querier.oracle_price_query_count() > 0);
+
+    // once the debts are repaid, nothing is priced, however much collateral is withdrawn
+    for denom in denoms {
+        DEBTS.remove(deps.as_mut().storage, (&withdrawer_addr, denom));
+    }
+    let query_count_before = deps.querier.oracle_price_query_count();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(withdrawer_addr.as_str(), &[]),
+        withdraw(denoms[2]),
+    )
+    .unwrap();
+    assert_eq!(deps.querier.oracle_price_query_count(), query_count_before);
+}
+
+#[test]
+fn withdrawing_all_and_consolidating_without_collateral() {
+    let TestSuite {
+        mut deps,
+        withdrawer_addr,
+        ..
+    } = setup_test();
+
+    // a disabled collateral position is not withdrawn
+    set_collateral(deps.as_mut(), &withdrawer_addr, "uosmo", Uint128::new(200), false);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(withdrawer_addr.as_str(), &[]),
+        ExecuteMsg::WithdrawAllAndConsolidate {
+            target_denom: "uusdc".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoCollateralToConsolidate {});
+}
+
+#[test]
+fn withdrawing_all_and_consolidating() {
+    let mut deps = th_setup(&[coin(10_000, "uatom"), coin(10_000, "uusdc")]);
+    let withdrawer_addr = Addr::unchecked("larry");
+    let block_time = 10000000;
+
+    // no time has passed since the last index update, so no interest is accrued
+    for denom in ["uatom", "uusdc"] {
+        let market = Market {
+            denom: denom.to_string(),
+            borrow_index: Decimal::one(),
+            liquidity_index: Decimal::one(),
+            indexes_last_updated: block_time,
+            collateral_total_scaled: Uint128::new(10_000) * SCALING_FACTOR,
+            ..Default::default()
+        };
+        MARKETS.save(deps.as_mut().storage, denom, &market).unwrap();
+    }
+
+    set_collateral(
+        deps.as_mut(),
+        &withdrawer_addr,
+        "uatom",
+        Uint128::new(1_000) * SCALING_FACTOR,
+        true,
+    );
+    set_collateral(
+        deps.as_mut(),
+        &withdrawer_addr,
+        "uosmo",
+        Uint128::new(2_000) * SCALING_FACTOR,
+        false,
+    );
+    set_collateral(
+        deps.as_mut(),
+        &withdrawer_addr,
+        "uusdc",
+        Uint128::new(300) * SCALING_FACTOR,
+        true,
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_block_time(block_time),
+        mock_info(withdrawer_addr.as_str(), &[]),
+        ExecuteMsg::WithdrawAllAndConsolidate {
+            target_denom: "uusdc".to_string(),
+        },
+    )
+    .unwrap();
+
+    // the uatom collateral is swapped into uusdc by the rewards collector
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: MarsAddressType::Incentives.to_string(),
+                msg: to_binary(&incentives::ExecuteMsg::BalanceChange {
+                    user_addr: withdrawer_addr.clone(),
+                    denom: "uatom".to_string(),
+                    user_amount_scaled_before: Uint128::new(1_000) * SCALING_FACTOR,
+                    total_amount_scaled_before: Uint128::new(10_000) * SCALING_FACTOR,
+                    kind: Some(PositionKind::Collateral),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: MarsAddressType::Incentives.to_string(),
+                msg: to_binary(&incentives::ExecuteMsg::BalanceChange {
+                    user_addr: withdrawer_addr.clone(),
+                    denom: "uusdc".to_string(),
+                    user_amount_scaled_before: Uint128::new(300) * SCALING_FACTOR,
+                    total_amount_scaled_before: Uint128::new(10_000) * SCALING_FACTOR,
+                    kind: Some(PositionKind::Collateral),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::reply_on_success(
+                WasmMsg::Execute {
+                    contract_addr: MarsAddressType::RewardsCollector.to_string(),
+                    msg: to_binary(&rewards_collector::ExecuteMsg::<Empty>::SwapFunds {
+                        denom_out: "uusdc".to_string(),
+                    })
+                    .unwrap(),
+                    funds: coins(1_000, "uatom"),
+                },
+                CONSOLIDATE_REPLY_ID
+            ),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("amount_uatom", "1000"),
+            attr("amount_uusdc", "300"),
+            attr("action", "withdraw_all_and_consolidate"),
+            attr("sender", "larry"),
+            attr("target_denom", "uusdc"),
+        ]
+    );
+
+    // the enabled collateral positions are closed, the disabled one is kept
+    assert!(!has_collateral_position(deps.as_ref(), &withdrawer_addr, "uatom"));
+    assert!(has_collateral_position(deps.as_ref(), &withdrawer_addr, "uosmo"));
+    assert!(!has_collateral_position(deps.as_ref(), &withdrawer_addr, "uusdc"));
+
+    // the swap yields 950 uusdc
+    deps.querier.set_contract_balances(&[coin(9_000, "uatom"), coin(10_950, "uusdc")]);
+
+    let res = reply(
+        deps.as_mut(),
+        mock_env_at_block_time(block_time),
+        Reply {
+            id: CONSOLIDATE_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+
+    // the withdrawn uusdc and the swap output are sent to the user
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: withdrawer_addr.to_string(),
+            amount: coins(1_250, "uusdc"),
+        })]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "send_consolidated_amount"),
+            attr("user", "larry"),
+            attr("target_denom", "uusdc"),
+            attr("swapped_amount", "950"),
+            attr("consolidated_amount", "1250"),
+        ]
+    );
+}
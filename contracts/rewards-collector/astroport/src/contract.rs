@@ -0,0 +1,52 @@
+use cosmwasm_std::Empty;
+use mars_rewards_collector_base::CollectorBase;
+
+use crate::AstroportRoute;
+
+/// The Astroport rewards collector contract inherits logics from the base collector contract, with
+/// the Astroport swap route plugin
+pub type AstroportCollector<'a> = CollectorBase<'a, AstroportRoute, Empty, Empty>;
+
+pub const CONTRACT_NAME: &str = "crates.io:mars-rewards-collector-astroport";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg(not(feature = "library"))]
+pub mod entry {
+    use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response};
+    use mars_red_bank_types::rewards_collector::{InstantiateMsg, QueryMsg};
+    use mars_rewards_collector_base::ContractResult;
+
+    use super::*;
+    use crate::msg::ExecuteMsg;
+
+    #[entry_point]
+    pub fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> ContractResult<Response> {
+        cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        AstroportCollector::default().instantiate(deps, msg)
+    }
+
+    #[entry_point]
+    pub fn execute(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> ContractResult<Response> {
+        AstroportCollector::default().execute(deps, env, info, msg)
+    }
+
+    #[entry_point]
+    pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> ContractResult<Response> {
+        AstroportCollector::default().reply(deps, env, reply)
+    }
+
+    #[entry_point]
+    pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> ContractResult<Binary> {
+        AstroportCollector::default().query(deps, env, msg)
+    }
+}
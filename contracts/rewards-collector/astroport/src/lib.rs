@@ -0,0 +1,5 @@
+pub mod contract;
+pub mod msg;
+pub mod route;
+
+pub use route::AstroportRoute;
@@ -0,0 +1,7 @@
+use mars_red_bank_types::rewards_collector;
+
+use crate::AstroportRoute;
+
+pub type ExecuteMsg = rewards_collector::ExecuteMsg<AstroportRoute>;
+pub type RouteResponse = rewards_collector::RouteResponse<AstroportRoute>;
+pub type RoutesResponse = rewards_collector::RoutesResponse<AstroportRoute>;
@@ -0,0 +1,238 @@
+use std::{collections::HashSet, fmt};
+
+use astroport::{
+    asset::{AssetInfo, PairInfo},
+    factory::QueryMsg as FactoryQueryMsg,
+    router::{
+        ExecuteMsg as RouterExecuteMsg, QueryMsg as RouterQueryMsg, SimulateSwapOperationsResponse,
+        SwapOperation,
+    },
+};
+use cosmwasm_std::{
+    coins, to_binary, Coin, CosmosMsg, Decimal, Empty, Env, QuerierWrapper, Uint128, WasmMsg,
+};
+use mars_red_bank_types::rewards_collector::MAX_SLIPPAGE_TOLERANCE_PERCENTAGE;
+use mars_rewards_collector_base::{ContractError, ContractResult, Route};
+use mars_utils::error::ValidationError;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A swap route through the Astroport router
+///
+/// Unlike Osmosis pools, Astroport pairs are contracts, so the route carries the addresses of the
+/// factory that the pairs are registered with and of the router that executes the swap operations.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AstroportRoute {
+    /// Address of the Astroport factory, used to check that the pair of every hop exists
+    pub factory: String,
+    /// Address of the Astroport router, which executes the swap operations
+    pub router: String,
+    /// The hops of the route, each swapping the output of the previous hop through an Astroport pair
+    pub hops: Vec<AstroportHop>,
+}
+
+/// A hop of an Astroport route, swapping the output of the previous hop into the ask denom through
+/// the pair of the two denoms
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AstroportHop {
+    pub ask_denom: String,
+}
+
+impl fmt::Display for AstroportRoute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = self.hops.iter().map(|hop| hop.ask_denom.clone()).collect::<Vec<_>>().join("|");
+        write!(f, "{s}")
+    }
+}
+
+impl AstroportRoute {
+    /// Convert the hops into the router's swap operations, given the input denom of the route
+    fn swap_operations(&self, denom_in: &str) -> ContractResult<Vec<SwapOperation>> {
+        if self.hops.is_empty() {
+            return Err(ContractError::InvalidRoute {
+                reason: "the route must contain at least one step".to_string(),
+            });
+        }
+
+        let mut offer_denom = denom_in;
+        let operations = self
+            .hops
+            .iter()
+            .map(|hop| {
+                let operation = SwapOperation::AstroSwap {
+                    offer_asset_info: native_asset_info(offer_denom),
+                    ask_asset_info: native_asset_info(&hop.ask_denom),
+                };
+                offer_denom = &hop.ask_denom;
+                operation
+            })
+            .collect();
+        Ok(operations)
+    }
+
+    /// Build a message executing the swap operations with the router
+    fn build_router_msg(
+        &self,
+        denom_in: &str,
+        amount: Uint128,
+        min_out_amount: Uint128,
+        max_spread: Decimal,
+    ) -> ContractResult<CosmosMsg> {
+        let swap_msg = WasmMsg::Execute {
+            contract_addr: self.router.clone(),
+            msg: to_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                operations: self.swap_operations(denom_in)?,
+                minimum_receive: Some(min_out_amount),
+                to: None,
+                max_spread: Some(max_spread),
+            })?,
+            funds: coins(amount.u128(), denom_in),
+        };
+        Ok(swap_msg.into())
+    }
+}
+
+impl Route<Empty, Empty> for AstroportRoute {
+    // Perform basic validation of the swap steps
+    fn validate(
+        &self,
+        querier: &QuerierWrapper,
+        denom_in: &str,
+        denom_out: &str,
+    ) -> ContractResult<()> {
+        let hops = &self.hops;
+
+        // there must be at least one step
+        if hops.is_empty() {
+            return Err(ContractError::InvalidRoute {
+                reason: "the route must contain at least one step".to_string(),
+            });
+        }
+
+        // for each step:
+        // - the factory must have a pair of the input and output denoms
+        // - the output denom must not be the same as the input denom of a previous step (i.e. the route must not contain a loop)
+        let mut prev_denom_out = denom_in;
+        let mut seen_denoms = HashSet::from([denom_in]);
+        for (i, hop) in hops.iter().enumerate() {
+            let pair: Result<PairInfo, _> = querier.query_wasm_smart(
+                &self.factory,
+                &FactoryQueryMsg::Pair {
+                    asset_infos: vec![
+                        native_asset_info(prev_denom_out),
+                        native_asset_info(&hop.ask_denom),
+                    ],
+                },
+            );
+            if pair.is_err() {
+                return Err(ContractError::InvalidRoute {
+                    reason: format!(
+                        "step {}: no pair found for {} and {}",
+                        i + 1,
+                        prev_denom_out,
+                        hop.ask_denom
+                    ),
+                });
+            }
+
+            if !seen_denoms.insert(&hop.ask_denom) {
+                return Err(ContractError::InvalidRoute {
+                    reason: format!("route contains a loop: denom {} seen twice", hop.ask_denom),
+                });
+            }
+
+            prev_denom_out = &hop.ask_denom;
+        }
+
+        // the route's final output denom must match the desired output denom
+        if prev_denom_out != denom_out {
+            return Err(ContractError::InvalidRoute {
+                reason: format!(
+                    "the route's output denom {prev_denom_out} does not match the desired output {denom_out}"
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Build a CosmosMsg that swaps given an input denom and amount, with the slippage tolerance as
+    /// the maximum spread of every hop
+    fn build_swap_msg(
+        &self,
+        env: &Env,
+        querier: &QuerierWrapper,
+        denom_in: &str,
+        amount: Uint128,
+        slippage_tolerance: Decimal,
+    ) -> ContractResult<CosmosMsg> {
+        // Astroport pairs reject a maximum spread above 50%
+        let max_spread = Decimal::percent(MAX_SLIPPAGE_TOLERANCE_PERCENTAGE);
+        if slippage_tolerance > max_spread {
+            return Err(ContractError::Validation(ValidationError::InvalidParam {
+                param_name: "max_spread".to_string(),
+                invalid_value: slippage_tolerance.to_string(),
+                predicate: format!("<= {max_spread}"),
+            }));
+        }
+
+        let min_out_amount =
+            self.min_out_amount(env, querier, denom_in, amount, slippage_tolerance)?;
+
+        self.build_router_msg(denom_in, amount, min_out_amount, slippage_tolerance)
+    }
+
+    /// Build a CosmosMsg that swaps the input amount, with the given minimum output amount
+    fn build_swap_msg_with_min_out(
+        &self,
+        _env: &Env,
+        denom_in: &str,
+        amount: Uint128,
+        min_out_amount: Uint128,
+    ) -> ContractResult<CosmosMsg> {
+        // the minimum output amount is enforced by the router, so the pairs are given the largest
+        // spread they accept instead of their much tighter default
+        let max_spread = Decimal::percent(MAX_SLIPPAGE_TOLERANCE_PERCENTAGE);
+        self.build_router_msg(denom_in, amount, min_out_amount, max_spread)
+    }
+
+    /// The Astroport router can't swap into an exact output amount
+    fn build_swap_exact_out_msg(
+        &self,
+        _env: &Env,
+        _querier: &QuerierWrapper,
+        _denom_in: &str,
+        _coin_out: &Coin,
+        _max_input: Uint128,
+        _slippage_tolerance: Decimal,
+    ) -> ContractResult<CosmosMsg> {
+        Err(ContractError::InvalidRoute {
+            reason: "exact output swaps are not supported by Astroport routes".to_string(),
+        })
+    }
+
+    /// Estimate the output amount by simulating the swap operations with the router, which accounts
+    /// for the pairs' current reserves and their swap fees
+    fn estimate_out_amount(
+        &self,
+        _env: &Env,
+        querier: &QuerierWrapper,
+        denom_in: &str,
+        amount: Uint128,
+    ) -> ContractResult<Uint128> {
+        let res: SimulateSwapOperationsResponse = querier.query_wasm_smart(
+            &self.router,
+            &RouterQueryMsg::SimulateSwapOperations {
+                offer_amount: amount,
+                operations: self.swap_operations(denom_in)?,
+            },
+        )?;
+        Ok(res.amount)
+    }
+}
+
+fn native_asset_info(denom: &str) -> AssetInfo {
+    AssetInfo::NativeToken {
+        denom: denom.to_string(),
+    }
+}
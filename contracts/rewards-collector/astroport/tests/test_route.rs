@@ -0,0 +1,209 @@
+use astroport::{
+    asset::{AssetInfo, PairInfo},
+    factory::{PairType, QueryMsg as FactoryQueryMsg},
+    router::{
+        ExecuteMsg as RouterExecuteMsg, QueryMsg as RouterQueryMsg, SimulateSwapOperationsResponse,
+        SwapOperation,
+    },
+};
+use cosmwasm_std::{
+    coin, coins, from_binary,
+    testing::{mock_env, MockQuerier},
+    to_binary, Addr, ContractResult as CwContractResult, CosmosMsg, Decimal, QuerierWrapper,
+    SystemResult, Uint128, WasmMsg, WasmQuery,
+};
+use mars_rewards_collector_astroport::{route::AstroportHop, AstroportRoute};
+use mars_rewards_collector_base::{ContractError, Route};
+use mars_utils::error::ValidationError;
+
+/// A querier with an Astroport factory holding the uatom/uosmo and uosmo/umars pairs, and a router
+/// simulating every swap to output half of the input amount
+fn mock_querier() -> MockQuerier {
+    let mut querier = MockQuerier::new(&[]);
+    querier.update_wasm(|query| match query {
+        WasmQuery::Smart {
+            contract_addr,
+            msg,
+        } if contract_addr == "factory" => {
+            let FactoryQueryMsg::Pair {
+                asset_infos,
+            } = from_binary(msg).unwrap()
+            else {
+                panic!("unexpected factory query");
+            };
+            let pairs = [["uatom", "uosmo"], ["uosmo", "umars"]];
+            let exists = pairs.iter().any(|pair| {
+                pair.iter().all(|denom| asset_infos.contains(&native_asset_info(denom)))
+            });
+            if !exists {
+                return SystemResult::Ok(CwContractResult::Err("pair not found".to_string()));
+            }
+            let pair_info = PairInfo {
+                asset_infos,
+                contract_addr: Addr::unchecked("pair"),
+                liquidity_token: Addr::unchecked("lp_token"),
+                pair_type: PairType::Xyk {},
+            };
+            SystemResult::Ok(CwContractResult::Ok(to_binary(&pair_info).unwrap()))
+        }
+        WasmQuery::Smart {
+            contract_addr,
+            msg,
+        } if contract_addr == "router" => {
+            let RouterQueryMsg::SimulateSwapOperations {
+                offer_amount,
+                ..
+            } = from_binary(msg).unwrap()
+            else {
+                panic!("unexpected router query");
+            };
+            let res = SimulateSwapOperationsResponse {
+                amount: offer_amount / Uint128::new(2),
+            };
+            SystemResult::Ok(CwContractResult::Ok(to_binary(&res).unwrap()))
+        }
+        _ => panic!("unexpected wasm query"),
+    });
+    querier
+}
+
+fn mock_route(ask_denoms: &[&str]) -> AstroportRoute {
+    AstroportRoute {
+        factory: "factory".to_string(),
+        router: "router".to_string(),
+        hops: ask_denoms
+            .iter()
+            .map(|denom| AstroportHop {
+                ask_denom: denom.to_string(),
+            })
+            .collect(),
+    }
+}
+
+fn native_asset_info(denom: &str) -> AssetInfo {
+    AssetInfo::NativeToken {
+        denom: denom.to_string(),
+    }
+}
+
+#[test]
+fn validating_route() {
+    let querier = mock_querier();
+    let querier = QuerierWrapper::new(&querier);
+
+    // a route through existing pairs is valid
+    mock_route(&["uosmo", "umars"]).validate(&querier, "uatom", "umars").unwrap();
+
+    // the route must contain at least one step
+    let err = mock_route(&[]).validate(&querier, "uatom", "umars").unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidRoute {
+            reason: "the route must contain at least one step".to_string()
+        }
+    );
+
+    // the pair of every step must exist
+    let err = mock_route(&["umars"]).validate(&querier, "uatom", "umars").unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidRoute {
+            reason: "step 1: no pair found for uatom and umars".to_string()
+        }
+    );
+
+    // the route must not contain a loop
+    let err = mock_route(&["uosmo", "uatom"]).validate(&querier, "uatom", "uosmo").unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidRoute {
+            reason: "route contains a loop: denom uatom seen twice".to_string()
+        }
+    );
+
+    // the route's output denom must match the desired output
+    let err = mock_route(&["uosmo"]).validate(&querier, "uatom", "umars").unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidRoute {
+            reason: "the route's output denom uosmo does not match the desired output umars"
+                .to_string()
+        }
+    );
+}
+
+#[test]
+fn swapping_exact_in() {
+    let querier = mock_querier();
+    let querier = QuerierWrapper::new(&querier);
+
+    let msg = mock_route(&["uosmo", "umars"])
+        .build_swap_msg(&mock_env(), &querier, "uatom", Uint128::new(1_000), Decimal::percent(3))
+        .unwrap();
+
+    // the router simulates an output of 500 umars, so the minimum is 500 * (1 - 0.03) = 485
+    let expected: CosmosMsg = WasmMsg::Execute {
+        contract_addr: "router".to_string(),
+        msg: to_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+            operations: vec![
+                SwapOperation::AstroSwap {
+                    offer_asset_info: native_asset_info("uatom"),
+                    ask_asset_info: native_asset_info("uosmo"),
+                },
+                SwapOperation::AstroSwap {
+                    offer_asset_info: native_asset_info("uosmo"),
+                    ask_asset_info: native_asset_info("umars"),
+                },
+            ],
+            minimum_receive: Some(Uint128::new(485)),
+            to: None,
+            max_spread: Some(Decimal::percent(3)),
+        })
+        .unwrap(),
+        funds: coins(1_000, "uatom"),
+    }
+    .into();
+    assert_eq!(msg, expected);
+}
+
+#[test]
+fn swapping_with_slippage_too_high() {
+    let querier = mock_querier();
+    let querier = QuerierWrapper::new(&querier);
+
+    // Astroport pairs reject a maximum spread above 50%
+    let err = mock_route(&["uosmo", "umars"])
+        .build_swap_msg(&mock_env(), &querier, "uatom", Uint128::new(1_000), Decimal::percent(51))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Validation(ValidationError::InvalidParam {
+            param_name: "max_spread".to_string(),
+            invalid_value: "0.51".to_string(),
+            predicate: "<= 0.5".to_string(),
+        })
+    );
+}
+
+#[test]
+fn swapping_exact_out_is_not_supported() {
+    let querier = mock_querier();
+    let querier = QuerierWrapper::new(&querier);
+
+    let err = mock_route(&["uosmo", "umars"])
+        .build_swap_exact_out_msg(
+            &mock_env(),
+            &querier,
+            "uatom",
+            &coin(500, "umars"),
+            Uint128::new(1_000),
+            Decimal::percent(3),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidRoute {
+            reason: "exact output swaps are not supported by Astroport routes".to_string()
+        }
+    );
+}
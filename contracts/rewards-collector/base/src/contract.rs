@@ -1,29 +1,61 @@
-use std::marker::PhantomData;
+use std::{collections::HashMap, iter, marker::PhantomData};
 
 use cosmwasm_std::{
-    to_binary, Addr, Binary, Coin, CosmosMsg, CustomMsg, CustomQuery, Deps, DepsMut, Env, IbcMsg,
-    IbcTimeout, MessageInfo, Order, Response, StdResult, Uint128, WasmMsg,
+    attr, coin, to_binary, Addr, Attribute, BankMsg, Binary, Coin, CosmosMsg, CustomMsg,
+    CustomQuery, Decimal, Deps, DepsMut, Env, IbcMsg, IbcTimeout, MessageInfo, Order,
+    QuerierWrapper, Reply, ReplyOn, Response, StdResult, Storage, SubMsg, Timestamp, Uint128,
+    WasmMsg,
 };
 use cw_storage_plus::{Bound, Item, Map};
 use mars_owner::{Owner, OwnerInit::SetInitialOwner, OwnerUpdate};
 use mars_red_bank_types::{
     address_provider::{self, MarsAddressType},
-    incentives, red_bank,
+    error::MarsError,
+    incentives, oracle, red_bank,
     rewards_collector::{
-        Config, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, RouteResponse,
-        RoutesResponse, UpdateConfig,
+        AssetInfo, BalanceReconciliationResponse, Config, ConfigResponse, ContractOwnership,
+        DistributedTotalResponse, DistributionStatsResponse, ExecuteMsg, InstantiateMsg,
+        OwnershipStatusResponse, PendingBalanceValue, QueryMsg, ResidualAfterCycleResponse,
+        RewardRecipient, RouteExistsResponse, RouteResponse, RouteWindow, RoutesResponse,
+        SimulateSwapResponse, TotalPendingValueResponse, UpdateConfig,
+        MAX_SLIPPAGE_TOLERANCE_PERCENTAGE,
     },
 };
-use mars_utils::helpers::{option_string_to_addr, validate_native_denom};
+use mars_utils::{
+    error::ValidationError,
+    helpers::{decimal_param_le_one, option_string_to_addr, validate_native_denom},
+};
 
 use crate::{
-    helpers::{stringify_option_amount, unwrap_option_amount},
-    ContractError, ContractResult, Route,
+    helpers::{
+        ibc_timeout, query_contract_ownership, query_recipient_addr, split_by_weights,
+        stringify_option_amount, stringify_option_u64, unwrap_option_amount,
+        validate_local_recipients, Cw20ExecuteMsg,
+    },
+    ContractError, ContractResult, MsgTransfer, Route,
 };
 
 const DEFAULT_LIMIT: u32 = 5;
 const MAX_LIMIT: u32 = 10;
 
+/// Reply ID of a swap whose output is forwarded to the fee collector
+pub const FORWARD_TO_FEE_COLLECTOR_REPLY_ID: u64 = 1;
+
+/// Reply ID of the incentive rewards claim starting a harvest, after which the assets are swapped
+pub const HARVEST_CLAIM_REPLY_ID: u64 = 2;
+
+/// Reply ID of the last swap of a harvest, after which the rewards are distributed
+pub const HARVEST_SWAP_REPLY_ID: u64 = 3;
+
+/// Reply ID of a `SwapExactIn` swap, after which the amount actually received is reported
+pub const SWAP_EXACT_IN_REPLY_ID: u64 = 4;
+
+/// Reply ID of a `SwapFunds` swap, after which the output is sent back to the sender
+pub const SWAP_FUNDS_REPLY_ID: u64 = 5;
+
+/// Maximum number of swap messages a single `SwapAssets` call may produce
+pub const MAX_SWAP_MSGS: usize = 20;
+
 pub struct CollectorBase<'a, R, M, Q>
 where
     R: Route<M, Q>,
@@ -36,6 +68,26 @@ where
     pub config: Item<'a, Config>,
     /// The trade route for each pair of input/output assets
     pub routes: Map<'a, (String, String), R>,
+    /// Optional validity window of a trade route; routes without one are always active
+    pub route_windows: Map<'a, (String, String), RouteWindow>,
+    /// Optional slippage tolerance of a trade route, overriding the configured one
+    pub route_slippages: Map<'a, (String, String), Decimal>,
+    /// Fee collector address, output denom and the contract's balance of it before a swap whose
+    /// output is to be forwarded to the fee collector
+    pub pending_forward: Item<'a, (String, String, Uint128)>,
+    /// Output denom and the contract's balance of it before a `SwapExactIn` swap
+    pub pending_swap: Item<'a, (String, Uint128)>,
+    /// Sender, output denom and the contract's balance of it before a `SwapFunds` swap
+    pub pending_return: Item<'a, (String, String, Uint128)>,
+    /// The contract's balances before the incentive rewards claim of a harvest
+    pub pending_harvest: Item<'a, Vec<Coin>>,
+    /// Cumulative amount of each denom distributed to each recipient, keyed by the denom and the
+    /// recipient's display name, i.e. its module name or address
+    pub distributed: Map<'a, (String, String), Uint128>,
+    /// The contract's expected balance of each denom after its last swap or distribution, i.e. the
+    /// balance at the time net of the amount swapped or distributed, plus the estimated output of
+    /// the swaps into the denom since
+    pub expected_balances: Map<'a, String, Uint128>,
     /// Phantom data that holds the custom message type
     pub custom_msg: PhantomData<M>,
     /// Phantom data that holds the custom query type
@@ -53,6 +105,14 @@ where
             owner: Owner::new("owner"),
             config: Item::new("config"),
             routes: Map::new("routes"),
+            route_windows: Map::new("route_windows"),
+            route_slippages: Map::new("route_slippages"),
+            pending_forward: Item::new("pending_forward"),
+            pending_swap: Item::new("pending_swap"),
+            pending_return: Item::new("pending_return"),
+            pending_harvest: Item::new("pending_harvest"),
+            distributed: Map::new("distributed"),
+            expected_balances: Map::new("expected_balances"),
             custom_msg: PhantomData,
             custom_query: PhantomData,
         }
@@ -70,6 +130,7 @@ where
 
         let cfg = Config::checked(deps.api, msg)?;
         cfg.validate()?;
+        validate_local_recipients(deps.api, &cfg.reward_splits)?;
 
         self.owner.initialize(
             deps.storage,
@@ -91,16 +152,60 @@ where
         info: MessageInfo,
         msg: ExecuteMsg<R>,
     ) -> ContractResult<Response<M>> {
+        // anything moving the contract's funds is suspended while paused
+        if matches!(
+            msg,
+            ExecuteMsg::WithdrawFromRedBank { .. }
+                | ExecuteMsg::DistributeRewards { .. }
+                | ExecuteMsg::DistributeRewardsMany { .. }
+                | ExecuteMsg::SwapAsset { .. }
+                | ExecuteMsg::SwapAssets { .. }
+                | ExecuteMsg::SwapExactIn { .. }
+                | ExecuteMsg::SwapExactOut { .. }
+                | ExecuteMsg::SwapFunds { .. }
+                | ExecuteMsg::ClaimIncentiveRewards {}
+                | ExecuteMsg::Harvest {}
+                | ExecuteMsg::DistributeCw20 { .. }
+        ) && self.config.load(deps.storage)?.paused
+        {
+            return Err(ContractError::Paused {});
+        }
+
         match msg {
             ExecuteMsg::UpdateOwner(update) => self.update_owner(deps, info, update),
             ExecuteMsg::UpdateConfig {
                 new_cfg,
             } => self.update_config(deps, info.sender, new_cfg),
+            ExecuteMsg::SetPaused {
+                paused,
+            } => self.set_paused(deps, info.sender, paused),
             ExecuteMsg::SetRoute {
                 denom_in,
                 denom_out,
                 route,
-            } => self.set_route(deps, info.sender, denom_in, denom_out, route),
+                slippage_override,
+            } => self.set_route(deps, info.sender, denom_in, denom_out, route, slippage_override),
+            ExecuteMsg::SetTimeBoundedRoute {
+                denom_in,
+                denom_out,
+                route,
+                valid_from,
+                valid_until,
+            } => self.set_time_bounded_route(
+                deps,
+                info.sender,
+                denom_in,
+                denom_out,
+                route,
+                RouteWindow {
+                    valid_from,
+                    valid_until,
+                },
+            ),
+            ExecuteMsg::RemoveRoute {
+                denom_in,
+                denom_out,
+            } => self.remove_route(deps, info.sender, denom_in, denom_out),
             ExecuteMsg::WithdrawFromRedBank {
                 denom,
                 amount,
@@ -108,17 +213,65 @@ where
             ExecuteMsg::DistributeRewards {
                 denom,
                 amount,
-            } => self.distribute_rewards(deps, env, denom, amount),
+                timeout_timestamp,
+            } => self.distribute_rewards(deps, env, info.sender, denom, amount, timeout_timestamp),
+            ExecuteMsg::DistributeRewardsMany {
+                denoms,
+            } => self.distribute_rewards_many(deps, env, denoms),
             ExecuteMsg::SwapAsset {
                 denom,
                 amount,
-            } => self.swap_asset(deps, env, denom, amount),
+                send_to_fee_collector,
+            } => self.swap_asset(deps, env, denom, amount, send_to_fee_collector.unwrap_or(false)),
+            ExecuteMsg::SwapAssets {
+                denoms,
+            } => self.swap_assets(deps, env, denoms),
+            ExecuteMsg::SwapExactIn {
+                denom_in,
+                amount,
+                denom_out,
+                slippage,
+                min_output,
+            } => {
+                self.owner.assert_owner(deps.storage, &info.sender)?;
+                self.swap_exact_in(deps, env, denom_in, amount, denom_out, slippage, min_output)
+            }
+            ExecuteMsg::SwapExactOut {
+                coin_out,
+                denom_in,
+                max_input,
+                slippage,
+            } => {
+                self.owner.assert_owner(deps.storage, &info.sender)?;
+                self.swap_exact_out(deps, env, coin_out, denom_in, max_input, slippage)
+            }
+            ExecuteMsg::SwapFunds {
+                denom_out,
+            } => self.swap_funds(deps, env, info, denom_out),
             ExecuteMsg::ClaimIncentiveRewards {} => self.claim_incentive_rewards(deps),
+            ExecuteMsg::Harvest {} => self.harvest(deps, env),
+            ExecuteMsg::DistributeCw20 {
+                contract_addr,
+                amount,
+            } => self.distribute_cw20(deps, contract_addr, amount),
         }
     }
 
-    pub fn query(&self, deps: Deps<Q>, msg: QueryMsg) -> StdResult<Binary> {
-        match msg {
+    pub fn reply(&self, deps: DepsMut<Q>, env: Env, reply: Reply) -> ContractResult<Response<M>> {
+        match reply.id {
+            FORWARD_TO_FEE_COLLECTOR_REPLY_ID => self.forward_to_fee_collector(deps, env),
+            HARVEST_CLAIM_REPLY_ID => self.harvest_swap(deps, env),
+            HARVEST_SWAP_REPLY_ID => self.harvest_distribute(deps, env),
+            SWAP_EXACT_IN_REPLY_ID => self.swap_exact_in_reply(deps, env),
+            SWAP_FUNDS_REPLY_ID => self.return_swapped_funds(deps, env),
+            id => Err(ContractError::UnknownReplyId {
+                id,
+            }),
+        }
+    }
+
+    pub fn query(&self, deps: Deps<Q>, env: Env, msg: QueryMsg) -> ContractResult<Binary> {
+        let res = match msg {
             QueryMsg::Config {} => to_binary(&self.query_config(deps)?),
             QueryMsg::Route {
                 denom_in,
@@ -128,7 +281,42 @@ where
                 start_after,
                 limit,
             } => to_binary(&self.query_routes(deps, start_after, limit)?),
-        }
+            QueryMsg::ResidualAfterCycle {
+                denom,
+            } => to_binary(&self.query_residual_after_cycle(deps, env, denom)?),
+            QueryMsg::PendingIncentiveRewards {} => {
+                to_binary(&self.query_pending_incentive_rewards(deps, env)?)
+            }
+            QueryMsg::OwnershipStatus {} => to_binary(&self.query_ownership_status(deps, env)?),
+            QueryMsg::MinOutput {
+                coin_in,
+                denom_out,
+                slippage,
+            } => to_binary(&self.query_min_output(deps, env, coin_in, denom_out, slippage)?),
+            QueryMsg::TotalPendingValue {
+                denoms,
+            } => to_binary(&self.query_total_pending_value(deps, env, denoms)?),
+            QueryMsg::ProjectedDistribution {
+                denoms,
+            } => to_binary(&self.query_projected_distribution(deps, env, denoms)?),
+            QueryMsg::RouteExists {
+                denom_in,
+                denom_out,
+            } => to_binary(&self.query_route_exists(deps, env, denom_in, denom_out)?),
+            QueryMsg::SimulateSwap {
+                denom_in,
+                denom_out,
+                amount,
+            } => to_binary(&self.query_simulate_swap(deps, env, denom_in, denom_out, amount)?),
+            QueryMsg::DistributionStats {
+                start_after,
+                limit,
+            } => to_binary(&self.query_distribution_stats(deps, start_after, limit)?),
+            QueryMsg::BalanceReconciliation {
+                denom,
+            } => to_binary(&self.query_balance_reconciliation(deps, env, denom)?),
+        };
+        res.map_err(Into::into)
     }
 
     fn update_owner(
@@ -152,30 +340,63 @@ where
 
         let UpdateConfig {
             address_provider,
-            safety_tax_rate,
-            safety_fund_denom,
-            fee_collector_denom,
+            reward_splits,
             channel_id,
             timeout_seconds,
+            timeout_blocks,
+            counterparty_height,
             slippage_tolerance,
+            ibc_memo,
+            local_fee_collector,
         } = new_cfg;
 
         cfg.address_provider =
             option_string_to_addr(deps.api, address_provider, cfg.address_provider)?;
-        cfg.safety_tax_rate = safety_tax_rate.unwrap_or(cfg.safety_tax_rate);
-        cfg.safety_fund_denom = safety_fund_denom.unwrap_or(cfg.safety_fund_denom);
-        cfg.fee_collector_denom = fee_collector_denom.unwrap_or(cfg.fee_collector_denom);
+        cfg.reward_splits = reward_splits.unwrap_or(cfg.reward_splits);
         cfg.channel_id = channel_id.unwrap_or(cfg.channel_id);
         cfg.timeout_seconds = timeout_seconds.unwrap_or(cfg.timeout_seconds);
+        if let Some(blocks) = timeout_blocks {
+            cfg.timeout_blocks = (blocks > 0).then_some(blocks);
+        }
+        cfg.counterparty_height = counterparty_height.or(cfg.counterparty_height);
         cfg.slippage_tolerance = slippage_tolerance.unwrap_or(cfg.slippage_tolerance);
+        if let Some(memo) = ibc_memo {
+            cfg.ibc_memo = (!memo.is_empty()).then_some(memo);
+        }
+        if let Some(addr) = local_fee_collector {
+            cfg.local_fee_collector = if addr.is_empty() {
+                None
+            } else {
+                Some(deps.api.addr_validate(&addr)?)
+            };
+        }
 
         cfg.validate()?;
+        validate_local_recipients(deps.api, &cfg.reward_splits)?;
 
         self.config.save(deps.storage, &cfg)?;
 
         Ok(Response::new().add_attribute("action", "mars/rewards-collector/update_config"))
     }
 
+    fn set_paused(
+        &self,
+        deps: DepsMut<Q>,
+        sender: Addr,
+        paused: bool,
+    ) -> ContractResult<Response<M>> {
+        self.owner.assert_owner(deps.storage, &sender)?;
+
+        self.config.update(deps.storage, |mut cfg| -> StdResult<_> {
+            cfg.paused = paused;
+            Ok(cfg)
+        })?;
+
+        Ok(Response::new()
+            .add_attribute("action", "mars/rewards-collector/set_paused")
+            .add_attribute("paused", paused.to_string()))
+    }
+
     fn set_route(
         &self,
         deps: DepsMut<Q>,
@@ -183,6 +404,7 @@ where
         denom_in: String,
         denom_out: String,
         route: R,
+        slippage_override: Option<Decimal>,
     ) -> ContractResult<Response<M>> {
         self.owner.assert_owner(deps.storage, &sender)?;
 
@@ -191,13 +413,131 @@ where
 
         route.validate(&deps.querier, &denom_in, &denom_out)?;
 
-        self.routes.save(deps.storage, (denom_in.clone(), denom_out.clone()), &route)?;
+        let key = (denom_in.clone(), denom_out.clone());
+        self.routes.save(deps.storage, key.clone(), &route)?;
+        self.route_windows.remove(deps.storage, key.clone());
+        match slippage_override {
+            Some(slippage) => {
+                // an override is held to the same cap as the configured slippage tolerance
+                let max_slippage = Decimal::percent(MAX_SLIPPAGE_TOLERANCE_PERCENTAGE);
+                if slippage > max_slippage {
+                    return Err(ValidationError::InvalidParam {
+                        param_name: "slippage_override".to_string(),
+                        invalid_value: slippage.to_string(),
+                        predicate: format!("<= {max_slippage}"),
+                    }
+                    .into());
+                }
+                self.route_slippages.save(deps.storage, key, &slippage)?;
+            }
+            None => self.route_slippages.remove(deps.storage, key),
+        }
 
         Ok(Response::new()
             .add_attribute("action", "mars/rewards-collector/set_instructions")
             .add_attribute("denom_in", denom_in)
             .add_attribute("denom_out", denom_out)
-            .add_attribute("route", route.to_string()))
+            .add_attribute("route", route.to_string())
+            .add_attribute(
+                "slippage_override",
+                slippage_override.map_or_else(|| "none".to_string(), |s| s.to_string()),
+            ))
+    }
+
+    fn set_time_bounded_route(
+        &self,
+        deps: DepsMut<Q>,
+        sender: Addr,
+        denom_in: String,
+        denom_out: String,
+        route: R,
+        window: RouteWindow,
+    ) -> ContractResult<Response<M>> {
+        self.owner.assert_owner(deps.storage, &sender)?;
+
+        validate_native_denom(&denom_in)?;
+        validate_native_denom(&denom_out)?;
+
+        route.validate(&deps.querier, &denom_in, &denom_out)?;
+        window.validate()?;
+
+        let key = (denom_in.clone(), denom_out.clone());
+        self.routes.save(deps.storage, key.clone(), &route)?;
+        self.route_windows.save(deps.storage, key.clone(), &window)?;
+        self.route_slippages.remove(deps.storage, key);
+
+        Ok(Response::new()
+            .add_attribute("action", "mars/rewards-collector/set_time_bounded_route")
+            .add_attribute("denom_in", denom_in)
+            .add_attribute("denom_out", denom_out)
+            .add_attribute("route", route.to_string())
+            .add_attribute("valid_from", stringify_option_u64(window.valid_from))
+            .add_attribute("valid_until", stringify_option_u64(window.valid_until)))
+    }
+
+    fn remove_route(
+        &self,
+        deps: DepsMut<Q>,
+        sender: Addr,
+        denom_in: String,
+        denom_out: String,
+    ) -> ContractResult<Response<M>> {
+        self.owner.assert_owner(deps.storage, &sender)?;
+
+        let key = (denom_in.clone(), denom_out.clone());
+        if !self.routes.has(deps.storage, key.clone()) {
+            return Err(ContractError::RouteNotFound {
+                denom_in,
+                denom_out,
+            });
+        }
+
+        self.routes.remove(deps.storage, key.clone());
+        self.route_windows.remove(deps.storage, key.clone());
+        self.route_slippages.remove(deps.storage, key);
+
+        Ok(Response::new()
+            .add_attribute("action", "mars/rewards-collector/remove_route")
+            .add_attribute("denom_in", denom_in)
+            .add_attribute("denom_out", denom_out))
+    }
+
+    /// Load the route for a pair of denoms, making sure it is usable at the current block time
+    fn load_active_route(
+        &self,
+        storage: &dyn Storage,
+        env: &Env,
+        denom_in: &str,
+        denom_out: &str,
+    ) -> ContractResult<R> {
+        let key = (denom_in.to_string(), denom_out.to_string());
+        let route = self.routes.load(storage, key.clone())?;
+
+        let time = env.block.time.seconds();
+        if let Some(window) = self.route_windows.may_load(storage, key)? {
+            if !window.is_active(time) {
+                return Err(ContractError::RouteNotActive {
+                    denom_in: denom_in.to_string(),
+                    denom_out: denom_out.to_string(),
+                    time,
+                });
+            }
+        }
+
+        Ok(route)
+    }
+
+    /// Slippage tolerance to apply to swaps along the route for a pair of denoms: the route's
+    /// override if it has one, otherwise the configured one
+    fn route_slippage_tolerance(
+        &self,
+        storage: &dyn Storage,
+        cfg: &Config,
+        denom_in: &str,
+        denom_out: &str,
+    ) -> StdResult<Decimal> {
+        let key = (denom_in.to_string(), denom_out.to_string());
+        Ok(self.route_slippages.may_load(storage, key)?.unwrap_or(cfg.slippage_tolerance))
     }
 
     fn withdraw_from_red_bank(
@@ -232,130 +572,710 @@ where
     }
 
     fn claim_incentive_rewards(&self, deps: DepsMut<Q>) -> ContractResult<Response<M>> {
+        let claim_msg = self.build_claim_incentive_rewards_msg(deps.as_ref())?;
+
+        Ok(Response::new()
+            .add_message(claim_msg)
+            .add_attribute("action", "claim_incentive_rewards"))
+    }
+
+    fn build_claim_incentive_rewards_msg(&self, deps: Deps<Q>) -> ContractResult<CosmosMsg<M>> {
         let cfg = self.config.load(deps.storage)?;
 
         let incentives_addr = address_provider::helpers::query_contract_addr(
-            deps.as_ref(),
+            deps,
             &cfg.address_provider,
             MarsAddressType::Incentives,
         )?;
 
-        let claim_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: incentives_addr.to_string(),
-            msg: to_binary(&incentives::ExecuteMsg::ClaimRewards {})?,
+            msg: to_binary(&incentives::ExecuteMsg::ClaimRewards {
+                recipient: None,
+            })?,
             funds: vec![],
-        });
+        }))
+    }
+
+    fn harvest(&self, deps: DepsMut<Q>, env: Env) -> ContractResult<Response<M>> {
+        let claim_msg = self.build_claim_incentive_rewards_msg(deps.as_ref())?;
+
+        // only the claimed rewards are swapped, not the rest of the contract's balances
+        let balances_before = deps.querier.query_all_balances(&env.contract.address)?;
+        self.pending_harvest.save(deps.storage, &balances_before)?;
 
         Ok(Response::new()
-            .add_message(claim_msg)
-            .add_attribute("action", "claim_incentive_rewards"))
+            .add_submessage(SubMsg::reply_on_success(claim_msg, HARVEST_CLAIM_REPLY_ID))
+            .add_attribute("action", "harvest"))
+    }
+
+    /// Swap the claimed amount of each asset that has an active route to every reward split denom
+    /// other than its own. Assets whose swaps would exceed `MAX_SWAP_MSGS` are skipped.
+    fn harvest_swap(&self, mut deps: DepsMut<Q>, env: Env) -> ContractResult<Response<M>> {
+        let cfg = self.config.load(deps.storage)?;
+
+        let balances_before = self.pending_harvest.load(deps.storage)?;
+        self.pending_harvest.remove(deps.storage);
+
+        let balances = deps.querier.query_all_balances(&env.contract.address)?;
+
+        let mut response = Response::new().add_attribute("action", "harvest_swap");
+        let mut swap_outputs = vec![];
+        for balance in balances {
+            let balance_before = balances_before
+                .iter()
+                .find(|coin| coin.denom == balance.denom)
+                .map(|coin| coin.amount)
+                .unwrap_or_default();
+            let claimed = balance.amount.saturating_sub(balance_before);
+            if claimed.is_zero() {
+                continue;
+            }
+
+            let target_splits: Vec<_> =
+                cfg.reward_splits.iter().filter(|split| split.denom != balance.denom).collect();
+            let swappable = target_splits.iter().all(|split| {
+                self.load_active_route(deps.storage, &env, &balance.denom, &split.denom).is_ok()
+            });
+            if !swappable {
+                continue;
+            }
+
+            // an asset is skipped as a whole rather than failing the harvest
+            if response.messages.len() + target_splits.len() > MAX_SWAP_MSGS {
+                response = response.add_attribute("skipped_denom", balance.denom);
+                continue;
+            }
+
+            let (messages, attrs, outputs) = self.build_swap_msgs(
+                deps.branch(),
+                &env,
+                &cfg,
+                &balance.denom,
+                Some(claimed),
+                false,
+            )?;
+            swap_outputs.extend(outputs);
+            response = response
+                .add_submessages(messages)
+                .add_attribute("denom", balance.denom)
+                .add_attributes(attrs);
+        }
+
+        self.record_swap_outputs(deps.storage, swap_outputs)?;
+
+        // the rewards are distributed once the last swap has completed, or right away if there is
+        // nothing to swap
+        match response.messages.last_mut() {
+            Some(last_msg) => {
+                last_msg.id = HARVEST_SWAP_REPLY_ID;
+                last_msg.reply_on = ReplyOn::Success;
+                Ok(response)
+            }
+            None => self.harvest_distribute(deps, env),
+        }
+    }
+
+    fn harvest_distribute(&self, mut deps: DepsMut<Q>, env: Env) -> ContractResult<Response<M>> {
+        let cfg = self.config.load(deps.storage)?;
+
+        let timeout =
+            ibc_timeout(&cfg, &env.block, env.block.time.plus_seconds(cfg.timeout_seconds));
+
+        let mut response = Response::new().add_attribute("action", "harvest_distribute");
+        let mut distributed_denoms: Vec<&str> = vec![];
+        for split in &cfg.reward_splits {
+            // a denom shared by several recipients is only distributed once
+            if distributed_denoms.contains(&split.denom.as_str()) {
+                continue;
+            }
+            distributed_denoms.push(&split.denom);
+
+            let (amount, messages, attrs) = self.build_distribute_msgs(
+                deps.branch(),
+                &env,
+                &cfg,
+                &split.denom,
+                None,
+                timeout.clone(),
+            )?;
+            response = response
+                .add_messages(messages)
+                .add_attribute("denom", &split.denom)
+                .add_attribute("amount", amount)
+                .add_attributes(attrs);
+        }
+
+        Ok(response)
     }
 
     fn swap_asset(
         &self,
-        deps: DepsMut<Q>,
+        mut deps: DepsMut<Q>,
         env: Env,
         denom: String,
         amount: Option<Uint128>,
+        send_to_fee_collector: bool,
+    ) -> ContractResult<Response<M>> {
+        let cfg = self.config.load(deps.storage)?;
+
+        let (messages, attrs, swap_outputs) =
+            self.build_swap_msgs(deps.branch(), &env, &cfg, &denom, amount, send_to_fee_collector)?;
+        self.record_swap_outputs(deps.storage, swap_outputs)?;
+
+        Ok(Response::new()
+            .add_submessages(messages)
+            .add_attribute("action", "swap_asset")
+            .add_attribute("denom", denom)
+            .add_attributes(attrs)
+            .add_attribute("slippage_tolerance", cfg.slippage_tolerance.to_string()))
+    }
+
+    fn swap_assets(
+        &self,
+        mut deps: DepsMut<Q>,
+        env: Env,
+        denoms: Vec<(String, Option<Uint128>)>,
+    ) -> ContractResult<Response<M>> {
+        let cfg = self.config.load(deps.storage)?;
+
+        let mut response = Response::new().add_attribute("action", "swap_assets");
+        let mut swap_outputs = vec![];
+        for (denom, amount) in denoms {
+            let (messages, attrs, outputs) =
+                self.build_swap_msgs(deps.branch(), &env, &cfg, &denom, amount, false)?;
+            swap_outputs.extend(outputs);
+            response = response
+                .add_submessages(messages)
+                .add_attribute("denom", denom)
+                .add_attributes(attrs);
+        }
+
+        if response.messages.len() > MAX_SWAP_MSGS {
+            return Err(ContractError::TooManySwapMsgs {
+                max: MAX_SWAP_MSGS,
+            });
+        }
+
+        self.record_swap_outputs(deps.storage, swap_outputs)?;
+
+        Ok(response.add_attribute("slippage_tolerance", cfg.slippage_tolerance.to_string()))
+    }
+
+    fn swap_exact_in(
+        &self,
+        deps: DepsMut<Q>,
+        env: Env,
+        denom_in: String,
+        amount: Option<Uint128>,
+        denom_out: String,
+        slippage: Option<Decimal>,
+        min_output: Option<Uint128>,
+    ) -> ContractResult<Response<M>> {
+        let amount = unwrap_option_amount(&deps.querier, &env.contract.address, &denom_in, amount)?;
+        let route = self.load_active_route(deps.storage, &env, &denom_in, &denom_out)?;
+
+        // an explicit minimum output bypasses the estimate, unless a slippage is also given
+        let min_out_amount = match (slippage, min_output) {
+            (None, Some(min_output)) => min_output,
+            (slippage, min_output) => {
+                let slippage = match slippage {
+                    Some(slippage) => slippage,
+                    None => {
+                        let cfg = self.config.load(deps.storage)?;
+                        self.route_slippage_tolerance(deps.storage, &cfg, &denom_in, &denom_out)?
+                    }
+                };
+                decimal_param_le_one(slippage, "slippage")?;
+                let estimated_min_out =
+                    route.min_out_amount(&env, &deps.querier, &denom_in, amount, slippage)?;
+                estimated_min_out.max(min_output.unwrap_or_default())
+            }
+        };
+        if min_out_amount.is_zero() {
+            return Err(ContractError::ZeroMinOutput {});
+        }
+
+        let swap_msg =
+            route.build_swap_msg_with_min_out(&env, &denom_in, amount, min_out_amount)?;
+
+        let out_amount = route.estimate_out_amount(&env, &deps.querier, &denom_in, amount)?;
+        self.record_outflow(deps.storage, &deps.querier, &env, &denom_in, amount)?;
+        self.record_swap_outputs(deps.storage, vec![(denom_out.clone(), out_amount)])?;
+
+        let balance_before = deps.querier.query_balance(&env.contract.address, &denom_out)?.amount;
+        self.pending_swap.save(deps.storage, &(denom_out.clone(), balance_before))?;
+
+        Ok(Response::new()
+            .add_submessage(SubMsg::reply_on_success(swap_msg, SWAP_EXACT_IN_REPLY_ID))
+            .add_attribute("action", "swap_exact_in")
+            .add_attribute("coin_in", coin(amount.u128(), &denom_in).to_string())
+            .add_attribute("denom_out", denom_out)
+            .add_attribute("min_output", min_out_amount))
+    }
+
+    fn swap_exact_out(
+        &self,
+        deps: DepsMut<Q>,
+        env: Env,
+        coin_out: Coin,
+        denom_in: String,
+        max_input: Uint128,
+        slippage: Decimal,
+    ) -> ContractResult<Response<M>> {
+        decimal_param_le_one(slippage, "slippage")?;
+
+        let swap_msg = self
+            .load_active_route(deps.storage, &env, &denom_in, &coin_out.denom)?
+            .build_swap_exact_out_msg(
+                &env,
+                &deps.querier,
+                &denom_in,
+                &coin_out,
+                max_input,
+                slippage,
+            )?;
+
+        Ok(Response::new()
+            .add_message(swap_msg)
+            .add_attribute("action", "swap_exact_out")
+            .add_attribute("denom_in", denom_in)
+            .add_attribute("coin_out", coin_out.to_string())
+            .add_attribute("max_input", max_input)
+            .add_attribute("slippage", slippage.to_string()))
+    }
+
+    fn swap_funds(
+        &self,
+        deps: DepsMut<Q>,
+        env: Env,
+        info: MessageInfo,
+        denom_out: String,
     ) -> ContractResult<Response<M>> {
         let cfg = self.config.load(deps.storage)?;
 
+        let addresses = address_provider::helpers::query_contract_addrs(
+            deps.as_ref(),
+            &cfg.address_provider,
+            vec![MarsAddressType::Incentives, MarsAddressType::RedBank],
+        )?;
+        if !addresses.values().any(|addr| *addr == info.sender) {
+            return Err(MarsError::Unauthorized {}.into());
+        }
+
+        let coin_in = match info.funds.as_slice() {
+            [coin_in] if !coin_in.amount.is_zero() => coin_in,
+            _ => return Err(ContractError::InvalidSwapFunds {}),
+        };
+
+        let slippage_tolerance =
+            self.route_slippage_tolerance(deps.storage, &cfg, &coin_in.denom, &denom_out)?;
+        let swap_msg = self
+            .load_active_route(deps.storage, &env, &coin_in.denom, &denom_out)?
+            .build_swap_msg(
+                &env,
+                &deps.querier,
+                &coin_in.denom,
+                coin_in.amount,
+                slippage_tolerance,
+            )?;
+
+        // only the output of this swap is sent back, not the revenue held in the same denom
+        let balance_before = deps.querier.query_balance(&env.contract.address, &denom_out)?.amount;
+        self.pending_return
+            .save(deps.storage, &(info.sender.to_string(), denom_out.clone(), balance_before))?;
+
+        Ok(Response::new()
+            .add_submessage(SubMsg::reply_on_success(swap_msg, SWAP_FUNDS_REPLY_ID))
+            .add_attribute("action", "swap_funds")
+            .add_attribute("coin_in", coin_in.to_string())
+            .add_attribute("denom_out", denom_out))
+    }
+
+    fn return_swapped_funds(&self, deps: DepsMut<Q>, env: Env) -> ContractResult<Response<M>> {
+        let (sender, denom_out, balance_before) = self.pending_return.load(deps.storage)?;
+        self.pending_return.remove(deps.storage);
+
+        let balance = deps.querier.query_balance(&env.contract.address, &denom_out)?.amount;
+        let amount_out = balance.checked_sub(balance_before)?;
+
+        let mut response = Response::new();
+        if !amount_out.is_zero() {
+            response = response.add_message(BankMsg::Send {
+                to_address: sender.clone(),
+                amount: vec![coin(amount_out.u128(), &denom_out)],
+            });
+        }
+
+        Ok(response
+            .add_attribute("action", "return_swapped_funds")
+            .add_attribute("to", sender)
+            .add_attribute("denom_out", denom_out)
+            .add_attribute("amount_out", amount_out))
+    }
+
+    /// Build the messages splitting the given amount of an asset between the recipients and
+    /// swapping each share into the recipient's denom. The amount swapped is deducted from the
+    /// asset's expected balance, and the estimated outputs staying in the contract are returned
+    /// to be recorded once the swaps of every asset are built.
+    fn build_swap_msgs(
+        &self,
+        deps: DepsMut<Q>,
+        env: &Env,
+        cfg: &Config,
+        denom: &str,
+        amount: Option<Uint128>,
+        send_to_fee_collector: bool,
+    ) -> ContractResult<(Vec<SubMsg<M>>, Vec<Attribute>, Vec<(String, Uint128)>)> {
         // if amount is None, swap the total balance
         let amount_to_swap =
-            unwrap_option_amount(&deps.querier, &env.contract.address, &denom, amount)?;
+            unwrap_option_amount(&deps.querier, &env.contract.address, denom, amount)?;
+
+        // split the amount to swap between the recipients
+        let weights: Vec<_> = cfg.reward_splits.iter().map(|split| split.weight).collect();
+        let amounts = split_by_weights(amount_to_swap, &weights)?;
+
+        // the fee collector module's address in the address provider is on Mars Hub, so the share
+        // can only be sent directly to a fee collector configured on this chain
+        let local_fee_collector = if send_to_fee_collector {
+            Some(cfg.local_fee_collector.clone().ok_or(ContractError::NoLocalFeeCollector {})?)
+        } else {
+            None
+        };
+        let fee_collector = RewardRecipient::Module(MarsAddressType::FeeCollector);
+        let mut forwarding = false;
+
+        // the shares swapped or forwarded, as opposed to those already in the recipient's denom
+        let mut amount_out = Uint128::zero();
 
-        // split the amount to swap between the safety fund and the fee collector
-        let amount_safety_fund = amount_to_swap * cfg.safety_tax_rate;
-        let amount_fee_collector = amount_to_swap.checked_sub(amount_safety_fund)?;
         let mut messages = vec![];
+        let mut attrs = vec![];
+        let mut swap_outputs = vec![];
+        for (split, amount) in cfg.reward_splits.iter().zip(amounts) {
+            attrs.push(attr(format!("amount_{}", split.recipient), amount));
 
-        // execute the swap to safety fund denom, if the amount to swap is non-zero,
-        // and if the denom is not already the safety fund denom
-        if !amount_safety_fund.is_zero() && denom != cfg.safety_fund_denom {
-            messages.push(
-                self.routes
-                    .load(deps.storage, (denom.clone(), cfg.safety_fund_denom))?
-                    .build_swap_msg(
-                        &env,
-                        &deps.querier,
-                        &denom,
-                        amount_safety_fund,
-                        cfg.slippage_tolerance,
-                    )?,
-            );
+            if amount.is_zero() {
+                continue;
+            }
+
+            // only the first fee collector share is forwarded, as the forwarded amount is
+            // computed from the contract's balance before and after the swap
+            let forward_to = local_fee_collector
+                .as_ref()
+                .filter(|_| !forwarding && split.recipient == fee_collector);
+
+            // if the denom is already the recipient's denom, there is nothing to swap
+            if denom == split.denom {
+                if let Some(fee_collector_addr) = forward_to {
+                    forwarding = true;
+                    amount_out = amount_out.checked_add(amount)?;
+                    messages.push(SubMsg::new(BankMsg::Send {
+                        to_address: fee_collector_addr.to_string(),
+                        amount: vec![coin(amount.u128(), denom)],
+                    }));
+                }
+                continue;
+            }
+
+            let slippage_tolerance =
+                self.route_slippage_tolerance(deps.storage, cfg, denom, &split.denom)?;
+            let route = self.load_active_route(deps.storage, env, denom, &split.denom)?;
+            let swap_msg =
+                route.build_swap_msg(env, &deps.querier, denom, amount, slippage_tolerance)?;
+            amount_out = amount_out.checked_add(amount)?;
+
+            if let Some(fee_collector_addr) = forward_to {
+                forwarding = true;
+
+                let balance_before =
+                    deps.querier.query_balance(&env.contract.address, &split.denom)?.amount;
+                self.pending_forward.save(
+                    deps.storage,
+                    &(fee_collector_addr.to_string(), split.denom.clone(), balance_before),
+                )?;
+
+                // executed first so that no other swap changes the balance before the reply
+                messages.insert(
+                    0,
+                    SubMsg::reply_on_success(swap_msg, FORWARD_TO_FEE_COLLECTOR_REPLY_ID),
+                );
+            } else {
+                // unlike the output of a forwarded swap, this output stays in the contract
+                let out_amount = route.estimate_out_amount(env, &deps.querier, denom, amount)?;
+                swap_outputs.push((split.denom.clone(), out_amount));
+                messages.push(SubMsg::new(swap_msg));
+            }
         }
 
-        // execute the swap to fee collector denom, if the amount to swap is non-zero,
-        // and if the denom is not already the fee collector denom
-        if !amount_fee_collector.is_zero() && denom != cfg.fee_collector_denom {
-            messages.push(
-                self.routes
-                    .load(deps.storage, (denom.clone(), cfg.fee_collector_denom))?
-                    .build_swap_msg(
-                        &env,
-                        &deps.querier,
-                        &denom,
-                        amount_fee_collector,
-                        cfg.slippage_tolerance,
-                    )?,
-            );
+        self.record_outflow(deps.storage, &deps.querier, env, denom, amount_out)?;
+
+        Ok((messages, attrs, swap_outputs))
+    }
+
+    fn forward_to_fee_collector(&self, deps: DepsMut<Q>, env: Env) -> ContractResult<Response<M>> {
+        let (fee_collector_addr, denom, balance_before) =
+            self.pending_forward.load(deps.storage)?;
+        self.pending_forward.remove(deps.storage);
+
+        let balance = deps.querier.query_balance(&env.contract.address, &denom)?.amount;
+        let amount = balance.checked_sub(balance_before)?;
+
+        let mut response = Response::new();
+        if !amount.is_zero() {
+            response = response.add_message(BankMsg::Send {
+                to_address: fee_collector_addr.clone(),
+                amount: vec![coin(amount.u128(), &denom)],
+            });
         }
 
-        Ok(Response::new()
-            .add_messages(messages)
-            .add_attribute("action", "swap_asset")
+        Ok(response
+            .add_attribute("action", "forward_to_fee_collector")
+            .add_attribute("to", fee_collector_addr)
             .add_attribute("denom", denom)
-            .add_attribute("amount_safety_fund", amount_safety_fund)
-            .add_attribute("amount_fee_collector", amount_fee_collector)
-            .add_attribute("slippage_tolerance", cfg.slippage_tolerance.to_string()))
+            .add_attribute("amount", amount))
+    }
+
+    fn swap_exact_in_reply(&self, deps: DepsMut<Q>, env: Env) -> ContractResult<Response<M>> {
+        let (denom_out, balance_before) = self.pending_swap.load(deps.storage)?;
+        self.pending_swap.remove(deps.storage);
+
+        let balance = deps.querier.query_balance(&env.contract.address, &denom_out)?.amount;
+        let amount_out = balance.checked_sub(balance_before)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "swap_exact_in_reply")
+            .add_attribute("denom_out", denom_out)
+            .add_attribute("amount_out", amount_out))
     }
 
     fn distribute_rewards(
         &self,
         deps: DepsMut<Q>,
         env: Env,
+        sender: Addr,
         denom: String,
         amount: Option<Uint128>,
+        timeout_timestamp: Option<Timestamp>,
     ) -> ContractResult<Response<M>> {
         let cfg = self.config.load(deps.storage)?;
 
-        let to_address = if denom == cfg.safety_fund_denom {
-            address_provider::helpers::query_module_addr(
-                deps.as_ref(),
-                &cfg.address_provider,
-                MarsAddressType::SafetyFund,
-            )?
-        } else if denom == cfg.fee_collector_denom {
-            address_provider::helpers::query_module_addr(
-                deps.as_ref(),
-                &cfg.address_provider,
-                MarsAddressType::FeeCollector,
-            )?
-        } else {
-            return Err(ContractError::AssetNotEnabledForDistribution {
-                denom,
-            });
+        let timeout = match timeout_timestamp {
+            Some(timestamp) => {
+                self.owner.assert_owner(deps.storage, &sender)?;
+                if timestamp <= env.block.time {
+                    return Err(ContractError::TimeoutNotInFuture {
+                        timeout: timestamp.seconds(),
+                        now: env.block.time.seconds(),
+                    });
+                }
+                timestamp
+            }
+            None => env.block.time.plus_seconds(cfg.timeout_seconds),
         };
 
-        let amount_to_distribute =
-            unwrap_option_amount(&deps.querier, &env.contract.address, &denom, amount)?;
-
-        let transfer_msg = CosmosMsg::Ibc(IbcMsg::Transfer {
-            channel_id: cfg.channel_id,
-            to_address: to_address.to_string(),
-            amount: Coin {
-                denom: denom.clone(),
-                amount: amount_to_distribute,
-            },
-            timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(cfg.timeout_seconds)),
-        });
+        let (amount_to_distribute, messages, attrs) = self.build_distribute_msgs(
+            deps,
+            &env,
+            &cfg,
+            &denom,
+            amount,
+            ibc_timeout(&cfg, &env.block, timeout),
+        )?;
 
         Ok(Response::new()
-            .add_message(transfer_msg)
+            .add_messages(messages)
             .add_attribute("action", "distribute_rewards")
             .add_attribute("denom", denom)
             .add_attribute("amount", amount_to_distribute)
-            .add_attribute("to", to_address))
+            .add_attributes(attrs))
+    }
+
+    fn distribute_rewards_many(
+        &self,
+        mut deps: DepsMut<Q>,
+        env: Env,
+        denoms: Vec<(String, Option<Uint128>)>,
+    ) -> ContractResult<Response<M>> {
+        let cfg = self.config.load(deps.storage)?;
+        let timeout =
+            ibc_timeout(&cfg, &env.block, env.block.time.plus_seconds(cfg.timeout_seconds));
+
+        let mut response = Response::new().add_attribute("action", "distribute_rewards_many");
+        for (denom, amount) in denoms {
+            let (amount_to_distribute, messages, attrs) = self.build_distribute_msgs(
+                deps.branch(),
+                &env,
+                &cfg,
+                &denom,
+                amount,
+                timeout.clone(),
+            )?;
+            response = response
+                .add_messages(messages)
+                .add_attribute("denom", denom)
+                .add_attribute("amount", amount_to_distribute)
+                .add_attributes(attrs);
+        }
+
+        Ok(response)
+    }
+
+    /// Build the IBC transfers sending the given amount of a reward split denom to the recipients
+    /// sharing it, and add each recipient's share to its distributed total. Return the amount
+    /// distributed along with the messages.
+    fn build_distribute_msgs(
+        &self,
+        deps: DepsMut<Q>,
+        env: &Env,
+        cfg: &Config,
+        denom: &str,
+        amount: Option<Uint128>,
+        timeout: IbcTimeout,
+    ) -> ContractResult<(Uint128, Vec<CosmosMsg<M>>, Vec<Attribute>)> {
+        let splits: Vec<_> =
+            cfg.reward_splits.iter().filter(|split| split.denom == denom).collect();
+        if splits.is_empty() {
+            return Err(ContractError::AssetNotEnabledForDistribution {
+                denom: denom.to_string(),
+            });
+        }
+
+        let amount_to_distribute =
+            unwrap_option_amount(&deps.querier, &env.contract.address, denom, amount)?;
+
+        // recipients sharing the same denom split the amount in proportion to their weights
+        let weights: Vec<_> = splits.iter().map(|split| split.weight).collect();
+        let amounts = split_by_weights(amount_to_distribute, &weights)?;
+
+        let mut messages = vec![];
+        let mut attrs = vec![];
+        for (split, amount) in splits.into_iter().zip(amounts) {
+            if amount.is_zero() {
+                continue;
+            }
+
+            let to_address =
+                query_recipient_addr(deps.as_ref(), &cfg.address_provider, &split.recipient)?;
+
+            self.distributed.update(
+                deps.storage,
+                (denom.to_string(), split.recipient.to_string()),
+                |total| -> StdResult<_> { Ok(total.unwrap_or_default().checked_add(amount)?) },
+            )?;
+
+            let amount = Coin {
+                denom: denom.to_string(),
+                amount,
+            };
+
+            // `IbcMsg::Transfer` has no memo, so a transfer carrying one is sent as a Stargate msg
+            let transfer_msg = match cfg.ibc_memo_for(&to_address) {
+                Some(memo) => MsgTransfer::new(
+                    &cfg.channel_id,
+                    &env.contract.address,
+                    &to_address,
+                    amount,
+                    &timeout,
+                    memo,
+                )
+                .into_cosmos_msg(),
+                None => CosmosMsg::Ibc(IbcMsg::Transfer {
+                    channel_id: cfg.channel_id.clone(),
+                    to_address: to_address.clone(),
+                    amount,
+                    timeout: timeout.clone(),
+                }),
+            };
+            messages.push(transfer_msg);
+            attrs.push(attr("to", to_address));
+        }
+
+        self.record_outflow(deps.storage, &deps.querier, env, denom, amount_to_distribute)?;
+
+        Ok((amount_to_distribute, messages, attrs))
+    }
+
+    /// Set the expected balance of a denom to the contract's current balance, net of the given
+    /// amount that is about to be swapped or distributed
+    fn record_outflow(
+        &self,
+        storage: &mut dyn Storage,
+        querier: &QuerierWrapper<Q>,
+        env: &Env,
+        denom: &str,
+        amount: Uint128,
+    ) -> ContractResult<()> {
+        let balance = querier.query_balance(&env.contract.address, denom)?.amount;
+        self.expected_balances.save(storage, denom.to_string(), &balance.checked_sub(amount)?)?;
+        Ok(())
+    }
+
+    /// Add the estimated outputs of swaps to the expected balances of their output denoms. The
+    /// outputs are only received once the swaps are executed, so they're recorded after the
+    /// outflows of the same call, which reset the expected balance to the current one.
+    fn record_swap_outputs(
+        &self,
+        storage: &mut dyn Storage,
+        swap_outputs: Vec<(String, Uint128)>,
+    ) -> StdResult<()> {
+        for (denom, amount) in swap_outputs {
+            self.expected_balances.update(storage, denom, |expected| -> StdResult<_> {
+                Ok(expected.unwrap_or_default().checked_add(amount)?)
+            })?;
+        }
+        Ok(())
+    }
+
+    fn distribute_cw20(
+        &self,
+        deps: DepsMut<Q>,
+        contract_addr: String,
+        amount: Uint128,
+    ) -> ContractResult<Response<M>> {
+        let cfg = self.config.load(deps.storage)?;
+
+        let token = deps.api.addr_validate(&contract_addr)?;
+        let asset = AssetInfo::Cw20 {
+            contract_addr: token.to_string(),
+        };
+
+        // the token can't be swapped into the split denoms, so it's split between all recipients
+        let weights: Vec<_> = cfg.reward_splits.iter().map(|split| split.weight).collect();
+        let amounts = split_by_weights(amount, &weights)?;
+
+        let mut response = Response::new()
+            .add_attribute("action", "distribute_cw20")
+            .add_attribute("token", &token)
+            .add_attribute("amount", amount);
+        for (split, amount) in cfg.reward_splits.iter().zip(amounts) {
+            if amount.is_zero() {
+                continue;
+            }
+
+            let local_recipient =
+                split.local_recipient.clone().ok_or_else(|| ContractError::NoLocalRecipient {
+                    recipient: split.recipient.to_string(),
+                })?;
+
+            self.distributed.update(
+                deps.storage,
+                (asset.to_string(), split.recipient.to_string()),
+                |total| -> StdResult<_> { Ok(total.unwrap_or_default().checked_add(amount)?) },
+            )?;
+
+            response = response
+                .add_message(WasmMsg::Execute {
+                    contract_addr: token.to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: local_recipient.clone(),
+                        amount,
+                    })?,
+                    funds: vec![],
+                })
+                .add_attribute("to", local_recipient);
+        }
+
+        Ok(response)
     }
 
     fn query_config(&self, deps: Deps<Q>) -> StdResult<ConfigResponse> {
@@ -365,12 +1285,15 @@ where
             owner: owner_state.owner,
             proposed_new_owner: owner_state.proposed,
             address_provider: cfg.address_provider.into(),
-            safety_tax_rate: cfg.safety_tax_rate,
-            safety_fund_denom: cfg.safety_fund_denom,
-            fee_collector_denom: cfg.fee_collector_denom,
+            reward_splits: cfg.reward_splits,
             channel_id: cfg.channel_id,
             timeout_seconds: cfg.timeout_seconds,
+            timeout_blocks: cfg.timeout_blocks,
+            counterparty_height: cfg.counterparty_height,
             slippage_tolerance: cfg.slippage_tolerance,
+            ibc_memo: cfg.ibc_memo,
+            paused: cfg.paused,
+            local_fee_collector: cfg.local_fee_collector.map(Into::into),
         })
     }
 
@@ -380,10 +1303,40 @@ where
         denom_in: String,
         denom_out: String,
     ) -> StdResult<RouteResponse<R>> {
+        let key = (denom_in.clone(), denom_out.clone());
         Ok(RouteResponse {
-            denom_in: denom_in.clone(),
-            denom_out: denom_out.clone(),
-            route: self.routes.load(deps.storage, (denom_in, denom_out))?,
+            route: self.routes.load(deps.storage, key.clone())?,
+            slippage_override: self.route_slippages.may_load(deps.storage, key)?,
+            denom_in,
+            denom_out,
+        })
+    }
+
+    fn query_route_exists(
+        &self,
+        deps: Deps<Q>,
+        env: Env,
+        denom_in: String,
+        denom_out: String,
+    ) -> StdResult<RouteExistsResponse> {
+        if !self.routes.has(deps.storage, (denom_in.clone(), denom_out.clone())) {
+            return Ok(RouteExistsResponse {
+                exists: false,
+                usable: false,
+                reason: Some(format!("no route set for {denom_in} -> {denom_out}")),
+            });
+        }
+
+        // estimating the output of a minimal swap fails if any price record is missing
+        let usable =
+            self.load_active_route(deps.storage, &env, &denom_in, &denom_out).and_then(|route| {
+                route.estimate_out_amount(&env, &deps.querier, &denom_in, Uint128::one())
+            });
+
+        Ok(RouteExistsResponse {
+            exists: true,
+            usable: usable.is_ok(),
+            reason: usable.err().map(|err| err.to_string()),
         })
     }
 
@@ -402,6 +1355,7 @@ where
             .map(|item| {
                 let (k, v) = item?;
                 Ok(RouteResponse {
+                    slippage_override: self.route_slippages.may_load(deps.storage, k.clone())?,
                     denom_in: k.0,
                     denom_out: k.1,
                     route: v,
@@ -409,4 +1363,301 @@ where
             })
             .collect()
     }
+
+    fn query_distribution_stats(
+        &self,
+        deps: Deps<Q>,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<DistributionStatsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        self.distributed
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let ((denom, recipient), amount) = item?;
+                Ok(DistributedTotalResponse {
+                    denom,
+                    recipient,
+                    amount,
+                })
+            })
+            .collect()
+    }
+
+    fn query_balance_reconciliation(
+        &self,
+        deps: Deps<Q>,
+        env: Env,
+        denom: String,
+    ) -> StdResult<BalanceReconciliationResponse> {
+        let balance = deps.querier.query_balance(&env.contract.address, &denom)?.amount;
+        let expected_balance =
+            self.expected_balances.may_load(deps.storage, denom.clone())?.unwrap_or_default();
+
+        Ok(BalanceReconciliationResponse {
+            denom,
+            balance,
+            expected_balance,
+            surplus: balance.saturating_sub(expected_balance),
+            deficit: expected_balance.saturating_sub(balance),
+        })
+    }
+
+    fn query_pending_incentive_rewards(&self, deps: Deps<Q>, env: Env) -> StdResult<Uint128> {
+        let cfg = self.config.load(deps.storage)?;
+
+        let incentives_addr = address_provider::helpers::query_contract_addr(
+            deps,
+            &cfg.address_provider,
+            MarsAddressType::Incentives,
+        )?;
+
+        deps.querier.query_wasm_smart(
+            incentives_addr,
+            &incentives::QueryMsg::UserUnclaimedRewards {
+                user: env.contract.address.into(),
+            },
+        )
+    }
+
+    fn query_simulate_swap(
+        &self,
+        deps: Deps<Q>,
+        env: Env,
+        denom_in: String,
+        denom_out: String,
+        amount: Uint128,
+    ) -> ContractResult<SimulateSwapResponse> {
+        let amount_out = self
+            .load_active_route(deps.storage, &env, &denom_in, &denom_out)?
+            .estimate_out_amount(&env, &deps.querier, &denom_in, amount)?;
+
+        Ok(SimulateSwapResponse {
+            amount_out,
+        })
+    }
+
+    fn query_min_output(
+        &self,
+        deps: Deps<Q>,
+        env: Env,
+        coin_in: Coin,
+        denom_out: String,
+        slippage: Decimal,
+    ) -> ContractResult<Uint128> {
+        decimal_param_le_one(slippage, "slippage")?;
+
+        self.load_active_route(deps.storage, &env, &coin_in.denom, &denom_out)?.min_out_amount(
+            &env,
+            &deps.querier,
+            &coin_in.denom,
+            coin_in.amount,
+            slippage,
+        )
+    }
+
+    fn query_total_pending_value(
+        &self,
+        deps: Deps<Q>,
+        env: Env,
+        denoms: Vec<String>,
+    ) -> ContractResult<TotalPendingValueResponse> {
+        let cfg = self.config.load(deps.storage)?;
+
+        let oracle_addr = address_provider::helpers::query_contract_addr(
+            deps,
+            &cfg.address_provider,
+            MarsAddressType::Oracle,
+        )?;
+
+        let mut total_value = Uint128::zero();
+        let mut balances = vec![];
+        let mut unpriced = vec![];
+        for denom in denoms {
+            let amount = deps.querier.query_balance(&env.contract.address, &denom)?.amount;
+
+            let price_res = deps.querier.query_wasm_smart::<oracle::PriceResponse>(
+                &oracle_addr,
+                &oracle::QueryMsg::Price {
+                    denom: denom.clone(),
+                },
+            );
+            match price_res {
+                Ok(res) => {
+                    let value = amount * res.price;
+                    total_value = total_value.checked_add(value)?;
+                    balances.push(PendingBalanceValue {
+                        denom,
+                        amount,
+                        price: res.price,
+                        value,
+                    });
+                }
+                Err(_) => unpriced.push(coin(amount.u128(), denom)),
+            }
+        }
+
+        Ok(TotalPendingValueResponse {
+            total_value,
+            balances,
+            unpriced,
+        })
+    }
+
+    fn query_projected_distribution(
+        &self,
+        deps: Deps<Q>,
+        env: Env,
+        denoms: Vec<String>,
+    ) -> ContractResult<Vec<(RewardRecipient, Coin)>> {
+        let cfg = self.config.load(deps.storage)?;
+
+        let weights: Vec<_> = cfg.reward_splits.iter().map(|split| split.weight).collect();
+
+        let mut totals = vec![Uint128::zero(); cfg.reward_splits.len()];
+        for denom in denoms {
+            let balance = deps.querier.query_balance(&env.contract.address, &denom)?.amount;
+
+            // split the balance between the recipients, same as `swap_asset`
+            let amounts = split_by_weights(balance, &weights)?;
+            for ((split, amount), total) in cfg.reward_splits.iter().zip(amounts).zip(&mut totals) {
+                if amount.is_zero() {
+                    continue;
+                }
+
+                let amount_out = if denom == split.denom {
+                    amount
+                } else {
+                    self.load_active_route(deps.storage, &env, &denom, &split.denom)?
+                        .estimate_out_amount(&env, &deps.querier, &denom, amount)?
+                };
+                *total = total.checked_add(amount_out)?;
+            }
+        }
+
+        Ok(cfg
+            .reward_splits
+            .into_iter()
+            .zip(totals)
+            .map(|(split, total)| (split.recipient, coin(total.u128(), split.denom)))
+            .collect())
+    }
+
+    fn query_ownership_status(
+        &self,
+        deps: Deps<Q>,
+        env: Env,
+    ) -> StdResult<Vec<OwnershipStatusResponse>> {
+        let cfg = self.config.load(deps.storage)?;
+
+        let mut statuses = vec![OwnershipStatusResponse {
+            contract: "address_provider".to_string(),
+            address: Some(cfg.address_provider.to_string()),
+            ownership: query_contract_ownership(deps, &cfg.address_provider),
+        }];
+
+        // each address is resolved on its own, so one that isn't set doesn't fail the whole query
+        for address_type in
+            [MarsAddressType::RedBank, MarsAddressType::Incentives, MarsAddressType::Oracle]
+        {
+            let addr = address_provider::helpers::query_contract_addr(
+                deps,
+                &cfg.address_provider,
+                address_type,
+            )
+            .ok();
+            statuses.push(OwnershipStatusResponse {
+                contract: address_type.to_string(),
+                address: addr.as_ref().map(Addr::to_string),
+                ownership: addr.and_then(|addr| query_contract_ownership(deps, &addr)),
+            });
+        }
+
+        // the rewards collector's own owner is read from storage rather than queried
+        let owner_state = self.owner.query(deps.storage)?;
+        statuses.push(OwnershipStatusResponse {
+            contract: MarsAddressType::RewardsCollector.to_string(),
+            address: Some(env.contract.address.into()),
+            ownership: Some(ContractOwnership {
+                owner: owner_state.owner,
+                proposed_new_owner: owner_state.proposed,
+            }),
+        });
+
+        Ok(statuses)
+    }
+
+    fn query_residual_after_cycle(
+        &self,
+        deps: Deps<Q>,
+        env: Env,
+        denom: String,
+    ) -> ContractResult<ResidualAfterCycleResponse> {
+        let cfg = self.config.load(deps.storage)?;
+
+        let mut balances = HashMap::new();
+        for d in iter::once(&denom).chain(cfg.reward_splits.iter().map(|split| &split.denom)) {
+            if !balances.contains_key(d) {
+                let balance = deps.querier.query_balance(&env.contract.address, d)?.amount;
+                balances.insert(d.clone(), balance);
+            }
+        }
+
+        // split the balance between the recipients, same as `swap_asset`
+        let weights: Vec<_> = cfg.reward_splits.iter().map(|split| split.weight).collect();
+        let amounts = split_by_weights(balances[&denom], &weights)?;
+
+        // simulate the swaps, crediting the estimated output amounts to the target denoms
+        for (split, amount) in cfg.reward_splits.iter().zip(amounts) {
+            if amount.is_zero() || denom == split.denom {
+                continue;
+            }
+
+            let amount_out = self
+                .load_active_route(deps.storage, &env, &denom, &split.denom)?
+                .estimate_out_amount(&env, &deps.querier, &denom, amount)?;
+
+            let balance_in = balances[&denom].checked_sub(amount)?;
+            balances.insert(denom.clone(), balance_in);
+            let balance_out = balances[&split.denom].checked_add(amount_out)?;
+            balances.insert(split.denom.clone(), balance_out);
+        }
+
+        // simulate the distributions, each of which sends out the full balance of the target denom
+        // to the recipients sharing it, same as `distribute_rewards`
+        let mut distributed = vec![Uint128::zero(); cfg.reward_splits.len()];
+        for split in &cfg.reward_splits {
+            // a denom shared by several recipients is only distributed once
+            let balance = balances.insert(split.denom.clone(), Uint128::zero()).unwrap_or_default();
+            if balance.is_zero() {
+                continue;
+            }
+
+            let indexes: Vec<_> = cfg
+                .reward_splits
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.denom == split.denom)
+                .map(|(i, _)| i)
+                .collect();
+            let weights: Vec<_> = indexes.iter().map(|i| cfg.reward_splits[*i].weight).collect();
+            for (i, amount) in indexes.into_iter().zip(split_by_weights(balance, &weights)?) {
+                distributed[i] = amount;
+            }
+        }
+
+        Ok(ResidualAfterCycleResponse {
+            denom_residual: balances[&denom],
+            distributed: cfg
+                .reward_splits
+                .into_iter()
+                .zip(distributed)
+                .map(|(split, amount)| (split.recipient, coin(amount.u128(), split.denom)))
+                .collect(),
+            denom,
+        })
+    }
 }
@@ -15,6 +15,9 @@ pub enum ContractError {
     #[error("{0}")]
     Validation(#[from] ValidationError),
 
+    #[error("{0}")]
+    Version(#[from] cw2::VersionError),
+
     #[error("{0}")]
     Owner(#[from] OwnerError),
 
@@ -39,6 +42,58 @@ pub enum ContractError {
     InvalidRoute {
         reason: String,
     },
+
+    #[error("No route found for {denom_in} -> {denom_out}")]
+    RouteNotFound {
+        denom_in: String,
+        denom_out: String,
+    },
+
+    #[error("Too many swap messages, at most {max} can be executed at once")]
+    TooManySwapMsgs {
+        max: usize,
+    },
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId {
+        id: u64,
+    },
+
+    #[error("IBC timeout {timeout} is not after the current block time {now}")]
+    TimeoutNotInFuture {
+        timeout: u64,
+        now: u64,
+    },
+
+    #[error("Route {denom_in} -> {denom_out} is not active at time {time}")]
+    RouteNotActive {
+        denom_in: String,
+        denom_out: String,
+        time: u64,
+    },
+
+    #[error("Estimated input {required} is larger than the maximum input {max_input}")]
+    MaxInputExceeded {
+        required: Uint128,
+        max_input: Uint128,
+    },
+
+    #[error("Minimum output of the swap can't be zero")]
+    ZeroMinOutput {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("No fee collector is configured on this chain")]
+    NoLocalFeeCollector {},
+
+    #[error("Exactly one coin must be sent to be swapped")]
+    InvalidSwapFunds {},
+
+    #[error("Recipient {recipient} has no local recipient to transfer CW20 rewards to")]
+    NoLocalRecipient {
+        recipient: String,
+    },
 }
 
 pub type ContractResult<T> = Result<T, ContractError>;
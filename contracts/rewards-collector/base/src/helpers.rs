@@ -1,4 +1,12 @@
-use cosmwasm_std::{Addr, QuerierWrapper, Uint128};
+use cosmwasm_std::{
+    Addr, Api, BlockInfo, CustomQuery, Decimal, Deps, Fraction, IbcTimeout, IbcTimeoutBlock,
+    QuerierWrapper, StdResult, Timestamp, Uint128,
+};
+use mars_red_bank_types::{
+    address_provider,
+    rewards_collector::{Config, ContractOwnership, RewardRecipient, RewardSplit},
+};
+use serde::{Deserialize, Serialize};
 
 use crate::{ContractError, ContractResult};
 
@@ -30,6 +38,86 @@ pub(crate) fn stringify_option_amount(amount: Option<Uint128>) -> String {
     amount.map_or_else(|| "undefined".to_string(), |amount| amount.to_string())
 }
 
+/// Convert an optional u64 value to string. If the value is undefined, return `undefined`
+pub(crate) fn stringify_option_u64(value: Option<u64>) -> String {
+    value.map_or_else(|| "undefined".to_string(), |value| value.to_string())
+}
+
+/// The `Transfer` message of the CW20 standard
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Cw20ExecuteMsg {
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+    },
+}
+
+/// Validate the local recipients of the reward splits, as they're stored unchecked
+pub(crate) fn validate_local_recipients(api: &dyn Api, splits: &[RewardSplit]) -> StdResult<()> {
+    for split in splits {
+        if let Some(local_recipient) = &split.local_recipient {
+            api.addr_validate(local_recipient)?;
+        }
+    }
+    Ok(())
+}
+
+/// Build the timeout of an IBC transfer of rewards: the given timestamp, combined with a height
+/// `timeout_blocks` past the counterparty chain's current one if the config sets it.
+///
+/// The height is compared against the counterparty chain's, so it's estimated from this chain's
+/// height and the configured offset between the two.
+pub(crate) fn ibc_timeout(cfg: &Config, block: &BlockInfo, timestamp: Timestamp) -> IbcTimeout {
+    match (cfg.timeout_blocks, &cfg.counterparty_height) {
+        (Some(blocks), Some(counterparty)) => {
+            let timeout_block = IbcTimeoutBlock {
+                revision: counterparty.revision,
+                height: block.height.saturating_add_signed(counterparty.height_offset) + blocks,
+            };
+            IbcTimeout::with_both(timeout_block, timestamp)
+        }
+        _ => IbcTimeout::with_timestamp(timestamp),
+    }
+}
+
+/// Split an amount in proportion to the given weights, which don't need to sum to one.
+/// The rounding remainder goes to the last share, so that the shares add up to the full amount.
+pub(crate) fn split_by_weights(
+    amount: Uint128,
+    weights: &[Decimal],
+) -> ContractResult<Vec<Uint128>> {
+    let total_weight = weights.iter().fold(Decimal::zero(), |acc, weight| acc + *weight);
+
+    let mut remaining = amount;
+    let mut shares = Vec::with_capacity(weights.len());
+    for (i, weight) in weights.iter().enumerate() {
+        let share = if i == weights.len() - 1 {
+            remaining
+        } else {
+            amount.checked_multiply_ratio(weight.numerator(), total_weight.numerator())?
+        };
+        remaining = remaining.checked_sub(share)?;
+        shares.push(share);
+    }
+
+    Ok(shares)
+}
+
+/// Resolve the Mars Hub address of a reward recipient
+pub(crate) fn query_recipient_addr(
+    deps: Deps<impl CustomQuery>,
+    address_provider_addr: &Addr,
+    recipient: &RewardRecipient,
+) -> StdResult<String> {
+    match recipient {
+        RewardRecipient::Module(address_type) => {
+            address_provider::helpers::query_module_addr(deps, address_provider_addr, *address_type)
+        }
+        RewardRecipient::Addr(addr) => Ok(addr.clone()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::{
@@ -79,9 +167,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn splitting_by_weights() {
+        let weights = [Decimal::percent(25), Decimal::percent(25), Decimal::percent(50)];
+        assert_eq!(
+            split_by_weights(Uint128::new(1001), &weights),
+            Ok(vec![Uint128::new(250), Uint128::new(250), Uint128::new(501)])
+        );
+
+        // weights are relative to their sum
+        let weights = [Decimal::percent(10), Decimal::percent(30)];
+        assert_eq!(
+            split_by_weights(Uint128::new(1000), &weights),
+            Ok(vec![Uint128::new(250), Uint128::new(750)])
+        );
+    }
+
     #[test]
     fn stringifying_option_amount() {
         assert_eq!(stringify_option_amount(Some(Uint128::new(42069))), "42069".to_string());
         assert_eq!(stringify_option_amount(None), "undefined".to_string());
     }
 }
+
+/// The `config` query exposed by every Mars contract
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ConfigQuery {
+    Config {},
+}
+
+/// The owner fields every Mars contract includes in its config response. Other fields are ignored,
+/// so the same struct can parse the config of any contract.
+#[derive(Deserialize)]
+struct OwnerFields {
+    owner: Option<String>,
+    proposed_new_owner: Option<String>,
+}
+
+/// Query the current and proposed owner of a Mars contract. Return `None` if the query fails, e.g.
+/// if the contract's config doesn't have the owner fields.
+pub(crate) fn query_contract_ownership(
+    deps: Deps<impl CustomQuery>,
+    contract_addr: &Addr,
+) -> Option<ContractOwnership> {
+    deps.querier.query_wasm_smart::<OwnerFields>(contract_addr, &ConfigQuery::Config {}).ok().map(
+        |fields| ContractOwnership {
+            owner: fields.owner,
+            proposed_new_owner: fields.proposed_new_owner,
+        },
+    )
+}
@@ -0,0 +1,78 @@
+use cosmwasm_std::{Addr, Binary, Coin, CosmosMsg, IbcTimeout};
+use prost::Message;
+
+/// ICS-20 `MsgTransfer` of ibc-go, which unlike `IbcMsg::Transfer` can carry a memo
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgTransfer {
+    #[prost(string, tag = "1")]
+    pub source_port: String,
+    #[prost(string, tag = "2")]
+    pub source_channel: String,
+    #[prost(message, optional, tag = "3")]
+    pub token: Option<ProtoCoin>,
+    #[prost(string, tag = "4")]
+    pub sender: String,
+    #[prost(string, tag = "5")]
+    pub receiver: String,
+    /// Timeout height on the destination chain; zero if disabled
+    #[prost(message, optional, tag = "6")]
+    pub timeout_height: Option<Height>,
+    /// Timeout in nanoseconds since the unix epoch; zero if disabled
+    #[prost(uint64, tag = "7")]
+    pub timeout_timestamp: u64,
+    #[prost(string, tag = "8")]
+    pub memo: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoCoin {
+    #[prost(string, tag = "1")]
+    pub denom: String,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Height {
+    #[prost(uint64, tag = "1")]
+    pub revision_number: u64,
+    #[prost(uint64, tag = "2")]
+    pub revision_height: u64,
+}
+
+impl MsgTransfer {
+    pub const TYPE_URL: &'static str = "/ibc.applications.transfer.v1.MsgTransfer";
+
+    pub fn new(
+        channel_id: &str,
+        sender: &Addr,
+        receiver: &str,
+        amount: Coin,
+        timeout: &IbcTimeout,
+        memo: String,
+    ) -> Self {
+        Self {
+            source_port: "transfer".to_string(),
+            source_channel: channel_id.to_string(),
+            token: Some(ProtoCoin {
+                denom: amount.denom,
+                amount: amount.amount.to_string(),
+            }),
+            sender: sender.to_string(),
+            receiver: receiver.to_string(),
+            timeout_height: timeout.block().map(|block| Height {
+                revision_number: block.revision,
+                revision_height: block.height,
+            }),
+            timeout_timestamp: timeout.timestamp().map(|time| time.nanos()).unwrap_or_default(),
+            memo,
+        }
+    }
+
+    pub fn into_cosmos_msg<M>(self) -> CosmosMsg<M> {
+        CosmosMsg::Stargate {
+            type_url: Self::TYPE_URL.to_string(),
+            value: Binary(self.encode_to_vec()),
+        }
+    }
+}
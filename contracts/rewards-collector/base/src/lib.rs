@@ -1,8 +1,10 @@
 mod contract;
 mod error;
 mod helpers;
+mod ibc;
 mod traits;
 
 pub use contract::*;
 pub use error::*;
+pub use ibc::*;
 pub use traits::*;
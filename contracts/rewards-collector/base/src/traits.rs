@@ -1,6 +1,8 @@
 use std::fmt::{Debug, Display};
 
-use cosmwasm_std::{CosmosMsg, CustomMsg, CustomQuery, Decimal, Env, QuerierWrapper, Uint128};
+use cosmwasm_std::{
+    Coin, CosmosMsg, CustomMsg, CustomQuery, Decimal, Env, QuerierWrapper, Uint128,
+};
 use schemars::JsonSchema;
 use serde::{de::DeserializeOwned, Serialize};
 
@@ -29,4 +31,51 @@ where
         amount: Uint128,
         slippage_tolerance: Decimal,
     ) -> ContractResult<CosmosMsg<M>>;
+
+    /// Build a message for executing the trade, given an input denom and amount, enforcing the
+    /// given minimum output amount as is
+    fn build_swap_msg_with_min_out(
+        &self,
+        env: &Env,
+        denom_in: &str,
+        amount: Uint128,
+        min_out_amount: Uint128,
+    ) -> ContractResult<CosmosMsg<M>>;
+
+    /// Build a message for executing a trade that outputs exactly the given coin, spending at most
+    /// the estimated input amount plus slippage tolerance, capped to `max_input`.
+    /// The route's final output denom must be the denom of `coin_out`.
+    fn build_swap_exact_out_msg(
+        &self,
+        env: &Env,
+        querier: &QuerierWrapper<Q>,
+        denom_in: &str,
+        coin_out: &Coin,
+        max_input: Uint128,
+        slippage_tolerance: Decimal,
+    ) -> ContractResult<CosmosMsg<M>>;
+
+    /// Estimate the amount of output denom received from trading a given input denom and amount,
+    /// before applying slippage tolerance
+    fn estimate_out_amount(
+        &self,
+        env: &Env,
+        querier: &QuerierWrapper<Q>,
+        denom_in: &str,
+        amount: Uint128,
+    ) -> ContractResult<Uint128>;
+
+    /// Compute the minimum output amount enforced by the message `build_swap_msg` returns, i.e. the
+    /// estimated output amount net of slippage tolerance
+    fn min_out_amount(
+        &self,
+        env: &Env,
+        querier: &QuerierWrapper<Q>,
+        denom_in: &str,
+        amount: Uint128,
+        slippage_tolerance: Decimal,
+    ) -> ContractResult<Uint128> {
+        let out_amount = self.estimate_out_amount(env, querier, denom_in, amount)?;
+        Ok((Decimal::one() - slippage_tolerance) * out_amount)
+    }
 }
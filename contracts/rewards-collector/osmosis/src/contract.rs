@@ -12,12 +12,12 @@ pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(not(feature = "library"))]
 pub mod entry {
-    use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+    use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response};
     use mars_red_bank_types::rewards_collector::{InstantiateMsg, QueryMsg};
     use mars_rewards_collector_base::ContractResult;
 
     use super::*;
-    use crate::msg::ExecuteMsg;
+    use crate::{migrations, msg::ExecuteMsg};
 
     #[entry_point]
     pub fn instantiate(
@@ -41,7 +41,17 @@ pub mod entry {
     }
 
     #[entry_point]
-    pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-        OsmosisCollector::default().query(deps, msg)
+    pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> ContractResult<Response> {
+        OsmosisCollector::default().reply(deps, env, reply)
+    }
+
+    #[entry_point]
+    pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> ContractResult<Binary> {
+        OsmosisCollector::default().query(deps, env, msg)
+    }
+
+    #[entry_point]
+    pub fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> ContractResult<Response> {
+        migrations::v1_0_1::migrate(deps)
     }
 }
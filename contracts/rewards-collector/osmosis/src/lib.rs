@@ -1,5 +1,6 @@
 pub mod contract;
 mod helpers;
+mod migrations;
 pub mod msg;
 pub mod route;
 
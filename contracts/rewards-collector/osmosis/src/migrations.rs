@@ -0,0 +1,175 @@
+/// Migration logic for Rewards Collector contract with version: 1.0.1
+pub mod v1_0_1 {
+    use cosmwasm_std::{Decimal, DepsMut, Response};
+    use mars_red_bank_types::{
+        address_provider::MarsAddressType,
+        rewards_collector::{Config, RewardRecipient, RewardSplit},
+    };
+    use mars_rewards_collector_base::ContractResult;
+
+    use crate::contract::{OsmosisCollector, CONTRACT_NAME, CONTRACT_VERSION};
+
+    const FROM_VERSION: &str = "1.0.1";
+
+    pub fn migrate(deps: DepsMut) -> ContractResult<Response> {
+        // make sure we're migrating the correct contract and from the correct version
+        cw2::assert_contract_version(deps.as_ref().storage, CONTRACT_NAME, FROM_VERSION)?;
+
+        // map the old safety fund / fee collector split to the new reward splits, leaving out
+        // a recipient whose share is zero
+        let old_config = old_state::CONFIG.load(deps.storage)?;
+        let reward_splits = [
+            (MarsAddressType::SafetyFund, old_config.safety_fund_denom, old_config.safety_tax_rate),
+            (
+                MarsAddressType::FeeCollector,
+                old_config.fee_collector_denom,
+                Decimal::one() - old_config.safety_tax_rate,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, _, weight)| !weight.is_zero())
+        .map(|(address_type, denom, weight)| RewardSplit {
+            recipient: RewardRecipient::Module(address_type),
+            denom,
+            weight,
+            local_recipient: None,
+        })
+        .collect();
+
+        let config = Config {
+            address_provider: old_config.address_provider,
+            reward_splits,
+            channel_id: old_config.channel_id,
+            timeout_seconds: old_config.timeout_seconds,
+            timeout_blocks: None,
+            counterparty_height: None,
+            slippage_tolerance: old_config.slippage_tolerance,
+            ibc_memo: None,
+            paused: false,
+            local_fee_collector: None,
+        };
+        config.validate()?;
+
+        // the new config is stored under the same key, replacing the old one
+        OsmosisCollector::default().config.save(deps.storage, &config)?;
+
+        // update contract version
+        cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", FROM_VERSION)
+            .add_attribute("to_version", CONTRACT_VERSION))
+    }
+
+    pub mod old_state {
+        use cosmwasm_schema::cw_serde;
+        use cosmwasm_std::{Addr, Decimal};
+        use cw_storage_plus::Item;
+
+        pub const CONFIG: Item<Config> = Item::new("config");
+
+        /// Config with the revenue split between the safety fund and the fee collector only
+        #[cw_serde]
+        pub struct Config {
+            pub address_provider: Addr,
+            pub safety_tax_rate: Decimal,
+            pub safety_fund_denom: String,
+            pub fee_collector_denom: String,
+            pub channel_id: String,
+            pub timeout_seconds: u64,
+            pub slippage_tolerance: Decimal,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use cosmwasm_std::{attr, testing::mock_dependencies, Addr};
+
+        use super::*;
+
+        fn old_config(safety_tax_rate: Decimal) -> old_state::Config {
+            old_state::Config {
+                address_provider: Addr::unchecked("address_provider"),
+                safety_tax_rate,
+                safety_fund_denom: "uusdc".to_string(),
+                fee_collector_denom: "umars".to_string(),
+                channel_id: "channel-69".to_string(),
+                timeout_seconds: 300,
+                slippage_tolerance: Decimal::percent(3),
+            }
+        }
+
+        #[test]
+        fn migrating_two_target_config() {
+            let mut deps = mock_dependencies();
+
+            cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, FROM_VERSION).unwrap();
+            old_state::CONFIG
+                .save(deps.as_mut().storage, &old_config(Decimal::percent(25)))
+                .unwrap();
+
+            let res = migrate(deps.as_mut()).unwrap();
+            assert_eq!(res.messages, vec![]);
+            assert_eq!(
+                res.attributes,
+                vec![
+                    attr("action", "migrate"),
+                    attr("from_version", "1.0.1"),
+                    attr("to_version", "1.1.0")
+                ]
+            );
+
+            let config = OsmosisCollector::default().config.load(&deps.storage).unwrap();
+            assert_eq!(
+                config,
+                Config {
+                    address_provider: Addr::unchecked("address_provider"),
+                    reward_splits: vec![
+                        RewardSplit {
+                            recipient: RewardRecipient::Module(MarsAddressType::SafetyFund),
+                            denom: "uusdc".to_string(),
+                            weight: Decimal::percent(25),
+                            local_recipient: None,
+                        },
+                        RewardSplit {
+                            recipient: RewardRecipient::Module(MarsAddressType::FeeCollector),
+                            denom: "umars".to_string(),
+                            weight: Decimal::percent(75),
+                            local_recipient: None,
+                        },
+                    ],
+                    channel_id: "channel-69".to_string(),
+                    timeout_seconds: 300,
+                    timeout_blocks: None,
+                    counterparty_height: None,
+                    slippage_tolerance: Decimal::percent(3),
+                    ibc_memo: None,
+                    paused: false,
+                    local_fee_collector: None,
+                }
+            );
+        }
+
+        #[test]
+        fn migrating_config_without_safety_tax() {
+            let mut deps = mock_dependencies();
+
+            cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, FROM_VERSION).unwrap();
+            old_state::CONFIG.save(deps.as_mut().storage, &old_config(Decimal::zero())).unwrap();
+
+            migrate(deps.as_mut()).unwrap();
+
+            let config = OsmosisCollector::default().config.load(&deps.storage).unwrap();
+            assert_eq!(
+                config.reward_splits,
+                vec![RewardSplit {
+                    recipient: RewardRecipient::Module(MarsAddressType::FeeCollector),
+                    denom: "umars".to_string(),
+                    weight: Decimal::one(),
+                    local_recipient: None,
+                }]
+            );
+        }
+    }
+}
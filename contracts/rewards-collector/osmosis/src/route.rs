@@ -1,13 +1,18 @@
-use std::fmt;
+use std::{cmp::min, collections::HashSet, fmt};
 
-use cosmwasm_std::{BlockInfo, CosmosMsg, Decimal, Empty, Env, Fraction, QuerierWrapper, Uint128};
+use cosmwasm_std::{
+    BlockInfo, Coin as CwCoin, CosmosMsg, Decimal, Empty, Env, Fraction, QuerierWrapper, Uint128,
+};
 use mars_osmosis::helpers::{has_denom, query_arithmetic_twap_price, query_pool};
 use mars_rewards_collector_base::{ContractError, ContractResult, Route};
 use osmosis_std::types::{
     cosmos::base::v1beta1::Coin,
     osmosis::{
-        gamm::v1beta1::MsgSwapExactAmountIn,
-        poolmanager::v1beta1::SwapAmountInRoute as OsmosisSwapAmountInRoute,
+        gamm::v1beta1::{MsgSwapExactAmountIn, MsgSwapExactAmountOut},
+        poolmanager::v1beta1::{
+            SwapAmountInRoute as OsmosisSwapAmountInRoute,
+            SwapAmountOutRoute as OsmosisSwapAmountOutRoute,
+        },
     },
 };
 use schemars::JsonSchema;
@@ -74,8 +79,10 @@ impl Route<Empty, Empty> for OsmosisRoute {
         // for each step:
         // - the pool must contain the input and output denoms
         // - the output denom must not be the same as the input denom of a previous step (i.e. the route must not contain a loop)
+        // - the pool must not be used by a previous step, which only pays its swap fee twice
         let mut prev_denom_out = denom_in;
         let mut seen_denoms = hashset(&[denom_in]);
+        let mut seen_pools = HashSet::new();
         for (i, step) in steps.iter().enumerate() {
             let pool = query_pool(querier, step.pool_id)?;
 
@@ -110,6 +117,12 @@ impl Route<Empty, Empty> for OsmosisRoute {
                 });
             }
 
+            if !seen_pools.insert(step.pool_id) {
+                return Err(ContractError::InvalidRoute {
+                    reason: format!("step {}: pool {} is used twice", i + 1, step.pool_id),
+                });
+            }
+
             prev_denom_out = &step.token_out_denom;
             seen_denoms.insert(&step.token_out_denom);
         }
@@ -134,6 +147,24 @@ impl Route<Empty, Empty> for OsmosisRoute {
         denom_in: &str,
         amount: Uint128,
         slippage_tolerance: Decimal,
+    ) -> ContractResult<CosmosMsg> {
+        self.0.first().ok_or(ContractError::InvalidRoute {
+            reason: "the route must contain at least one step".to_string(),
+        })?;
+
+        let min_out_amount =
+            self.min_out_amount(env, querier, denom_in, amount, slippage_tolerance)?;
+
+        self.build_swap_msg_with_min_out(env, denom_in, amount, min_out_amount)
+    }
+
+    /// Build a CosmosMsg that swaps the input amount, with the given minimum output amount
+    fn build_swap_msg_with_min_out(
+        &self,
+        env: &Env,
+        denom_in: &str,
+        amount: Uint128,
+        min_out_amount: Uint128,
     ) -> ContractResult<CosmosMsg> {
         let steps = &self.0;
 
@@ -141,9 +172,6 @@ impl Route<Empty, Empty> for OsmosisRoute {
             reason: "the route must contain at least one step".to_string(),
         })?;
 
-        let out_amount = query_out_amount(querier, &env.block, denom_in, amount, steps)?;
-        let min_out_amount = (Decimal::one() - slippage_tolerance) * out_amount;
-
         let routes: Vec<_> = steps
             .iter()
             .map(|step| OsmosisSwapAmountInRoute {
@@ -163,14 +191,83 @@ impl Route<Empty, Empty> for OsmosisRoute {
         .into();
         Ok(swap_msg)
     }
+
+    /// Build a CosmosMsg that swaps into an exact output coin
+    fn build_swap_exact_out_msg(
+        &self,
+        env: &Env,
+        querier: &QuerierWrapper,
+        denom_in: &str,
+        coin_out: &CwCoin,
+        max_input: Uint128,
+        slippage_tolerance: Decimal,
+    ) -> ContractResult<CosmosMsg> {
+        let steps = &self.0;
+
+        let last_step = steps.last().ok_or(ContractError::InvalidRoute {
+            reason: "the route must contain at least one step".to_string(),
+        })?;
+        if last_step.token_out_denom != coin_out.denom {
+            return Err(ContractError::InvalidRoute {
+                reason: format!(
+                    "the route's output denom {} does not match the desired output {}",
+                    last_step.token_out_denom, coin_out.denom
+                ),
+            });
+        }
+
+        let in_amount = query_in_amount(querier, &env.block, denom_in, coin_out.amount, steps)?;
+        if in_amount > max_input {
+            return Err(ContractError::MaxInputExceeded {
+                required: in_amount,
+                max_input,
+            });
+        }
+        let max_in_amount = min((Decimal::one() + slippage_tolerance) * in_amount, max_input);
+
+        // unlike the exact-in routes, each step of an exact-out route is given its input denom
+        let mut step_denom_in = denom_in;
+        let routes: Vec<_> = steps
+            .iter()
+            .map(|step| {
+                let route = OsmosisSwapAmountOutRoute {
+                    pool_id: step.pool_id,
+                    token_in_denom: step_denom_in.to_string(),
+                };
+                step_denom_in = &step.token_out_denom;
+                route
+            })
+            .collect();
+        let swap_msg: CosmosMsg = MsgSwapExactAmountOut {
+            sender: env.contract.address.to_string(),
+            routes,
+            token_in_max_amount: max_in_amount.to_string(),
+            token_out: Some(Coin {
+                denom: coin_out.denom.clone(),
+                amount: coin_out.amount.to_string(),
+            }),
+        }
+        .into();
+        Ok(swap_msg)
+    }
+
+    fn estimate_out_amount(
+        &self,
+        env: &Env,
+        querier: &QuerierWrapper,
+        denom_in: &str,
+        amount: Uint128,
+    ) -> ContractResult<Uint128> {
+        query_out_amount(querier, &env.block, denom_in, amount, &self.0)
+    }
 }
 
 /// Query how much amount of denom_out we get for denom_in.
 ///
 /// Example calculation:
 /// If we want to swap atom to usdc and configured routes are [pool_1 (atom/osmo), pool_69 (osmo/usdc)] (no direct pool of atom/usdc):
-/// 1) query pool_1 to get price for atom/osmo
-/// 2) query pool_69 to get price for osmo/usdc
+/// 1) query pool_1 to get price for atom/osmo, net of pool_1's swap fee
+/// 2) query pool_69 to get price for osmo/usdc, net of pool_69's swap fee
 /// 3) atom/usdc = (price for atom/osmo) * (price for osmo/usdc)
 /// 4) out_amount = (atom amount) * (price for atom/usdc) = usdc amount
 fn query_out_amount(
@@ -180,6 +277,32 @@ fn query_out_amount(
     amount: Uint128,
     steps: &[SwapAmountInRoute],
 ) -> ContractResult<Uint128> {
+    let price = query_route_price(querier, block, denom_in, steps)?;
+    let out_amount = amount.checked_multiply_ratio(price.numerator(), price.denominator())?;
+    Ok(out_amount)
+}
+
+/// Query how much amount of denom_in we need to get the given amount of denom_out, i.e. the
+/// output amount divided by the route's price computed as in `query_out_amount`
+fn query_in_amount(
+    querier: &QuerierWrapper,
+    block: &BlockInfo,
+    denom_in: &str,
+    out_amount: Uint128,
+    steps: &[SwapAmountInRoute],
+) -> ContractResult<Uint128> {
+    let price = query_route_price(querier, block, denom_in, steps)?;
+    let in_amount = out_amount.checked_multiply_ratio(price.denominator(), price.numerator())?;
+    Ok(in_amount)
+}
+
+/// Query the price of denom_in in the route's output denom, net of the swap fee of every hop
+fn query_route_price(
+    querier: &QuerierWrapper,
+    block: &BlockInfo,
+    denom_in: &str,
+    steps: &[SwapAmountInRoute],
+) -> ContractResult<Decimal> {
     let start_time = block.time.seconds() - TWAP_WINDOW_SIZE_SECONDS;
 
     let mut price = Decimal::one();
@@ -192,10 +315,11 @@ fn query_out_amount(
             &step.token_out_denom,
             start_time,
         )?;
-        price = price.checked_mul(step_price)?;
+        // the fee is taken from the input of every hop, so it compounds over the route
+        let swap_fee = query_pool(querier, step.pool_id)?.swap_fee()?;
+        price = price.checked_mul(step_price)?.checked_mul(Decimal::one() - swap_fee)?;
         denom_in = step.token_out_denom.clone();
     }
 
-    let out_amount = amount.checked_multiply_ratio(price.numerator(), price.denominator())?;
-    Ok(out_amount)
+    Ok(price)
 }
@@ -8,18 +8,34 @@ use cosmwasm_std::{
     Coin, Decimal, Deps, OwnedDeps,
 };
 use mars_osmosis::helpers::{Pool, QueryPoolResponse};
-use mars_red_bank_types::rewards_collector::{Config, ExecuteMsg, InstantiateMsg, QueryMsg};
+use mars_red_bank_types::{
+    address_provider::MarsAddressType,
+    rewards_collector::{
+        Config, ExecuteMsg, InstantiateMsg, QueryMsg, RewardRecipient, RewardSplit,
+    },
+};
 use mars_rewards_collector_osmosis::{contract::entry, route::SwapAmountInRoute, OsmosisRoute};
 use mars_testing::{mock_info, MarsMockQuerier};
-use osmosis_std::types::osmosis::gamm::v1beta1::PoolAsset;
+use osmosis_std::types::osmosis::gamm::v1beta1::{PoolAsset, PoolParams};
 
 pub fn mock_instantiate_msg() -> InstantiateMsg {
     InstantiateMsg {
         owner: "owner".to_string(),
         address_provider: "address_provider".to_string(),
-        safety_tax_rate: Decimal::percent(25),
-        safety_fund_denom: "uusdc".to_string(),
-        fee_collector_denom: "umars".to_string(),
+        reward_splits: vec![
+            RewardSplit {
+                recipient: RewardRecipient::Module(MarsAddressType::SafetyFund),
+                denom: "uusdc".to_string(),
+                weight: Decimal::percent(25),
+                local_recipient: None,
+            },
+            RewardSplit {
+                recipient: RewardRecipient::Module(MarsAddressType::FeeCollector),
+                denom: "umars".to_string(),
+                weight: Decimal::percent(75),
+                local_recipient: None,
+            },
+        ],
         channel_id: "channel-69".to_string(),
         timeout_seconds: 300,
         slippage_tolerance: Decimal::percent(3),
@@ -109,6 +125,7 @@ pub fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
             &[coin(1, "uatom"), coin(1, "uosmo")],
             &[5000u64, 5000u64],
             &coin(1, "uLP"),
+            None,
         ),
     );
     deps.querier.set_query_pool_response(
@@ -118,6 +135,7 @@ pub fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
             &[coin(1, "uatom"), coin(1, "uusdc")],
             &[5000u64, 5000u64],
             &coin(1, "uLP"),
+            None,
         ),
     );
     deps.querier.set_query_pool_response(
@@ -127,6 +145,7 @@ pub fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
             &[coin(1, "uosmo"), coin(1, "uusdc")],
             &[5000u64, 5000u64],
             &coin(1, "uLP"),
+            None,
         ),
     );
     deps.querier.set_query_pool_response(
@@ -136,6 +155,7 @@ pub fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
             &[coin(1, "uosmo"), coin(1, "umars")],
             &[5000u64, 5000u64],
             &coin(1, "uLP"),
+            None,
         ),
     );
 
@@ -154,6 +174,7 @@ pub fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
                 denom_in: denom_in.to_string(),
                 denom_out: denom_out.to_string(),
                 route,
+                slippage_override: None,
             },
         )
         .unwrap();
@@ -162,16 +183,21 @@ pub fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
     deps
 }
 
-fn prepare_query_pool_response(
+pub fn prepare_query_pool_response(
     pool_id: u64,
     assets: &[Coin],
     weights: &[u64],
     shares: &Coin,
+    swap_fee: Option<Decimal>,
 ) -> QueryPoolResponse {
     let pool = Pool {
         address: "address".to_string(),
         id: pool_id.to_string(),
-        pool_params: None,
+        pool_params: swap_fee.map(|swap_fee| PoolParams {
+            swap_fee: swap_fee.to_string(),
+            exit_fee: Decimal::zero().to_string(),
+            smooth_weight_change_params: None,
+        }),
         future_pool_governor: "future_pool_governor".to_string(),
         total_shares: Some(osmosis_std::types::cosmos::base::v1beta1::Coin {
             denom: shares.denom.clone(),
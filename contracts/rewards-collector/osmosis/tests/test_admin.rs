@@ -28,24 +28,27 @@ fn instantiating() {
             owner: Some("owner".to_string()),
             proposed_new_owner: None,
             address_provider: config.address_provider.to_string(),
-            safety_tax_rate: config.safety_tax_rate,
-            safety_fund_denom: config.safety_fund_denom,
-            fee_collector_denom: config.fee_collector_denom,
+            reward_splits: config.reward_splits,
             channel_id: config.channel_id,
             timeout_seconds: config.timeout_seconds,
+            timeout_blocks: None,
+            counterparty_height: None,
             slippage_tolerance: config.slippage_tolerance,
+            ibc_memo: None,
+            paused: false,
+            local_fee_collector: None,
         }
     );
 
-    // init config with safety_tax_rate greater than 1; should fail
-    init_msg.safety_tax_rate = Decimal::percent(150);
+    // init config with a weight greater than 1; should fail
+    init_msg.reward_splits[0].weight = Decimal::percent(150);
 
     let info = mock_info("deployer");
     let err = instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap_err();
     assert_eq!(
         err,
         ContractError::Validation(ValidationError::InvalidParam {
-            param_name: "safety_tax_rate".to_string(),
+            param_name: "weight".to_string(),
             invalid_value: "1.5".to_string(),
             predicate: "<= 1".to_string(),
         })
@@ -80,8 +83,12 @@ fn updating_config_if_invalid_slippage() {
 fn updating_config() {
     let mut deps = helpers::setup_test();
 
+    let mut reward_splits = mock_instantiate_msg().reward_splits;
+    reward_splits[0].weight = Decimal::percent(69);
+    reward_splits[1].weight = Decimal::percent(31);
+
     let new_cfg = UpdateConfig {
-        safety_tax_rate: Some(Decimal::percent(69)),
+        reward_splits: Some(reward_splits.clone()),
         ..Default::default()
     };
 
@@ -93,9 +100,11 @@ fn updating_config() {
     let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
     assert_eq!(err, ContractError::Owner(NotOwner {}));
 
-    // update config with safety_tax_rate greater than 1
+    // update config with weights that don't sum to 1
+    let mut invalid_splits = reward_splits.clone();
+    invalid_splits[1].weight = Decimal::percent(56);
     let mut invalid_cfg = new_cfg.clone();
-    invalid_cfg.safety_tax_rate = Some(Decimal::percent(125));
+    invalid_cfg.reward_splits = Some(invalid_splits);
 
     let info = mock_info("owner");
     let msg = ExecuteMsg::UpdateConfig {
@@ -105,9 +114,9 @@ fn updating_config() {
     assert_eq!(
         err,
         ContractError::Validation(ValidationError::InvalidParam {
-            param_name: "safety_tax_rate".to_string(),
+            param_name: "reward_splits".to_string(),
             invalid_value: "1.25".to_string(),
-            predicate: "<= 1".to_string(),
+            predicate: "weights sum to 1".to_string(),
         })
     );
 
@@ -118,7 +127,7 @@ fn updating_config() {
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let cfg: ConfigResponse = helpers::query(deps.as_ref(), QueryMsg::Config {});
-    assert_eq!(cfg.safety_tax_rate, Decimal::percent(69));
+    assert_eq!(cfg.reward_splits, reward_splits);
 }
 
 #[test]
@@ -144,3 +153,141 @@ fn updating_config_if_invalid_timeout_seconds() {
         })
     );
 }
+
+#[test]
+fn updating_config_ibc_memo() {
+    let mut deps = helpers::setup_test();
+
+    let update_memo = |memo: &str| ExecuteMsg::UpdateConfig {
+        new_cfg: UpdateConfig {
+            ibc_memo: Some(memo.to_string()),
+            ..Default::default()
+        },
+    };
+
+    // the memo must be a JSON object
+    for invalid_memo in ["hello", r#"{"forward":{"receiver":"{recipient}"}"#, "[]"] {
+        let err = execute(deps.as_mut(), mock_env(), mock_info("owner"), update_memo(invalid_memo))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Validation(ValidationError::InvalidParam {
+                param_name: "ibc_memo".to_string(),
+                invalid_value: invalid_memo.to_string(),
+                predicate: "a JSON object".to_string(),
+            })
+        );
+    }
+
+    let memo = r#"{"forward":{"receiver":"{recipient}","port":"transfer","channel":"channel-1"}}"#;
+    execute(deps.as_mut(), mock_env(), mock_info("owner"), update_memo(memo)).unwrap();
+    let cfg: ConfigResponse = helpers::query(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(cfg.ibc_memo, Some(memo.to_string()));
+
+    // an empty memo removes it
+    execute(deps.as_mut(), mock_env(), mock_info("owner"), update_memo("")).unwrap();
+    let cfg: ConfigResponse = helpers::query(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(cfg.ibc_memo, None);
+}
+
+#[test]
+fn updating_config_local_fee_collector() {
+    let mut deps = helpers::setup_test();
+
+    let update_fee_collector = |addr: &str| ExecuteMsg::UpdateConfig {
+        new_cfg: UpdateConfig {
+            local_fee_collector: Some(addr.to_string()),
+            ..Default::default()
+        },
+    };
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner"), update_fee_collector("fee_collector"))
+        .unwrap();
+    let cfg: ConfigResponse = helpers::query(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(cfg.local_fee_collector, Some("fee_collector".to_string()));
+
+    // an empty address removes it
+    execute(deps.as_mut(), mock_env(), mock_info("owner"), update_fee_collector("")).unwrap();
+    let cfg: ConfigResponse = helpers::query(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(cfg.local_fee_collector, None);
+}
+
+#[test]
+fn pausing() {
+    let mut deps = helpers::setup_test();
+
+    // non-owner is not authorized
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake"),
+        ExecuteMsg::SetPaused {
+            paused: true,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Owner(NotOwner {}));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::SetPaused {
+            paused: true,
+        },
+    )
+    .unwrap();
+    let cfg: ConfigResponse = helpers::query(deps.as_ref(), QueryMsg::Config {});
+    assert!(cfg.paused);
+
+    let distribute_msg = ExecuteMsg::DistributeRewards {
+        denom: "umars".to_string(),
+        amount: None,
+        timeout_timestamp: None,
+    };
+    for msg in [
+        ExecuteMsg::WithdrawFromRedBank {
+            denom: "uatom".to_string(),
+            amount: None,
+        },
+        distribute_msg.clone(),
+        ExecuteMsg::SwapAsset {
+            denom: "uatom".to_string(),
+            amount: None,
+            send_to_fee_collector: None,
+        },
+        ExecuteMsg::ClaimIncentiveRewards {},
+    ] {
+        let err = execute(deps.as_mut(), mock_env(), mock_info("jake"), msg).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+    }
+
+    // the config can still be updated while paused
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::UpdateConfig {
+            new_cfg: UpdateConfig {
+                timeout_seconds: Some(600),
+                ..Default::default()
+            },
+        },
+    )
+    .unwrap();
+    let cfg: ConfigResponse = helpers::query(deps.as_ref(), QueryMsg::Config {});
+    assert!(cfg.paused);
+
+    // unpausing restores normal behavior
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::SetPaused {
+            paused: false,
+        },
+    )
+    .unwrap();
+    let res = execute(deps.as_mut(), mock_env(), mock_info("jake"), distribute_msg).unwrap();
+    assert_eq!(res.messages.len(), 1);
+}
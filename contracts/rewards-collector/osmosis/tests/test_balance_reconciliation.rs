@@ -0,0 +1,59 @@
+use cosmwasm_std::{coin, testing::mock_env, Deps, Uint128};
+use mars_red_bank_types::rewards_collector::{BalanceReconciliationResponse, QueryMsg};
+use mars_rewards_collector_osmosis::{contract::entry::execute, msg::ExecuteMsg};
+use mars_testing::mock_info;
+
+mod helpers;
+
+fn query_reconciliation(deps: Deps) -> BalanceReconciliationResponse {
+    helpers::query(
+        deps,
+        QueryMsg::BalanceReconciliation {
+            denom: "umars".to_string(),
+        },
+    )
+}
+
+#[test]
+fn reconciling_balance_with_surplus() {
+    let mut deps = helpers::setup_test();
+
+    // nothing has been swapped or distributed yet, so the whole balance is unaccounted for
+    assert_eq!(
+        query_reconciliation(deps.as_ref()),
+        BalanceReconciliationResponse {
+            denom: "umars".to_string(),
+            balance: Uint128::new(8964),
+            expected_balance: Uint128::zero(),
+            surplus: Uint128::new(8964),
+            deficit: Uint128::zero(),
+        }
+    );
+
+    // distributing 8000 umars out of 8964 leaves 964 umars expected
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake"),
+        ExecuteMsg::DistributeRewards {
+            denom: "umars".to_string(),
+            amount: Some(Uint128::new(8000)),
+            timeout_timestamp: None,
+        },
+    )
+    .unwrap();
+
+    // the transfer leaves the contract, and 500 umars are sent to it out-of-band
+    deps.querier.set_contract_balances(&[coin(1464, "umars")]);
+
+    assert_eq!(
+        query_reconciliation(deps.as_ref()),
+        BalanceReconciliationResponse {
+            denom: "umars".to_string(),
+            balance: Uint128::new(1464),
+            expected_balance: Uint128::new(964),
+            surplus: Uint128::new(500),
+            deficit: Uint128::zero(),
+        }
+    );
+}
@@ -1,9 +1,21 @@
 use cosmwasm_std::{
-    coin, testing::mock_env, CosmosMsg, IbcMsg, IbcTimeout, SubMsg, Timestamp, Uint128,
+    attr, coin,
+    testing::{mock_env, MOCK_CONTRACT_ADDR},
+    Addr, Binary, CosmosMsg, Decimal, IbcMsg, IbcTimeout, IbcTimeoutBlock, SubMsg, Timestamp,
+    Uint128, WasmMsg,
 };
-use mars_rewards_collector_base::ContractError;
+use mars_owner::OwnerError::NotOwner;
+use mars_red_bank_types::{
+    address_provider::MarsAddressType,
+    rewards_collector::{
+        ConfigResponse, CounterpartyHeight, DistributedTotalResponse, DistributionStatsResponse,
+        QueryMsg, RewardRecipient, RewardSplit, UpdateConfig,
+    },
+};
+use mars_rewards_collector_base::{ContractError, MsgTransfer};
 use mars_rewards_collector_osmosis::{contract::entry::execute, msg::ExecuteMsg};
 use mars_testing::{mock_env as mock_env_at_height_and_time, mock_info, MockEnvParams};
+use osmosis_std::types::osmosis::twap::v1beta1::ArithmeticTwapToNowResponse;
 
 mod helpers;
 
@@ -24,6 +36,7 @@ fn distributing_rewards() {
         ExecuteMsg::DistributeRewards {
             denom: "uusdc".to_string(),
             amount: Some(Uint128::new(123)),
+            timeout_timestamp: None,
         },
     )
     .unwrap();
@@ -46,6 +59,7 @@ fn distributing_rewards() {
         ExecuteMsg::DistributeRewards {
             denom: "umars".to_string(),
             amount: None,
+            timeout_timestamp: None,
         },
     )
     .unwrap();
@@ -68,6 +82,557 @@ fn distributing_rewards() {
         ExecuteMsg::DistributeRewards {
             denom: "uatom".to_string(),
             amount: Some(Uint128::new(123)),
+            timeout_timestamp: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AssetNotEnabledForDistribution {
+            denom: "uatom".to_string()
+        }
+    );
+}
+
+#[test]
+fn distributing_rewards_with_timeout_blocks() {
+    let mut deps = helpers::setup_test();
+
+    let env = mock_env_at_height_and_time(MockEnvParams {
+        block_height: 10000,
+        block_time: Timestamp::from_seconds(17000000),
+    });
+
+    let update_timeout_blocks = |blocks: u64| ExecuteMsg::UpdateConfig {
+        new_cfg: UpdateConfig {
+            timeout_blocks: Some(blocks),
+            ..Default::default()
+        },
+    };
+    let distribute_umars = ExecuteMsg::DistributeRewards {
+        denom: "umars".to_string(),
+        amount: None,
+        timeout_timestamp: None,
+    };
+
+    // the height timeout needs the counterparty chain's revision and height
+    let err = execute(deps.as_mut(), env.clone(), mock_info("owner"), update_timeout_blocks(50))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Validation(ValidationError::InvalidParam {
+            param_name: "counterparty_height".to_string(),
+            invalid_value: "None".to_string(),
+            predicate: "set along with timeout_blocks".to_string(),
+        })
+    );
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner"),
+        ExecuteMsg::UpdateConfig {
+            new_cfg: UpdateConfig {
+                timeout_blocks: Some(50),
+                counterparty_height: Some(CounterpartyHeight {
+                    revision: 2,
+                    height_offset: -3000,
+                }),
+                ..Default::default()
+            },
+        },
+    )
+    .unwrap();
+
+    // the transfer times out at whichever comes first of the timestamp and the height, which is
+    // counted on the counterparty chain
+    let res =
+        execute(deps.as_mut(), env.clone(), mock_info("jake"), distribute_umars.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Ibc(IbcMsg::Transfer {
+            channel_id: "channel-69".to_string(),
+            to_address: "fee_collector".to_string(),
+            amount: coin(8964, "umars"),
+            timeout: IbcTimeout::with_both(
+                IbcTimeoutBlock {
+                    revision: 2,
+                    height: 7050,
+                },
+                Timestamp::from_seconds(17000300)
+            ),
+        }))]
+    );
+
+    // zero removes the height timeout, going back to the timestamp only
+    execute(deps.as_mut(), env.clone(), mock_info("owner"), update_timeout_blocks(0)).unwrap();
+    let cfg: ConfigResponse = helpers::query(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(cfg.timeout_blocks, None);
+
+    let res = execute(deps.as_mut(), env, mock_info("jake"), distribute_umars).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Ibc(IbcMsg::Transfer {
+            channel_id: "channel-69".to_string(),
+            to_address: "fee_collector".to_string(),
+            amount: coin(8964, "umars"),
+            timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(17000300)),
+        }))]
+    );
+}
+
+#[test]
+fn distributing_rewards_with_ibc_memo() {
+    let mut deps = helpers::setup_test();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::UpdateConfig {
+            new_cfg: UpdateConfig {
+                ibc_memo: Some(r#"{"forward":{"receiver":"{recipient}"}}"#.to_string()),
+                ..Default::default()
+            },
+        },
+    )
+    .unwrap();
+
+    let env = mock_env_at_height_and_time(MockEnvParams {
+        block_height: 10000,
+        block_time: Timestamp::from_seconds(17000000),
+    });
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("jake"),
+        ExecuteMsg::DistributeRewards {
+            denom: "uusdc".to_string(),
+            amount: Some(Uint128::new(123)),
+            timeout_timestamp: None,
+        },
+    )
+    .unwrap();
+
+    // the transfer is sent as a Stargate msg, with the recipient filled into the memo
+    let transfer = MsgTransfer::new(
+        "channel-69",
+        &Addr::unchecked(MOCK_CONTRACT_ADDR),
+        "safety_fund",
+        coin(123, "uusdc"),
+        &IbcTimeout::with_timestamp(Timestamp::from_seconds(17000300)),
+        r#"{"forward":{"receiver":"safety_fund"}}"#.to_string(),
+    );
+    assert_eq!(transfer.timeout_timestamp, 17000300 * 1_000_000_000);
+    assert_eq!(res.messages, vec![SubMsg::new(transfer.into_cosmos_msg())]);
+}
+
+#[test]
+fn tracking_distributed_totals() {
+    let mut deps = helpers::setup_test();
+
+    let distribute = |denom: &str, amount: Option<u128>| ExecuteMsg::DistributeRewards {
+        denom: denom.to_string(),
+        amount: amount.map(Uint128::new),
+        timeout_timestamp: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("jake"), distribute("uusdc", Some(123))).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("jake"), distribute("uusdc", Some(100))).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("jake"), distribute("umars", None)).unwrap();
+
+    let stats: DistributionStatsResponse = helpers::query(
+        deps.as_ref(),
+        QueryMsg::DistributionStats {
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(
+        stats,
+        vec![
+            DistributedTotalResponse {
+                denom: "umars".to_string(),
+                recipient: "fee_collector".to_string(),
+                amount: Uint128::new(8964),
+            },
+            DistributedTotalResponse {
+                denom: "uusdc".to_string(),
+                recipient: "safety_fund".to_string(),
+                amount: Uint128::new(223),
+            },
+        ]
+    );
+
+    let stats: DistributionStatsResponse = helpers::query(
+        deps.as_ref(),
+        QueryMsg::DistributionStats {
+            start_after: Some(("umars".to_string(), "fee_collector".to_string())),
+            limit: Some(1),
+        },
+    );
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].denom, "uusdc");
+}
+
+#[test]
+fn distributing_cw20() {
+    let mut deps = helpers::setup_test();
+
+    let msg = ExecuteMsg::DistributeCw20 {
+        contract_addr: "cw20_token".to_string(),
+        amount: Uint128::new(500),
+    };
+
+    // CW20 tokens can't be sent over IBC, so every recipient needs one on this chain
+    let err = execute(deps.as_mut(), mock_env(), mock_info("jake"), msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NoLocalRecipient {
+            recipient: "safety_fund".to_string()
+        }
+    );
+
+    let mut reward_splits = helpers::mock_instantiate_msg().reward_splits;
+    reward_splits[0].local_recipient = Some("safety_fund_local".to_string());
+    reward_splits[1].local_recipient = Some("fee_collector_local".to_string());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::UpdateConfig {
+            new_cfg: UpdateConfig {
+                reward_splits: Some(reward_splits),
+                ..Default::default()
+            },
+        },
+    )
+    .unwrap();
+
+    // the tokens are split according to the reward split weights
+    let res = execute(deps.as_mut(), mock_env(), mock_info("jake"), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: "cw20_token".to_string(),
+                msg: Binary::from(
+                    br#"{"transfer":{"recipient":"safety_fund_local","amount":"125"}}"#
+                ),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: "cw20_token".to_string(),
+                msg: Binary::from(
+                    br#"{"transfer":{"recipient":"fee_collector_local","amount":"375"}}"#
+                ),
+                funds: vec![],
+            }),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "distribute_cw20"),
+            attr("token", "cw20_token"),
+            attr("amount", "500"),
+            attr("to", "safety_fund_local"),
+            attr("to", "fee_collector_local"),
+        ]
+    );
+
+    // the distributed totals are keyed by the prefixed token address
+    let stats: DistributionStatsResponse = helpers::query(
+        deps.as_ref(),
+        QueryMsg::DistributionStats {
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(
+        stats,
+        vec![
+            DistributedTotalResponse {
+                denom: "cw20:cw20_token".to_string(),
+                recipient: "fee_collector".to_string(),
+                amount: Uint128::new(375),
+            },
+            DistributedTotalResponse {
+                denom: "cw20:cw20_token".to_string(),
+                recipient: "safety_fund".to_string(),
+                amount: Uint128::new(125),
+            },
+        ]
+    );
+}
+
+#[test]
+fn redistributing_rewards_after_ibc_timeout() {
+    let mut deps = helpers::setup_test();
+
+    let env = mock_env_at_height_and_time(MockEnvParams {
+        block_height: 10000,
+        block_time: Timestamp::from_seconds(17000000),
+    });
+    let distribute_umars = ExecuteMsg::DistributeRewards {
+        denom: "umars".to_string(),
+        amount: None,
+        timeout_timestamp: None,
+    };
+    let expected_transfer = SubMsg::new(CosmosMsg::Ibc(IbcMsg::Transfer {
+        channel_id: "channel-69".to_string(),
+        to_address: "fee_collector".to_string(),
+        amount: coin(8964, "umars"),
+        timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(17000300)),
+    }));
+
+    let res =
+        execute(deps.as_mut(), env.clone(), mock_info("jake"), distribute_umars.clone()).unwrap();
+    assert_eq!(res.messages, vec![expected_transfer.clone()]);
+
+    // the transfer module refunds a timed out transfer to the collector, without notifying it.
+    // The refunded funds are part of the balance, so distributing the whole balance again sends
+    // them out
+    deps.querier.set_contract_balances(&[coin(0, "uatom"), coin(0, "uusdc"), coin(8964, "umars")]);
+
+    let res = execute(deps.as_mut(), env, mock_info("jake"), distribute_umars).unwrap();
+    assert_eq!(res.messages, vec![expected_transfer]);
+}
+
+#[test]
+fn distributing_rewards_with_three_way_split() {
+    let mut deps = helpers::setup_test();
+
+    // a grants program takes a cut of the revenue, in the same denom as the fee collector
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::UpdateConfig {
+            new_cfg: UpdateConfig {
+                reward_splits: Some(vec![
+                    RewardSplit {
+                        recipient: RewardRecipient::Module(MarsAddressType::SafetyFund),
+                        denom: "uusdc".to_string(),
+                        weight: Decimal::percent(20),
+                        local_recipient: None,
+                    },
+                    RewardSplit {
+                        recipient: RewardRecipient::Module(MarsAddressType::FeeCollector),
+                        denom: "umars".to_string(),
+                        weight: Decimal::percent(50),
+                        local_recipient: None,
+                    },
+                    RewardSplit {
+                        recipient: RewardRecipient::Addr("mars1grants".to_string()),
+                        denom: "umars".to_string(),
+                        weight: Decimal::percent(30),
+                        local_recipient: None,
+                    },
+                ]),
+                ..Default::default()
+            },
+        },
+    )
+    .unwrap();
+
+    for (pool_id, base_asset, quote_asset, price) in [
+        (1, "uatom", "uosmo", Decimal::from_ratio(125u128, 10u128)),
+        (69, "uosmo", "uusdc", Decimal::from_ratio(10u128, 1u128)),
+        (420, "uosmo", "umars", Decimal::from_ratio(5u128, 10u128)),
+    ] {
+        deps.querier.set_arithmetic_twap_price(
+            pool_id,
+            base_asset,
+            quote_asset,
+            ArithmeticTwapToNowResponse {
+                arithmetic_twap: price.to_string(),
+            },
+        );
+    }
+
+    // swap uatom; one swap per recipient
+    //
+    // safety fund:   42069 * 0.2 = 8413
+    // fee collector: 42069 * 0.5 = 21034
+    // grants:        42069 - 8413 - 21034 = 12622
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake"),
+        ExecuteMsg::SwapAsset {
+            denom: "uatom".to_string(),
+            amount: Some(Uint128::new(42069)),
+            send_to_fee_collector: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "swap_asset"),
+            attr("denom", "uatom"),
+            attr("amount_safety_fund", "8413"),
+            attr("amount_fee_collector", "21034"),
+            attr("amount_mars1grants", "12622"),
+            attr("slippage_tolerance", "0.03"),
+        ]
+    );
+
+    // distribute umars; the fee collector and the grants program split it 50:30
+    //
+    // fee collector: 8964 * 50 / 80 = 5602
+    // grants:        8964 - 5602 = 3362
+    let env = mock_env_at_height_and_time(MockEnvParams {
+        block_height: 10000,
+        block_time: Timestamp::from_seconds(17000000),
+    });
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("jake"),
+        ExecuteMsg::DistributeRewards {
+            denom: "umars".to_string(),
+            amount: None,
+            timeout_timestamp: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Ibc(IbcMsg::Transfer {
+                channel_id: "channel-69".to_string(),
+                to_address: "fee_collector".to_string(),
+                amount: coin(5602, "umars"),
+                timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(17000300))
+            })),
+            SubMsg::new(CosmosMsg::Ibc(IbcMsg::Transfer {
+                channel_id: "channel-69".to_string(),
+                to_address: "mars1grants".to_string(),
+                amount: coin(3362, "umars"),
+                timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(17000300))
+            })),
+        ]
+    );
+}
+
+#[test]
+fn distributing_rewards_with_explicit_timeout() {
+    let mut deps = helpers::setup_test();
+
+    let env = mock_env_at_height_and_time(MockEnvParams {
+        block_height: 10000,
+        block_time: Timestamp::from_seconds(17000000),
+    });
+    let distribute_msg = |timeout_timestamp: Timestamp| ExecuteMsg::DistributeRewards {
+        denom: "uusdc".to_string(),
+        amount: Some(Uint128::new(123)),
+        timeout_timestamp: Some(timeout_timestamp),
+    };
+
+    // only the owner can override the timeout
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("jake"),
+        distribute_msg(Timestamp::from_seconds(17086400)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Owner(NotOwner {}));
+
+    // the timeout must be in the future
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner"),
+        distribute_msg(Timestamp::from_seconds(17000000)),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TimeoutNotInFuture {
+            timeout: 17000000,
+            now: 17000000,
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("owner"),
+        distribute_msg(Timestamp::from_seconds(17086400)),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Ibc(IbcMsg::Transfer {
+            channel_id: "channel-69".to_string(),
+            to_address: "safety_fund".to_string(),
+            amount: coin(123, "uusdc"),
+            timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(17086400))
+        }))]
+    );
+}
+
+#[test]
+fn distributing_rewards_of_many_denoms() {
+    let mut deps = helpers::setup_test();
+
+    let env = mock_env_at_height_and_time(MockEnvParams {
+        block_height: 10000,
+        block_time: Timestamp::from_seconds(17000000),
+    });
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("jake"),
+        ExecuteMsg::DistributeRewardsMany {
+            denoms: vec![
+                ("uusdc".to_string(), Some(Uint128::new(123))),
+                ("umars".to_string(), None),
+            ],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Ibc(IbcMsg::Transfer {
+                channel_id: "channel-69".to_string(),
+                to_address: "safety_fund".to_string(),
+                amount: coin(123, "uusdc"),
+                timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(17000300))
+            })),
+            SubMsg::new(CosmosMsg::Ibc(IbcMsg::Transfer {
+                channel_id: "channel-69".to_string(),
+                to_address: "fee_collector".to_string(),
+                amount: coin(8964, "umars"),
+                timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(17000300))
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "distribute_rewards_many"),
+            attr("denom", "uusdc"),
+            attr("amount", "123"),
+            attr("to", "safety_fund"),
+            attr("denom", "umars"),
+            attr("amount", "8964"),
+            attr("to", "fee_collector"),
+        ]
+    );
+
+    // nothing is distributed if any of the denoms isn't a reward split denom
+    let err = execute(
+        deps.as_mut(),
+        env,
+        mock_info("jake"),
+        ExecuteMsg::DistributeRewardsMany {
+            denoms: vec![("uusdc".to_string(), None), ("uatom".to_string(), None)],
         },
     )
     .unwrap_err();
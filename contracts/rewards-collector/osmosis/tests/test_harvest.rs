@@ -0,0 +1,134 @@
+use cosmwasm_std::{
+    attr, coin, testing::mock_env, to_binary, CosmosMsg, Decimal, IbcMsg, IbcTimeout, Reply,
+    ReplyOn, SubMsg, SubMsgResponse, SubMsgResult, WasmMsg,
+};
+use mars_red_bank_types::incentives;
+use mars_rewards_collector_base::{HARVEST_CLAIM_REPLY_ID, HARVEST_SWAP_REPLY_ID};
+use mars_rewards_collector_osmosis::{
+    contract::entry::{execute, reply},
+    msg::ExecuteMsg,
+    route::SwapAmountInRoute,
+    OsmosisRoute,
+};
+use mars_testing::mock_info;
+use osmosis_std::types::osmosis::twap::v1beta1::ArithmeticTwapToNowResponse;
+
+mod helpers;
+
+fn mock_reply(id: u64) -> Reply {
+    Reply {
+        id,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    }
+}
+
+#[test]
+fn harvesting() {
+    let mut deps = helpers::setup_test();
+
+    for (pool_id, base_denom, quote_denom) in [
+        (1, "uatom", "uosmo"),
+        (69, "uosmo", "uusdc"),
+        (69, "uusdc", "uosmo"),
+        (420, "uosmo", "umars"),
+        (420, "umars", "uosmo"),
+    ] {
+        deps.querier.set_arithmetic_twap_price(
+            pool_id,
+            base_denom,
+            quote_denom,
+            ArithmeticTwapToNowResponse {
+                arithmetic_twap: Decimal::one().to_string(),
+            },
+        );
+    }
+
+    // umars -> uosmo -> uusdc
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::SetRoute {
+            denom_in: "umars".to_string(),
+            denom_out: "uusdc".to_string(),
+            route: OsmosisRoute(vec![
+                SwapAmountInRoute {
+                    pool_id: 420,
+                    token_out_denom: "uosmo".to_string(),
+                },
+                SwapAmountInRoute {
+                    pool_id: 69,
+                    token_out_denom: "uusdc".to_string(),
+                },
+            ]),
+            slippage_override: None,
+        },
+    )
+    .unwrap();
+
+    // the harvest starts by claiming the incentive rewards
+    let res =
+        execute(deps.as_mut(), mock_env(), mock_info("jake"), ExecuteMsg::Harvest {}).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "incentives".to_string(),
+                msg: to_binary(&incentives::ExecuteMsg::ClaimRewards {
+                    recipient: None,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            HARVEST_CLAIM_REPLY_ID,
+        )]
+    );
+
+    // the claim pays out 1036 umars on top of the contract's balances
+    deps.querier.set_contract_balances(&[
+        coin(88888, "uatom"),
+        coin(1234, "uusdc"),
+        coin(10000, "umars"),
+    ]);
+
+    // only the claimed umars are swapped, into uusdc for the safety fund's share. The uatom and
+    // uusdc the contract held before the claim are left as is.
+    let res = reply(deps.as_mut(), mock_env(), mock_reply(HARVEST_CLAIM_REPLY_ID)).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    let denoms: Vec<_> =
+        res.attributes.iter().filter(|attribute| attribute.key == "denom").collect();
+    assert_eq!(denoms, vec![&attr("denom", "umars")]);
+    assert!(res.attributes.contains(&attr("amount_safety_fund", "259")));
+
+    // the last swap triggers the distribution
+    let last_msg = res.messages.last().unwrap();
+    assert_eq!(last_msg.id, HARVEST_SWAP_REPLY_ID);
+    assert_eq!(last_msg.reply_on, ReplyOn::Success);
+
+    // the swaps leave the contract with the reward split denoms only
+    deps.querier.set_contract_balances(&[coin(23456, "uusdc"), coin(76543, "umars")]);
+
+    let env = mock_env();
+    let res = reply(deps.as_mut(), env.clone(), mock_reply(HARVEST_SWAP_REPLY_ID)).unwrap();
+    let timeout = IbcTimeout::with_timestamp(env.block.time.plus_seconds(300));
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Ibc(IbcMsg::Transfer {
+                channel_id: "channel-69".to_string(),
+                to_address: "safety_fund".to_string(),
+                amount: coin(23456, "uusdc"),
+                timeout: timeout.clone(),
+            })),
+            SubMsg::new(CosmosMsg::Ibc(IbcMsg::Transfer {
+                channel_id: "channel-69".to_string(),
+                to_address: "fee_collector".to_string(),
+                amount: coin(76543, "umars"),
+                timeout,
+            })),
+        ]
+    );
+}
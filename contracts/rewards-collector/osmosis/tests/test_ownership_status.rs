@@ -0,0 +1,91 @@
+use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
+use mars_red_bank_types::{
+    address_provider::MarsAddressType,
+    rewards_collector::{ContractOwnership, OwnershipStatusResponse, QueryMsg},
+};
+
+mod helpers;
+
+#[test]
+fn querying_ownership_status() {
+    let mut deps = helpers::setup_test();
+
+    // a handover in progress on the address provider, completed on the red bank
+    deps.querier.set_contract_owner("address_provider", Some("owner"), Some("new_owner"));
+    deps.querier.set_contract_owner("red_bank", Some("new_owner"), None);
+
+    // incentives and oracle have no owner state, so their config queries fail
+    let res: Vec<OwnershipStatusResponse> =
+        helpers::query(deps.as_ref(), QueryMsg::OwnershipStatus {});
+    assert_eq!(
+        res,
+        vec![
+            OwnershipStatusResponse {
+                contract: "address_provider".to_string(),
+                address: Some("address_provider".to_string()),
+                ownership: Some(ContractOwnership {
+                    owner: Some("owner".to_string()),
+                    proposed_new_owner: Some("new_owner".to_string()),
+                }),
+            },
+            OwnershipStatusResponse {
+                contract: "red_bank".to_string(),
+                address: Some("red_bank".to_string()),
+                ownership: Some(ContractOwnership {
+                    owner: Some("new_owner".to_string()),
+                    proposed_new_owner: None,
+                }),
+            },
+            OwnershipStatusResponse {
+                contract: "incentives".to_string(),
+                address: Some("incentives".to_string()),
+                ownership: None,
+            },
+            OwnershipStatusResponse {
+                contract: "oracle".to_string(),
+                address: Some("oracle".to_string()),
+                ownership: None,
+            },
+            OwnershipStatusResponse {
+                contract: "rewards_collector".to_string(),
+                address: Some(MOCK_CONTRACT_ADDR.to_string()),
+                ownership: Some(ContractOwnership {
+                    owner: Some("owner".to_string()),
+                    proposed_new_owner: None,
+                }),
+            },
+        ]
+    );
+}
+
+#[test]
+fn querying_ownership_status_with_missing_address() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_contract_owner("red_bank", Some("owner"), None);
+    deps.querier.remove_address(MarsAddressType::Oracle);
+
+    // the oracle's address isn't set, which doesn't prevent the other contracts being reported
+    let res: Vec<OwnershipStatusResponse> =
+        helpers::query(deps.as_ref(), QueryMsg::OwnershipStatus {});
+    assert_eq!(res.len(), 5);
+    assert_eq!(
+        res[1],
+        OwnershipStatusResponse {
+            contract: "red_bank".to_string(),
+            address: Some("red_bank".to_string()),
+            ownership: Some(ContractOwnership {
+                owner: Some("owner".to_string()),
+                proposed_new_owner: None,
+            }),
+        }
+    );
+    assert_eq!(
+        res[3],
+        OwnershipStatusResponse {
+            contract: "oracle".to_string(),
+            address: None,
+            ownership: None,
+        }
+    );
+}
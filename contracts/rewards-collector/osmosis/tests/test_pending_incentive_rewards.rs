@@ -0,0 +1,15 @@
+use cosmwasm_std::{testing::MOCK_CONTRACT_ADDR, Addr, Uint128};
+use mars_red_bank_types::{address_provider::MarsAddressType, rewards_collector::QueryMsg};
+
+mod helpers;
+
+#[test]
+fn querying_pending_incentive_rewards() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_incentives_address(Addr::unchecked(MarsAddressType::Incentives.to_string()));
+    deps.querier.set_unclaimed_rewards(MOCK_CONTRACT_ADDR.to_string(), Uint128::new(12345));
+
+    let res: Uint128 = helpers::query(deps.as_ref(), QueryMsg::PendingIncentiveRewards {});
+    assert_eq!(res, Uint128::new(12345));
+}
@@ -0,0 +1,48 @@
+use cosmwasm_std::{coin, Coin, Decimal};
+use mars_red_bank_types::{
+    address_provider::MarsAddressType,
+    rewards_collector::{QueryMsg, RewardRecipient},
+};
+use osmosis_std::types::osmosis::twap::v1beta1::ArithmeticTwapToNowResponse;
+
+mod helpers;
+
+#[test]
+fn querying_projected_distribution() {
+    let mut deps = helpers::setup_test();
+
+    for (pool_id, base_denom, quote_denom, price) in [
+        (1, "uatom", "uosmo", Decimal::from_ratio(2u128, 1u128)),
+        (69, "uosmo", "uusdc", Decimal::percent(50)),
+        (69, "uusdc", "uosmo", Decimal::from_ratio(2u128, 1u128)),
+        (420, "uosmo", "umars", Decimal::percent(10)),
+    ] {
+        deps.querier.set_arithmetic_twap_price(
+            pool_id,
+            base_denom,
+            quote_denom,
+            ArithmeticTwapToNowResponse {
+                arithmetic_twap: price.to_string(),
+            },
+        );
+    }
+
+    // uatom: 88888 * 0.25 = 22222 to the safety fund, 66666 to the fee collector
+    // uusdc: 1234 * 0.25 = 308 to the safety fund, 926 to the fee collector
+    //
+    // safety fund:   22222 * 2 * 0.5 + 308 (not swapped) = 22530 uusdc
+    // fee collector: 66666 * 2 * 0.1 + 926 * 2 * 0.1 = 13333 + 185 = 13518 umars
+    let res: Vec<(RewardRecipient, Coin)> = helpers::query(
+        deps.as_ref(),
+        QueryMsg::ProjectedDistribution {
+            denoms: vec!["uatom".to_string(), "uusdc".to_string()],
+        },
+    );
+    assert_eq!(
+        res,
+        vec![
+            (RewardRecipient::Module(MarsAddressType::SafetyFund), coin(22530, "uusdc")),
+            (RewardRecipient::Module(MarsAddressType::FeeCollector), coin(13518, "umars")),
+        ]
+    );
+}
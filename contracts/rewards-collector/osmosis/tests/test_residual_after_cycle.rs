@@ -0,0 +1,82 @@
+use cosmwasm_std::{coin, testing::mock_env, Decimal, Uint128};
+use mars_red_bank_types::{
+    address_provider::MarsAddressType,
+    rewards_collector::{QueryMsg, ResidualAfterCycleResponse, RewardRecipient},
+};
+use mars_rewards_collector_osmosis::contract::entry;
+use osmosis_std::types::osmosis::twap::v1beta1::ArithmeticTwapToNowResponse;
+
+mod helpers;
+
+#[test]
+fn querying_residual_after_cycle() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_arithmetic_twap_price(
+        1,
+        "uatom",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(125u128, 10u128).to_string(),
+        },
+    );
+    deps.querier.set_arithmetic_twap_price(
+        69,
+        "uosmo",
+        "uusdc",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(10u128, 1u128).to_string(),
+        },
+    );
+    deps.querier.set_arithmetic_twap_price(
+        420,
+        "uosmo",
+        "umars",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(5u128, 10u128).to_string(),
+        },
+    );
+
+    let res: ResidualAfterCycleResponse = helpers::query(
+        deps.as_ref(),
+        QueryMsg::ResidualAfterCycle {
+            denom: "uatom".to_string(),
+        },
+    );
+
+    // amount for safety fund:   88888 * 0.25 = 22222
+    // amount for fee collector: 88888 - 22222 = 66666
+    //
+    // 1 uatom = 12.5 uosmo = 125 uusdc
+    // 1 uatom = 12.5 uosmo = 6.25 umars
+    //
+    // safety fund:   1234 + 22222 * 125 = 2778984
+    // fee collector: 8964 + 66666 * 6.25 = 425626
+    assert_eq!(
+        res,
+        ResidualAfterCycleResponse {
+            denom: "uatom".to_string(),
+            denom_residual: Uint128::zero(),
+            distributed: vec![
+                (RewardRecipient::Module(MarsAddressType::SafetyFund), coin(2778984, "uusdc")),
+                (RewardRecipient::Module(MarsAddressType::FeeCollector), coin(425626, "umars")),
+            ],
+        }
+    );
+}
+
+#[test]
+fn querying_residual_after_cycle_without_route() {
+    let mut deps = helpers::setup_test();
+    deps.querier.set_contract_balances(&[coin(1000, "uosmo")]);
+
+    // there is no route from OSMO to USDC, so the cycle can't be completed
+    let res = entry::query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::ResidualAfterCycle {
+            denom: "uosmo".to_string(),
+        },
+    );
+    assert!(res.is_err());
+}
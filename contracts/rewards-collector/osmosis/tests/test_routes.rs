@@ -1,12 +1,13 @@
-use cosmwasm_std::testing::mock_env;
+use cosmwasm_std::{attr, coin, testing::mock_env, Decimal, Deps};
 use mars_owner::OwnerError::NotOwner;
-use mars_red_bank_types::rewards_collector::{QueryMsg, RouteResponse};
+use mars_red_bank_types::rewards_collector::{QueryMsg, RouteExistsResponse, RouteResponse};
 use mars_rewards_collector_base::{ContractError, Route};
 use mars_rewards_collector_osmosis::{
     contract::entry::execute, msg::ExecuteMsg, route::SwapAmountInRoute, OsmosisRoute,
 };
 use mars_testing::mock_info;
 use mars_utils::error::ValidationError;
+use osmosis_std::types::osmosis::twap::v1beta1::ArithmeticTwapToNowResponse;
 
 use crate::helpers::mock_routes;
 
@@ -31,11 +32,13 @@ fn setting_route() {
         denom_in: "uatom".to_string(),
         denom_out: "umars".to_string(),
         route: OsmosisRoute(steps.clone()),
+        slippage_override: None,
     };
     let invalid_msg = ExecuteMsg::SetRoute {
         denom_in: "uatom".to_string(),
         denom_out: "umars".to_string(),
         route: OsmosisRoute(vec![]),
+        slippage_override: None,
     };
 
     // non-owner is not authorized
@@ -64,6 +67,52 @@ fn setting_route() {
     assert_eq!(res.route, OsmosisRoute(steps));
 }
 
+#[test]
+fn setting_route_with_slippage_override() {
+    let mut deps = helpers::setup_test();
+
+    let route = mock_routes().get(&("uusdc", "umars")).unwrap().clone();
+    let msg = |slippage_override: Option<Decimal>| ExecuteMsg::SetRoute {
+        denom_in: "uusdc".to_string(),
+        denom_out: "umars".to_string(),
+        route: route.clone(),
+        slippage_override,
+    };
+    let query_route = |deps: Deps| -> RouteResponse<OsmosisRoute> {
+        helpers::query(
+            deps,
+            QueryMsg::Route {
+                denom_in: "uusdc".to_string(),
+                denom_out: "umars".to_string(),
+            },
+        )
+    };
+
+    // the override is capped like the configured slippage tolerance
+    let err =
+        execute(deps.as_mut(), mock_env(), mock_info("owner"), msg(Some(Decimal::percent(51))))
+            .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Validation(ValidationError::InvalidParam {
+            param_name: "slippage_override".to_string(),
+            invalid_value: "0.51".to_string(),
+            predicate: "<= 0.5".to_string(),
+        })
+    );
+    assert_eq!(query_route(deps.as_ref()).slippage_override, None);
+
+    let res =
+        execute(deps.as_mut(), mock_env(), mock_info("owner"), msg(Some(Decimal::percent(1))))
+            .unwrap();
+    assert!(res.attributes.contains(&attr("slippage_override", "0.01")));
+    assert_eq!(query_route(deps.as_ref()).slippage_override, Some(Decimal::percent(1)));
+
+    // setting the route again without an override goes back to the configured tolerance
+    execute(deps.as_mut(), mock_env(), mock_info("owner"), msg(None)).unwrap();
+    assert_eq!(query_route(deps.as_ref()).slippage_override, None);
+}
+
 #[test]
 fn denom_with_invalid_char() {
     let mut deps = helpers::setup_test();
@@ -83,6 +132,7 @@ fn denom_with_invalid_char() {
         denom_in: "hadb%akdjb!".to_string(),
         denom_out: "askd&7ab12d&".to_string(),
         route: OsmosisRoute(steps),
+        slippage_override: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), mock_info("owner"), msg);
@@ -114,6 +164,7 @@ fn invalid_denom_length() {
         denom_in: "qw".to_string(),
         denom_out: "qwrouwetsdknfsljvnsdkjfhw".to_string(),
         route: OsmosisRoute(steps),
+        slippage_override: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), mock_info("owner"), msg);
@@ -136,21 +187,25 @@ fn querying_routes() {
             denom_in: "uatom".to_string(),
             denom_out: "umars".to_string(),
             route: routes.get(&("uatom", "umars")).unwrap().clone(),
+            slippage_override: None,
         },
         RouteResponse {
             denom_in: "uatom".to_string(),
             denom_out: "uusdc".to_string(),
             route: routes.get(&("uatom", "uusdc")).unwrap().clone(),
+            slippage_override: None,
         },
         RouteResponse {
             denom_in: "uosmo".to_string(),
             denom_out: "umars".to_string(),
             route: routes.get(&("uosmo", "umars")).unwrap().clone(),
+            slippage_override: None,
         },
         RouteResponse {
             denom_in: "uusdc".to_string(),
             denom_out: "umars".to_string(),
             route: routes.get(&("uusdc", "umars")).unwrap().clone(),
+            slippage_override: None,
         },
     ];
 
@@ -259,6 +314,58 @@ fn validating_route() {
         })
     );
 
+    // invalid - the output is the input denom
+    let route = OsmosisRoute(vec![
+        SwapAmountInRoute {
+            pool_id: 1,
+            token_out_denom: "uosmo".to_string(),
+        },
+        SwapAmountInRoute {
+            pool_id: 1,
+            token_out_denom: "uatom".to_string(),
+        },
+    ]);
+    assert_eq!(
+        route.validate(q, "uatom", "uatom"),
+        Err(ContractError::InvalidRoute {
+            reason: "route contains a loop: denom uatom seen twice".to_string()
+        })
+    );
+
+    // invalid - route uses the same pool twice, without revisiting a denom
+    // this example: ATOM -> USDC -> OSMO -> MARS, the first and last steps through pool 100
+    let mut deps_multi_asset = helpers::setup_test();
+    deps_multi_asset.querier.set_query_pool_response(
+        100,
+        helpers::prepare_query_pool_response(
+            100,
+            &[coin(1, "uatom"), coin(1, "uosmo"), coin(1, "uusdc"), coin(1, "umars")],
+            &[2500u64, 2500u64, 2500u64, 2500u64],
+            &coin(1, "uLP"),
+            None,
+        ),
+    );
+    let route = OsmosisRoute(vec![
+        SwapAmountInRoute {
+            pool_id: 100,
+            token_out_denom: "uusdc".to_string(),
+        },
+        SwapAmountInRoute {
+            pool_id: 69,
+            token_out_denom: "uosmo".to_string(),
+        },
+        SwapAmountInRoute {
+            pool_id: 100,
+            token_out_denom: "umars".to_string(),
+        },
+    ]);
+    assert_eq!(
+        route.validate(&deps_multi_asset.as_ref().querier, "uatom", "umars"),
+        Err(ContractError::InvalidRoute {
+            reason: "step 3: pool 100 is used twice".to_string()
+        })
+    );
+
     // invalid - route's final output denom does not match the desired output
     let route = OsmosisRoute(vec![
         SwapAmountInRoute {
@@ -290,6 +397,23 @@ fn validating_route() {
         },
     ]);
     assert_eq!(route.validate(q, "uatom", "umars"), Ok(()));
+
+    // valid - three steps
+    let route = OsmosisRoute(vec![
+        SwapAmountInRoute {
+            pool_id: 68,
+            token_out_denom: "uusdc".to_string(),
+        },
+        SwapAmountInRoute {
+            pool_id: 69,
+            token_out_denom: "uosmo".to_string(),
+        },
+        SwapAmountInRoute {
+            pool_id: 420,
+            token_out_denom: "umars".to_string(),
+        },
+    ]);
+    assert_eq!(route.validate(q, "uatom", "umars"), Ok(()));
 }
 
 #[test]
@@ -306,3 +430,193 @@ fn stringifying_route() {
     ]);
     assert_eq!(route.to_string(), "1:uosmo|420:umars".to_string());
 }
+
+#[test]
+fn setting_time_bounded_route() {
+    let mut deps = helpers::setup_test();
+
+    let route = mock_routes().get(&("uatom", "umars")).unwrap().clone();
+
+    // the window must not be empty
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::SetTimeBoundedRoute {
+            denom_in: "uatom".to_string(),
+            denom_out: "umars".to_string(),
+            route: route.clone(),
+            valid_from: Some(200),
+            valid_until: Some(100),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Validation(ValidationError::InvalidParam {
+            param_name: "valid_until".to_string(),
+            invalid_value: "100".to_string(),
+            predicate: "> 200".to_string(),
+        })
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::SetTimeBoundedRoute {
+            denom_in: "uatom".to_string(),
+            denom_out: "umars".to_string(),
+            route: route.clone(),
+            valid_from: None,
+            valid_until: Some(100),
+        },
+    )
+    .unwrap();
+
+    let res: RouteResponse<OsmosisRoute> = helpers::query(
+        deps.as_ref(),
+        QueryMsg::Route {
+            denom_in: "uatom".to_string(),
+            denom_out: "umars".to_string(),
+        },
+    );
+    assert_eq!(res.route, route);
+}
+
+#[test]
+fn querying_route_exists() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_arithmetic_twap_price(
+        69,
+        "uusdc",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(1u128, 10u128).to_string(),
+        },
+    );
+    deps.querier.set_arithmetic_twap_price(
+        420,
+        "uosmo",
+        "umars",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(5u128, 10u128).to_string(),
+        },
+    );
+
+    // the uatom -> umars route expired before the current block
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::SetTimeBoundedRoute {
+            denom_in: "uatom".to_string(),
+            denom_out: "umars".to_string(),
+            route: mock_routes().get(&("uatom", "umars")).unwrap().clone(),
+            valid_from: None,
+            valid_until: Some(100),
+        },
+    )
+    .unwrap();
+
+    let query_route_exists = |deps: Deps, denom_in: &str, denom_out: &str| -> RouteExistsResponse {
+        helpers::query(
+            deps,
+            QueryMsg::RouteExists {
+                denom_in: denom_in.to_string(),
+                denom_out: denom_out.to_string(),
+            },
+        )
+    };
+
+    // missing route
+    let res = query_route_exists(deps.as_ref(), "umars", "uusdc");
+    assert_eq!(
+        res,
+        RouteExistsResponse {
+            exists: false,
+            usable: false,
+            reason: Some("no route set for umars -> uusdc".to_string()),
+        }
+    );
+
+    // expired route
+    let res = query_route_exists(deps.as_ref(), "uatom", "umars");
+    assert_eq!(
+        res,
+        RouteExistsResponse {
+            exists: true,
+            usable: false,
+            reason: Some(
+                ContractError::RouteNotActive {
+                    denom_in: "uatom".to_string(),
+                    denom_out: "umars".to_string(),
+                    time: mock_env().block.time.seconds(),
+                }
+                .to_string()
+            ),
+        }
+    );
+
+    // route without price records
+    let res = query_route_exists(deps.as_ref(), "uatom", "uusdc");
+    assert!(res.exists);
+    assert!(!res.usable);
+    assert!(res.reason.is_some());
+
+    // usable route
+    let res = query_route_exists(deps.as_ref(), "uusdc", "umars");
+    assert_eq!(
+        res,
+        RouteExistsResponse {
+            exists: true,
+            usable: true,
+            reason: None,
+        }
+    );
+}
+
+#[test]
+fn removing_route() {
+    let mut deps = helpers::setup_test();
+
+    let remove_msg = ExecuteMsg::RemoveRoute {
+        denom_in: "uatom".to_string(),
+        denom_out: "umars".to_string(),
+    };
+
+    // only the owner can remove routes
+    let err =
+        execute(deps.as_mut(), mock_env(), mock_info("jake"), remove_msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Owner(NotOwner {}));
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner"), remove_msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "mars/rewards-collector/remove_route"),
+            attr("denom_in", "uatom"),
+            attr("denom_out", "umars"),
+        ]
+    );
+
+    let res: RouteExistsResponse = helpers::query(
+        deps.as_ref(),
+        QueryMsg::RouteExists {
+            denom_in: "uatom".to_string(),
+            denom_out: "umars".to_string(),
+        },
+    );
+    assert!(!res.exists);
+
+    // the route can't be removed twice
+    let err = execute(deps.as_mut(), mock_env(), mock_info("owner"), remove_msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::RouteNotFound {
+            denom_in: "uatom".to_string(),
+            denom_out: "umars".to_string(),
+        }
+    );
+}
@@ -1,14 +1,28 @@
 use cosmwasm_std::{
-    testing::{mock_env, MOCK_CONTRACT_ADDR},
-    CosmosMsg, Decimal, Fraction, SubMsg, Uint128,
+    attr, coin, coins,
+    testing::{self, mock_env, MOCK_CONTRACT_ADDR},
+    BankMsg, CosmosMsg, Decimal, Fraction, Reply, SubMsg, SubMsgResponse, SubMsgResult, Uint128,
+};
+use mars_owner::OwnerError::NotOwner;
+use mars_red_bank_types::{
+    error::MarsError,
+    rewards_collector::{ConfigResponse, QueryMsg, SimulateSwapResponse, UpdateConfig},
+};
+use mars_rewards_collector_base::{
+    ContractError, Route, FORWARD_TO_FEE_COLLECTOR_REPLY_ID, SWAP_EXACT_IN_REPLY_ID,
+    SWAP_FUNDS_REPLY_ID,
+};
+use mars_rewards_collector_osmosis::{
+    contract::entry::{self, execute, reply},
+    msg::ExecuteMsg,
+    route, OsmosisRoute,
 };
-use mars_red_bank_types::rewards_collector::{ConfigResponse, QueryMsg};
-use mars_rewards_collector_osmosis::{contract::entry::execute, msg::ExecuteMsg};
 use mars_testing::mock_info;
 use osmosis_std::types::{
     cosmos::base::v1beta1::Coin,
     osmosis::{
-        gamm::v1beta1::MsgSwapExactAmountIn, poolmanager::v1beta1::SwapAmountInRoute,
+        gamm::v1beta1::{MsgSwapExactAmountIn, MsgSwapExactAmountOut},
+        poolmanager::v1beta1::{SwapAmountInRoute, SwapAmountOutRoute},
         twap::v1beta1::ArithmeticTwapToNowResponse,
     },
 };
@@ -37,6 +51,7 @@ fn swapping_asset_if_quering_price_fails() {
         ExecuteMsg::SwapAsset {
             denom: "uatom".to_string(),
             amount: Some(Uint128::new(42069)),
+            send_to_fee_collector: None,
         },
     )
     .unwrap_err();
@@ -120,6 +135,7 @@ fn swapping_asset() {
         ExecuteMsg::SwapAsset {
             denom: "uatom".to_string(),
             amount: Some(Uint128::new(42069)),
+            send_to_fee_collector: None,
         },
     )
     .unwrap();
@@ -149,6 +165,57 @@ fn swapping_asset() {
     assert_eq!(res.messages[1], SubMsg::new(swap_msg));
 }
 
+#[test]
+fn estimating_out_amount_with_pool_swap_fees() {
+    let mut deps = helpers::setup_test();
+
+    // uatom -> uosmo -> uusdc, charging 0.2% and 0.3% respectively
+    deps.querier.set_query_pool_response(
+        1,
+        helpers::prepare_query_pool_response(
+            1,
+            &[coin(1, "uatom"), coin(1, "uosmo")],
+            &[5000u64, 5000u64],
+            &coin(1, "uLP"),
+            Some(Decimal::permille(2)),
+        ),
+    );
+    deps.querier.set_query_pool_response(
+        69,
+        helpers::prepare_query_pool_response(
+            69,
+            &[coin(1, "uosmo"), coin(1, "uusdc")],
+            &[5000u64, 5000u64],
+            &coin(1, "uLP"),
+            Some(Decimal::permille(3)),
+        ),
+    );
+    deps.querier.set_arithmetic_twap_price(
+        1,
+        "uatom",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(125u128, 10u128).to_string(),
+        },
+    );
+    deps.querier.set_arithmetic_twap_price(
+        69,
+        "uosmo",
+        "uusdc",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(10u128, 1u128).to_string(),
+        },
+    );
+
+    let route = helpers::mock_routes()[&("uatom", "uusdc")].clone();
+    let out_amount = route
+        .estimate_out_amount(&mock_env(), &deps.as_ref().querier, "uatom", Uint128::new(10_000))
+        .unwrap();
+
+    // 10_000 * 12.5 * (1 - 0.002) * 10 * (1 - 0.003) = 1_243_757.5
+    assert_eq!(out_amount, Uint128::new(1_243_757));
+}
+
 /// Here we test the case where the denom is already the target denom.
 ///
 /// For example, for the Osmosis outpost, we plan to set
@@ -195,6 +262,7 @@ fn skipping_swap_if_denom_matches() {
         ExecuteMsg::SwapAsset {
             denom: "uusdc".to_string(),
             amount: None,
+            send_to_fee_collector: None,
         },
     )
     .unwrap();
@@ -235,3 +303,838 @@ fn skipping_swap_if_denom_matches() {
     .into();
     assert_eq!(res.messages[0], SubMsg::new(swap_msg));
 }
+
+#[test]
+fn swapping_with_route_slippage_override() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_arithmetic_twap_price(
+        69,
+        "uusdc",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(1u128, 10u128).to_string(),
+        },
+    );
+    deps.querier.set_arithmetic_twap_price(
+        420,
+        "uosmo",
+        "umars",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(5u128, 10u128).to_string(),
+        },
+    );
+
+    // allow a looser slippage on the USDC -> MARS route than the configured 3%
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::SetRoute {
+            denom_in: "uusdc".to_string(),
+            denom_out: "umars".to_string(),
+            route: helpers::mock_routes().get(&("uusdc", "umars")).unwrap().clone(),
+            slippage_override: Some(Decimal::percent(20)),
+        },
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake"),
+        ExecuteMsg::SwapAsset {
+            denom: "uusdc".to_string(),
+            amount: None,
+            send_to_fee_collector: None,
+        },
+    )
+    .unwrap();
+
+    // amount for fee collector: 1234 - 308 = 926
+    // estimated out amount: 926 * 0.1 * 0.5 = 46
+    // min out amount: 46 * (1 - 0.2) = 36
+    assert_eq!(res.messages.len(), 1);
+    let swap_msg: CosmosMsg = MsgSwapExactAmountIn {
+        sender: MOCK_CONTRACT_ADDR.to_string(),
+        routes: vec![
+            SwapAmountInRoute {
+                pool_id: 69,
+                token_out_denom: "uosmo".to_string(),
+            },
+            SwapAmountInRoute {
+                pool_id: 420,
+                token_out_denom: "umars".to_string(),
+            },
+        ],
+        token_in: Some(Coin {
+            denom: "uusdc".to_string(),
+            amount: "926".to_string(),
+        }),
+        token_out_min_amount: "36".to_string(),
+    }
+    .into();
+    assert_eq!(res.messages[0], SubMsg::new(swap_msg));
+}
+
+#[test]
+fn swapping_directly_to_fee_collector() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_arithmetic_twap_price(
+        69,
+        "uusdc",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(1u128, 10u128).to_string(),
+        },
+    );
+    deps.querier.set_arithmetic_twap_price(
+        420,
+        "uosmo",
+        "umars",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(5u128, 10u128).to_string(),
+        },
+    );
+
+    let swap_msg = ExecuteMsg::SwapAsset {
+        denom: "uusdc".to_string(),
+        amount: None,
+        send_to_fee_collector: Some(true),
+    };
+
+    // the fee collector module in the address provider is on Mars Hub, so nothing can be sent
+    // directly until a fee collector on this chain is configured
+    let err = execute(deps.as_mut(), mock_env(), mock_info("jake"), swap_msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::NoLocalFeeCollector {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::UpdateConfig {
+            new_cfg: UpdateConfig {
+                local_fee_collector: Some("local_fee_collector".to_string()),
+                ..Default::default()
+            },
+        },
+    )
+    .unwrap();
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("jake"), swap_msg).unwrap();
+
+    // same swap as when the denom matches, but the fee collector's output is forwarded on reply
+    let swap_msg: CosmosMsg = MsgSwapExactAmountIn {
+        sender: MOCK_CONTRACT_ADDR.to_string(),
+        routes: vec![
+            SwapAmountInRoute {
+                pool_id: 69,
+                token_out_denom: "uosmo".to_string(),
+            },
+            SwapAmountInRoute {
+                pool_id: 420,
+                token_out_denom: "umars".to_string(),
+            },
+        ],
+        token_in: Some(Coin {
+            denom: "uusdc".to_string(),
+            amount: "926".to_string(),
+        }),
+        token_out_min_amount: "44".to_string(),
+    }
+    .into();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(swap_msg, FORWARD_TO_FEE_COLLECTOR_REPLY_ID)]
+    );
+
+    // the swap yields 45 umars on top of the 8964 umars the contract already held
+    deps.querier.set_contract_balances(&[coin(308, "uusdc"), coin(9009, "umars")]);
+
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: FORWARD_TO_FEE_COLLECTOR_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+
+    // only the swap output lands at the fee collector, not the pre-existing balance
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "local_fee_collector".to_string(),
+            amount: coins(45, "umars"),
+        })]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "forward_to_fee_collector"),
+            attr("to", "local_fee_collector"),
+            attr("denom", "umars"),
+            attr("amount", "45"),
+        ]
+    );
+}
+
+#[test]
+fn swapping_funds() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_arithmetic_twap_price(
+        69,
+        "uusdc",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(1u128, 10u128).to_string(),
+        },
+    );
+    deps.querier.set_arithmetic_twap_price(
+        420,
+        "uosmo",
+        "umars",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(5u128, 10u128).to_string(),
+        },
+    );
+
+    let swap_msg = ExecuteMsg::SwapFunds {
+        denom_out: "umars".to_string(),
+    };
+    let funds = coins(926, "uusdc");
+
+    // only the incentives contract and the Red Bank can swap funds through the contract
+    let err =
+        execute(deps.as_mut(), mock_env(), testing::mock_info("jake", &funds), swap_msg.clone())
+            .unwrap_err();
+    assert_eq!(err, ContractError::Mars(MarsError::Unauthorized {}));
+
+    // a single coin must be sent to be swapped
+    let err =
+        execute(deps.as_mut(), mock_env(), mock_info("incentives"), swap_msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::InvalidSwapFunds {});
+
+    let res =
+        execute(deps.as_mut(), mock_env(), testing::mock_info("incentives", &funds), swap_msg)
+            .unwrap();
+
+    let swap_msg: CosmosMsg = MsgSwapExactAmountIn {
+        sender: MOCK_CONTRACT_ADDR.to_string(),
+        routes: vec![
+            SwapAmountInRoute {
+                pool_id: 69,
+                token_out_denom: "uosmo".to_string(),
+            },
+            SwapAmountInRoute {
+                pool_id: 420,
+                token_out_denom: "umars".to_string(),
+            },
+        ],
+        token_in: Some(Coin {
+            denom: "uusdc".to_string(),
+            amount: "926".to_string(),
+        }),
+        token_out_min_amount: "44".to_string(),
+    }
+    .into();
+    assert_eq!(res.messages, vec![SubMsg::reply_on_success(swap_msg, SWAP_FUNDS_REPLY_ID)]);
+
+    // the swap yields 45 umars on top of the 8964 umars the contract already held
+    deps.querier.set_contract_balances(&[coin(1234, "uusdc"), coin(9009, "umars")]);
+
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: SWAP_FUNDS_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+
+    // only the swap output is sent back, not the revenue held in the same denom
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "incentives".to_string(),
+            amount: coins(45, "umars"),
+        })]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "return_swapped_funds"),
+            attr("to", "incentives"),
+            attr("denom_out", "umars"),
+            attr("amount_out", "45"),
+        ]
+    );
+}
+
+#[test]
+fn swapping_with_time_bounded_route() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_arithmetic_twap_price(
+        69,
+        "uusdc",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(1u128, 10u128).to_string(),
+        },
+    );
+    deps.querier.set_arithmetic_twap_price(
+        420,
+        "uosmo",
+        "umars",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(5u128, 10u128).to_string(),
+        },
+    );
+
+    let now = mock_env().block.time.seconds();
+    let valid_from = now + 100;
+    let valid_until = now + 200;
+
+    // replace the USDC -> MARS route with one that is only active within a time window
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::SetTimeBoundedRoute {
+            denom_in: "uusdc".to_string(),
+            denom_out: "umars".to_string(),
+            route: helpers::mock_routes().get(&("uusdc", "umars")).unwrap().clone(),
+            valid_from: Some(valid_from),
+            valid_until: Some(valid_until),
+        },
+    )
+    .unwrap();
+
+    let swap_msg = ExecuteMsg::SwapAsset {
+        denom: "uusdc".to_string(),
+        amount: None,
+        send_to_fee_collector: None,
+    };
+
+    // before the window opens
+    let err = execute(deps.as_mut(), mock_env(), mock_info("jake"), swap_msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::RouteNotActive {
+            denom_in: "uusdc".to_string(),
+            denom_out: "umars".to_string(),
+            time: now,
+        }
+    );
+
+    // within the window
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(150);
+    let res = execute(deps.as_mut(), env, mock_info("jake"), swap_msg.clone()).unwrap();
+    assert_eq!(res.messages.len(), 1);
+
+    // after the window closes; `valid_until` is exclusive
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(200);
+    let err = execute(deps.as_mut(), env, mock_info("jake"), swap_msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::RouteNotActive {
+            denom_in: "uusdc".to_string(),
+            denom_out: "umars".to_string(),
+            time: valid_until,
+        }
+    );
+}
+
+#[test]
+fn swapping_multiple_assets() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_contract_balances(&[
+        coin(88888, "uatom"),
+        coin(1234, "uusdc"),
+        coin(8964, "umars"),
+        coin(50000, "uosmo"),
+    ]);
+
+    // uosmo -> uusdc
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner"),
+        ExecuteMsg::SetRoute {
+            denom_in: "uosmo".to_string(),
+            denom_out: "uusdc".to_string(),
+            route: OsmosisRoute(vec![route::SwapAmountInRoute {
+                pool_id: 69,
+                token_out_denom: "uusdc".to_string(),
+            }]),
+            slippage_override: None,
+        },
+    )
+    .unwrap();
+
+    for (pool_id, base_asset, quote_asset, price) in [
+        (1, "uatom", "uosmo", Decimal::from_ratio(125u128, 10u128)),
+        (69, "uosmo", "uusdc", Decimal::from_ratio(10u128, 1u128)),
+        (69, "uusdc", "uosmo", Decimal::from_ratio(1u128, 10u128)),
+        (420, "uosmo", "umars", Decimal::from_ratio(5u128, 10u128)),
+    ] {
+        deps.querier.set_arithmetic_twap_price(
+            pool_id,
+            base_asset,
+            quote_asset,
+            ArithmeticTwapToNowResponse {
+                arithmetic_twap: price.to_string(),
+            },
+        );
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake"),
+        ExecuteMsg::SwapAssets {
+            denoms: vec![
+                ("uatom".to_string(), Some(Uint128::new(42069))),
+                ("uusdc".to_string(), None),
+                ("uosmo".to_string(), None),
+            ],
+        },
+    )
+    .unwrap();
+
+    // uatom is swapped for both recipients, uusdc only for the fee collector, and uosmo for both
+    let swapped_denoms: Vec<_> = res
+        .messages
+        .iter()
+        .map(|msg| match &msg.msg {
+            CosmosMsg::Stargate {
+                value,
+                ..
+            } => {
+                let msg = MsgSwapExactAmountIn::try_from(value.clone()).unwrap();
+                let token_in = msg.token_in.unwrap();
+                (token_in.denom, msg.routes.last().unwrap().token_out_denom.clone())
+            }
+            msg => panic!("expected a swap message, got {msg:?}"),
+        })
+        .collect();
+    assert_eq!(
+        swapped_denoms,
+        vec![
+            ("uatom".to_string(), "uusdc".to_string()),
+            ("uatom".to_string(), "umars".to_string()),
+            ("uusdc".to_string(), "umars".to_string()),
+            ("uosmo".to_string(), "uusdc".to_string()),
+            ("uosmo".to_string(), "umars".to_string()),
+        ]
+    );
+
+    // the number of swap messages produced by a single call is bounded
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake"),
+        ExecuteMsg::SwapAssets {
+            denoms: vec![("uatom".to_string(), Some(Uint128::new(100))); 11],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TooManySwapMsgs {
+            max: 20
+        }
+    );
+}
+
+#[test]
+fn querying_min_output() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_arithmetic_twap_price(
+        69,
+        "uusdc",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(1u128, 10u128).to_string(),
+        },
+    );
+    deps.querier.set_arithmetic_twap_price(
+        420,
+        "uosmo",
+        "umars",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(5u128, 10u128).to_string(),
+        },
+    );
+
+    // 926 * 0.1 * 0.5 * (1 - 0.03) = 44.911
+    let min_output: Uint128 = helpers::query(
+        deps.as_ref(),
+        QueryMsg::MinOutput {
+            coin_in: coin(926, "uusdc"),
+            denom_out: "umars".to_string(),
+            slippage: Decimal::percent(3),
+        },
+    );
+    assert_eq!(min_output, Uint128::new(44));
+
+    // the same minimum output is enforced when actually swapping
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake"),
+        ExecuteMsg::SwapAsset {
+            denom: "uusdc".to_string(),
+            amount: None,
+            send_to_fee_collector: None,
+        },
+    )
+    .unwrap();
+    let swap_msg: CosmosMsg = MsgSwapExactAmountIn {
+        sender: MOCK_CONTRACT_ADDR.to_string(),
+        routes: vec![
+            SwapAmountInRoute {
+                pool_id: 69,
+                token_out_denom: "uosmo".to_string(),
+            },
+            SwapAmountInRoute {
+                pool_id: 420,
+                token_out_denom: "umars".to_string(),
+            },
+        ],
+        token_in: Some(Coin {
+            denom: "uusdc".to_string(),
+            amount: "926".to_string(),
+        }),
+        token_out_min_amount: min_output.to_string(),
+    }
+    .into();
+    assert_eq!(res.messages[0], SubMsg::new(swap_msg));
+
+    // slippage tolerance can't exceed 100%
+    entry::query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::MinOutput {
+            coin_in: coin(926, "uusdc"),
+            denom_out: "umars".to_string(),
+            slippage: Decimal::percent(101),
+        },
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn querying_simulated_swap() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_arithmetic_twap_price(
+        69,
+        "uusdc",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(1u128, 10u128).to_string(),
+        },
+    );
+    deps.querier.set_arithmetic_twap_price(
+        420,
+        "uosmo",
+        "umars",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(5u128, 10u128).to_string(),
+        },
+    );
+
+    // 926 * 0.1 * 0.5 = 46.3, no slippage tolerance applied
+    let res: SimulateSwapResponse = helpers::query(
+        deps.as_ref(),
+        QueryMsg::SimulateSwap {
+            denom_in: "uusdc".to_string(),
+            denom_out: "umars".to_string(),
+            amount: Uint128::new(926),
+        },
+    );
+    assert_eq!(res.amount_out, Uint128::new(46));
+
+    // there is no route from umars to uusdc
+    entry::query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SimulateSwap {
+            denom_in: "umars".to_string(),
+            denom_out: "uusdc".to_string(),
+            amount: Uint128::new(926),
+        },
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn swapping_exact_in() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_arithmetic_twap_price(
+        1,
+        "uatom",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(125u128, 10u128).to_string(),
+        },
+    );
+    deps.querier.set_arithmetic_twap_price(
+        420,
+        "uosmo",
+        "umars",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(5u128, 10u128).to_string(),
+        },
+    );
+
+    let swap_exact_in =
+        |slippage: Option<Decimal>, min_output: Option<u128>| ExecuteMsg::SwapExactIn {
+            denom_in: "uatom".to_string(),
+            amount: Some(Uint128::new(10000)),
+            denom_out: "umars".to_string(),
+            slippage,
+            min_output: min_output.map(Uint128::new),
+        };
+
+    // only the owner can swap
+    let err = execute(deps.as_mut(), mock_env(), mock_info("jake"), swap_exact_in(None, None))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Owner(NotOwner {}));
+
+    // the estimated output is 10000 * 12.5 * 0.5 = 62500. An explicit min output is used as is,
+    // unless a slippage is also given, in which case the stricter minimum wins
+    for (slippage, min_output, token_out_min_amount) in [
+        (None, None, "60625"),
+        (None, Some(1), "1"),
+        (Some(Decimal::percent(5)), Some(61000), "61000"),
+        (Some(Decimal::percent(1)), Some(61000), "61875"),
+    ] {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner"),
+            swap_exact_in(slippage, min_output),
+        )
+        .unwrap();
+        let swap_msg: CosmosMsg = MsgSwapExactAmountIn {
+            sender: MOCK_CONTRACT_ADDR.to_string(),
+            routes: vec![
+                SwapAmountInRoute {
+                    pool_id: 1,
+                    token_out_denom: "uosmo".to_string(),
+                },
+                SwapAmountInRoute {
+                    pool_id: 420,
+                    token_out_denom: "umars".to_string(),
+                },
+            ],
+            token_in: Some(Coin {
+                denom: "uatom".to_string(),
+                amount: "10000".to_string(),
+            }),
+            token_out_min_amount: token_out_min_amount.to_string(),
+        }
+        .into();
+        assert_eq!(res.messages, vec![SubMsg::reply_on_success(swap_msg, SWAP_EXACT_IN_REPLY_ID)]);
+    }
+
+    // a zero floor, whether given or derived from a 100% slippage, is rejected
+    for (slippage, min_output) in [(None, Some(0)), (Some(Decimal::one()), None)] {
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner"),
+            swap_exact_in(slippage, min_output),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::ZeroMinOutput {});
+    }
+}
+
+#[test]
+fn swapping_exact_in_from_contract_balance() {
+    let mut deps = helpers::setup_test();
+
+    // funds deposited into the collector in a prior step, none are attached to the swap
+    deps.querier.set_contract_balances(&[coin(5000, "uatom")]);
+
+    let swap_exact_in = |amount: Option<u128>| ExecuteMsg::SwapExactIn {
+        denom_in: "uatom".to_string(),
+        amount: amount.map(Uint128::new),
+        denom_out: "umars".to_string(),
+        slippage: None,
+        min_output: Some(Uint128::new(30000)),
+    };
+
+    // can't swap more than the contract holds
+    let err = execute(deps.as_mut(), mock_env(), mock_info("owner"), swap_exact_in(Some(5001)))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AmountToDistributeTooLarge {
+            amount: Uint128::new(5001),
+            balance: Uint128::new(5000),
+        }
+    );
+
+    // without an amount, the whole balance is swapped
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner"), swap_exact_in(None)).unwrap();
+    let swap_msg: CosmosMsg = MsgSwapExactAmountIn {
+        sender: MOCK_CONTRACT_ADDR.to_string(),
+        routes: vec![
+            SwapAmountInRoute {
+                pool_id: 1,
+                token_out_denom: "uosmo".to_string(),
+            },
+            SwapAmountInRoute {
+                pool_id: 420,
+                token_out_denom: "umars".to_string(),
+            },
+        ],
+        token_in: Some(Coin {
+            denom: "uatom".to_string(),
+            amount: "5000".to_string(),
+        }),
+        token_out_min_amount: "30000".to_string(),
+    }
+    .into();
+    assert_eq!(res.messages, vec![SubMsg::reply_on_success(swap_msg, SWAP_EXACT_IN_REPLY_ID)]);
+
+    // the swap yields 31250 umars, which the reply reports as the amount actually received
+    deps.querier.set_contract_balances(&[coin(31250, "umars")]);
+
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: SWAP_EXACT_IN_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "swap_exact_in_reply"),
+            attr("denom_out", "umars"),
+            attr("amount_out", "31250"),
+        ]
+    );
+}
+
+#[test]
+fn swapping_exact_out() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_arithmetic_twap_price(
+        1,
+        "uatom",
+        "uosmo",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(125u128, 10u128).to_string(),
+        },
+    );
+    deps.querier.set_arithmetic_twap_price(
+        420,
+        "uosmo",
+        "umars",
+        ArithmeticTwapToNowResponse {
+            arithmetic_twap: Decimal::from_ratio(5u128, 10u128).to_string(),
+        },
+    );
+
+    let swap_exact_out = |max_input: u128| ExecuteMsg::SwapExactOut {
+        coin_out: coin(62500, "umars"),
+        denom_in: "uatom".to_string(),
+        max_input: Uint128::new(max_input),
+        slippage: Decimal::percent(5),
+    };
+
+    // only the owner can swap
+    let err =
+        execute(deps.as_mut(), mock_env(), mock_info("jake"), swap_exact_out(20000)).unwrap_err();
+    assert_eq!(err, ContractError::Owner(NotOwner {}));
+
+    // 62500 / (12.5 * 0.5) = 10000 uatom needed, which can't exceed the max input
+    let err =
+        execute(deps.as_mut(), mock_env(), mock_info("owner"), swap_exact_out(9999)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MaxInputExceeded {
+            required: Uint128::new(10000),
+            max_input: Uint128::new(9999),
+        }
+    );
+
+    // the input spent is capped by the slippage tolerance: 10000 * 1.05 = 10500, or by the max
+    // input if it's lower
+    for (max_input, token_in_max_amount) in [(20000, "10500"), (10200, "10200")] {
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner"), swap_exact_out(max_input))
+            .unwrap();
+        let swap_msg: CosmosMsg = MsgSwapExactAmountOut {
+            sender: MOCK_CONTRACT_ADDR.to_string(),
+            routes: vec![
+                SwapAmountOutRoute {
+                    pool_id: 1,
+                    token_in_denom: "uatom".to_string(),
+                },
+                SwapAmountOutRoute {
+                    pool_id: 420,
+                    token_in_denom: "uosmo".to_string(),
+                },
+            ],
+            token_in_max_amount: token_in_max_amount.to_string(),
+            token_out: Some(Coin {
+                denom: "umars".to_string(),
+                amount: "62500".to_string(),
+            }),
+        }
+        .into();
+        assert_eq!(res.messages, vec![SubMsg::new(swap_msg)]);
+    }
+
+    // the route's final output denom must be the requested output denom
+    let route = OsmosisRoute(vec![route::SwapAmountInRoute {
+        pool_id: 1,
+        token_out_denom: "uosmo".to_string(),
+    }]);
+    let err = route
+        .build_swap_exact_out_msg(
+            &mock_env(),
+            &deps.as_ref().querier,
+            "uatom",
+            &coin(62500, "umars"),
+            Uint128::new(20000),
+            Decimal::percent(5),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidRoute {
+            reason: "the route's output denom uosmo does not match the desired output umars"
+                .to_string()
+        }
+    );
+}
@@ -0,0 +1,48 @@
+use cosmwasm_std::{coin, Decimal, Uint128};
+use mars_red_bank_types::rewards_collector::{
+    PendingBalanceValue, QueryMsg, TotalPendingValueResponse,
+};
+
+mod helpers;
+
+#[test]
+fn querying_total_pending_value() {
+    let mut deps = helpers::setup_test();
+
+    deps.querier.set_contract_balances(&[
+        coin(1_000_000, "uosmo"),
+        coin(2_500_000, "uatom"),
+        coin(123_456, "uunpriced"),
+    ]);
+    deps.querier.set_oracle_price("uosmo", Decimal::percent(75));
+    deps.querier.set_oracle_price("uatom", Decimal::from_ratio(12u128, 1u128));
+
+    let res: TotalPendingValueResponse = helpers::query(
+        deps.as_ref(),
+        QueryMsg::TotalPendingValue {
+            denoms: vec!["uosmo".to_string(), "uunpriced".to_string(), "uatom".to_string()],
+        },
+    );
+    assert_eq!(
+        res,
+        TotalPendingValueResponse {
+            // 750_000 + 30_000_000
+            total_value: Uint128::new(30_750_000),
+            balances: vec![
+                PendingBalanceValue {
+                    denom: "uosmo".to_string(),
+                    amount: Uint128::new(1_000_000),
+                    price: Decimal::percent(75),
+                    value: Uint128::new(750_000),
+                },
+                PendingBalanceValue {
+                    denom: "uatom".to_string(),
+                    amount: Uint128::new(2_500_000),
+                    price: Decimal::from_ratio(12u128, 1u128),
+                    value: Uint128::new(30_000_000),
+                },
+            ],
+            unpriced: vec![coin(123_456, "uunpriced")],
+        }
+    );
+}
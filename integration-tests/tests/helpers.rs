@@ -28,6 +28,13 @@ pub fn default_asset_params() -> InitOrUpdateAssetParams {
         deposit_enabled: Some(true),
         borrow_enabled: Some(true),
         deposit_cap: None,
+        value_haircut: None,
+        min_deposit_amount: None,
+        reserve_recipient: None,
+        dust_threshold: None,
+        uncollateralized_debt_accrues_interest: None,
+        close_factor: None,
+        collateral_close_factor: None,
     }
 }
 
@@ -50,6 +57,13 @@ pub fn default_asset_params_with(
         deposit_enabled: Some(true),
         borrow_enabled: Some(true),
         deposit_cap: None,
+        value_haircut: None,
+        min_deposit_amount: None,
+        reserve_recipient: None,
+        dust_threshold: None,
+        uncollateralized_debt_accrues_interest: None,
+        close_factor: None,
+        collateral_close_factor: None,
     }
 }
 
@@ -374,6 +374,56 @@ fn multiple_users() {
     assert_eq!(rewards_balance, Uint128::new(720000)); // 288000 + (86400*5)
 }
 
+// User A and user B deposit the same amount of usdc in the red bank
+// User A withdraws half of the deposit after one day, without claiming rewards first
+#[test]
+fn rewards_settled_at_pre_withdraw_balance() {
+    let owner = Addr::unchecked("owner");
+    let mut mock_env = MockEnvBuilder::new(None, owner).build();
+
+    let red_bank = mock_env.red_bank.clone();
+    red_bank.init_asset(&mut mock_env, "uusdc", default_asset_params());
+
+    let incentives = mock_env.incentives.clone();
+    incentives.init_asset_incentive_from_current_block(&mut mock_env, "uusdc", 5, ONE_WEEK_IN_SEC);
+
+    let user_a = Addr::unchecked("user_a");
+    let user_b = Addr::unchecked("user_b");
+    let funded_amt = 10_000_000_000u128;
+    mock_env.fund_account(&user_a, &[coin(funded_amt, "uusdc")]);
+    mock_env.fund_account(&user_b, &[coin(funded_amt, "uusdc")]);
+
+    mock_env.fund_account(&incentives.contract_addr, &[coin(funded_amt, "umars")]);
+
+    red_bank.deposit(&mut mock_env, &user_a, coin(funded_amt, "uusdc")).unwrap();
+    red_bank.deposit(&mut mock_env, &user_b, coin(funded_amt, "uusdc")).unwrap();
+
+    mock_env.increment_by_time(86400); // 24 hours
+
+    let rewards_balance = incentives.query_unclaimed_rewards(&mut mock_env, &user_a);
+    assert_eq!(rewards_balance, Uint128::new(216000)); // (86400*5) * (1/2)
+
+    red_bank.withdraw(&mut mock_env, &user_a, "uusdc", Some(Uint128::new(funded_amt / 2))).unwrap();
+    let user_collateral = red_bank.query_user_collateral(&mut mock_env, &user_a, "uusdc");
+    assert_eq!(user_collateral.amount.u128(), funded_amt / 2);
+
+    // the rewards accrued on the full deposit are kept after the withdrawal
+    let rewards_balance = incentives.query_unclaimed_rewards(&mut mock_env, &user_a);
+    assert_eq!(rewards_balance, Uint128::new(216000));
+
+    mock_env.increment_by_time(86400); // 24 hours
+
+    let rewards_balance = incentives.query_unclaimed_rewards(&mut mock_env, &user_a);
+    assert_eq!(rewards_balance, Uint128::new(360000)); // 216000 + (86400*5) * (1/3)
+
+    let rewards_balance = incentives.query_unclaimed_rewards(&mut mock_env, &user_b);
+    assert_eq!(rewards_balance, Uint128::new(504000)); // 216000 + (86400*5) * (2/3)
+
+    incentives.claim_rewards(&mut mock_env, &user_a).unwrap();
+    let mars_balance = mock_env.query_balance(&user_a, "umars").unwrap();
+    assert_eq!(mars_balance.amount, Uint128::new(360000));
+}
+
 // User A attempts to claim rewards but there is not enough mars in the incentives contract
 #[test]
 fn insufficient_mars() {
@@ -568,3 +618,71 @@ fn rewards_distributed_among_users_and_rewards_collector() {
         mock_env.query_balance(&rewards_collector.contract_addr, "umars").unwrap();
     assert_eq!(umars_balance_rc.amount, rewards_balance_rc);
 }
+
+// User A deposits usdc, borrows mars and uses their mars rewards to repay the debt
+#[test]
+fn claim_and_repay() {
+    let owner = Addr::unchecked("owner");
+    let mut mock_env = MockEnvBuilder::new(None, owner).build();
+
+    let oracle = mock_env.oracle.clone();
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::one());
+    oracle.set_price_source_fixed(&mut mock_env, "umars", Decimal::from_ratio(2u128, 1u128));
+
+    let red_bank = mock_env.red_bank.clone();
+    red_bank.init_asset(&mut mock_env, "uusdc", default_asset_params());
+    red_bank.init_asset(&mut mock_env, "umars", default_asset_params());
+
+    let incentives = mock_env.incentives.clone();
+    incentives.init_asset_incentive_from_current_block(&mut mock_env, "uusdc", 10, ONE_WEEK_IN_SEC);
+
+    let funded_amt = 10_000_000_000u128;
+    mock_env.fund_account(&incentives.contract_addr, &[coin(funded_amt, "umars")]);
+
+    // provide some mars liquidity to borrow from
+    let lender = Addr::unchecked("lender");
+    mock_env.fund_account(&lender, &[coin(funded_amt, "umars")]);
+    red_bank.deposit(&mut mock_env, &lender, coin(funded_amt, "umars")).unwrap();
+
+    let user = Addr::unchecked("user_a");
+    mock_env.fund_account(&user, &[coin(funded_amt, "uusdc")]);
+    red_bank.deposit(&mut mock_env, &user, coin(funded_amt, "uusdc")).unwrap();
+    red_bank.borrow(&mut mock_env, &user, "umars", 500_000).unwrap();
+
+    mock_env.increment_by_time(86400); // 24 hours
+
+    let rewards_balance = incentives.query_unclaimed_rewards(&mut mock_env, &user);
+    assert_eq!(rewards_balance, Uint128::new(864000));
+
+    // rewards can only repay a debt in another denom if the rewards collector can swap them into it
+    incentives.claim_and_repay(&mut mock_env, &user, "uusdc").unwrap_err();
+
+    // rewards exceed the debt: the debt is fully repaid and the rest is sent to the user
+    let debt = red_bank.query_user_debt(&mut mock_env, &user, "umars");
+    assert!(debt.amount > Uint128::new(500_000));
+    incentives.claim_and_repay(&mut mock_env, &user, "umars").unwrap();
+
+    let debt_after = red_bank.query_user_debt(&mut mock_env, &user, "umars");
+    assert_eq!(debt_after.amount, Uint128::zero());
+    let balance = mock_env.query_balance(&user, "umars").unwrap();
+    assert_eq!(balance.amount, Uint128::new(500_000 + 864000) - debt.amount);
+    let rewards_balance = incentives.query_unclaimed_rewards(&mut mock_env, &user);
+    assert_eq!(rewards_balance, Uint128::zero());
+
+    // debt exceeds the rewards: all of the rewards go towards the debt
+    red_bank.borrow(&mut mock_env, &user, "umars", 2_000_000).unwrap();
+    let balance_before = mock_env.query_balance(&user, "umars").unwrap();
+    let red_bank_balance_before = mock_env.query_balance(&red_bank.contract_addr, "umars").unwrap();
+
+    mock_env.increment_by_time(86400); // 24 hours
+
+    let debt = red_bank.query_user_debt(&mut mock_env, &user, "umars");
+    incentives.claim_and_repay(&mut mock_env, &user, "umars").unwrap();
+
+    let debt_after = red_bank.query_user_debt(&mut mock_env, &user, "umars");
+    assert!(debt_after.amount < debt.amount);
+    let balance = mock_env.query_balance(&user, "umars").unwrap();
+    assert_eq!(balance, balance_before);
+    let red_bank_balance = mock_env.query_balance(&red_bank.contract_addr, "umars").unwrap();
+    assert_eq!(red_bank_balance.amount, red_bank_balance_before.amount + Uint128::new(864000));
+}
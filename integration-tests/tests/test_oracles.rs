@@ -17,7 +17,7 @@ use mars_red_bank_types::{
         ExecuteMsg::{Borrow, Deposit},
         InstantiateMsg as InstantiateRedBank,
     },
-    rewards_collector::InstantiateMsg as InstantiateRewards,
+    rewards_collector::{InstantiateMsg as InstantiateRewards, RewardRecipient, RewardSplit},
 };
 use osmosis_test_tube::{
     Account, Gamm, Module, OsmosisTestApp, RunnerResult, SigningAccount, Wasm,
@@ -1036,6 +1036,11 @@ fn setup_redbank(wasm: &Wasm<OsmosisTestApp>, signer: &SigningAccount) -> (Strin
             config: CreateOrUpdateConfig {
                 address_provider: Some(addr_provider_addr.clone()),
                 close_factor: Some(Decimal::percent(10)),
+                apply_value_haircut: None,
+                min_liquidation_value: None,
+                exclude_paused_collateral: None,
+                min_oracle_sources: None,
+                max_price_age: None,
             },
         },
     );
@@ -1058,9 +1063,20 @@ fn setup_redbank(wasm: &Wasm<OsmosisTestApp>, signer: &SigningAccount) -> (Strin
         &InstantiateRewards {
             owner: (signer.address()),
             address_provider: addr_provider_addr.clone(),
-            safety_tax_rate: Decimal::percent(25),
-            safety_fund_denom: "uosmo".to_string(),
-            fee_collector_denom: "uosmo".to_string(),
+            reward_splits: vec![
+                RewardSplit {
+                    recipient: RewardRecipient::Module(MarsAddressType::SafetyFund),
+                    denom: "uosmo".to_string(),
+                    weight: Decimal::percent(25),
+                    local_recipient: None,
+                },
+                RewardSplit {
+                    recipient: RewardRecipient::Module(MarsAddressType::FeeCollector),
+                    denom: "uosmo".to_string(),
+                    weight: Decimal::percent(75),
+                    local_recipient: None,
+                },
+            ],
             channel_id: "channel-1".to_string(),
             timeout_seconds: 60,
             slippage_tolerance: Decimal::new(Uint128::from(1u128)),
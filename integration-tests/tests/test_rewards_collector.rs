@@ -3,7 +3,10 @@ use mars_red_bank_types::{
     address_provider::{
         ExecuteMsg as ExecuteMsgAddr, InstantiateMsg as InstantiateAddr, MarsAddressType,
     },
-    rewards_collector::{ExecuteMsg, InstantiateMsg as InstantiateRewards, UpdateConfig},
+    rewards_collector::{
+        ExecuteMsg, InstantiateMsg as InstantiateRewards, RewardRecipient, RewardSplit,
+        UpdateConfig,
+    },
 };
 use mars_rewards_collector_osmosis::{route::SwapAmountInRoute, OsmosisRoute};
 use osmosis_test_tube::{Account, Gamm, Module, OsmosisTestApp, Wasm};
@@ -60,9 +63,20 @@ fn swapping_rewards() {
         &InstantiateRewards {
             owner: signer.address(),
             address_provider: addr_provider_addr,
-            safety_tax_rate: Decimal::percent(25),
-            safety_fund_denom: safety_fund_denom.to_string(),
-            fee_collector_denom: fee_collector_denom.to_string(),
+            reward_splits: vec![
+                RewardSplit {
+                    recipient: RewardRecipient::Module(MarsAddressType::SafetyFund),
+                    denom: safety_fund_denom.to_string(),
+                    weight: Decimal::percent(25),
+                    local_recipient: None,
+                },
+                RewardSplit {
+                    recipient: RewardRecipient::Module(MarsAddressType::FeeCollector),
+                    denom: fee_collector_denom.to_string(),
+                    weight: Decimal::percent(75),
+                    local_recipient: None,
+                },
+            ],
             channel_id: "channel-1".to_string(),
             timeout_seconds: 60,
             slippage_tolerance: Decimal::percent(1),
@@ -122,6 +136,7 @@ fn swapping_rewards() {
                 pool_id: pool_usdc_osmo,
                 token_out_denom: safety_fund_denom.to_string(),
             }]),
+            slippage_override: None,
         },
         &[],
         signer,
@@ -136,6 +151,7 @@ fn swapping_rewards() {
                 pool_id: pool_mars_osmo,
                 token_out_denom: fee_collector_denom.to_string(),
             }]),
+            slippage_override: None,
         },
         &[],
         signer,
@@ -156,6 +172,7 @@ fn swapping_rewards() {
                     token_out_denom: safety_fund_denom.to_string(),
                 },
             ]),
+            slippage_override: None,
         },
         &[],
         signer,
@@ -176,6 +193,7 @@ fn swapping_rewards() {
                     token_out_denom: fee_collector_denom.to_string(),
                 },
             ]),
+            slippage_override: None,
         },
         &[],
         signer,
@@ -201,6 +219,7 @@ fn swapping_rewards() {
         &ExecuteMsg::<OsmosisRoute>::SwapAsset {
             denom: "uosmo".to_string(),
             amount: None,
+            send_to_fee_collector: None,
         },
         &[],
         signer,
@@ -213,6 +232,7 @@ fn swapping_rewards() {
         &ExecuteMsg::<OsmosisRoute>::SwapAsset {
             denom: "uatom".to_string(),
             amount: None,
+            send_to_fee_collector: None,
         },
         &[],
         signer,
@@ -289,9 +309,20 @@ fn distribute_rewards_if_ibc_channel_invalid() {
         &InstantiateRewards {
             owner: signer.address(),
             address_provider: addr_provider_addr,
-            safety_tax_rate: Decimal::percent(50),
-            safety_fund_denom: safety_fund_denom.to_string(),
-            fee_collector_denom: fee_collector_denom.to_string(),
+            reward_splits: vec![
+                RewardSplit {
+                    recipient: RewardRecipient::Module(MarsAddressType::SafetyFund),
+                    denom: safety_fund_denom.to_string(),
+                    weight: Decimal::percent(50),
+                    local_recipient: None,
+                },
+                RewardSplit {
+                    recipient: RewardRecipient::Module(MarsAddressType::FeeCollector),
+                    denom: fee_collector_denom.to_string(),
+                    weight: Decimal::percent(50),
+                    local_recipient: None,
+                },
+            ],
             channel_id: "".to_string(),
             timeout_seconds: 60,
             slippage_tolerance: Decimal::percent(1),
@@ -316,6 +347,7 @@ fn distribute_rewards_if_ibc_channel_invalid() {
             &ExecuteMsg::<OsmosisRoute>::DistributeRewards {
                 denom: "uusdc".to_string(),
                 amount: None,
+                timeout_timestamp: None,
             },
             &[],
             signer,
@@ -329,12 +361,14 @@ fn distribute_rewards_if_ibc_channel_invalid() {
         &ExecuteMsg::<OsmosisRoute>::UpdateConfig {
             new_cfg: UpdateConfig {
                 address_provider: None,
-                safety_tax_rate: None,
-                safety_fund_denom: None,
-                fee_collector_denom: None,
+                reward_splits: None,
                 channel_id: Some("channel-1".to_string()),
                 timeout_seconds: None,
+                timeout_blocks: None,
+                counterparty_height: None,
                 slippage_tolerance: None,
+                ibc_memo: None,
+                local_fee_collector: None,
             },
         },
         &[],
@@ -349,6 +383,7 @@ fn distribute_rewards_if_ibc_channel_invalid() {
             &ExecuteMsg::<OsmosisRoute>::DistributeRewards {
                 denom: "umars".to_string(),
                 amount: None,
+                timeout_timestamp: None,
             },
             &[],
             signer,
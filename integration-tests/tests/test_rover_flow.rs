@@ -51,17 +51,22 @@ fn rover_flow() {
     assert!(debt.uncollateralized);
     assert_eq!(debt.amount.u128(), rover_uusdc_limit);
 
-    // should be possible to update the credit line to less than current debt
+    // can't update the credit line to less than current debt
     let half_rover_uusdc_limit = rover_uusdc_limit / 2u128;
-    red_bank
-        .update_uncollateralized_loan_limit(
-            &mut mock_env,
-            &owner,
-            &rover,
-            "uusdc",
-            Uint128::from(half_rover_uusdc_limit),
-        )
-        .unwrap();
+    let res_err = red_bank.update_uncollateralized_loan_limit(
+        &mut mock_env,
+        &owner,
+        &rover,
+        "uusdc",
+        Uint128::from(half_rover_uusdc_limit),
+    );
+    assert_err(
+        res_err,
+        ContractError::UncollateralizedLoanLimitBelowDebt {
+            new_limit: Uint128::from(half_rover_uusdc_limit),
+            debt: Uint128::from(rover_uusdc_limit),
+        },
+    );
 
     // can't borrow above the credit line
     let res_err = red_bank.borrow(&mut mock_env, &rover, "uusdc", 1u128);
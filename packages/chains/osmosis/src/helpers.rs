@@ -46,6 +46,14 @@ impl Pool {
             coin(Uint128::from_str(&osmosis_coin.amount)?.u128(), &osmosis_coin.denom);
         Ok(cosmwasm_coin)
     }
+
+    /// Fee charged by the pool on the input amount of each swap
+    pub fn swap_fee(&self) -> StdResult<Decimal> {
+        match &self.pool_params {
+            Some(params) => Decimal::from_str(&params.swap_fee),
+            None => Ok(Decimal::zero()),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -191,4 +199,25 @@ mod tests {
         let res = Pool::unwrap_coin(&pool.pool_assets[1].token).unwrap();
         assert_eq!(res, coin(430, "denom_2"));
     }
+
+    #[test]
+    fn reading_swap_fee() {
+        let mut pool = Pool {
+            id: "1111".to_string(),
+            address: "".to_string(),
+            pool_params: None,
+            future_pool_governor: "".to_string(),
+            pool_assets: vec![],
+            total_shares: None,
+            total_weight: "".to_string(),
+        };
+        assert_eq!(pool.swap_fee().unwrap(), Decimal::zero());
+
+        pool.pool_params = Some(PoolParams {
+            swap_fee: "0.002000000000000000".to_string(),
+            exit_fee: "0.000000000000000000".to_string(),
+            smooth_weight_change_params: None,
+        });
+        assert_eq!(pool.swap_fee().unwrap(), Decimal::permille(2));
+    }
 }
@@ -1,7 +1,7 @@
 use std::{collections::HashMap, fmt};
 
 use cosmwasm_std::{Addr, Coin, Decimal, Fraction, QuerierWrapper, StdResult, Uint128};
-use mars_red_bank_types::red_bank::Market;
+use mars_red_bank_types::red_bank::{self, Market};
 
 use crate::{error::HealthError, query::MarsQuerier};
 
@@ -15,6 +15,29 @@ pub struct Position {
     pub liquidation_threshold: Decimal,
 }
 
+impl From<&red_bank::Position> for Position {
+    fn from(p: &red_bank::Position) -> Self {
+        // if it is an "uncollateralized" debt, then it won't count towards their health factor
+        let debt_amount = if p.uncollateralized_debt {
+            Uint128::zero()
+        } else {
+            p.debt_amount
+        };
+
+        // discount the collateral by the asset's value haircut, if any
+        let collateral_amount = p.collateral_amount * (Decimal::one() - p.value_haircut);
+
+        Position {
+            denom: p.denom.clone(),
+            price: p.asset_price,
+            collateral_amount,
+            debt_amount,
+            max_ltv: p.max_ltv,
+            liquidation_threshold: p.liquidation_threshold,
+        }
+    }
+}
+
 #[derive(Default, Debug, PartialEq, Eq)]
 pub struct Health {
     /// The sum of the value of all debts
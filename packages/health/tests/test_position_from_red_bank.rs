@@ -0,0 +1,36 @@
+use cosmwasm_std::{Decimal, Uint128};
+use mars_health::health::Position;
+use mars_red_bank_types::red_bank;
+
+#[test]
+fn position_from_red_bank_position() {
+    let position = red_bank::Position {
+        denom: "osmo".to_string(),
+        collateral_amount: Uint128::new(1000),
+        debt_amount: Uint128::new(500),
+        uncollateralized_debt: false,
+        max_ltv: Decimal::percent(50),
+        liquidation_threshold: Decimal::percent(60),
+        asset_price: Decimal::from_atomics(23654u128, 4).unwrap(),
+        value_haircut: Decimal::percent(15),
+    };
+
+    assert_eq!(
+        Position::from(&position),
+        Position {
+            denom: "osmo".to_string(),
+            price: Decimal::from_atomics(23654u128, 4).unwrap(),
+            collateral_amount: Uint128::new(850),
+            debt_amount: Uint128::new(500),
+            max_ltv: Decimal::percent(50),
+            liquidation_threshold: Decimal::percent(60),
+        }
+    );
+
+    // uncollateralized debt doesn't count towards health
+    let position = red_bank::Position {
+        uncollateralized_debt: true,
+        ..position
+    };
+    assert_eq!(Position::from(&position).debt_amount, Uint128::zero());
+}
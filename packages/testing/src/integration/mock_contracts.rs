@@ -19,7 +19,8 @@ pub fn mock_incentives_contract() -> Box<dyn Contract<Empty>> {
         mars_incentives::contract::execute,
         mars_incentives::contract::instantiate,
         mars_incentives::contract::query,
-    );
+    )
+    .with_reply(mars_incentives::contract::reply);
     Box::new(contract)
 }
 
@@ -37,7 +38,8 @@ pub fn mock_red_bank_contract() -> Box<dyn Contract<Empty>> {
         mars_red_bank::contract::execute,
         mars_red_bank::contract::instantiate,
         mars_red_bank::contract::query,
-    );
+    )
+    .with_reply(mars_red_bank::contract::reply);
     Box::new(contract)
 }
 
@@ -103,7 +103,8 @@ impl Incentives {
                 self.contract_addr.clone(),
                 &incentives::ExecuteMsg::SetAssetIncentive {
                     denom: denom.to_string(),
-                    emission_per_second: Some(emission_per_second.into()),
+                    kind: None,
+                    emission: Some(incentives::Emission::PerSecond(emission_per_second.into())),
                     start_time: Some(current_block_time),
                     duration: Some(duration),
                 },
@@ -126,7 +127,8 @@ impl Incentives {
                 self.contract_addr.clone(),
                 &incentives::ExecuteMsg::SetAssetIncentive {
                     denom: denom.to_string(),
-                    emission_per_second: Some(emission_per_second.into()),
+                    kind: None,
+                    emission: Some(incentives::Emission::PerSecond(emission_per_second.into())),
                     start_time: Some(start_time),
                     duration: Some(duration),
                 },
@@ -147,7 +149,8 @@ impl Incentives {
                 self.contract_addr.clone(),
                 &incentives::ExecuteMsg::SetAssetIncentive {
                     denom: denom.to_string(),
-                    emission_per_second: Some(emission_per_second.into()),
+                    kind: None,
+                    emission: Some(incentives::Emission::PerSecond(emission_per_second.into())),
                     start_time: None,
                     duration: None,
                 },
@@ -160,7 +163,25 @@ impl Incentives {
         env.app.execute_contract(
             sender.clone(),
             self.contract_addr.clone(),
-            &incentives::ExecuteMsg::ClaimRewards {},
+            &incentives::ExecuteMsg::ClaimRewards {
+                recipient: None,
+            },
+            &[],
+        )
+    }
+
+    pub fn claim_and_repay(
+        &self,
+        env: &mut MockEnv,
+        sender: &Addr,
+        debt_denom: &str,
+    ) -> AnyResult<AppResponse> {
+        env.app.execute_contract(
+            sender.clone(),
+            self.contract_addr.clone(),
+            &incentives::ExecuteMsg::ClaimAndRepay {
+                debt_denom: debt_denom.to_string(),
+            },
             &[],
         )
     }
@@ -449,9 +470,7 @@ pub struct MockEnvBuilder {
     close_factor: Decimal,
 
     // rewards-collector params
-    safety_tax_rate: Decimal,
-    safety_fund_denom: String,
-    fee_collector_denom: String,
+    reward_splits: Vec<rewards_collector::RewardSplit>,
     slippage_tolerance: Decimal,
 
     pyth_contract_addr: String,
@@ -469,9 +488,24 @@ impl MockEnvBuilder {
             base_denom: "uosmo".to_string(),
             base_denom_decimals: 6u8,
             close_factor: Decimal::percent(80),
-            safety_tax_rate: Decimal::percent(50),
-            safety_fund_denom: "uusdc".to_string(),
-            fee_collector_denom: "uusdc".to_string(),
+            reward_splits: vec![
+                rewards_collector::RewardSplit {
+                    recipient: rewards_collector::RewardRecipient::Module(
+                        MarsAddressType::SafetyFund,
+                    ),
+                    denom: "uusdc".to_string(),
+                    weight: Decimal::percent(50),
+                    local_recipient: None,
+                },
+                rewards_collector::RewardSplit {
+                    recipient: rewards_collector::RewardRecipient::Module(
+                        MarsAddressType::FeeCollector,
+                    ),
+                    denom: "uusdc".to_string(),
+                    weight: Decimal::percent(50),
+                    local_recipient: None,
+                },
+            ],
             slippage_tolerance: Decimal::percent(5),
             pyth_contract_addr: "osmo1svg55quy7jjee6dn0qx85qxxvx5cafkkw4tmqpcjr9dx99l0zrhs4usft5"
                 .to_string(), // correct bech32 addr to pass validation
@@ -498,18 +532,11 @@ impl MockEnvBuilder {
         self
     }
 
-    pub fn safety_tax_rate(&mut self, percentage: Decimal) -> &mut Self {
-        self.safety_tax_rate = percentage;
-        self
-    }
-
-    pub fn safety_fund_denom(&mut self, denom: &str) -> &mut Self {
-        self.safety_fund_denom = denom.to_string();
-        self
-    }
-
-    pub fn fee_collector_denom(&mut self, denom: &str) -> &mut Self {
-        self.fee_collector_denom = denom.to_string();
+    pub fn reward_splits(
+        &mut self,
+        reward_splits: Vec<rewards_collector::RewardSplit>,
+    ) -> &mut Self {
+        self.reward_splits = reward_splits;
         self
     }
 
@@ -635,6 +662,11 @@ impl MockEnvBuilder {
                     config: CreateOrUpdateConfig {
                         address_provider: Some(address_provider_addr.to_string()),
                         close_factor: Some(self.close_factor),
+                        apply_value_haircut: None,
+                        min_liquidation_value: None,
+                        exclude_paused_collateral: None,
+                        min_oracle_sources: None,
+                        max_price_age: None,
                     },
                 },
                 &[],
@@ -654,9 +686,7 @@ impl MockEnvBuilder {
                 &rewards_collector::InstantiateMsg {
                     owner: self.owner.to_string(),
                     address_provider: address_provider_addr.to_string(),
-                    safety_tax_rate: self.safety_tax_rate,
-                    safety_fund_denom: self.safety_fund_denom.clone(),
-                    fee_collector_denom: self.fee_collector_denom.clone(),
+                    reward_splits: self.reward_splits.clone(),
                     channel_id: "0".to_string(),
                     timeout_seconds: 900,
                     slippage_tolerance: self.slippage_tolerance,
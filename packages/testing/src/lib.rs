@@ -10,6 +10,7 @@ mod mock_address_provider;
 mod mocks;
 mod oracle_querier;
 mod osmosis_querier;
+mod owner_querier;
 mod pyth_querier;
 mod red_bank_querier;
 mod redemption_rate_querier;
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use cosmwasm_std::{
     from_binary, from_slice,
     testing::{MockQuerier, MOCK_CONTRACT_ADDR},
@@ -10,7 +12,11 @@ use mars_oracle_osmosis::{
     DowntimeDetector,
 };
 use mars_osmosis::helpers::QueryPoolResponse;
-use mars_red_bank_types::{address_provider, incentives, oracle, red_bank};
+use mars_red_bank_types::{
+    address_provider::{self, MarsAddressType},
+    incentives, oracle, red_bank,
+    rewards_collector::ContractOwnership,
+};
 use osmosis_std::types::osmosis::{
     downtimedetector::v1beta1::RecoveredSinceDowntimeOfLengthResponse,
     poolmanager::v1beta1::SpotPriceResponse,
@@ -23,6 +29,7 @@ use crate::{
     mock_address_provider,
     oracle_querier::OracleQuerier,
     osmosis_querier::{OsmosisQuerier, PriceKey},
+    owner_querier::{ConfigQuery, OwnerQuerier},
     pyth_querier::PythQuerier,
     red_bank_querier::RedBankQuerier,
     redemption_rate_querier::RedemptionRateQuerier,
@@ -33,9 +40,11 @@ pub struct MarsMockQuerier {
     oracle_querier: OracleQuerier,
     incentives_querier: IncentivesQuerier,
     osmosis_querier: OsmosisQuerier,
+    owner_querier: OwnerQuerier,
     pyth_querier: PythQuerier,
     redbank_querier: RedBankQuerier,
     redemption_rate_querier: RedemptionRateQuerier,
+    missing_addresses: HashSet<MarsAddressType>,
 }
 
 impl Querier for MarsMockQuerier {
@@ -61,9 +70,11 @@ impl MarsMockQuerier {
             oracle_querier: OracleQuerier::default(),
             incentives_querier: IncentivesQuerier::default(),
             osmosis_querier: OsmosisQuerier::default(),
+            owner_querier: OwnerQuerier::default(),
             pyth_querier: PythQuerier::default(),
             redbank_querier: RedBankQuerier::default(),
             redemption_rate_querier: Default::default(),
+            missing_addresses: HashSet::new(),
         }
     }
 
@@ -73,10 +84,29 @@ impl MarsMockQuerier {
         self.base.update_balance(contract_addr.to_string(), contract_balances.to_vec());
     }
 
+    /// Make the address provider fail queries for the given address type, as if it was never set
+    pub fn remove_address(&mut self, address_type: MarsAddressType) {
+        self.missing_addresses.insert(address_type);
+    }
+
     pub fn set_oracle_price(&mut self, denom: &str, price: Decimal) {
         self.oracle_querier.prices.insert(denom.to_string(), price);
     }
 
+    pub fn set_oracle_price_source_count(&mut self, denom: &str, count: u32) {
+        self.oracle_querier.price_source_counts.insert(denom.to_string(), count);
+    }
+
+    /// Set the time of the data an oracle price is computed from, as returned alongside the price
+    pub fn set_oracle_price_timestamp(&mut self, denom: &str, timestamp: u64) {
+        self.oracle_querier.price_timestamps.insert(denom.to_string(), timestamp);
+    }
+
+    /// Number of oracle price queries made since the querier was created
+    pub fn oracle_price_query_count(&self) -> u32 {
+        self.oracle_querier.price_query_count.get()
+    }
+
     pub fn set_incentives_address(&mut self, address: Addr) {
         self.incentives_querier.incentives_addr = address;
     }
@@ -163,6 +193,14 @@ impl MarsMockQuerier {
             .insert((user.into(), collateral.denom.clone()), collateral);
     }
 
+    pub fn set_red_bank_user_debt(
+        &mut self,
+        user: impl Into<String>,
+        debt: red_bank::UserDebtResponse,
+    ) {
+        self.redbank_querier.users_denoms_debts.insert((user.into(), debt.denom.clone()), debt);
+    }
+
     pub fn set_redbank_user_position(
         &mut self,
         user_address: String,
@@ -171,6 +209,23 @@ impl MarsMockQuerier {
         self.redbank_querier.users_positions.insert(user_address, position);
     }
 
+    /// Set the current and proposed owner returned by a contract's `config` query. Config queries
+    /// of contracts without an owner state fail.
+    pub fn set_contract_owner(
+        &mut self,
+        contract_addr: &str,
+        owner: Option<&str>,
+        proposed_new_owner: Option<&str>,
+    ) {
+        self.owner_querier.owners.insert(
+            Addr::unchecked(contract_addr),
+            ContractOwnership {
+                owner: owner.map(String::from),
+                proposed_new_owner: proposed_new_owner.map(String::from),
+            },
+        );
+    }
+
     pub fn set_redemption_rate(
         &mut self,
         denom: &str,
@@ -192,6 +247,11 @@ impl MarsMockQuerier {
             }) => {
                 let contract_addr = Addr::unchecked(contract_addr);
 
+                // Config Queries (only the owner fields are mocked)
+                if let Ok(ConfigQuery::Config {}) = from_binary::<ConfigQuery>(msg) {
+                    return self.owner_querier.handle_query(&contract_addr);
+                }
+
                 // Address Provider Queries
                 let parse_address_provider_query: StdResult<address_provider::QueryMsg> =
                     from_binary(msg);
@@ -199,6 +259,7 @@ impl MarsMockQuerier {
                     return mock_address_provider::handle_query(
                         &contract_addr,
                         address_provider_query,
+                        &self.missing_addresses,
                     );
                 }
 
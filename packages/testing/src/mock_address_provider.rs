@@ -1,10 +1,17 @@
+use std::collections::HashSet;
+
 use cosmwasm_std::{to_binary, Addr, Binary, ContractResult, QuerierResult};
-use mars_red_bank_types::address_provider::{AddressResponseItem, QueryMsg};
+use mars_red_bank_types::address_provider::{AddressResponseItem, MarsAddressType, QueryMsg};
 
 // NOTE: Addresses here are all hardcoded as we always use those to target a specific contract
-// in tests. This module implicitly supposes those are used.
+// in tests. This module implicitly supposes those are used. Address types in `missing` are treated
+// as never set, so querying them fails like it would on the real address provider.
 
-pub fn handle_query(contract_addr: &Addr, query: QueryMsg) -> QuerierResult {
+pub fn handle_query(
+    contract_addr: &Addr,
+    query: QueryMsg,
+    missing: &HashSet<MarsAddressType>,
+) -> QuerierResult {
     let address_provider = Addr::unchecked("address_provider");
     if *contract_addr != address_provider {
         panic!(
@@ -13,6 +20,10 @@ pub fn handle_query(contract_addr: &Addr, query: QueryMsg) -> QuerierResult {
     }
 
     let ret: ContractResult<Binary> = match query {
+        QueryMsg::Address(address_type) if missing.contains(&address_type) => {
+            ContractResult::Err(format!("[mock]: {address_type} address not found"))
+        }
+
         QueryMsg::Address(address_type) => {
             let res = AddressResponseItem {
                 address_type,
@@ -22,6 +33,13 @@ pub fn handle_query(contract_addr: &Addr, query: QueryMsg) -> QuerierResult {
         }
 
         QueryMsg::Addresses(address_types) => {
+            if let Some(address_type) = address_types.iter().find(|t| missing.contains(*t)) {
+                return Ok(ContractResult::Err(format!(
+                    "[mock]: {address_type} address not found"
+                )))
+                .into();
+            }
+
             let addresses = address_types
                 .into_iter()
                 .map(|address_type| AddressResponseItem {
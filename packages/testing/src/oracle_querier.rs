@@ -1,11 +1,15 @@
-use std::collections::HashMap;
+use std::{cell::Cell, collections::HashMap};
 
 use cosmwasm_std::{to_binary, Addr, Binary, ContractResult, Decimal, QuerierResult};
-use mars_red_bank_types::oracle::{PriceResponse, QueryMsg};
+use mars_red_bank_types::oracle::{PriceResponse, PriceWithTimestampResponse, QueryMsg};
 
 #[derive(Default)]
 pub struct OracleQuerier {
     pub prices: HashMap<String, Decimal>,
+    pub price_source_counts: HashMap<String, u32>,
+    pub price_timestamps: HashMap<String, u64>,
+    /// Number of price queries handled so far, including failed ones
+    pub price_query_count: Cell<u32>,
 }
 
 impl OracleQuerier {
@@ -14,6 +18,8 @@ impl OracleQuerier {
             QueryMsg::Price {
                 denom,
             } => {
+                self.price_query_count.set(self.price_query_count.get() + 1);
+
                 let option_price = self.prices.get(&denom);
 
                 if let Some(price) = option_price {
@@ -27,6 +33,29 @@ impl OracleQuerier {
                 }
             }
 
+            QueryMsg::PriceSourceCount {
+                denom,
+            } => match self.price_source_counts.get(&denom) {
+                Some(count) => to_binary(count).into(),
+                None => Err(format!("[mock]: could not find price source for {denom}")).into(),
+            },
+
+            QueryMsg::PriceWithTimestamp {
+                denom,
+            } => {
+                self.price_query_count.set(self.price_query_count.get() + 1);
+
+                match (self.prices.get(&denom), self.price_timestamps.get(&denom)) {
+                    (Some(price), Some(timestamp)) => to_binary(&PriceWithTimestampResponse {
+                        denom,
+                        price: *price,
+                        timestamp: *timestamp,
+                    })
+                    .into(),
+                    _ => Err(format!("[mock]: could not find oracle price for {denom}")).into(),
+                }
+            }
+
             _ => Err("[mock]: Unsupported oracle query").into(),
         };
 
@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+use cosmwasm_std::{to_binary, Addr, Binary, ContractResult, QuerierResult};
+use mars_red_bank_types::rewards_collector::ContractOwnership;
+use serde::Deserialize;
+
+/// The `config` query every Mars contract exposes, of which only the owner fields are mocked
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum ConfigQuery {
+    Config {},
+}
+
+#[derive(Default)]
+pub struct OwnerQuerier {
+    pub owners: HashMap<Addr, ContractOwnership>,
+}
+
+impl OwnerQuerier {
+    pub fn handle_query(&self, contract_addr: &Addr) -> QuerierResult {
+        let res: ContractResult<Binary> = match self.owners.get(contract_addr) {
+            Some(ownership) => to_binary(ownership).into(),
+            None => Err(format!("[mock]: no owner state set for contract {contract_addr}")).into(),
+        };
+
+        Ok(res).into()
+    }
+}
@@ -1,14 +1,16 @@
 use std::collections::HashMap;
 
 use cosmwasm_std::{to_binary, Binary, ContractResult, QuerierResult};
+use mars_red_bank::interest_rates::{compute_underlying_amount, ScalingOperation};
 use mars_red_bank_types::red_bank::{
-    Market, QueryMsg, UserCollateralResponse, UserPositionResponse,
+    Market, QueryMsg, UserCollateralResponse, UserDebtResponse, UserPositionResponse,
 };
 
 #[derive(Default)]
 pub struct RedBankQuerier {
     pub markets: HashMap<String, Market>,
     pub users_denoms_collaterals: HashMap<(String, String), UserCollateralResponse>,
+    pub users_denoms_debts: HashMap<(String, String), UserDebtResponse>,
     pub users_positions: HashMap<String, UserPositionResponse>,
 }
 
@@ -28,12 +30,32 @@ impl RedBankQuerier {
                 Some(collateral) => to_binary(&collateral).into(),
                 None => Err(format!("[mock]: could not find the collateral for {user}")).into(),
             },
+            QueryMsg::UserDebt {
+                user,
+                denom,
+            } => match self.users_denoms_debts.get(&(user.clone(), denom)) {
+                Some(debt) => to_binary(&debt).into(),
+                None => Err(format!("[mock]: could not find the debt for {user}")).into(),
+            },
             QueryMsg::UserPosition {
                 user,
             } => match self.users_positions.get(&user) {
                 Some(market) => to_binary(&market).into(),
                 None => Err(format!("[mock]: could not find the position for {user}")).into(),
             },
+            QueryMsg::UnderlyingLiquidityAmount {
+                denom,
+                amount_scaled,
+            } => match self.markets.get(&denom) {
+                Some(market) => compute_underlying_amount(
+                    amount_scaled,
+                    market.liquidity_index,
+                    ScalingOperation::Truncate,
+                )
+                .and_then(|amount| to_binary(&amount))
+                .into(),
+                None => Err(format!("[mock]: could not find the market for {denom}")).into(),
+            },
             _ => Err("[mock]: Unsupported red_bank query".to_string()).into(),
         };
         Ok(ret).into()
@@ -1,5 +1,7 @@
+use std::fmt;
+
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Decimal, Uint128};
+use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
 use mars_owner::OwnerUpdate;
 
 /// Global configuration
@@ -9,13 +11,76 @@ pub struct Config {
     pub address_provider: Addr,
     /// Mars Token Denom
     pub mars_denom: String,
+    /// Duration (in seconds) over which rewards vest linearly after being accrued. Zero disables
+    /// vesting
+    pub vesting_duration: u64,
+    /// Address the emissions of ended incentives that weren't distributed are refunded to
+    pub funder: Option<Addr>,
+}
+
+/// Rewards accrued by a user at a given time, vesting according to the configured duration.
+///
+/// The vested amount of a tranche is:
+///
+/// `amount * min(current_time - accrued_at, vesting_duration) / vesting_duration`
+#[cw_serde]
+pub struct VestingTranche {
+    /// Amount of rewards accrued
+    pub amount: Uint128,
+    /// Time (in seconds) since the UNIX epoch at which the rewards were accrued
+    pub accrued_at: u64,
+    /// Amount of the vested rewards already sent to the user
+    pub released: Uint128,
+}
+
+/// Kind of Red Bank position an incentive is distributed to
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum PositionKind {
+    /// Assets deposited as collateral
+    #[default]
+    Collateral,
+    /// Assets borrowed
+    Debt,
+}
+
+impl fmt::Display for PositionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            PositionKind::Collateral => "collateral",
+            PositionKind::Debt => "debt",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// How much MARS an incentive emits to be then distributed to all Red Bank depositors
+#[cw_serde]
+pub enum Emission {
+    /// MARS emitted per second of block time
+    PerSecond(Uint128),
+    /// MARS emitted per block, regardless of how long blocks take to be produced
+    PerBlock(Uint128),
+}
+
+impl Emission {
+    /// Amount emitted per unit (second or block)
+    pub fn amount(&self) -> Uint128 {
+        match self {
+            Emission::PerSecond(amount) | Emission::PerBlock(amount) => *amount,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount().is_zero()
+    }
 }
 
 /// Incentive Metadata for a given incentive
 #[cw_serde]
 pub struct AssetIncentive {
-    /// How much MARS per second is emitted to be then distributed to all Red Bank depositors
-    pub emission_per_second: Uint128,
+    /// How much MARS is emitted to be then distributed to all Red Bank depositors
+    pub emission: Emission,
     /// Start time of the incentive (in seconds) since the UNIX epoch (00:00:00 on 1970-01-01 UTC)
     pub start_time: u64,
     /// How many seconds the incentives last
@@ -24,6 +89,26 @@ pub struct AssetIncentive {
     pub index: Decimal,
     /// Last time (in seconds) index was updated
     pub last_updated: u64,
+    /// Last block height at which index was updated
+    pub last_updated_height: u64,
+    /// MARS emitted while nothing was supplied, so not yet accrued into the index. It's accrued
+    /// once something is supplied again while the incentive emits, or refunded to the funder
+    pub unemitted: Uint128,
+    /// Additional schedules emitting alongside the main one, into the same index
+    pub schedules: Vec<IncentiveSchedule>,
+}
+
+/// An emission schedule of an asset incentive
+#[cw_serde]
+pub struct IncentiveSchedule {
+    /// Identifier of the schedule, unique for the asset. The incentive's main schedule has id 0
+    pub id: u64,
+    /// How much MARS is emitted while the schedule is running
+    pub emission: Emission,
+    /// Start time of the schedule (in seconds) since the UNIX epoch
+    pub start_time: u64,
+    /// How many seconds the schedule lasts
+    pub duration: u64,
 }
 
 /// Incentive Metadata for a given incentive denom
@@ -31,8 +116,10 @@ pub struct AssetIncentive {
 pub struct AssetIncentiveResponse {
     /// Asset denom
     pub denom: String,
-    /// How much MARS per second is emitted to be then distributed to all Red Bank depositors
-    pub emission_per_second: Uint128,
+    /// Kind of position the incentive is distributed to
+    pub kind: PositionKind,
+    /// How much MARS is emitted to be then distributed to all Red Bank depositors
+    pub emission: Emission,
     /// Start time of the incentive (in seconds) since the UNIX epoch (00:00:00 on 1970-01-01 UTC)
     pub start_time: u64,
     /// How many seconds the incentives last
@@ -41,17 +128,24 @@ pub struct AssetIncentiveResponse {
     pub index: Decimal,
     /// Last time (in seconds) index was updated
     pub last_updated: u64,
+    /// Last block height at which index was updated
+    pub last_updated_height: u64,
+    /// MARS emitted while nothing was supplied, so not yet accrued into the index
+    pub unemitted: Uint128,
 }
 
 impl AssetIncentiveResponse {
-    pub fn from(denom: String, ai: AssetIncentive) -> Self {
+    pub fn from(denom: String, kind: PositionKind, ai: AssetIncentive) -> Self {
         Self {
             denom,
-            emission_per_second: ai.emission_per_second,
+            kind,
+            emission: ai.emission,
             start_time: ai.start_time,
             duration: ai.duration,
             index: ai.index,
             last_updated: ai.last_updated,
+            last_updated_height: ai.last_updated_height,
+            unemitted: ai.unemitted,
         }
     }
 }
@@ -68,16 +162,18 @@ pub struct InstantiateMsg {
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    /// Set incentive params for an asset to its depositor at Red Bank.
+    /// Set incentive params for an asset to its depositors or borrowers at Red Bank.
     ///
     /// If there is no incentive for the asset, all params are required.
     /// New incentive can be set (rescheduled) if current one has finished (current_block_time > start_time + duration).
     SetAssetIncentive {
         /// Asset denom associated with the incentives
         denom: String,
-        /// How many MARS will be assigned per second to be distributed among all Red Bank
-        /// depositors
-        emission_per_second: Option<Uint128>,
+        /// Kind of position the incentive is distributed to. Defaults to collateral
+        kind: Option<PositionKind>,
+        /// How many MARS will be assigned per second or per block to be distributed among all
+        /// Red Bank depositors
+        emission: Option<Emission>,
         /// Start time of the incentive (in seconds) since the UNIX epoch (00:00:00 on 1970-01-01 UTC).
         start_time: Option<u64>,
         /// How many seconds the incentives last
@@ -97,22 +193,100 @@ pub enum ExecuteMsg {
         user_amount_scaled_before: Uint128,
         /// The market's total scaled collateral amount up to the instant before the change
         total_amount_scaled_before: Uint128,
+        /// Kind of position whose balance changed, the amounts being the scaled debt amounts for
+        /// a debt position. Defaults to collateral
+        kind: Option<PositionKind>,
     },
 
     /// Claim rewards. MARS rewards accrued by the user will be staked into xMARS before
     /// being sent.
-    ClaimRewards {},
+    /// If vesting is enabled, only the vested portion of the rewards is sent, and the rest keeps
+    /// vesting until a later claim.
+    ClaimRewards {
+        /// Address the rewards are sent to. Defaults to the sender
+        recipient: Option<String>,
+    },
+
+    /// Claim only the rewards accrued on the collateral incentives of the given denoms, which is
+    /// cheaper than claiming across every market the user is in. This includes the rewards set
+    /// aside for those denoms on past balance changes. Rewards of other denoms are left unclaimed.
+    ClaimRewardsForDenoms {
+        collateral_denoms: Vec<String>,
+    },
+
+    /// Claim rewards and use them to repay the sender's debt in the given denom at the Red Bank,
+    /// instead of sending them to the sender. Any rewards left after fully repaying the debt are
+    /// sent to the sender.
+    /// If the debt is in another denom than the rewards, the rewards are first swapped into it
+    /// through the rewards collector, which must have a route between the two denoms.
+    ClaimAndRepay {
+        /// Denom of the debt to repay
+        debt_denom: String,
+    },
+
+    /// Add an emission schedule to an existing collateral incentive, running concurrently with
+    /// its other schedules (only callable by owner)
+    AddIncentiveSchedule {
+        /// Denom of the collateral asset that is the incentive target
+        collateral_denom: String,
+        /// Denom of the reward token. Must be the configured MARS denom
+        incentive_denom: String,
+        /// How much MARS is emitted while the schedule is running
+        emission: Emission,
+        /// Start time of the schedule (in seconds) since the UNIX epoch. Can't be in the past
+        start_time: u64,
+        /// How many seconds the schedule lasts
+        duration: u64,
+    },
+
+    /// Add denoms to and remove denoms from the whitelist of denoms incentives can be set up in
+    /// (only callable by owner). Incentives already set up in a removed denom can still be
+    /// claimed, but not modified or extended
+    UpdateIncentiveDenomWhitelist {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+
+    /// Register or deregister a vault contract that compounds its rewards (only callable by owner).
+    /// When a registered vault claims rewards, it is notified of the claimed amounts with a
+    /// `VaultCallbackMsg::RewardsClaimed` message in the same transaction.
+    UpdateCompoundingVault {
+        vault: String,
+        registered: bool,
+    },
+
+    /// Refund the MARS an ended collateral incentive couldn't distribute, because nothing was
+    /// supplied from some point until it ended, to the configured funder (only callable by owner or
+    /// funder). The incentive ends once all of its schedules have ended
+    ReclaimUnemittedIncentive {
+        /// Denom of the collateral asset that is the incentive target
+        collateral_denom: String,
+        /// Denom of the reward token. Must be the configured MARS denom
+        incentive_denom: String,
+    },
 
     /// Update contract config (only callable by owner)
     UpdateConfig {
         address_provider: Option<String>,
         mars_denom: Option<String>,
+        vesting_duration: Option<u64>,
+        funder: Option<String>,
     },
 
     /// Manages admin role state
     UpdateOwner(OwnerUpdate),
 }
 
+/// Message sent to a registered compounding vault after it claims rewards
+#[cw_serde]
+pub enum VaultCallbackMsg {
+    /// Rewards claimed by the vault, per denom. Sent after the rewards themselves, so the vault
+    /// already holds them when handling this message.
+    RewardsClaimed {
+        rewards: Vec<Coin>,
+    },
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -124,20 +298,122 @@ pub enum QueryMsg {
     #[returns(AssetIncentiveResponse)]
     AssetIncentive {
         denom: String,
+        /// Kind of position the incentive is distributed to. Defaults to collateral
+        kind: Option<PositionKind>,
     },
 
-    /// Enumerate asset incentives with pagination
+    /// Enumerate asset incentives of a kind of position with pagination
     #[returns(Vec<AssetIncentiveResponse>)]
     AssetIncentives {
+        /// Kind of position the incentives are distributed to. Defaults to collateral
+        kind: Option<PositionKind>,
         start_after: Option<String>,
         limit: Option<u32>,
     },
 
-    /// Query user current unclaimed rewards
+    /// Query all emission schedules of a collateral incentive, starting with its main one
+    #[returns(Vec<IncentiveSchedule>)]
+    IncentiveSchedules {
+        /// Denom of the collateral asset that is the incentive target
+        collateral_denom: String,
+        /// Denom of the reward token. Must be the configured MARS denom
+        incentive_denom: String,
+    },
+
+    /// Query the denoms incentives can be set up in
+    #[returns(Vec<String>)]
+    IncentiveDenomWhitelist {},
+
+    /// Query the rewards the user would be paid by claiming now. Rewards that are still vesting
+    /// aren't included, nor, with vesting enabled, the rewards accrued since the user's last
+    /// balance change, which only start vesting once claimed. `ProjectedUserRewards` at the current
+    /// time includes both.
     #[returns(Uint128)]
     UserUnclaimedRewards {
         user: String,
     },
+
+    /// Query the sum of the rewards stored as unclaimed across all users. Rewards accrued since a
+    /// user's last balance change or claim, or still vesting, aren't included
+    #[returns(Uint128)]
+    TotalUnclaimedRewards {},
+
+    /// Query the inputs used to compute a user's accrued rewards for a given asset, for debugging
+    #[returns(RewardDebugInfoResponse)]
+    RewardDebugInfo {
+        user: String,
+        /// Denom of the collateral asset that is the incentive target
+        collateral_denom: String,
+        /// Denom of the reward token. Must be the configured MARS denom
+        incentive_denom: String,
+    },
+
+    /// Query a user's index and pending rewards for a collateral incentive, along with their stored
+    /// unclaimed rewards
+    #[returns(UserRewardStateResponse)]
+    UserRewardState {
+        user: String,
+        /// Denom of the collateral asset that is the incentive target
+        collateral_denom: String,
+        /// Denom of the reward token. Must be the configured MARS denom
+        incentive_denom: String,
+    },
+
+    /// Query the total rewards a user has claimed over the contract's lifetime, per incentive denom
+    #[returns(Vec<Coin>)]
+    UserClaimedTotals {
+        user: String,
+    },
+
+    /// Query the incentives currently emitting rewards to a user's enabled collateral and debt
+    /// positions, with an entry for each running schedule of an incentive
+    #[returns(Vec<UserActiveIncentiveResponse>)]
+    UserActiveIncentives {
+        user: String,
+    },
+
+    /// Query the time (in seconds) at which a user started accruing an asset's incentive, i.e.
+    /// when their index for the asset was first set. None if it has never been set
+    #[returns(Option<u64>)]
+    UserAssetIndexSetAt {
+        user: String,
+        denom: String,
+    },
+
+    /// Project the user's unclaimed rewards, including rewards that are still vesting, at a future
+    /// time (in seconds) if they hold their positions until then.
+    ///
+    /// Assumes each asset's total supply and the user's balance stay constant until then. Rewards
+    /// stop accruing at the end of each incentive.
+    #[returns(Uint128)]
+    ProjectedUserRewards {
+        user: String,
+        future_timestamp: u64,
+    },
+
+    /// Query the reward token emitted per second per scaled collateral unit of an asset at its
+    /// current total supply, i.e. the rate at which the asset incentive index grows. Zero if the
+    /// incentive isn't active or nothing is supplied. Fails for per-block incentives, as the time
+    /// blocks take isn't known
+    #[returns(Decimal)]
+    RewardRatePerUnit {
+        /// Denom of the collateral asset that is the incentive target
+        collateral_denom: String,
+        /// Denom of the reward token. Must be the configured MARS denom
+        incentive_denom: String,
+    },
+
+    /// Query the annual percentage rate an incentive currently pays to depositors of an asset,
+    /// i.e. the value of a year of emissions divided by the value of the market's total
+    /// collateral, both as priced by the oracle. Zero if the incentive isn't active or nothing is
+    /// supplied. Fails for per-block incentives, as the time blocks take isn't known
+    #[returns(Decimal)]
+    AssetIncentiveApr {
+        /// Denom of the collateral asset that is the incentive target
+        collateral_denom: String,
+        /// Denom of the reward token. Must be the configured MARS denom
+        incentive_denom: String,
+    },
 }
 
 #[cw_serde]
@@ -150,4 +426,45 @@ pub struct ConfigResponse {
     pub address_provider: Addr,
     /// Mars Token Denom
     pub mars_denom: String,
+    /// Duration (in seconds) over which rewards vest linearly after being accrued
+    pub vesting_duration: u64,
+    /// Address the emissions of ended incentives that weren't distributed are refunded to
+    pub funder: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct RewardDebugInfoResponse {
+    /// Asset incentive index accrued up to the current block
+    pub asset_index_now: Decimal,
+    /// User index as stored in the contract (i.e. as of the user's last balance change or claim)
+    pub user_index_stored: Decimal,
+    /// User's scaled collateral amount in the Red Bank
+    pub user_collateral_scaled: Uint128,
+    /// Rewards accrued by the user since the stored user index, not yet added to unclaimed rewards
+    pub accrued_since: Uint128,
+}
+
+#[cw_serde]
+pub struct UserRewardStateResponse {
+    /// User index as stored in the contract (i.e. as of the user's last balance change or claim)
+    pub user_index: Decimal,
+    /// Asset incentive index accrued up to the current block
+    pub asset_index: Decimal,
+    /// Rewards stored as unclaimed for the user on the asset's collateral incentive
+    pub unclaimed: Uint128,
+    /// Rewards accrued on the asset since the stored user index, not yet added to unclaimed
+    /// rewards
+    pub pending: Uint128,
+}
+
+#[cw_serde]
+pub struct UserActiveIncentiveResponse {
+    /// Denom of the asset the user is earning rewards on
+    pub collateral_denom: String,
+    /// Kind of the user's position earning the rewards
+    pub kind: PositionKind,
+    /// Denom of the reward token
+    pub incentive_denom: String,
+    /// How much of the reward token is emitted to all depositors of the asset
+    pub emission: Emission,
 }
@@ -35,6 +35,11 @@ pub enum ExecuteMsg<T> {
     UpdateConfig {
         base_denom: Option<String>,
     },
+    /// Record a coin's current TWAP price along with the block time, so that it can be used later
+    /// instead of the live price (only callable by owner). Fails if the price isn't TWAP-based.
+    SnapshotPrice {
+        denom: String,
+    },
 }
 
 #[cw_serde]
@@ -75,6 +80,34 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Query a coin's price along with the time (in seconds) of the oldest data it is computed
+    /// from, e.g. the publish time of a Pyth price or the last update of a redemption rate. Prices
+    /// computed from on-chain state when queried, such as spot or TWAP prices, are timestamped with
+    /// the current block time.
+    #[returns(PriceWithTimestampResponse)]
+    PriceWithTimestamp {
+        denom: String,
+    },
+    /// Query the most recent price snapshot of a coin.
+    ///
+    /// If `max_age` (in seconds) is provided, the query fails if the snapshot is older than that.
+    #[returns(PriceSnapshotResponse)]
+    PriceSnapshot {
+        denom: String,
+        max_age: Option<u64>,
+    },
+    /// Query how many independent sources back a coin's price, i.e. how many distinct pools or
+    /// feeds are tried before the price query fails
+    #[returns(u32)]
+    PriceSourceCount {
+        denom: String,
+    },
+    /// Query the window of the TWAP a coin's current price is computed with, and the start time of
+    /// the records it averages. Fails if the price isn't TWAP-based.
+    #[returns(TwapWindowResponse)]
+    TwapWindow {
+        denom: String,
+    },
 }
 
 #[cw_serde]
@@ -99,10 +132,42 @@ pub struct PriceResponse {
     pub price: Decimal,
 }
 
+#[cw_serde]
+pub struct PriceWithTimestampResponse {
+    pub denom: String,
+    pub price: Decimal,
+    /// Time (in seconds) of the oldest data the price is computed from
+    pub timestamp: u64,
+}
+
+#[cw_serde]
+pub struct PriceSnapshot {
+    pub price: Decimal,
+    /// Block time (in seconds) at which the price was recorded
+    pub timestamp: u64,
+}
+
+#[cw_serde]
+pub struct PriceSnapshotResponse {
+    pub denom: String,
+    pub price: Decimal,
+    pub timestamp: u64,
+}
+
+#[cw_serde]
+pub struct TwapWindowResponse {
+    pub denom: String,
+    /// Window size in seconds
+    pub window_size: u64,
+    /// Block time (in seconds) of the start of the window, i.e. the current block time minus the
+    /// window size
+    pub start_time: u64,
+}
+
 pub mod helpers {
     use cosmwasm_std::{Decimal, QuerierWrapper, StdResult};
 
-    use super::{PriceResponse, QueryMsg};
+    use super::{PriceResponse, PriceWithTimestampResponse, QueryMsg};
 
     pub fn query_price(
         querier: &QuerierWrapper,
@@ -117,4 +182,17 @@ pub mod helpers {
         )?;
         Ok(res.price)
     }
+
+    pub fn query_price_with_timestamp(
+        querier: &QuerierWrapper,
+        oracle: impl Into<String>,
+        denom: impl Into<String>,
+    ) -> StdResult<PriceWithTimestampResponse> {
+        querier.query_wasm_smart(
+            oracle.into(),
+            &QueryMsg::PriceWithTimestamp {
+                denom: denom.into(),
+            },
+        )
+    }
 }
@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Decimal, StdResult, Uint128};
+use cosmwasm_std::{Addr, Decimal, StdResult, Uint128};
 use mars_utils::{
     error::ValidationError,
     helpers::{decimal_param_le_one, decimal_param_lt_one},
@@ -47,6 +47,47 @@ pub struct Market {
     pub borrow_enabled: bool,
     /// Deposit Cap (defined in terms of the asset)
     pub deposit_cap: Uint128,
+
+    /// Percentage by which the collateral value is discounted on top of max LTV, to account for
+    /// concentration or liquidity risk
+    #[serde(default)]
+    pub value_haircut: Decimal,
+
+    /// Minimum amount of a deposit opening a new collateral position, to avoid dust positions
+    #[serde(default)]
+    pub min_deposit_amount: Uint128,
+
+    /// Address the swept reserves are sent to instead of the rewards collector, if any
+    #[serde(default)]
+    pub reserve_recipient: Option<Addr>,
+    /// Scaled collateral minted to the rewards collector as reserves since they were last swept
+    /// or the reserve recipient last changed. Only this part of the collector's collateral
+    /// position is swept, not what it deposited itself.
+    #[serde(default)]
+    pub unswept_reserves_scaled: Uint128,
+
+    /// Debt amount below which a user can close their debt position with `CloseDustDebt`
+    #[serde(default)]
+    pub dust_threshold: Uint128,
+
+    /// Whether uncollateralized debt accrues interest like collateralized debt does. If not, it's
+    /// stored unscaled and isn't included in `debt_total_scaled`.
+    #[serde(default = "default_accrues_interest")]
+    pub uncollateralized_debt_accrues_interest: bool,
+
+    /// Close factor applied when liquidating debt in this asset, instead of the global one
+    #[serde(default)]
+    pub close_factor: Option<Decimal>,
+    /// Close factor applied when this asset is the collateral seized in a liquidation. Takes
+    /// precedence over the debt market's close factor and the global one.
+    #[serde(default)]
+    pub collateral_close_factor: Option<Decimal>,
+}
+
+/// Markets stored before the setting existed keep their uncollateralized debt scaled, so it has to
+/// keep accruing interest
+fn default_accrues_interest() -> bool {
+    true
 }
 
 impl Default for Market {
@@ -69,6 +110,14 @@ impl Default for Market {
             borrow_enabled: true,
             // By default the cap should be unlimited (no cap)
             deposit_cap: Uint128::MAX,
+            value_haircut: Decimal::zero(),
+            min_deposit_amount: Uint128::zero(),
+            reserve_recipient: None,
+            unswept_reserves_scaled: Uint128::zero(),
+            dust_threshold: Uint128::zero(),
+            uncollateralized_debt_accrues_interest: true,
+            close_factor: None,
+            collateral_close_factor: None,
         }
     }
 }
@@ -79,6 +128,13 @@ impl Market {
         decimal_param_le_one(self.max_loan_to_value, "max_loan_to_value")?;
         decimal_param_le_one(self.liquidation_threshold, "liquidation_threshold")?;
         decimal_param_le_one(self.liquidation_bonus, "liquidation_bonus")?;
+        decimal_param_le_one(self.value_haircut, "value_haircut")?;
+        if let Some(close_factor) = self.close_factor {
+            decimal_param_le_one(close_factor, "close_factor")?;
+        }
+        if let Some(collateral_close_factor) = self.collateral_close_factor {
+            decimal_param_le_one(collateral_close_factor, "collateral_close_factor")?;
+        }
 
         // liquidation_threshold should be greater than max_loan_to_value
         if self.liquidation_threshold <= self.max_loan_to_value {
@@ -94,6 +150,11 @@ impl Market {
         Ok(())
     }
 
+    /// A market is paused when both deposits and borrows are disabled
+    pub fn is_paused(&self) -> bool {
+        !self.deposit_enabled && !self.borrow_enabled
+    }
+
     pub fn update_interest_rates(&mut self, current_utilization_rate: Decimal) -> StdResult<()> {
         self.borrow_rate = self.interest_rate_model.get_borrow_rate(current_utilization_rate)?;
 
@@ -86,6 +86,13 @@ pub enum ExecuteMsg {
         on_behalf_of: Option<String>,
     },
 
+    /// Repay a debt smaller than the market's dust threshold in full and remove the debt position.
+    /// Coins used to repay must be sent in the transaction this call is made, any excess is
+    /// refunded.
+    CloseDustDebt {
+        denom: String,
+    },
+
     /// Liquidate under-collateralized native loans. Coins used to repay must be sent in the
     /// transaction this call is made.
     ///
@@ -107,12 +114,37 @@ pub enum ExecuteMsg {
         /// Option to enable (true) / disable (false) asset as collateral
         enable: bool,
     },
+
+    /// Send the reserves accrued in a market to the rewards collector, or to the market's reserve
+    /// recipient if it has one. Can be called by anyone.
+    ///
+    /// Reserves are minted as collateral shares of the rewards collector; this withdraws the ones
+    /// minted since the last sweep and sends the underlying coins to the recipient. The rest of
+    /// the collector's collateral position, e.g. its own deposits, is left untouched.
+    SweepReserves {
+        /// Asset whose reserves are to be swept
+        denom: String,
+    },
+
+    /// Withdraw all of the sender's enabled collateral and consolidate it into a single denom,
+    /// swapping every other asset into it through the rewards collector, which must have a route
+    /// from each of them. The consolidated amount is sent to the sender once all swaps are done.
+    /// Fails if the sender's remaining debt would be liquidatable without the collateral.
+    WithdrawAllAndConsolidate {
+        /// Denom the collateral is consolidated into
+        target_denom: String,
+    },
 }
 
 #[cw_serde]
 pub struct CreateOrUpdateConfig {
     pub address_provider: Option<String>,
     pub close_factor: Option<Decimal>,
+    pub apply_value_haircut: Option<bool>,
+    pub min_liquidation_value: Option<Uint128>,
+    pub exclude_paused_collateral: Option<bool>,
+    pub min_oracle_sources: Option<u32>,
+    pub max_price_age: Option<u64>,
 }
 
 #[cw_serde]
@@ -136,6 +168,24 @@ pub struct InitOrUpdateAssetParams {
     pub borrow_enabled: Option<bool>,
     /// Deposit Cap defined in terms of the asset (Unlimited by default)
     pub deposit_cap: Option<Uint128>,
+    /// Percentage by which the collateral value is discounted (Zero by default)
+    pub value_haircut: Option<Decimal>,
+    /// Minimum amount of a deposit opening a new collateral position (Zero by default)
+    pub min_deposit_amount: Option<Uint128>,
+    /// Address the swept reserves are sent to instead of the rewards collector (None by default).
+    /// Reserves accrued before it is set or changed stay with the rewards collector.
+    pub reserve_recipient: Option<String>,
+    /// Debt amount below which a user can close their debt position (Zero by default)
+    pub dust_threshold: Option<Uint128>,
+    /// Whether uncollateralized debt accrues interest (true by default). Can only be set when
+    /// initializing the market.
+    pub uncollateralized_debt_accrues_interest: Option<bool>,
+    /// Close factor overriding the global one when liquidating debt in this asset (None by
+    /// default)
+    pub close_factor: Option<Decimal>,
+    /// Close factor overriding the debt market's and the global ones when this asset is the
+    /// seized collateral (None by default)
+    pub collateral_close_factor: Option<Decimal>,
 }
 
 #[cw_serde]
@@ -158,6 +208,17 @@ pub enum QueryMsg {
         limit: Option<u32>,
     },
 
+    /// Get the number of unique users currently supplying and borrowing across all markets
+    #[returns(crate::red_bank::UserCountsResponse)]
+    UserCounts {},
+
+    /// Get a market's total scaled collateral and debt amounts, along with their underlying
+    /// amounts and the indices at the current block
+    #[returns(crate::red_bank::MarketScaledTotalsResponse)]
+    MarketScaledTotals {
+        denom: String,
+    },
+
     /// Get uncollateralized limit for given user and asset
     #[returns(crate::red_bank::UncollateralizedLoanLimitResponse)]
     UncollateralizedLoanLimit {
@@ -203,12 +264,142 @@ pub enum QueryMsg {
         limit: Option<u32>,
     },
 
+    /// Get the denoms of all markets a user has a collateral or debt position in, sorted
+    /// alphabetically. Cheaper than `UserPosition` as no values are computed.
+    #[returns(Vec<String>)]
+    UserMarkets {
+        user: String,
+    },
+
+    /// Get a user's most recent liquidations, oldest first
+    #[returns(Vec<crate::red_bank::LiquidationRecord>)]
+    UserLiquidationHistory {
+        user: String,
+    },
+
     /// Get user position
     #[returns(crate::red_bank::UserPositionResponse)]
     UserPosition {
         user: String,
     },
 
+    /// Break the user's liquidation health factor down into each enabled collateral asset's
+    /// contribution to its numerator, along with the total debt value it is divided by
+    #[returns(crate::red_bank::HealthDecompositionResponse)]
+    HealthDecomposition {
+        user: String,
+    },
+
+    /// Get how much the user's liquidation health factor drops per 1% decrease of the price of each
+    /// of their enabled collateral assets. Empty if the user isn't borrowing.
+    #[returns(Vec<crate::red_bank::HealthSensitivityResponse>)]
+    HealthSensitivity {
+        user: String,
+    },
+
+    /// Break the user's health down per asset they have a position in, sorted by denom
+    #[returns(Vec<crate::red_bank::UserHealthBreakdownResponse>)]
+    UserHealthBreakdown {
+        user: String,
+    },
+
+    /// Get the user's current loan-to-value ratio, along with the max LTV and liquidation threshold
+    /// of their collateral
+    #[returns(crate::red_bank::CurrentLtvResponse)]
+    CurrentLtv {
+        user: String,
+    },
+
+    /// Get the combined health of a list of users, weighted by the size of their positions.
+    /// At most 30 users can be queried at once.
+    #[returns(crate::red_bank::AggregateHealthResponse)]
+    AggregateHealth {
+        users: Vec<String>,
+    },
+
+    /// Get the users in a list whose collateralized debt is worth more than their enabled
+    /// collateral, by how much, and the total shortfall across them.
+    /// At most 30 users can be queried at once.
+    #[returns(crate::red_bank::BadDebtResponse)]
+    BadDebt {
+        users: Vec<String>,
+    },
+
+    /// Get the value of a user's enabled collateral after applying each asset's value haircut
+    #[returns(crate::red_bank::HaircutCollateralValueResponse)]
+    HaircutCollateralValue {
+        user: String,
+    },
+
+    /// Get the interest a user's debt would accrue over the given number of seconds from now.
+    ///
+    /// The market's current borrow rate is assumed to stay constant over the whole period. In
+    /// practice it changes with the market's utilization, so this is only an estimate.
+    #[returns(crate::red_bank::ProjectedInterestResponse)]
+    ProjectedInterest {
+        user: String,
+        denom: String,
+        seconds: u64,
+    },
+
+    /// Get the minimum amount of the given asset a liquidatable user has to deposit to bring their
+    /// liquidation health factor back to at least 1.
+    #[returns(crate::red_bank::RescueDepositResponse)]
+    RescueDeposit {
+        user: String,
+        denom: String,
+    },
+
+    /// Get the maximum amount of the given asset a user can withdraw without becoming liquidatable.
+    /// This is the user's full collateral amount if they aren't borrowing.
+    #[returns(Uint128)]
+    MaxWithdrawable {
+        user: String,
+        denom: String,
+    },
+
+    /// Get the maximum amount of the given asset a user can borrow without exceeding their max LTV,
+    /// or their uncollateralized loan limit if they have one in that asset. Zero if the user is
+    /// already at or above their max LTV.
+    #[returns(Uint128)]
+    MaxBorrowable {
+        user: String,
+        denom: String,
+    },
+
+    /// Estimate the profit of liquidating a user by repaying the given amount of debt, net of the
+    /// supplied gas cost. Values are denominated in the oracle's base denom.
+    #[returns(crate::red_bank::EstimateLiquidationProfitResponse)]
+    EstimateLiquidationProfit {
+        user: String,
+        debt_denom: String,
+        collateral_denom: String,
+        repay_amount: Uint128,
+        /// Gas cost of the liquidation, denominated in the oracle's base denom like the returned
+        /// values. A fee paid in another denom has to be converted with its oracle price first.
+        gas_cost: Uint128,
+    },
+
+    /// Get, for each of the user's enabled collaterals, the amount that would be seized (including
+    /// the liquidation bonus) by repaying the given amount of debt, capped by the user's balance of
+    /// the collateral. Sorted by the seized value, highest first.
+    #[returns(Vec<crate::red_bank::SeizableCollateral>)]
+    SeizableCollaterals {
+        user: String,
+        debt_denom: String,
+        repay_amount: Uint128,
+    },
+
+    /// Pre-flight check for a market governance intends to add: verifies the oracle returns a price
+    /// for the denom and that the risk params are consistent with each other
+    #[returns(crate::red_bank::ValidateNewMarketResponse)]
+    ValidateNewMarket {
+        denom: String,
+        max_ltv: Decimal,
+        liquidation_threshold: Decimal,
+        liquidation_bonus: Decimal,
+    },
+
     /// Get liquidity scaled amount for a given underlying asset amount.
     /// (i.e: how much scaled collateral is added if the given amount is deposited)
     #[returns(Uint128)]
@@ -9,6 +9,31 @@ pub struct Config<T> {
     pub address_provider: T,
     /// Maximum percentage of outstanding debt that can be covered by a liquidator
     pub close_factor: Decimal,
+    /// Whether the markets' collateral value haircuts are applied when computing health
+    #[serde(default)]
+    pub apply_value_haircut: bool,
+    /// Minimum value of debt that a liquidation must repay, unless it fully closes the user's debt
+    /// or collateral position.
+    ///
+    /// NOTE: The value is denominated in the oracle's base denom (e.g. uosmo on Osmosis), not in
+    /// USD, so its USD equivalent moves with the base denom's price.
+    #[serde(default)]
+    pub min_liquidation_value: Uint128,
+    /// Whether collateral in paused markets is excluded when computing health.
+    ///
+    /// NOTE: Enabling this can make users liquidatable as soon as a market they hold collateral in
+    /// is paused.
+    #[serde(default)]
+    pub exclude_paused_collateral: bool,
+    /// Minimum number of oracle price sources backing a market's denom for deposits or borrows of
+    /// that market to be enabled. Zero disables the check.
+    #[serde(default)]
+    pub min_oracle_sources: u32,
+    /// Maximum age (in seconds) of the data behind the oracle prices health is computed with, e.g.
+    /// the publish time of a Pyth price or the last update of a redemption rate. If non-zero,
+    /// health checks fail if any price is older. Zero disables the check.
+    #[serde(default)]
+    pub max_price_age: u64,
 }
 
 impl<T> Config<T> {
@@ -43,6 +68,23 @@ pub struct Debt {
     pub uncollateralized: bool,
 }
 
+/// A liquidation of a user's position
+#[cw_serde]
+pub struct LiquidationRecord {
+    /// Address that repaid the debt
+    pub liquidator: String,
+    /// Denom of the repaid debt
+    pub debt_denom: String,
+    /// Amount of debt repaid
+    pub debt_amount: Uint128,
+    /// Denom of the seized collateral
+    pub collateral_denom: String,
+    /// Amount of collateral seized
+    pub collateral_amount: Uint128,
+    /// Block time (in seconds) of the liquidation
+    pub timestamp: u64,
+}
+
 #[cw_serde]
 pub enum UserHealthStatus {
     NotBorrowing,
@@ -53,15 +95,18 @@ pub enum UserHealthStatus {
 }
 
 /// User asset settlement
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct Position {
     pub denom: String,
     pub collateral_amount: Uint128,
     pub debt_amount: Uint128,
+    /// Whether the debt is backed by an uncollateralized loan limit rather than by collateral.
+    /// Such debt is bounded by the limit when borrowing and doesn't count towards health at all.
     pub uncollateralized_debt: bool,
     pub max_ltv: Decimal,
     pub liquidation_threshold: Decimal,
     pub asset_price: Decimal,
+    pub value_haircut: Decimal,
 }
 
 #[cw_serde]
@@ -76,6 +121,17 @@ pub struct ConfigResponse {
     pub address_provider: String,
     /// Maximum percentage of outstanding debt that can be covered by a liquidator
     pub close_factor: Decimal,
+    /// Whether the markets' collateral value haircuts are applied when computing health
+    pub apply_value_haircut: bool,
+    /// Minimum value of debt (denominated in the oracle's base denom, not USD) that a liquidation
+    /// must repay, unless it fully closes the user's debt or collateral position
+    pub min_liquidation_value: Uint128,
+    /// Whether collateral in paused markets is excluded when computing health
+    pub exclude_paused_collateral: bool,
+    /// Minimum number of oracle price sources required to enable a market
+    pub min_oracle_sources: u32,
+    /// Maximum age (in seconds) of the oracle prices used in health checks; zero if unchecked
+    pub max_price_age: u64,
 }
 
 #[cw_serde]
@@ -123,3 +179,185 @@ pub struct UserPositionResponse {
     pub weighted_liquidation_threshold_collateral: Uint128,
     pub health_status: UserHealthStatus,
 }
+
+#[cw_serde]
+pub struct HaircutCollateralValueResponse {
+    /// Total value of all enabled collateral assets
+    pub total_enabled_collateral: Uint128,
+    /// Total value of all enabled collateral assets, after applying each asset's value haircut
+    pub haircut_collateral_value: Uint128,
+}
+
+#[cw_serde]
+pub struct ProjectedInterestResponse {
+    /// Asset denom
+    pub denom: String,
+    /// Underlying debt amount at the current block
+    pub debt: Uint128,
+    /// Borrow rate the projection is based on
+    pub borrow_rate: Decimal,
+    /// Additional interest owed at the end of the period
+    pub projected_interest: Uint128,
+}
+
+#[cw_serde]
+pub struct HealthSensitivityResponse {
+    /// Collateral asset denom
+    pub denom: String,
+    /// Decrease of the user's liquidation health factor if the asset's price drops by 1%.
+    /// Zero if the health factor doesn't decrease, e.g. if the user also borrowed the asset.
+    pub liquidation_health_factor_change: Decimal,
+}
+
+#[cw_serde]
+pub struct UserHealthBreakdownResponse {
+    /// Asset denom
+    pub denom: String,
+    /// Value of the user's enabled collateral in the asset, net of its value haircut if applied
+    pub collateral_value: Uint128,
+    /// Value of the user's collateralized debt in the asset
+    pub debt_value: Uint128,
+    /// Collateral value weighted by the asset's max LTV
+    pub weighted_max_ltv_collateral: Uint128,
+    /// Collateral value weighted by the asset's liquidation threshold
+    pub weighted_liquidation_threshold_collateral: Uint128,
+}
+
+#[cw_serde]
+pub struct CurrentLtvResponse {
+    /// Total value of collateralized debts over total value of enabled collateral.
+    /// Zero if the user has no debt, `None` if the user has debt but no collateral.
+    pub ltv: Option<Decimal>,
+    /// Max LTV of the user's collateral, weighted by the value of each asset
+    pub max_ltv: Decimal,
+    /// Liquidation threshold of the user's collateral, weighted by the value of each asset
+    pub liquidation_threshold: Decimal,
+}
+
+#[cw_serde]
+pub struct BadDebtResponse {
+    /// Users with bad debt, in the order they were queried
+    pub users: Vec<UserBadDebt>,
+    /// Sum of the users' shortfalls
+    pub total_shortfall: Uint128,
+}
+
+#[cw_serde]
+pub struct UserBadDebt {
+    pub user: String,
+    /// Value of the user's collateralized debt not covered by their enabled collateral, even if
+    /// all of it is seized
+    pub shortfall: Uint128,
+}
+
+#[cw_serde]
+pub struct HealthDecompositionResponse {
+    /// Contribution of each of the user's enabled collateral assets, sorted by denom
+    pub collaterals: Vec<CollateralHealthContribution>,
+    /// Sum of the contributions, i.e. the numerator of the liquidation health factor
+    pub total_weighted_collateral: Uint128,
+    /// Total value of the user's collateralized debts, i.e. the denominator of the liquidation
+    /// health factor
+    pub total_debt_value: Uint128,
+    /// `None` if the user has no collateralized debt
+    pub liquidation_health_factor: Option<Decimal>,
+}
+
+#[cw_serde]
+pub struct CollateralHealthContribution {
+    /// Collateral asset denom
+    pub denom: String,
+    /// Value of the collateral, net of the asset's value haircut if it is applied
+    pub value: Uint128,
+    /// Value of the collateral weighted by the asset's liquidation threshold
+    pub weighted_value: Uint128,
+}
+
+#[cw_serde]
+pub struct AggregateHealthResponse {
+    /// Total value of the users' enabled collateral
+    pub total_collateral_value: Uint128,
+    /// Total value of the users' enabled collateral, weighted by each asset's liquidation threshold
+    pub total_weighted_liquidation_threshold_collateral: Uint128,
+    /// Total value of the users' collateralized debts
+    pub total_debt_value: Uint128,
+    /// Weighted liquidation threshold collateral divided by debt across all users.
+    /// `None` if none of the users has any debt.
+    pub liquidation_health_factor: Option<Decimal>,
+    /// Number of users whose own position can be liquidated
+    pub liquidatable_users: u32,
+}
+
+#[cw_serde]
+pub struct RescueDepositResponse {
+    /// Asset denom
+    pub denom: String,
+    /// Minimum amount of the asset to deposit for the user to no longer be liquidatable.
+    /// Zero if the user isn't liquidatable, `None` if depositing this asset can't rescue them.
+    pub amount: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct SeizableCollateral {
+    pub denom: String,
+    /// Amount of debt that would be repaid, after applying the close factor and capping it to the
+    /// user's balance of this collateral
+    pub debt_amount: Uint128,
+    /// Amount of this collateral that would be seized, including the liquidation bonus
+    pub collateral_amount: Uint128,
+    /// Value of the seized collateral, denominated in the oracle's base denom
+    pub collateral_value: Uint128,
+}
+
+#[cw_serde]
+pub struct EstimateLiquidationProfitResponse {
+    /// Amount of debt that would be repaid, after applying the close factor and capping it to the
+    /// user's collateral
+    pub debt_amount: Uint128,
+    /// Amount of collateral that would be seized, including the liquidation bonus
+    pub collateral_amount: Uint128,
+    /// Value of the repaid debt, denominated in the oracle's base denom
+    pub debt_value: Uint128,
+    /// Value of the seized collateral, denominated in the oracle's base denom
+    pub collateral_value: Uint128,
+    /// Collateral value minus debt value minus gas cost, zero if the liquidation isn't profitable
+    pub net_profit: Uint128,
+    /// Debt value plus gas cost minus collateral value, zero if the liquidation is profitable
+    pub net_loss: Uint128,
+}
+
+#[cw_serde]
+pub struct ValidateNewMarketResponse {
+    /// Asset denom
+    pub denom: String,
+    /// Issues that would make the market unusable, or that market validation would reject
+    pub errors: Vec<String>,
+    /// Issues that don't prevent adding the market but are likely unintended
+    pub warnings: Vec<String>,
+}
+
+#[cw_serde]
+pub struct MarketScaledTotalsResponse {
+    /// Asset denom
+    pub denom: String,
+    /// Total collateral scaled for the market's currency
+    pub collateral_total_scaled: Uint128,
+    /// Underlying amount of the total collateral at the current block
+    pub collateral_total_amount: Uint128,
+    /// Total debt scaled for the market's currency
+    pub debt_total_scaled: Uint128,
+    /// Underlying amount of the total debt at the current block
+    pub debt_total_amount: Uint128,
+    /// Liquidity index at the current block
+    pub liquidity_index: Decimal,
+    /// Borrow index at the current block
+    pub borrow_index: Decimal,
+}
+
+#[cw_serde]
+pub struct UserCountsResponse {
+    /// Number of users with a non-zero collateral position in at least one market
+    pub suppliers: u64,
+    /// Number of users with a non-zero debt position in at least one market
+    pub borrowers: u64,
+}
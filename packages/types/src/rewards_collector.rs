@@ -1,12 +1,24 @@
-use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Api, Decimal, StdResult, Uint128};
+use std::fmt;
+
+use cosmwasm_schema::{cw_serde, serde::de::IgnoredAny, QueryResponses};
+use cosmwasm_std::{from_slice, Addr, Api, Coin, Decimal, StdResult, Timestamp, Uint128};
 use mars_owner::OwnerUpdate;
 use mars_utils::{
     error::ValidationError,
     helpers::{decimal_param_le_one, integer_param_gt_zero, validate_native_denom},
 };
 
-const MAX_SLIPPAGE_TOLERANCE_PERCENTAGE: u64 = 50;
+use crate::address_provider::MarsAddressType;
+
+/// Maximum slippage tolerance, in percent, of the configured tolerance and of route overrides
+pub const MAX_SLIPPAGE_TOLERANCE_PERCENTAGE: u64 = 50;
+
+/// Prefix of the address of a CW20 token where a denom is expected, e.g. as the key of the
+/// distributed totals of the token
+pub const CW20_DENOM_PREFIX: &str = "cw20:";
+
+/// Placeholder in the IBC memo template that is replaced with the address of the recipient
+pub const IBC_MEMO_RECIPIENT_PLACEHOLDER: &str = "{recipient}";
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -14,12 +26,8 @@ pub struct InstantiateMsg {
     pub owner: String,
     /// Address provider returns addresses for all protocol contracts
     pub address_provider: String,
-    /// Percentage of fees that are sent to the safety fund
-    pub safety_tax_rate: Decimal,
-    /// The asset to which the safety fund share is converted
-    pub safety_fund_denom: String,
-    /// The asset to which the fee collector share is converted
-    pub fee_collector_denom: String,
+    /// How the protocol revenue is split between the recipients
+    pub reward_splits: Vec<RewardSplit>,
     /// The channel ID of the mars hub
     pub channel_id: String,
     /// Number of seconds after which an IBC transfer is to be considered failed, if no acknowledgement is received
@@ -28,30 +36,146 @@ pub struct InstantiateMsg {
     pub slippage_tolerance: Decimal,
 }
 
+/// Recipient of a share of the protocol revenue on Mars Hub
+#[cw_serde]
+pub enum RewardRecipient {
+    /// A Mars Hub module account whose address is stored in the address provider
+    Module(MarsAddressType),
+    /// Any other Mars Hub address
+    Addr(String),
+}
+
+impl fmt::Display for RewardRecipient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RewardRecipient::Module(address_type) => write!(f, "{address_type}"),
+            RewardRecipient::Addr(addr) => write!(f, "{addr}"),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct RewardSplit {
+    pub recipient: RewardRecipient,
+    /// The asset to which this share is converted before being distributed
+    pub denom: String,
+    /// Portion of the protocol revenue that goes to the recipient
+    pub weight: Decimal,
+    /// Address on this chain the recipient's share of CW20 rewards is transferred to, as CW20
+    /// tokens can't be sent over IBC. CW20 rewards can only be distributed if every split has one.
+    #[serde(default)]
+    pub local_recipient: Option<String>,
+}
+
+/// An asset held by the contract, either a native coin or a CW20 token
+#[cw_serde]
+pub enum AssetInfo {
+    Native {
+        denom: String,
+    },
+    Cw20 {
+        contract_addr: String,
+    },
+}
+
+impl fmt::Display for AssetInfo {
+    /// The denom the asset is keyed by: the native denom, or the CW20 address with the `cw20:`
+    /// prefix
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetInfo::Native {
+                denom,
+            } => write!(f, "{denom}"),
+            AssetInfo::Cw20 {
+                contract_addr,
+            } => write!(f, "{CW20_DENOM_PREFIX}{contract_addr}"),
+        }
+    }
+}
+
+/// Where the counterparty chain of the IBC transfers of rewards is, for counting their height
+/// timeout on it
+#[cw_serde]
+pub struct CounterpartyHeight {
+    /// Revision number of the counterparty chain, e.g. 1 for `mars-1`
+    pub revision: u64,
+    /// How far the counterparty chain's height is ahead of this chain's (negative if behind). As
+    /// the chains produce blocks at different rates, the owner should keep it up to date
+    pub height_offset: i64,
+}
+
 #[cw_serde]
 pub struct Config {
     /// Address provider returns addresses for all protocol contracts
     pub address_provider: Addr,
-    /// Percentage of fees that are sent to the safety fund
-    pub safety_tax_rate: Decimal,
-    /// The asset to which the safety fund share is converted
-    pub safety_fund_denom: String,
-    /// The asset to which the fee collector share is converted
-    pub fee_collector_denom: String,
+    /// How the protocol revenue is split between the recipients
+    pub reward_splits: Vec<RewardSplit>,
     /// The channel ID of the mars hub
     pub channel_id: String,
     /// Number of seconds after which an IBC transfer is to be considered failed, if no acknowledgement is received
     pub timeout_seconds: u64,
+    /// Number of blocks of the counterparty chain after which an IBC transfer is also to be
+    /// considered failed, if no acknowledgement is received. Only the timestamp timeout applies if
+    /// not set.
+    pub timeout_blocks: Option<u64>,
+    /// Revision and height offset of the counterparty chain, required along with `timeout_blocks`
+    pub counterparty_height: Option<CounterpartyHeight>,
     /// Maximum percentage of price movement (minimum amount you accept to receive during swap)
     pub slippage_tolerance: Decimal,
+    /// Template of the memo attached to the IBC transfers of the rewards, e.g. to route them
+    /// through packet-forward-middleware. `{recipient}` is replaced with the recipient's address.
+    pub ibc_memo: Option<String>,
+    /// Whether swaps, distributions, withdrawals and claims are suspended by the owner
+    #[serde(default)]
+    pub paused: bool,
+    /// Address of a fee collector module on this chain, which the fee collector's share of a swap
+    /// can be sent to directly. Not set when the fee collector is only on Mars Hub.
+    #[serde(default)]
+    pub local_fee_collector: Option<Addr>,
 }
 
 impl Config {
     pub fn validate(&self) -> Result<(), ValidationError> {
-        decimal_param_le_one(self.safety_tax_rate, "safety_tax_rate")?;
+        if self.reward_splits.is_empty() {
+            return Err(ValidationError::InvalidParam {
+                param_name: "reward_splits".to_string(),
+                invalid_value: "[]".to_string(),
+                predicate: "not empty".to_string(),
+            });
+        }
+
+        let mut total_weight = Decimal::zero();
+        for split in &self.reward_splits {
+            if split.weight.is_zero() {
+                return Err(ValidationError::InvalidParam {
+                    param_name: "weight".to_string(),
+                    invalid_value: split.weight.to_string(),
+                    predicate: "> 0".to_string(),
+                });
+            }
+            decimal_param_le_one(split.weight, "weight")?;
+            validate_native_denom(&split.denom)?;
+            total_weight += split.weight;
+        }
+
+        if total_weight != Decimal::one() {
+            return Err(ValidationError::InvalidParam {
+                param_name: "reward_splits".to_string(),
+                invalid_value: total_weight.to_string(),
+                predicate: "weights sum to 1".to_string(),
+            });
+        }
 
         integer_param_gt_zero(self.timeout_seconds, "timeout_seconds")?;
 
+        if self.timeout_blocks.is_some() && self.counterparty_height.is_none() {
+            return Err(ValidationError::InvalidParam {
+                param_name: "counterparty_height".to_string(),
+                invalid_value: "None".to_string(),
+                predicate: "set along with timeout_blocks".to_string(),
+            });
+        }
+
         if self.slippage_tolerance > Decimal::percent(MAX_SLIPPAGE_TOLERANCE_PERCENTAGE) {
             return Err(ValidationError::InvalidParam {
                 param_name: "slippage_tolerance".to_string(),
@@ -60,23 +184,42 @@ impl Config {
             });
         }
 
-        validate_native_denom(&self.safety_fund_denom)?;
-        validate_native_denom(&self.fee_collector_denom)?;
+        if let Some(memo) = &self.ibc_memo {
+            // the template must still be a JSON object once the placeholder is filled in
+            let memo_json = memo.replace(IBC_MEMO_RECIPIENT_PLACEHOLDER, "recipient");
+            if !memo_json.trim_start().starts_with('{')
+                || from_slice::<IgnoredAny>(memo_json.as_bytes()).is_err()
+            {
+                return Err(ValidationError::InvalidParam {
+                    param_name: "ibc_memo".to_string(),
+                    invalid_value: memo.clone(),
+                    predicate: "a JSON object".to_string(),
+                });
+            }
+        }
 
         Ok(())
     }
+
+    /// The memo of an IBC transfer of rewards to the given recipient, if one is configured
+    pub fn ibc_memo_for(&self, recipient: &str) -> Option<String> {
+        self.ibc_memo.as_ref().map(|memo| memo.replace(IBC_MEMO_RECIPIENT_PLACEHOLDER, recipient))
+    }
 }
 
 impl Config {
     pub fn checked(api: &dyn Api, msg: InstantiateMsg) -> StdResult<Config> {
         Ok(Config {
             address_provider: api.addr_validate(&msg.address_provider)?,
-            safety_tax_rate: msg.safety_tax_rate,
-            safety_fund_denom: msg.safety_fund_denom,
-            fee_collector_denom: msg.fee_collector_denom,
+            reward_splits: msg.reward_splits,
             channel_id: msg.channel_id,
             timeout_seconds: msg.timeout_seconds,
+            timeout_blocks: None,
+            counterparty_height: None,
             slippage_tolerance: msg.slippage_tolerance,
+            ibc_memo: None,
+            paused: false,
+            local_fee_collector: None,
         })
     }
 }
@@ -86,18 +229,23 @@ impl Config {
 pub struct UpdateConfig {
     /// Address provider returns addresses for all protocol contracts
     pub address_provider: Option<String>,
-    /// Percentage of fees that are sent to the safety fund
-    pub safety_tax_rate: Option<Decimal>,
-    /// The asset to which the safety fund share is converted
-    pub safety_fund_denom: Option<String>,
-    /// The asset to which the fee collector share is converted
-    pub fee_collector_denom: Option<String>,
+    /// How the protocol revenue is split between the recipients
+    pub reward_splits: Option<Vec<RewardSplit>>,
     /// The channel id of the mars hub
     pub channel_id: Option<String>,
     /// Number of seconds after which an IBC transfer is to be considered failed, if no acknowledgement is received
     pub timeout_seconds: Option<u64>,
+    /// Number of blocks of the counterparty chain after which an IBC transfer is also to be
+    /// considered failed; zero removes it
+    pub timeout_blocks: Option<u64>,
+    /// Revision and height offset of the counterparty chain
+    pub counterparty_height: Option<CounterpartyHeight>,
     /// Maximum percentage of price movement (minimum amount you accept to receive during swap)
     pub slippage_tolerance: Option<Decimal>,
+    /// Template of the memo attached to the IBC transfers of the rewards; an empty one removes it
+    pub ibc_memo: Option<String>,
+    /// Address of a fee collector module on this chain; an empty one removes it
+    pub local_fee_collector: Option<String>,
 }
 
 #[cw_serde]
@@ -110,6 +258,12 @@ pub enum ExecuteMsg<Route> {
         new_cfg: UpdateConfig,
     },
 
+    /// Suspend or resume swaps, distributions, withdrawals and incentive claims, e.g. during an
+    /// oracle incident. Only callable by the owner.
+    SetPaused {
+        paused: bool,
+    },
+
     /// Configure the route for swapping an asset
     ///
     /// This is chain-specific, and can include parameters such as slippage tolerance and the routes
@@ -118,6 +272,28 @@ pub enum ExecuteMsg<Route> {
         denom_in: String,
         denom_out: String,
         route: Route,
+        /// Slippage tolerance to apply to swaps along this route instead of the configured one,
+        /// e.g. a tighter one for a thin pool. Capped like the configured one.
+        slippage_override: Option<Decimal>,
+    },
+
+    /// Configure a route that is only usable within a time window, in seconds since the UNIX epoch.
+    ///
+    /// Useful for pre-staging a route change: a route with `valid_from` in the future can be set
+    /// ahead of time and will not be used for swaps until then.
+    SetTimeBoundedRoute {
+        denom_in: String,
+        denom_out: String,
+        route: Route,
+        valid_from: Option<u64>,
+        valid_until: Option<u64>,
+    },
+
+    /// Remove the route for swapping an input denom into an output denom, e.g. once its pools are
+    /// deprecated
+    RemoveRoute {
+        denom_in: String,
+        denom_out: String,
     },
 
     /// Withdraw coins from the red bank
@@ -126,18 +302,77 @@ pub enum ExecuteMsg<Route> {
         amount: Option<Uint128>,
     },
 
-    /// Distribute the accrued protocol income between the safety fund and the fee modules on mars hub,
-    /// according to the split set in config.
+    /// Distribute the accrued protocol income to the recipients whose reward split is in the given
+    /// denom, in proportion to their weights.
     /// Callable by any address.
     DistributeRewards {
         denom: String,
         amount: Option<Uint128>,
+        /// Exact IBC timeout to use instead of the configured `timeout_seconds` from the current
+        /// block, e.g. to match a known relayer schedule. Must be in the future, and can only be
+        /// set by the owner.
+        timeout_timestamp: Option<Timestamp>,
     },
 
-    /// Swap any asset on the contract
+    /// Distribute the accrued protocol income of several denoms in one transaction, same as
+    /// `DistributeRewards` for each of them.
+    /// If an amount is None, the contract's whole balance of the denom is distributed.
+    DistributeRewardsMany {
+        denoms: Vec<(String, Option<Uint128>)>,
+    },
+
+    /// Swap any asset on the contract into the denoms of the reward splits, in proportion to their
+    /// weights
     SwapAsset {
         denom: String,
         amount: Option<Uint128>,
+        /// Send the fee collector's share directly to the configured `local_fee_collector` once
+        /// swapped, instead of leaving it on the contract for `DistributeRewards` (false by
+        /// default). Fails if no local fee collector is configured.
+        send_to_fee_collector: Option<bool>,
+    },
+
+    /// Swap a list of assets on the contract into the denoms of the reward splits, same as
+    /// `SwapAsset` for each of them.
+    /// If an amount is None, the contract's whole balance of the asset is swapped.
+    SwapAssets {
+        denoms: Vec<(String, Option<Uint128>)>,
+    },
+
+    /// Swap the given amount of `denom_in` (the contract's whole balance if None) into `denom_out`.
+    /// `min_output`, if given, is enforced as the minimum output amount as is; `slippage` (the
+    /// configured slippage tolerance if neither is given) is applied to the estimated output
+    /// amount. If both are given, the larger of the two minimums is enforced. Fails if the minimum
+    /// output is zero.
+    /// Only callable by the owner.
+    SwapExactIn {
+        denom_in: String,
+        amount: Option<Uint128>,
+        denom_out: String,
+        slippage: Option<Decimal>,
+        min_output: Option<Uint128>,
+    },
+
+    /// Swap the contract's balance of `denom_in` into exactly `coin_out`, spending at most the
+    /// estimated input amount plus `slippage`, and never more than `max_input`. Only the input
+    /// actually needed is spent. The input is taken from the protocol revenue held by the
+    /// contract rather than from funds sent along, so the unspent part simply remains part of that
+    /// revenue, to be swapped or distributed like the rest of it.
+    /// Only callable by the owner.
+    SwapExactOut {
+        coin_out: Coin,
+        denom_in: String,
+        max_input: Uint128,
+        slippage: Decimal,
+    },
+
+    /// Swap the single coin sent along into `denom_out` through the configured route, applying
+    /// the route's slippage tolerance, and send the output back to the sender.
+    /// Only callable by the incentives contract, which uses it to repay debts in another denom than
+    /// the rewards with them, and by the Red Bank, which uses it to consolidate withdrawn
+    /// collateral into a single denom.
+    SwapFunds {
+        denom_out: String,
     },
 
     /// Claim rewards in incentives contract.
@@ -145,6 +380,56 @@ pub enum ExecuteMsg<Route> {
     /// We wanted to leave protocol rewards in the red-bank so they continue to work as liquidity (until the bot invokes WithdrawFromRedBank).
     /// As an side effect to this, if the market is incentivised with MARS tokens, the contract will also accrue MARS token incentives.
     ClaimIncentiveRewards {},
+
+    /// Claim the incentive rewards, then swap the claimed amount of each asset that has an active
+    /// route to each of the reward split denoms, then distribute the contract's whole balance of
+    /// each reward split denom.
+    ///
+    /// Each stage runs once the previous one has completed, so it acts on the balances resulting
+    /// from it. Assets whose swaps would exceed the maximum number of swap messages are left on
+    /// the contract, to be swapped with `SwapAsset`.
+    Harvest {},
+
+    /// Split the given amount of a CW20 token held by the contract between the reward split
+    /// recipients in proportion to their weights, transferring each share to the recipient's
+    /// `local_recipient` on this chain. CW20 tokens can't be swapped into the reward split denoms
+    /// or sent over IBC, so they're distributed as is. The distributed totals are keyed by
+    /// `cw20:<contract_addr>`.
+    DistributeCw20 {
+        contract_addr: String,
+        amount: Uint128,
+    },
+}
+
+/// Time window during which a swap route may be used.
+/// Bounds are in seconds since the UNIX epoch; `valid_from` is inclusive and `valid_until` exclusive.
+#[cw_serde]
+#[derive(Default)]
+pub struct RouteWindow {
+    pub valid_from: Option<u64>,
+    pub valid_until: Option<u64>,
+}
+
+impl RouteWindow {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if let (Some(valid_from), Some(valid_until)) = (self.valid_from, self.valid_until) {
+            if valid_from >= valid_until {
+                return Err(ValidationError::InvalidParam {
+                    param_name: "valid_until".to_string(),
+                    invalid_value: valid_until.to_string(),
+                    predicate: format!("> {valid_from}"),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the window includes the given timestamp
+    pub fn is_active(&self, time: u64) -> bool {
+        self.valid_from.map_or(true, |from| time >= from)
+            && self.valid_until.map_or(true, |until| time < until)
+    }
 }
 
 #[cw_serde]
@@ -155,18 +440,25 @@ pub struct ConfigResponse {
     pub proposed_new_owner: Option<String>,
     /// Address provider returns addresses for all protocol contracts
     pub address_provider: String,
-    /// Percentage of fees that are sent to the safety fund
-    pub safety_tax_rate: Decimal,
-    /// The asset to which the safety fund share is converted
-    pub safety_fund_denom: String,
-    /// The asset to which the fee collector share is converted
-    pub fee_collector_denom: String,
+    /// How the protocol revenue is split between the recipients
+    pub reward_splits: Vec<RewardSplit>,
     /// The channel ID of the mars hub
     pub channel_id: String,
     /// Number of seconds after which an IBC transfer is to be considered failed, if no acknowledgement is received
     pub timeout_seconds: u64,
+    /// Number of blocks of the counterparty chain after which an IBC transfer is also to be
+    /// considered failed, if any
+    pub timeout_blocks: Option<u64>,
+    /// Revision and height offset of the counterparty chain
+    pub counterparty_height: Option<CounterpartyHeight>,
     /// Maximum percentage of price movement (minimum amount you accept to receive during swap)
     pub slippage_tolerance: Decimal,
+    /// Template of the memo attached to the IBC transfers of the rewards
+    pub ibc_memo: Option<String>,
+    /// Whether swaps, distributions, withdrawals and claims are suspended
+    pub paused: bool,
+    /// Address of a fee collector module on this chain, if any
+    pub local_fee_collector: Option<String>,
 }
 
 #[cw_serde]
@@ -191,6 +483,75 @@ pub enum QueryMsg {
         start_after: Option<(String, String)>,
         limit: Option<u32>,
     },
+    /// Simulate a full `SwapAsset` + `DistributeRewards` cycle for the contract's entire balance of
+    /// a denom, using the routes' estimated output amounts, and return the expected balances left
+    /// in the contract afterwards
+    #[returns(ResidualAfterCycleResponse)]
+    ResidualAfterCycle {
+        denom: String,
+    },
+    /// Get the incentive rewards accrued on the contract's own collateral positions, which can be
+    /// claimed with `ClaimIncentiveRewards`
+    #[returns(Uint128)]
+    PendingIncentiveRewards {},
+    /// Get the current and proposed owner of each Mars contract, to track an ownership handover
+    /// across all contracts in one call
+    #[returns(Vec<OwnershipStatusResponse>)]
+    OwnershipStatus {},
+    /// Get the minimum output amount a swap of the given coin to `denom_out` would enforce, using
+    /// the route's estimated output amount and the given slippage tolerance
+    #[returns(Uint128)]
+    MinOutput {
+        coin_in: Coin,
+        denom_out: String,
+        slippage: Decimal,
+    },
+    /// Get the value of the contract's balances of the given denoms, priced by the oracle
+    #[returns(TotalPendingValueResponse)]
+    TotalPendingValue {
+        denoms: Vec<String>,
+    },
+    /// Project the coins each recipient would receive from swapping the contract's whole balance
+    /// of the given denoms, using the routes' estimated output amounts. Returned in the order of
+    /// the configured reward splits.
+    #[returns(Vec<(RewardRecipient, Coin)>)]
+    ProjectedDistribution {
+        denoms: Vec<String>,
+    },
+    /// Check whether a route is set for swapping an input denom into an output denom, and whether
+    /// a swap along it can currently be executed
+    #[returns(RouteExistsResponse)]
+    RouteExists {
+        denom_in: String,
+        denom_out: String,
+    },
+    /// Estimate the amount of `denom_out` a swap of the given amount of `denom_in` along the stored
+    /// route would currently produce, before slippage tolerance is applied
+    #[returns(SimulateSwapResponse)]
+    SimulateSwap {
+        denom_in: String,
+        denom_out: String,
+        amount: Uint128,
+    },
+    /// Enumerate the cumulative amounts distributed to each recipient over the contract's
+    /// lifetime, per denom. Paginated by `(denom, recipient)`.
+    #[returns(DistributionStatsResponse)]
+    DistributionStats {
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    /// Compare the contract's balance of a denom with the balance expected after its last swap or
+    /// distribution, i.e. the balance at the time net of the amount swapped or distributed, plus
+    /// the estimated output of the swaps into the denom since.
+    ///
+    /// Funds received since, e.g. revenue that is yet to be swapped or funds sent out-of-band, are
+    /// reported as a surplus, and swaps that delivered less than their estimated output as a
+    /// deficit. Exact-output swaps, whose input amount is only known once executed, aren't
+    /// accounted for.
+    #[returns(BalanceReconciliationResponse)]
+    BalanceReconciliation {
+        denom: String,
+    },
 }
 
 #[cw_serde]
@@ -198,6 +559,90 @@ pub struct RouteResponse<Route> {
     pub denom_in: String,
     pub denom_out: String,
     pub route: Route,
+    /// Slippage tolerance applied to swaps along the route, if not the configured one
+    pub slippage_override: Option<Decimal>,
 }
 
 pub type RoutesResponse<Route> = Vec<RouteResponse<Route>>;
+
+#[cw_serde]
+pub struct RouteExistsResponse {
+    /// Whether a route is set for the pair of denoms
+    pub exists: bool,
+    /// Whether the route is within its validity window and its output can be estimated, i.e. the
+    /// price records it is priced with are available
+    pub usable: bool,
+    /// Why the route can't be used, if it isn't usable
+    pub reason: Option<String>,
+}
+
+#[cw_serde]
+pub struct DistributedTotalResponse {
+    pub denom: String,
+    /// Display name of the recipient, i.e. its module name or address
+    pub recipient: String,
+    pub amount: Uint128,
+}
+
+pub type DistributionStatsResponse = Vec<DistributedTotalResponse>;
+
+#[cw_serde]
+pub struct BalanceReconciliationResponse {
+    pub denom: String,
+    /// The contract's actual balance of the denom
+    pub balance: Uint128,
+    /// The balance expected from the contract's swaps and distributions
+    pub expected_balance: Uint128,
+    /// Amount by which the actual balance exceeds the expected balance
+    pub surplus: Uint128,
+    /// Amount by which the actual balance falls short of the expected balance
+    pub deficit: Uint128,
+}
+
+#[cw_serde]
+pub struct SimulateSwapResponse {
+    pub amount_out: Uint128,
+}
+
+#[cw_serde]
+pub struct TotalPendingValueResponse {
+    /// Sum of the values of all priced balances, denominated in the oracle's base denom
+    pub total_value: Uint128,
+    pub balances: Vec<PendingBalanceValue>,
+    /// Balances whose denom has no oracle price. These are excluded from the total.
+    pub unpriced: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct PendingBalanceValue {
+    pub denom: String,
+    pub amount: Uint128,
+    pub price: Decimal,
+    pub value: Uint128,
+}
+
+#[cw_serde]
+pub struct OwnershipStatusResponse {
+    /// The contract's name, as in `MarsAddressType`, or `address_provider`
+    pub contract: String,
+    /// `None` if the address provider doesn't have the contract's address
+    pub address: Option<String>,
+    /// `None` if the contract's address or config couldn't be queried
+    pub ownership: Option<ContractOwnership>,
+}
+
+#[cw_serde]
+pub struct ContractOwnership {
+    pub owner: Option<String>,
+    pub proposed_new_owner: Option<String>,
+}
+
+#[cw_serde]
+pub struct ResidualAfterCycleResponse {
+    /// The input denom
+    pub denom: String,
+    /// Expected balance of the input denom left in the contract
+    pub denom_residual: Uint128,
+    /// Expected coins sent to each recipient, in the order of the configured reward splits
+    pub distributed: Vec<(RewardRecipient, Coin)>,
+}
@@ -121,9 +121,18 @@ export class Deployer {
     const msg = {
       owner: this.deployerAddress,
       address_provider: this.storage.addresses['address-provider']!,
-      safety_tax_rate: this.config.safetyFundFeeShare,
-      safety_fund_denom: this.config.safetyFundDenom,
-      fee_collector_denom: this.config.feeCollectorDenom,
+      reward_splits: [
+        {
+          recipient: { module: 'safety_fund' },
+          denom: this.config.safetyFundDenom,
+          weight: this.config.safetyFundFeeShare,
+        },
+        {
+          recipient: { module: 'fee_collector' },
+          denom: this.config.feeCollectorDenom,
+          weight: String(1 - Number(this.config.safetyFundFeeShare)),
+        },
+      ],
       channel_id: this.config.channelId,
       timeout_seconds: this.config.rewardCollectorTimeoutSeconds,
       slippage_tolerance: this.config.slippage_tolerance,
@@ -30,14 +30,18 @@ export interface OracleInstantiateMsg {
   base_denom: string
 }
 
+export interface RewardSplit {
+  recipient: { module: string } | { addr: string }
+  denom: string
+  weight: string
+}
+
 export interface RewardsInstantiateMsg {
   owner: string
-  safety_fund_denom: string
   address_provider: string
   slippage_tolerance: string
-  safety_tax_rate: string
+  reward_splits: RewardSplit[]
   timeout_seconds: number
-  fee_collector_denom: string
   channel_id: string
 }
 